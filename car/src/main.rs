@@ -4,9 +4,7 @@
 #![no_main]
 #![no_std]
 
-extern crate panic_halt;
-
-use core::{arch::global_asm, time::Duration};
+use core::{arch::global_asm, ptr, time::Duration};
 use cortex_m::interrupt;
 use cortex_m_rt::entry;
 use tm4c123x_hal::{CorePeripherals, Peripherals};
@@ -14,6 +12,7 @@ use ucsc_ectf_util_no_std::{
     communication::RxChannel,
     eeprom::{EepromReadWriteField, SECRET_SIZE},
     messages::Uart1Message,
+    panic::register_secret_region,
     Runtime, RuntimePeripherals,
 };
 use zeroize::Zeroize;
@@ -36,6 +35,18 @@ jump_to_reset:
 "#
 );
 
+/// The buffer the key fob encryption key is read into. This is static so it can be registered with
+/// the panic handler, which wipes it if a panic happens while the key is in it.
+static mut KEY_FOB_ENCRYPTION_KEY: [u8; SECRET_SIZE] = [0; SECRET_SIZE];
+
+/// The buffer the car encryption key is read into. See [`KEY_FOB_ENCRYPTION_KEY`].
+static mut CAR_ENCRYPTION_KEY: [u8; SECRET_SIZE] = [0; SECRET_SIZE];
+
+extern "C" {
+    /// The bottom of the stack, defined in memory.x.
+    static _stack_end: u32;
+}
+
 #[entry]
 fn main() -> ! {
     // Enable interrupts because the bootloader disables them and leaves them disabled.
@@ -44,40 +55,55 @@ fn main() -> ! {
         interrupt::enable();
     }
 
+    // Wipe the key buffers if anything panics.
+    // SAFETY: The key buffers are statics, so they're valid for writes for the rest of the program.
+    unsafe {
+        register_secret_region(
+            ptr::addr_of_mut!(KEY_FOB_ENCRYPTION_KEY).cast(),
+            SECRET_SIZE,
+        );
+        register_secret_region(ptr::addr_of_mut!(CAR_ENCRYPTION_KEY).cast(), SECRET_SIZE);
+    }
+
     // Grab peripherals.
     let core_peripherals = CorePeripherals::take().unwrap();
     let peripherals = Peripherals::take().unwrap();
     let mut rt_peripherals = RuntimePeripherals::from((core_peripherals, peripherals));
 
     // Initialize runtime.
-    let mut rt = Runtime::new(
+    let mut rt = Runtime::new_or_panic(
         &mut rt_peripherals,
         &Default::default(),
         &Default::default(),
     );
 
-    // Transmit and receive using unlock keys.
-    let mut key_fob_encryption_key = [0; SECRET_SIZE];
-    let mut car_encryption_key = [0; SECRET_SIZE];
+    // Fault on a stack overflow instead of running off the bottom of the stack.
+    // SAFETY: _stack_end is defined in memory.x, and only its address is taken.
+    rt.configure_stack_guard(unsafe { ptr::addr_of!(_stack_end) });
+
+    // Transmit and receive using unlock keys. Replay protection isn't enabled on UART1 because these
+    // keys never change, so the key fob restarts its sequence numbers on every unlock. Replayed unlock
+    // messages are rejected by the random unlock challenge instead.
+    // SAFETY: This is the only place the key buffers are used, and main() only runs once, so these are
+    // the only references to them.
+    let key_fob_encryption_key = unsafe { &mut *ptr::addr_of_mut!(KEY_FOB_ENCRYPTION_KEY) };
+    let car_encryption_key = unsafe { &mut *ptr::addr_of_mut!(CAR_ENCRYPTION_KEY) };
 
     rt.eeprom_controller
         .read_slice(
             EepromReadWriteField::KeyFobEncryptionKey,
-            &mut key_fob_encryption_key,
+            key_fob_encryption_key,
         )
         .expect("EEPROM read failed: key fob encryption key.");
     rt.eeprom_controller
-        .read_slice(
-            EepromReadWriteField::CarEncryptionKey,
-            &mut car_encryption_key,
-        )
+        .read_slice(EepromReadWriteField::CarEncryptionKey, car_encryption_key)
         .expect("EEPROM read failed: car encryption key.");
 
     rt.uart1_controller
-        .change_rx_key(&key_fob_encryption_key.into());
+        .change_rx_key(&(*key_fob_encryption_key).into());
     key_fob_encryption_key.zeroize();
     rt.uart1_controller
-        .change_tx_key(&car_encryption_key.into());
+        .change_tx_key(&(*car_encryption_key).into());
     car_encryption_key.zeroize();
 
     // Listen for unlock requests.
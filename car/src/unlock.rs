@@ -1,7 +1,8 @@
 use crate::{eeprom_messages, MAX_MESSAGE_SIZE};
-use core::{mem, time::Duration};
+use core::time::Duration;
 use ucsc_ectf_util_no_std::{
-    communication::{CommunicationError, RxChannel, TxChannel},
+    communication::{CommunicationError, TxChannel},
+    constant_time_eq,
     eeprom::{EepromReadWriteField, CAR_ID_SIZE, MESSAGE_SIZE},
     features,
     messages::{
@@ -12,6 +13,11 @@ use ucsc_ectf_util_no_std::{
     Runtime,
 };
 
+/// The time every failure to receive an unlock challenge response is padded to, so a response
+/// rejected while it's being parsed can't be told apart from one that failed to authenticate. This
+/// is well above the time taken to decrypt the longest message.
+const RESPONSE_FAILURE_PADDING: Duration = Duration::from_millis(5);
+
 /// Unlocks the car.
 fn unlock_car(rt: &mut Runtime, challenge_response: &UnlockChallengeResponse) {
     let unlock_msg_bytes = eeprom_messages::get_unlock_message(&mut rt.eeprom_controller);
@@ -81,8 +87,7 @@ pub(crate) fn process_msg(rt: &mut Runtime, receive_msg: &Uart1Message) {
     }
 
     // Generate challenge.
-    let mut challenge = [0; mem::size_of::<Nonce>()];
-    rt.fill_rand_slice(&mut challenge);
+    let challenge: Nonce = rt.fill_rand_array();
 
     // Send challenge.
     let challenge_msg = Uart1Message::UnlockChallenge(UnlockChallenge { car_id, challenge });
@@ -102,6 +107,7 @@ pub(crate) fn process_msg(rt: &mut Runtime, receive_msg: &Uart1Message) {
     // Wait for challenge response.
     let mut response_bytes = [0; MAX_MESSAGE_SIZE];
     let mut timeout_timer = rt.hib_controller.create_timer(Duration::from_secs(1));
+    let mut padding_timer = rt.hib_controller.create_timer(RESPONSE_FAILURE_PADDING);
 
     let challenge_response = loop {
         // Make sure timer hasn't expired on this iteration first.
@@ -109,10 +115,12 @@ pub(crate) fn process_msg(rt: &mut Runtime, receive_msg: &Uart1Message) {
             return;
         }
 
-        let size_read = match rt
-            .uart1_controller
-            .recv_with_timeout(&mut response_bytes, &mut timeout_timer)
-        {
+        let size_read = match rt.uart1_controller.recv_with_timeout_padded(
+            &mut response_bytes,
+            &[],
+            &mut timeout_timer,
+            &mut padding_timer,
+        ) {
             Ok(size_read) => size_read,
             Err(CommunicationError::InternalError) => {
                 panic!("Failed to receive unlock challenge response (internal error).")
@@ -133,7 +141,7 @@ pub(crate) fn process_msg(rt: &mut Runtime, receive_msg: &Uart1Message) {
     }
 
     // Verify challenge.
-    if challenge_response.challenge_response != challenge {
+    if !constant_time_eq(&challenge_response.challenge_response, &challenge) {
         return;
     }
 
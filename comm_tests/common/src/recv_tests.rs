@@ -8,7 +8,8 @@ use ucsc_ectf_util_common::{communication::CommunicationError, timer::Timer};
 
 use crate::{RxTxChannel, METADATA_OVERHEAD, STARTING_SEED, MAX_MESSAGE_SIZE};
 
-/// Tests that trying to receive data into a buffer of length 0 immediately returns an error.
+/// Tests that trying to receive data into a buffer of length 0 immediately returns an error with the
+/// needed buffer length.
 pub fn empty_recv_error_test<T: RxTxChannel, U: Timer, V: Fn(Duration) -> U>(
     uart: &mut T,
     timer_fn: &V,
@@ -24,13 +25,15 @@ pub fn empty_recv_error_test<T: RxTxChannel, U: Timer, V: Fn(Duration) -> U>(
 
     assert_eq!(
         res,
-        Err(CommunicationError::RecvError),
+        Err(CommunicationError::BufferTooSmall {
+            needed: METADATA_OVERHEAD + 1
+        }),
         "Failed empty recv error test"
     );
 }
 
 /// Tests that trying to receive data into a buffer too small to hold any ciphertext immediately
-/// returns an error.
+/// returns an error with the needed buffer length.
 pub fn too_small_recv_error_test<T: RxTxChannel, U: Timer, V: Fn(Duration) -> U>(
     uart: &mut T,
     timer_fn: &V,
@@ -48,7 +51,9 @@ pub fn too_small_recv_error_test<T: RxTxChannel, U: Timer, V: Fn(Duration) -> U>
 
     assert_eq!(
         res,
-        Err(CommunicationError::RecvError),
+        Err(CommunicationError::BufferTooSmall {
+            needed: METADATA_OVERHEAD + 1
+        }),
         "Failed too small recv error test"
     );
 }
@@ -178,7 +183,11 @@ pub fn recv_should_timeout_test<T: RxTxChannel, U: Timer, V: Fn(Duration) -> U>(
     let mut timer = timer_fn(Duration::from_secs(1));
     let res = uart.recv_with_timeout(&mut test, &mut timer);
 
-    res.expect_err("Failed recv timeout test");
+    assert_eq!(
+        res,
+        Err(CommunicationError::Timeout),
+        "Failed recv timeout test"
+    );
 
     // Flush last message.
     let mut flush_timer = timer_fn(Duration::from_millis(50));
@@ -230,7 +239,11 @@ pub fn recv_should_data_timeout_test<T: RxTxChannel, U: Timer, V: Fn(Duration) -
     let mut timer = timer_fn(Duration::from_secs(1));
     let res = uart.recv_with_data_timeout(&mut test, &mut timer);
 
-    res.expect_err("Failed recv data timeout test");
+    assert_eq!(
+        res,
+        Err(CommunicationError::Timeout),
+        "Failed recv data timeout test"
+    );
 
     // Flush last message.
     let mut flush_timer = timer_fn(Duration::from_millis(100));
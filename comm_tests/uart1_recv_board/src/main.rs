@@ -34,7 +34,7 @@ fn main_test() -> ! {
     let mut rt_peripherals = peripherals.into();
 
     {
-        let mut rt = Runtime::new(
+        let mut rt = Runtime::new_or_panic(
             &mut rt_peripherals,
             &SEND_RX_KEY.into(),
             &SEND_TX_KEY.into(),
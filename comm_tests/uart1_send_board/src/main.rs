@@ -24,7 +24,7 @@ fn main_test() -> ! {
         Peripherals::take().unwrap(),
     );
     let mut rt_peripherals = peripherals.into();
-    let mut rt = Runtime::new(
+    let mut rt = Runtime::new_or_panic(
         &mut rt_peripherals,
         &RECV_RX_KEY.into(),
         &RECV_TX_KEY.into(),
@@ -166,6 +166,38 @@ const UNLOCK_MESSAGE_BOUNDS: EepromFieldBounds = EepromFieldBounds {
     size: MESSAGE_SIZE,
 };
 
+/// The number of words a transaction journal entry takes up. Each entry is a byte address followed by
+/// the word to write there.
+pub const JOURNAL_ENTRY_WORDS: usize = 2;
+
+/// The number of words at the start of the transaction journal before its entries. These are the
+/// commit marker and the entry count.
+pub const JOURNAL_HEADER_WORDS: usize = 2;
+
+/// The maximum number of words a single transaction can write.
+pub const JOURNAL_CAPACITY: usize = 48;
+
+/// The size of the transaction journal.
+const JOURNAL_SIZE: usize = (JOURNAL_HEADER_WORDS + JOURNAL_CAPACITY * JOURNAL_ENTRY_WORDS) * 4;
+
+/// The bounds of the transaction journal. The journal is reserved for EEPROM transactions and sits
+/// directly before the reserved message space.
+pub const JOURNAL_BOUNDS: EepromFieldBounds = EepromFieldBounds {
+    address: EEPROM_MESSAGES_START_ADDRESS - JOURNAL_SIZE,
+    size: JOURNAL_SIZE,
+};
+
+/// The bounds of the space between the last field and the transaction journal, which isn't used by any
+/// field. Raw words, such as wear-leveled counters and stored feature sets, are kept here, and a factory
+/// reset wipes all of it.
+pub const FREE_SPACE_BOUNDS: EepromFieldBounds = EepromFieldBounds {
+    address: FEATURE_THREE_SIGNED_PACKAGED_BOUNDS.address
+        + FEATURE_THREE_SIGNED_PACKAGED_BOUNDS.size,
+    size: JOURNAL_BOUNDS.address
+        - (FEATURE_THREE_SIGNED_PACKAGED_BOUNDS.address
+            + FEATURE_THREE_SIGNED_PACKAGED_BOUNDS.size),
+};
+
 /// This enum specifies the fields of the EEPROM that can be read from, but not written to.
 #[derive(Copy, Clone)]
 pub enum EepromReadOnlyField {
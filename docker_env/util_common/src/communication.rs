@@ -18,14 +18,41 @@
 //!       BogoStack.
 
 use crate::timer::Timer;
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
+mod bounded;
+pub mod challenge_response;
+mod duplex;
 pub mod lower_layers;
+mod writer;
+
+pub use bounded::*;
+pub use duplex::*;
+pub use writer::*;
+
+/// The maximum number of parts that can be given to [`TxChannel::send_vectored`] on a channel in the
+/// framing layer, which sends each part as a slice of one frame. Channels that copy the parts into a
+/// buffer take any number of parts.
+pub const MAX_VECTORED_PARTS: usize = 4;
 
 /// Type definition for any [`CommunicationError`] [`Results`](core::result::Result).
 pub type Result<T> = core::result::Result<T, CommunicationError>;
 
 /// A channel to receive data from. See the documentation for [`recv_with_timeout`](RxChannel::recv_with_timeout)
 /// and [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout) for more info.
+///
+/// # Memory safety
+///
+/// Every implementation must uphold the following invariant: a receive operation never writes more than
+/// ``dest.len()`` bytes into ``dest``, and the length it returns is never greater than ``dest.len()``. This
+/// must hold no matter what is received, including frames that are longer than ``dest`` or that claim a
+/// length inconsistent with the data that follows. A message that doesn't fit must be rejected with an error
+/// instead of being truncated or written past the end of the buffer. Channels wrapping another [`RxChannel`]
+/// must not trust the length returned by the wrapped channel and must check it against ``dest.len()`` before
+/// using it.
 pub trait RxChannel {
     /// Receives data from the channel, putting the data received into ``dest``, returning the
     /// number of bytes written to it upon success. The buffer provided should have enough
@@ -38,15 +65,18 @@ pub trait RxChannel {
     /// - [`CommunicationError::RecvError`] - There are a couple of cases when this can occur:
     ///   - If this is a channel receiving communications from a
     ///     [`FramedTxChannel`](lower_layers::framing::FramedTxChannel), then this error could occur
-    ///     if the provided buffer is too small to fit a whole message sent in a frame or if a malformed
-    ///     message was sent.
-    ///   - The timeout is reached.
+    ///     if a malformed message was sent.
     ///   - If this is a channel receiving communications from a channel in the crypto layer, such
     ///     as from an [`XChachaPoly1305Channel`](lower_layers::crypto::XChacha20Poly1305TxChannel)
-    ///     then this error could occur if the provided buffer isn't big enough to store the additional
-    ///     metadata, which can include a nonce and/or an authentication tag. Additionally, if the message
-    ///     sent couldn't be authenticated, which can occur due to data corruption, then this error
-    ///     will be returned.
+    ///     then this error could occur if the message sent couldn't be authenticated, which can occur
+    ///     due to data corruption.
+    /// - [`CommunicationError::BufferTooSmall`] - The provided buffer is too small to fit the whole message,
+    ///   including any metadata, such as a nonce and/or an authentication tag. The error contains the buffer
+    ///   length that's needed to receive the message.
+    /// - [`CommunicationError::Timeout`] - The timeout was reached before a complete message was received.
+    /// - [`CommunicationError::LineError`] - The hardware reported a framing, parity, break, or overrun error
+    ///   for a received byte. Only channels over hardware that reports these errors, such as a UART, return
+    ///   this.
     ///  - [`CommunicationError::InternalError`]
     ///    - This can occur if some internal error happens. This should only occur if something is wrong
     ///      with the implementation.
@@ -59,31 +89,229 @@ pub trait RxChannel {
     /// block is for the entire receive operation. If the timeout has passed and not enough bytes have been received,
     /// this function returns an error. Upon an error, a [`CommunicationError`] is given.
     ///
+    /// The timer is never reset by this function, so passing the same timer to several receives makes its
+    /// timeout apply to all of them together. See [`recv_until`](RxChannel::recv_until) for more info.
+    ///
     /// # ERRORS:
     ///
     /// - [`CommunicationError::RecvError`] - There are a couple of cases when this can occur:
     ///   - If this is a channel receiving communications from a
     ///     [`FramedTxChannel`](lower_layers::framing::FramedTxChannel), then this error could occur
-    ///     if the provided buffer is too small to fit a whole message sent in a frame or if a malformed
-    ///     message was sent.
-    ///   - The timeout is reached.
+    ///     if a malformed message was sent.
     ///   - If this is a channel receiving communications from a channel in the crypto layer, such
     ///     as from an [`XChachaPoly1305Channel`](lower_layers::crypto::XChacha20Poly1305TxChannel)
-    ///     then this error could occur if the provided buffer isn't big enough to store the additional
-    ///     metadata, which can include a nonce and/or an authentication tag. Additionally, if the message
-    ///     sent couldn't be authenticated, which can occur due to data corruption, then this error
-    ///     will be returned.
+    ///     then this error could occur if the message sent couldn't be authenticated, which can occur
+    ///     due to data corruption.
+    /// - [`CommunicationError::BufferTooSmall`] - The provided buffer is too small to fit the whole message,
+    ///   including any metadata, such as a nonce and/or an authentication tag. The error contains the buffer
+    ///   length that's needed to receive the message.
+    /// - [`CommunicationError::Timeout`] - The timeout was reached before a complete message was received.
+    /// - [`CommunicationError::LineError`] - The hardware reported a framing, parity, break, or overrun error
+    ///   for a received byte. Only channels over hardware that reports these errors, such as a UART, return
+    ///   this.
     ///  - [`CommunicationError::InternalError`]
     ///    - This can occur if some internal error happens. This should only occur if something is wrong
     ///      with the implementation.
     fn recv_with_timeout<T: Timer>(&mut self, dest: &mut [u8], timer: &mut T) -> Result<usize>;
+
+    /// Receives data from the channel in the same way as [`recv_with_timeout`](RxChannel::recv_with_timeout),
+    /// but stops once ``deadline`` has elapsed, no matter how many receives it has been shared across. This is
+    /// useful for enforcing a timeout on a whole exchange of several messages, such as a handshake, so that a
+    /// peer can't keep the exchange going forever by sending each message slowly. Create the timer once at the
+    /// start of the exchange and pass it to every receive in it.
+    ///
+    /// The deadline is never reset. If it has already elapsed, this returns an error without receiving
+    /// anything, even if a message is waiting.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_until<T: Timer>(&mut self, dest: &mut [u8], deadline: &mut T) -> Result<usize> {
+        if deadline.poll() {
+            return Err(CommunicationError::Timeout);
+        }
+
+        self.recv_with_timeout(dest, deadline)
+    }
+
+    /// Receives data from the channel in the same way as [`recv_with_timeout`](RxChannel::recv_with_timeout),
+    /// but gives up early once ``cancel`` is set, such as by an interrupt handler for a button the user
+    /// pressed to cancel. ``cancel`` isn't cleared by this function.
+    ///
+    /// ``cancel`` is checked every time the channel polls ``timer``, so cancellation takes effect as
+    /// promptly as the timeout does. The framing layer polls the timer before each attempt to read a
+    /// byte, so a receive through a UART channel is cancelled within about one byte read. Once the last
+    /// byte of a message has been read, the receive finishes, including any decryption by channels in
+    /// the crypto layer, before the flag is checked again. If ``cancel`` is already set, this returns
+    /// without receiving anything.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::Cancelled`] - ``cancel`` was set before a complete message was received.
+    ///
+    /// This function can also return any error from [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_cancellable<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+        cancel: &AtomicBool,
+    ) -> Result<usize> {
+        let mut timer = CancellableTimer { timer, cancel };
+
+        if timer.poll() {
+            return Err(timer.error());
+        }
+
+        self.recv_with_timeout(dest, &mut timer)
+            .map_err(|e| match e {
+                CommunicationError::Timeout => timer.error(),
+                e => e,
+            })
+    }
+
+    /// Receives data from the channel with a deadline that grows with the message, so a long message gets
+    /// the time it needs without making the receive wait that long for a short or absent one. The receive
+    /// has ``base`` to finish, plus ``per_byte`` for each byte read so far, but never longer than the
+    /// duration of ``timer``, which caps the entire receive operation. ``timer`` is also the clock the
+    /// deadline is measured with, since this crate has no clock of its own. It's never reset, so it should
+    /// be a fresh timer. If it isn't, the time it has already counted comes off the cap.
+    ///
+    /// None of the framing protocols have a length header, so there is no declared length to scale the
+    /// deadline from once the header is parsed. Instead, the deadline is extended as bytes are read. Bytes
+    /// are counted each time the channel resets the timer in
+    /// [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout), which the framing layer does for
+    /// each byte read. This includes framing overhead and any garbage before the frame, so the duration of
+    /// ``timer`` is what bounds how long a peer can keep the receive going.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_scaled<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        base: Duration,
+        per_byte: Duration,
+        timer: &mut T,
+    ) -> Result<usize> {
+        let mut timer = ScaledTimer {
+            start: timer.elapsed(),
+            timer,
+            base,
+            per_byte,
+            bytes: 0,
+        };
+
+        self.recv_with_data_timeout(dest, &mut timer)
+    }
+
+    /// Receives data from the channel in the same way as [`recv_with_timeout`](RxChannel::recv_with_timeout),
+    /// also returning the number of raw bytes read from the underlying transport, such as a UART, whether or
+    /// not a message was received. This is for diagnostics. On a timeout, it shows how much of a frame
+    /// arrived, which helps tell a slow or noisy link from a silent one. The raw count includes framing
+    /// overhead and any garbage skipped before the frame, so it's usually larger than the message.
+    ///
+    /// The default implementation can't see the raw bytes, so it reports the number of bytes written to
+    /// ``dest`` upon success and zero upon an error. The UART channels and channels wrapping them override
+    /// this to report the real count.
+    ///
+    /// # ERRORS:
+    ///
+    /// The result contains the same errors as [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, Result<usize>) {
+        let res = self.recv_with_timeout(dest, timer);
+
+        (*res.as_ref().unwrap_or(&0), res)
+    }
+
+    /// Attempts to receive a message from the channel without blocking. Returns ``Ok(None)`` immediately
+    /// unless a complete message has already arrived and been buffered by the channel. Otherwise, the
+    /// message is received into ``dest`` and ``Ok(Some(n))`` is returned, where ``n`` is the number of
+    /// bytes written into ``dest``. Since the whole message has already arrived, this never waits for
+    /// data, so there is no timer. ``dest`` may be written to even if ``Ok(None)`` is returned.
+    ///
+    /// Only a channel that buffers received bytes in the background, such as UART1 with interrupt-driven
+    /// reception, can tell that a complete message has arrived without reading it. The default
+    /// implementation can't, so it always returns ``Ok(None)``. Use
+    /// [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout) with channels that don't override
+    /// this.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout),
+    /// apart from [`CommunicationError::Timeout`].
+    fn try_recv(&mut self, _dest: &mut [u8]) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
+    /// Receives a message that must exactly fill ``dest``. This is useful for fixed-size messages, where
+    /// any other length means the message is invalid. The provided time to block is for the entire receive
+    /// operation, like in [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    ///
+    /// Channels that need extra space in ``dest`` for metadata, such as channels in the crypto layer,
+    /// override this so that the message must exactly fill ``dest`` minus the space for the metadata.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - The message received wasn't exactly the expected length.
+    ///
+    /// This function can also return any error from [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_exact<T: Timer>(&mut self, dest: &mut [u8], timer: &mut T) -> Result<()> {
+        if self.recv_with_timeout(dest, timer)? != dest.len() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        Ok(())
+    }
+
+    /// Receives data into a [`Buffer`] in the same way as
+    /// [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout). The whole capacity of ``dest`` is
+    /// available to receive into, and upon success, the length of ``dest`` is set to the number of bytes
+    /// received, which is also returned. Upon an error, the length of ``dest`` is set to zero.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout).
+    fn recv_buffer_with_data_timeout<B: Buffer + ?Sized, T: Timer>(
+        &mut self,
+        dest: &mut B,
+        timer: &mut T,
+    ) -> Result<usize> {
+        let res = self.recv_with_data_timeout(dest.capacity_mut(), timer);
+        dest.set_filled_len(*res.as_ref().unwrap_or(&0));
+
+        res
+    }
+
+    /// Receives data into a [`Buffer`] in the same way as [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    /// The whole capacity of ``dest`` is available to receive into, and upon success, the length of ``dest``
+    /// is set to the number of bytes received, which is also returned. Upon an error, the length of ``dest``
+    /// is set to zero.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_buffer_with_timeout<B: Buffer + ?Sized, T: Timer>(
+        &mut self,
+        dest: &mut B,
+        timer: &mut T,
+    ) -> Result<usize> {
+        let res = self.recv_with_timeout(dest.capacity_mut(), timer);
+        dest.set_filled_len(*res.as_ref().unwrap_or(&0));
+
+        res
+    }
 }
 
 /// A channel to send data through. See the documentation for [`send`](TxChannel::send) for
 /// more info.
 pub trait TxChannel {
     /// Sends the data from ``src`` through the channel. Upon an error, a [`CommunicationError`]
-    /// is given.
+    /// is given. A channel may use ``src`` as scratch space while sending it, so its contents should be
+    /// considered garbage once this function returns.
     ///
     /// # ERRORS:
     ///
@@ -93,6 +321,263 @@ pub trait TxChannel {
     ///   - This can occur if some internal error happens. This should only occur if something is wrong
     ///     with the implementation.
     fn send(&mut self, src: &mut [u8]) -> Result<()>;
+
+    /// Blocks until all data sent through the channel has left the device. [`send`](TxChannel::send) can
+    /// return while data is still being transmitted, so this should be called before doing anything that
+    /// could cut off the transmission, such as signaling that a transaction is complete right before
+    /// power may be removed. Channels wrapping another [`TxChannel`] forward this to the wrapped channel.
+    ///
+    /// The default implementation does nothing, which is correct for channels that finish transmitting
+    /// before [`send`](TxChannel::send) returns.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`]
+    ///   - This could occur if any implementation-based error occurs while flushing the channel.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Sends the filled part of a [`Buffer`] through the channel in the same way as
+    /// [`send`](TxChannel::send).
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`send`](TxChannel::send).
+    fn send_buffer<B: Buffer + ?Sized>(&mut self, src: &mut B) -> Result<()> {
+        self.send(src.filled_mut())
+    }
+
+    /// Sends the concatenation of ``parts`` through the channel as one message, in the same way as
+    /// [`send`](TxChannel::send). This is useful for messages made of a header and a payload that
+    /// live in different places. The parts must total at most ``MAX_LEN`` bytes, which the caller
+    /// picks to fit the largest message it sends this way, such as with
+    /// ``ch.send_vectored::<64>(&[header, payload])``.
+    ///
+    /// The default implementation concatenates the parts into a buffer of ``MAX_LEN`` bytes on the
+    /// stack before sending, so ``MAX_LEN`` should be no larger than needed. Channels in the framing
+    /// layer override this to write each part into the frame without copying, so they never use the
+    /// buffer. Channels in the crypto layer, which need a contiguous mutable buffer to encrypt in,
+    /// copy the parts into a buffer of ``MAX_LEN`` bytes as well.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`]
+    ///   - The parts total more than ``MAX_LEN`` bytes.
+    ///   - The channel is in the framing layer and there are more than [`MAX_VECTORED_PARTS`] parts.
+    ///
+    /// This function can also return any error from [`send`](TxChannel::send).
+    fn send_vectored<const MAX_LEN: usize>(&mut self, parts: &[&[u8]]) -> Result<()> {
+        let mut buff = [0; MAX_LEN];
+        let mut len = 0;
+
+        for part in parts {
+            buff.get_mut(len..len + part.len())
+                .ok_or(CommunicationError::SendError)?
+                .copy_from_slice(part);
+            len += part.len();
+        }
+
+        self.send(&mut buff[..len])
+    }
+
+    /// Sends the data from ``src`` through the channel, retrying with exponential backoff if the send
+    /// fails with [`CommunicationError::SendError`] or [`CommunicationError::Timeout`], such as when the
+    /// other device is momentarily unresponsive. At most ``attempts`` sends are made, and at least one is
+    /// always made. Any other error, including [`CommunicationError::InternalError`] and
+    /// [`CommunicationError::SequenceExhausted`], is returned right away without retrying, since trying
+    /// again won't fix it. An empty ``src`` or one longer than ``MAX_LEN`` can never be sent, so it's
+    /// refused with [`CommunicationError::SendError`] before the first attempt.
+    ///
+    /// The wait before the first retry is between half of ``base_delay`` and ``base_delay``, and the wait
+    /// doubles before each retry after that. Where a wait falls in that range is picked with ``jitter``,
+    /// which should give random numbers so that two devices retrying at the same time don't stay in lock
+    /// step. ``idle`` is called repeatedly while waiting, so a watchdog can be fed during long waits. On
+    /// the car and fob, ``communication::send_retry()`` in the no_std util crate fills both in, using
+    /// the secondary CSPRNG for the jitter and feeding the watchdog while idle.
+    ///
+    /// The provided timer is the deadline for the whole operation and is used to measure the waits. A
+    /// retry is only made if its wait ends before the deadline.
+    ///
+    /// Each attempt sends ``src`` with [`send_vectored`](TxChannel::send_vectored), which never modifies
+    /// ``src``, since channels in the crypto layer encrypt the buffer given to [`send`](TxChannel::send)
+    /// in place, so a retry of that buffer would send garbage. ``MAX_LEN`` is passed on to it, so ``src``
+    /// must be at most ``MAX_LEN`` bytes long, and channels that copy the parts use a buffer of that size.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - ``src`` is empty or longer than ``MAX_LEN`` bytes.
+    /// - [`CommunicationError::Timeout`] - A send failed and the wait before retrying wouldn't end before
+    ///   the deadline.
+    ///
+    /// This function can also return the error from the last attempt of
+    /// [`send_vectored`](TxChannel::send_vectored).
+    fn send_retry<const MAX_LEN: usize, T: Timer>(
+        &mut self,
+        src: &[u8],
+        attempts: u32,
+        base_delay: Duration,
+        timer: &mut T,
+        mut jitter: impl FnMut() -> u32,
+        mut idle: impl FnMut(),
+    ) -> Result<()> {
+        if src.is_empty() || src.len() > MAX_LEN {
+            return Err(CommunicationError::SendError);
+        }
+
+        let mut delay = base_delay;
+
+        for _ in 1..attempts.max(1) {
+            match self.send_vectored::<MAX_LEN>(&[src]) {
+                Err(CommunicationError::SendError | CommunicationError::Timeout) => {}
+                res => return res,
+            }
+
+            // Scale half of the delay by a random fraction and add it to the other half.
+            let half = delay / 2;
+            let extra_nanos = (half.as_nanos() * jitter() as u128) >> u32::BITS;
+            let wait = half + Duration::from_nanos(extra_nanos.min(u64::MAX as u128) as u64);
+
+            if wait >= timer.remaining() {
+                return Err(CommunicationError::Timeout);
+            }
+
+            let wait_until = timer.elapsed() + wait;
+
+            while timer.elapsed() < wait_until {
+                idle();
+            }
+
+            delay = delay.saturating_mul(2);
+        }
+
+        self.send_vectored::<MAX_LEN>(&[src])
+    }
+}
+
+/// A buffer that can be sent from and received into with [`TxChannel::send_buffer`],
+/// [`RxChannel::recv_buffer_with_timeout`], and [`RxChannel::recv_buffer_with_data_timeout`]. A buffer
+/// has a capacity and a filled length, so growable buffers such as [`heapless::Vec`] only send the bytes
+/// they hold and have their length set to the number of bytes received. Byte slices and arrays are
+/// always completely filled, so their whole length is sent and their length is never changed.
+pub trait Buffer {
+    /// Gets the filled part of the buffer, which is what gets sent.
+    fn filled_mut(&mut self) -> &mut [u8];
+
+    /// Gets the whole capacity of the buffer to receive into. This may grow the buffer to its capacity.
+    fn capacity_mut(&mut self) -> &mut [u8];
+
+    /// Sets the length of the filled part of the buffer after receiving ``len`` bytes into the slice from
+    /// [`capacity_mut`](Buffer::capacity_mut). ``len`` is never greater than the length of that slice.
+    fn set_filled_len(&mut self, len: usize);
+}
+
+impl Buffer for [u8] {
+    fn filled_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn capacity_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn set_filled_len(&mut self, _len: usize) {}
+}
+
+impl<const N: usize> Buffer for [u8; N] {
+    fn filled_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn capacity_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn set_filled_len(&mut self, _len: usize) {}
+}
+
+impl<const N: usize> Buffer for heapless::Vec<u8, N> {
+    fn filled_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn capacity_mut(&mut self) -> &mut [u8] {
+        // This can't fail since the length is never greater than the capacity.
+        let _ = self.resize(N, 0);
+
+        self
+    }
+
+    fn set_filled_len(&mut self, len: usize) {
+        self.truncate(len);
+    }
+}
+
+/// A [`Timer`] that's also up once a cancel flag is set, used by [`RxChannel::recv_cancellable`].
+struct CancellableTimer<'a, T: Timer> {
+    timer: &'a mut T,
+    cancel: &'a AtomicBool,
+}
+
+impl<T: Timer> CancellableTimer<'_, T> {
+    /// Gets the error to return once this timer is up, depending on whether it was cancelled.
+    fn error(&self) -> CommunicationError {
+        if self.cancel.load(Ordering::Acquire) {
+            CommunicationError::Cancelled
+        } else {
+            CommunicationError::Timeout
+        }
+    }
+}
+
+impl<T: Timer> Timer for CancellableTimer<'_, T> {
+    fn poll(&mut self) -> bool {
+        self.cancel.load(Ordering::Acquire) || self.timer.poll()
+    }
+
+    fn reset(&mut self) {
+        self.timer.reset();
+    }
+
+    fn duration(&self) -> Duration {
+        self.timer.duration()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.timer.elapsed()
+    }
+}
+
+/// A [`Timer`] whose duration grows by a set amount each time it's reset, up to the duration of the
+/// wrapped timer, used by [`RxChannel::recv_scaled`]. Each reset counts as a byte read instead of
+/// restarting the timer. The wrapped timer is never reset, so the time elapsed since ``start`` is the
+/// real time the receive has taken.
+struct ScaledTimer<'a, T: Timer> {
+    timer: &'a mut T,
+    start: Duration,
+    base: Duration,
+    per_byte: Duration,
+    bytes: u32,
+}
+
+impl<T: Timer> Timer for ScaledTimer<'_, T> {
+    fn poll(&mut self) -> bool {
+        self.timer.poll() || self.elapsed() >= self.duration()
+    }
+
+    fn reset(&mut self) {
+        self.bytes = self.bytes.saturating_add(1);
+    }
+
+    fn duration(&self) -> Duration {
+        self.base
+            .saturating_add(self.per_byte.saturating_mul(self.bytes))
+            .min(self.timer.duration().saturating_sub(self.start))
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.timer.elapsed().saturating_sub(self.start)
+    }
 }
 
 /// The possible errors that can occur while sending or receiving data through an [`RxChannel`] or a
@@ -109,4 +594,39 @@ pub enum CommunicationError {
 
     /// An error that can occur if an internal error is encountered that should never happen.
     InternalError,
+
+    /// An error that occurs during a receive operation if the timer elapses before a complete message is
+    /// received. This is expected when polling for a message that may never come. See
+    /// [RxChannel::recv_with_timeout] and [RxChannel::recv_with_data_timeout] for more details.
+    Timeout,
+
+    /// An error that occurs during a receive operation if the destination buffer is too small to hold the
+    /// message. See [RxChannel::recv_with_timeout] and [RxChannel::recv_with_data_timeout] for more details.
+    BufferTooSmall {
+        /// The length the destination buffer needs to be to receive the message, including the space
+        /// needed for any metadata.
+        needed: usize,
+    },
+
+    /// An error that occurs during a receive operation if an authenticated message was received that was
+    /// already received before or is older than the last message received.
+    ReplayDetected,
+
+    /// An error that occurs during a receive operation if it was cancelled before a complete message was
+    /// received. See [RxChannel::recv_cancellable] for more details.
+    Cancelled,
+
+    /// An error that occurs during a receive operation if the hardware reported a physical-layer error,
+    /// such as a framing, parity, break, or overrun error, for a byte of the message. The corrupted byte is
+    /// discarded instead of being decoded, so the rest of the message can't be trusted.
+    LineError,
+
+    /// An error that occurs if an operation that only makes sense with a secret key, such as binding
+    /// associated data to a message, is attempted on a channel that was created without one, such as a
+    /// UART controller created with ``without_key()``. Nothing is sent or received.
+    KeyNotSet,
+
+    /// An error that occurs during a send operation if the channel has used up every sequence number for
+    /// its key. Nothing more can be sent until the key is changed, which restarts the sequence numbers.
+    SequenceExhausted,
 }
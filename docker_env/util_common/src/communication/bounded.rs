@@ -0,0 +1,127 @@
+//! This module contains the [`BoundedChannel`] wrapper, which enforces a maximum message size chosen at
+//! compile time.
+
+use super::{CommunicationError, Result, RxChannel, TxChannel};
+use crate::timer::Timer;
+use core::sync::atomic::AtomicBool;
+
+/// A wrapper around a channel that rejects any message longer than ``MAX_FRAME`` bytes, so each build can
+/// pick its own bound, such as small messages on the fob and larger ones on the car. The bound applies to
+/// the messages given to and returned by the wrapped channel, which is the same quantity in both
+/// directions. Wrapping a crypto layer channel bounds the plaintext, not the plaintext plus its metadata,
+/// so a message that can be sent on one end can always be received on the other.
+///
+/// The framing protocols have no length header and don't buffer frames themselves, so this costs no RAM.
+/// Sending a message longer than ``MAX_FRAME`` always fails with [`CommunicationError::SendError`] without
+/// sending anything. Receiving a longer message always fails with
+/// [`CommunicationError::BufferTooSmall`], and whatever of it was written to ``dest`` is cleared. A
+/// ``needed`` length greater than ``MAX_FRAME`` means that the message can never be received on this
+/// channel, no matter how big ``dest`` is.
+pub struct BoundedChannel<C, const MAX_FRAME: usize> {
+    channel: C,
+}
+
+impl<C, const MAX_FRAME: usize> BoundedChannel<C, MAX_FRAME> {
+    /// Creates a new [`BoundedChannel`] wrapping the provided channel.
+    pub fn new(channel: C) -> Self {
+        Self { channel }
+    }
+
+    /// Gets a mutable reference to the wrapped channel. Messages sent and received through it directly
+    /// aren't bounded.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.channel
+    }
+
+    /// Consumes the [`BoundedChannel`], returning the wrapped channel.
+    pub fn into_inner(self) -> C {
+        self.channel
+    }
+
+    /// Checks the length of a message received into ``dest``, clearing it and returning
+    /// [`CommunicationError::BufferTooSmall`] if it's longer than ``MAX_FRAME`` bytes.
+    fn check(dest: &mut [u8], len: usize) -> Result<usize> {
+        if len > MAX_FRAME {
+            let received = len.min(dest.len());
+            dest[..received].fill(0);
+
+            return Err(CommunicationError::BufferTooSmall { needed: len });
+        }
+
+        Ok(len)
+    }
+}
+
+impl<C: RxChannel, const MAX_FRAME: usize> RxChannel for BoundedChannel<C, MAX_FRAME> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> Result<usize> {
+        let len = self.channel.recv_with_data_timeout(dest, timer)?;
+
+        Self::check(dest, len)
+    }
+
+    fn recv_with_timeout<T: Timer>(&mut self, dest: &mut [u8], timer: &mut T) -> Result<usize> {
+        let len = self.channel.recv_with_timeout(dest, timer)?;
+
+        Self::check(dest, len)
+    }
+
+    fn recv_until<T: Timer>(&mut self, dest: &mut [u8], deadline: &mut T) -> Result<usize> {
+        let len = self.channel.recv_until(dest, deadline)?;
+
+        Self::check(dest, len)
+    }
+
+    fn recv_cancellable<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+        cancel: &AtomicBool,
+    ) -> Result<usize> {
+        let len = self.channel.recv_cancellable(dest, timer, cancel)?;
+
+        Self::check(dest, len)
+    }
+
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, Result<usize>) {
+        let (progress, res) = self.channel.recv_progress(dest, timer);
+
+        (progress, res.and_then(|len| Self::check(dest, len)))
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> Result<Option<usize>> {
+        match self.channel.try_recv(dest)? {
+            Some(len) => Self::check(dest, len).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<C: TxChannel, const MAX_FRAME: usize> TxChannel for BoundedChannel<C, MAX_FRAME> {
+    fn send(&mut self, src: &mut [u8]) -> Result<()> {
+        if src.len() > MAX_FRAME {
+            return Err(CommunicationError::SendError);
+        }
+
+        self.channel.send(src)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.channel.flush()
+    }
+
+    fn send_vectored<const MAX_LEN: usize>(&mut self, parts: &[&[u8]]) -> Result<()> {
+        if parts.iter().map(|part| part.len()).sum::<usize>() > MAX_FRAME {
+            return Err(CommunicationError::SendError);
+        }
+
+        self.channel.send_vectored::<MAX_LEN>(parts)
+    }
+}
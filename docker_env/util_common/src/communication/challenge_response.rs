@@ -0,0 +1,77 @@
+//! This module contains a challenge-response authentication scheme for proving possession of a
+//! shared key.
+//!
+//! The verifier issues a random [`Challenge`] with [`issue_challenge()`] and sends its bytes to the
+//! prover, which computes a response with [`respond()`]. The verifier then checks the response with
+//! [`verify_response()`]. Responses are a keyed SHA3-256 hash of the challenge, so they can only be
+//! computed with the shared key and are only valid for the challenge they were computed for.
+//!
+//! [`verify_response()`] consumes the [`Challenge`], so each issued challenge can be verified at most
+//! once, and a response to an earlier challenge is rejected because it was computed over different
+//! bytes. The caller is still responsible for never reusing a challenge, such as by sending the same
+//! challenge bytes again, and for issuing a new challenge for each authentication attempt.
+
+use super::lower_layers::crypto::RandomSource;
+use crate::constant_time_eq;
+use sha3::{Digest, Sha3_256};
+use zeroize::Zeroize;
+
+/// The size of a challenge in bytes.
+pub const CHALLENGE_SIZE: usize = 32;
+
+/// The size of a response in bytes.
+pub const RESPONSE_SIZE: usize = 32;
+
+/// The size of the shared key in bytes.
+pub const CHALLENGE_KEY_SIZE: usize = 32;
+
+/// The label hashed before the key and challenge, to keep responses from being valid hashes for any
+/// other use of the same key.
+const RESPONSE_LABEL: &[u8] = b"ucsc-ectf-2023 challenge response";
+
+/// A challenge issued by [`issue_challenge()`]. This can't be copied or cloned so that it can only be
+/// verified once with [`verify_response()`].
+pub struct Challenge([u8; CHALLENGE_SIZE]);
+
+impl Challenge {
+    /// Returns the bytes of the challenge to send to the prover.
+    pub fn as_bytes(&self) -> &[u8; CHALLENGE_SIZE] {
+        &self.0
+    }
+}
+
+/// Issues a new random challenge.
+pub fn issue_challenge<R: RandomSource>(random_source: &mut R) -> Challenge {
+    let mut challenge = [0; CHALLENGE_SIZE];
+    random_source.fill_rand_slice(&mut challenge);
+
+    Challenge(challenge)
+}
+
+/// Computes the response to a challenge with the shared key.
+pub fn respond(
+    challenge: &[u8; CHALLENGE_SIZE],
+    key: &[u8; CHALLENGE_KEY_SIZE],
+) -> [u8; RESPONSE_SIZE] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(RESPONSE_LABEL);
+    hasher.update(key);
+    hasher.update(challenge);
+
+    hasher.finalize().into()
+}
+
+/// Verifies a response to a challenge, returning whether the response was computed with the shared
+/// key over this challenge. The comparison is done in constant time. The challenge is consumed, so
+/// a new challenge must be issued for the next attempt.
+pub fn verify_response(
+    challenge: Challenge,
+    key: &[u8; CHALLENGE_KEY_SIZE],
+    response: &[u8; RESPONSE_SIZE],
+) -> bool {
+    let mut expected = respond(&challenge.0, key);
+    let valid = constant_time_eq(&expected, response);
+    expected.zeroize();
+
+    valid
+}
@@ -0,0 +1,123 @@
+//! This module contains the [`DuplexChannel`] wrapper, which combines the sending and receiving halves
+//! of a bidirectional channel to provide request-response exchanges.
+
+use super::{Result, RxChannel, TxChannel};
+use crate::timer::Timer;
+use core::sync::atomic::AtomicBool;
+
+/// A wrapper around a channel that can both send and receive data, such as a UART controller. This
+/// provides [`request_response`](DuplexChannel::request_response) to send a request and wait for its
+/// reply in one call. The wrapped channel can still be used directly through the [`RxChannel`] and
+/// [`TxChannel`] implementations of this struct.
+pub struct DuplexChannel<C: RxChannel + TxChannel> {
+    channel: C,
+}
+
+impl<C: RxChannel + TxChannel> DuplexChannel<C> {
+    /// Creates a new [`DuplexChannel`] wrapping the provided channel.
+    pub fn new(channel: C) -> Self {
+        Self { channel }
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.channel
+    }
+
+    /// Consumes the [`DuplexChannel`], returning the wrapped channel.
+    pub fn into_inner(self) -> C {
+        self.channel
+    }
+
+    /// Sends the request in ``req`` and then waits for one reply, putting the reply into ``resp`` and
+    /// returning the number of bytes written to it upon success. ``resp`` is cleared before anything is
+    /// sent so that data from a previous exchange can never be mistaken for the reply. The provided timer
+    /// is reset once the request has been sent, so the timeout only applies to receiving the reply, and
+    /// it's for the entire receive operation.
+    ///
+    /// Nothing received while the request is being sent is discarded, but nothing is received then
+    /// either, so any part of the reply that arrives early is only kept if the wrapped channel buffers
+    /// it. The UART channels only have their 16-byte receive FIFO for this, so if more than 16 bytes of
+    /// the reply arrive before the request has been sent, the rest of them are lost and receiving the
+    /// reply fails. This doesn't happen with peers that only reply once they've received the whole
+    /// request, since sending returns as soon as the last byte is queued. Note that channels in the
+    /// crypto layer encrypt ``req`` in place, so its contents should be considered garbage once this
+    /// function returns.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns any error from [`TxChannel::send`] if the request couldn't be sent, in
+    /// which case no reply is waited for. Otherwise, it returns any error from
+    /// [`RxChannel::recv_with_timeout`].
+    pub fn request_response<T: Timer>(
+        &mut self,
+        req: &mut [u8],
+        resp: &mut [u8],
+        timer: &mut T,
+    ) -> Result<usize> {
+        resp.fill(0);
+
+        self.channel.send(req)?;
+
+        timer.reset();
+
+        self.channel.recv_with_timeout(resp, timer)
+    }
+}
+
+impl<C: RxChannel + TxChannel> RxChannel for DuplexChannel<C> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> Result<usize> {
+        self.channel.recv_with_data_timeout(dest, timer)
+    }
+
+    fn recv_with_timeout<T: Timer>(&mut self, dest: &mut [u8], timer: &mut T) -> Result<usize> {
+        self.channel.recv_with_timeout(dest, timer)
+    }
+
+    fn recv_until<T: Timer>(&mut self, dest: &mut [u8], deadline: &mut T) -> Result<usize> {
+        self.channel.recv_until(dest, deadline)
+    }
+
+    fn recv_cancellable<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+        cancel: &AtomicBool,
+    ) -> Result<usize> {
+        self.channel.recv_cancellable(dest, timer, cancel)
+    }
+
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, Result<usize>) {
+        self.channel.recv_progress(dest, timer)
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> Result<Option<usize>> {
+        self.channel.try_recv(dest)
+    }
+
+    fn recv_exact<T: Timer>(&mut self, dest: &mut [u8], timer: &mut T) -> Result<()> {
+        self.channel.recv_exact(dest, timer)
+    }
+}
+
+impl<C: RxChannel + TxChannel> TxChannel for DuplexChannel<C> {
+    fn send(&mut self, src: &mut [u8]) -> Result<()> {
+        self.channel.send(src)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.channel.flush()
+    }
+
+    fn send_vectored<const MAX_LEN: usize>(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.channel.send_vectored::<MAX_LEN>(parts)
+    }
+}
@@ -12,17 +12,270 @@
 //! Each message sent will contain a 24-byte nonce, a 16-byte authentication tag, and the
 //! ciphertext given a 32-byte symmetric key to encrypt and decrypt communications. The
 //! authentication tag provided will be checked against the message body to prevent message tampering.
-//! This means that any buffers used to received messages from an [`XChacha20Poly1305RxChannel`] must
-//! have enough space to store the additional metadata, totaling 40 bytes. This is stored in the
-//! constant ``XChacha20Poly1305RxChannel::METADATA_SIZE``. This channel requires random number
-//! generation. Because of this, it requires a [`RandomSource`].
+//! Each message also carries an 8-byte sequence number, which is sent in the clear and authenticated
+//! as associated data, so the receiving channel can optionally use it to reject replayed messages.
+//! Messages are encrypted and decrypted in place. This means that any buffers used to received
+//! messages from an [`XChacha20Poly1305RxChannel`] must have enough space to store the additional
+//! metadata, totaling 48 bytes. This is stored in the constant
+//! ``XChacha20Poly1305RxChannel::METADATA_SIZE``. This channel requires random number generation.
+//! Because of this, it requires a [`RandomSource`]. The first byte of each nonce is its [`NonceMode`]
+//! and the other 23 bytes are random. The sending channel remembers the random part of its last few
+//! nonces and refuses to send if one repeats, since that means the [`RandomSource`] is broken. Where
+//! the [`RandomSource`] can't be trusted, such as early in boot, the sending channel can instead be
+//! created with [`NonceMode::Synthetic`], which derives the random part from the message, so a reused
+//! nonce doesn't break confidentiality.
+//!
+//! ## [`Aes128GcmRxChannel`] and [`Aes128GcmTxChannel`]
+//! These channels provide the same guarantees and interface as the XChacha20Poly1305 channels, but use
+//! AES-128-GCM with a 16-byte [`Aes128GcmKey`] for interoperability with tooling that expects it. Each
+//! message sent will contain a 12-byte nonce followed by the ciphertext and a 16-byte authentication tag,
+//! totaling [`AES_128_GCM_METADATA_SIZE`] bytes of metadata along with the sequence number. There's
+//! no [`NonceMode`] byte, so all 96 bits of each nonce are random and [`NonceMode::Synthetic`] isn't
+//! available. Since the sequence number isn't part of the nonce, restarting it after a reboot doesn't
+//! risk reusing a nonce, and the sending channel checks its recent nonces for repeats in the same
+//! way. A random 96-bit nonce is only unlikely to repeat for so many messages though, so a key used
+//! with these channels shouldn't encrypt more than 2^32 messages.
+//!
+//! ## [`MacOnlyRxChannel`] and [`MacOnlyTxChannel`]
+//! These channels provide message integrity without confidentiality, for messages that aren't secret
+//! but mustn't be spoofed. The payload is sent in the clear, followed by an 8-byte sequence number and a
+//! 16-byte tag, totaling [`MAC_METADATA_SIZE`] bytes of metadata. The tag is a keyed SHA3-256 hash of the
+//! sequence number and payload with a 32-byte [`MacKey`], and it's checked in constant time. The
+//! sequence number works in the same way as in the AEAD channels, including replay protection, but no
+//! [`RandomSource`] is needed.
+//!
+//! ## Other ciphers
+//! Both pairs of channels above are type aliases of the generic [`AeadRxChannel`] and [`AeadTxChannel`],
+//! which work with any cipher implementing [`ChannelAead`]. [`AeadChannel`] combines both directions into
+//! one channel.
 //!
 //! See the documentation for [`communication`](crate::communication) for a description of the BogoStack
 //! and more info on the other layers of the BogoStack.
 
+mod aead;
+mod aes128gcm;
 mod chachapoly1305;
+mod mac;
 
+pub use self::aead::*;
+pub use aes128gcm::*;
 pub use chachapoly1305::*;
+pub use mac::*;
+
+use super::framing::crc::CRC_SIZE;
+use crate::communication::{self, CommunicationError};
+use chacha20poly1305::aead::{Key, KeyInit, KeySizeUser};
+use rand_core::CryptoRng;
+use zeroize::Zeroize;
+
+/// The size of the sequence number before each AEAD ciphertext and after each MAC payload.
+const SEQUENCE_NUMBER_SIZE: usize = 8;
+
+/// The maximum number of bytes of associated data that can be given to
+/// [`AeadTxChannel::send_with_aad`] and the matching receive functions. The sequence number is
+/// authenticated along with the associated data, so both are put together in a buffer of this size plus
+/// 8 bytes on the stack.
+pub const MAX_AAD_SIZE: usize = 64;
+
+/// Gets the smallest ``dest`` that can receive a message with up to ``payload_max`` bytes of plaintext
+/// on an [`XChacha20Poly1305RxChannel`] wrapping a BogoFraming or COBS channel. This is the payload plus
+/// the [`METADATA_SIZE`] of 48 bytes, which is the 8-byte sequence number, the 24-byte nonce, and the
+/// 16-byte tag. BogoFraming and COBS add nothing, since they're decoded as each byte is received, so
+/// their encoding and delimiters never take space in ``dest``. At least one byte of payload is always
+/// needed, so a ``payload_max`` of 0 is treated as 1.
+///
+/// The CRC and fragmentation layers do take space:
+///
+/// - A [`CrcFramedRxChannel`](super::framing::crc::CrcFramedRxChannel) under the crypto channel
+///   receives its CRC into ``dest``, so it needs [`CRC_SIZE`] more bytes. Use [`min_crc_recv_buffer()`]
+///   for it instead.
+/// - A [`ReassemblingRxChannel`](super::fragmentation::ReassemblingRxChannel) over the crypto channel
+///   sends each fragment as its own message, header included. ``payload_max`` is then the fragment
+///   size, and the largest message has [`FIRST_FRAGMENT_HEADER_SIZE`](super::fragmentation::FIRST_FRAGMENT_HEADER_SIZE)
+///   fewer bytes than that in its first fragment and [`FRAGMENT_HEADER_SIZE`](super::fragmentation::FRAGMENT_HEADER_SIZE)
+///   fewer in every other fragment.
+///
+/// Since this is a ``const fn``, a buffer that's too small can be made a build error where it's
+/// declared:
+///
+/// ```ignore
+/// const MAX_PAYLOAD: usize = 64;
+/// const RECV_BUFFER_SIZE: usize = 112;
+///
+/// const _: () = assert!(RECV_BUFFER_SIZE >= min_recv_buffer(MAX_PAYLOAD));
+/// ```
+///
+/// The other channels need [`AES_128_GCM_METADATA_SIZE`] or [`MAC_METADATA_SIZE`] bytes on top of the
+/// payload instead.
+pub const fn min_recv_buffer(payload_max: usize) -> usize {
+    let payload_max = if payload_max == 0 { 1 } else { payload_max };
+
+    payload_max.saturating_add(METADATA_SIZE)
+}
+
+/// Gets the smallest ``dest`` that can receive a message with up to ``payload_max`` bytes of plaintext
+/// on an [`XChacha20Poly1305RxChannel`] wrapping a
+/// [`CrcFramedRxChannel`](super::framing::crc::CrcFramedRxChannel). This is [`min_recv_buffer()`] plus
+/// the [`CRC_SIZE`] of 2 bytes.
+pub const fn min_crc_recv_buffer(payload_max: usize) -> usize {
+    min_recv_buffer(payload_max).saturating_add(CRC_SIZE)
+}
+
+/// A key stored by a channel. This is wiped when it's dropped or replaced. Cipher instances are only
+/// created from it for the duration of one encryption or decryption so that the only long-lived copy
+/// of a key is the wiped [`StoredKey`]. The ciphers wipe their own copies of the key when they're
+/// dropped.
+struct StoredKey<A: KeySizeUser>(Key<A>);
+
+impl<A: KeySizeUser> StoredKey<A> {
+    /// Copies a key into a [`StoredKey`].
+    fn new(key: &Key<A>) -> Self {
+        Self(key.clone())
+    }
+}
+
+impl<A: KeyInit> StoredKey<A> {
+    /// Creates a cipher instance from this key.
+    fn cipher(&self) -> A {
+        A::new(&self.0)
+    }
+}
+
+impl<A: KeySizeUser> Drop for StoredKey<A> {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+/// Generates the sequence numbers of a sending channel. Each sequence number is
+/// [`SEQUENCE_NUMBER_SIZE`] bytes in big-endian byte order and is one more than the last.
+struct SequenceCounter {
+    sequence_number: u64,
+}
+
+impl SequenceCounter {
+    /// Creates a new [`SequenceCounter`] starting from sequence number 0.
+    fn new() -> Self {
+        Self { sequence_number: 0 }
+    }
+
+    /// Gets the next sequence number as big-endian bytes. The sequence number is used up even if the
+    /// message ends up not being sent so that it's never reused.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SequenceExhausted`] - Every sequence number has been used.
+    fn next_sequence_number(&mut self) -> communication::Result<[u8; SEQUENCE_NUMBER_SIZE]> {
+        let sequence_number = self.sequence_number.to_be_bytes();
+
+        self.sequence_number = self
+            .sequence_number
+            .checked_add(1)
+            .ok_or(CommunicationError::SequenceExhausted)?;
+
+        Ok(sequence_number)
+    }
+
+    /// Restarts the sequence from sequence number 0.
+    fn reset(&mut self) {
+        self.sequence_number = 0;
+    }
+}
+
+/// The default number of recent nonces an [`AeadTxChannel`] remembers to detect a broken random number
+/// generator. See [`AeadTxChannel`] for more details.
+pub const DEFAULT_NONCE_HISTORY: usize = 8;
+
+/// The fewest random bytes a nonce can have for a [`NonceHistory`] to check it. With fewer, a healthy
+/// random number generator repeats the random bytes often enough to make the check fail spuriously.
+const MIN_NONCE_HISTORY_RANDOM_SIZE: usize = 8;
+
+/// Remembers a fingerprint of the random part of the last ``N`` nonces generated by a sending channel,
+/// so a random number generator that starts repeating itself is caught before a nonce is reused with a
+/// different message. Each nonce takes 8 bytes.
+struct NonceHistory<const N: usize> {
+    fingerprints: [u64; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> NonceHistory<N> {
+    /// Creates a new, empty [`NonceHistory`].
+    fn new() -> Self {
+        Self {
+            fingerprints: [0; N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Remembers the random part of a nonce, returning ``false`` if it's the same as the random part of
+    /// a remembered nonce. The oldest nonce is forgotten once ``N`` nonces are remembered. Nonces with
+    /// fewer than [`MIN_NONCE_HISTORY_RANDOM_SIZE`] random bytes are always accepted.
+    fn insert(&mut self, random_bytes: &[u8]) -> bool {
+        if N == 0 || random_bytes.len() < MIN_NONCE_HISTORY_RANDOM_SIZE {
+            return true;
+        }
+
+        // Fold the random bytes into 64 bits, so nonces of any size take the same space.
+        let fingerprint = random_bytes.chunks(8).fold(0, |acc, chunk| {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            acc ^ u64::from_le_bytes(bytes)
+        });
+
+        if self.fingerprints[..self.len].contains(&fingerprint) {
+            return false;
+        }
+
+        self.fingerprints[self.next] = fingerprint;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+
+        true
+    }
+}
+
+/// Tracks the sequence numbers accepted by a receiving channel to reject replayed messages.
+struct ReplayGuard {
+    enabled: bool,
+    last_sequence_number: Option<u64>,
+}
+
+impl ReplayGuard {
+    /// Creates a new [`ReplayGuard`] with replay protection disabled.
+    fn new() -> Self {
+        Self {
+            enabled: false,
+            last_sequence_number: None,
+        }
+    }
+
+    /// Checks the big-endian sequence number of an authenticated message, returning whether the message
+    /// should be accepted. Every message is accepted when replay protection is disabled.
+    fn accept(&mut self, sequence_number: &[u8]) -> bool {
+        let mut sequence_number_bytes = [0; SEQUENCE_NUMBER_SIZE];
+        sequence_number_bytes.copy_from_slice(sequence_number);
+        let sequence_number = u64::from_be_bytes(sequence_number_bytes);
+
+        if !self.enabled {
+            return true;
+        }
+
+        if matches!(self.last_sequence_number, Some(last) if sequence_number <= last) {
+            return false;
+        }
+
+        self.last_sequence_number = Some(sequence_number);
+
+        true
+    }
+
+    /// Forgets the last accepted sequence number.
+    fn reset(&mut self) {
+        self.last_sequence_number = None;
+    }
+}
 
 /// Implemented for any channel that has encryption/decryption keys that can be changed after channel
 /// creation.
@@ -34,8 +287,11 @@ pub trait KeyedChannel {
     fn change_key(&mut self, new_key: &Self::KeyType);
 }
 
-/// Trait used for secure channels when they need random number generation.
-pub trait RandomSource {
+/// Trait used for secure channels when they need random number generation. The output is used for
+/// nonces, so it must be unpredictable. Only implement this for cryptographically secure sources that
+/// are seeded with fresh entropy, not for ones that repeat across reboots. Implementors must also
+/// implement [`CryptoRng`] to mark themselves as such.
+pub trait RandomSource: CryptoRng {
     /// Fills the provided slice with random bytes.
     fn fill_rand_slice<T: AsMut<[u8]>>(&mut self, slice_ref: T);
 }
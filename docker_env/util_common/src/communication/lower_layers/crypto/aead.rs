@@ -0,0 +1,1008 @@
+use super::{
+    KeyedChannel, NonceHistory, RandomSource, ReplayGuard, SequenceCounter, StoredKey,
+    DEFAULT_NONCE_HISTORY, MAX_AAD_SIZE, SEQUENCE_NUMBER_SIZE,
+};
+use crate::communication::{
+    self,
+    lower_layers::framing::{Frame, FramedTxChannel},
+    CommunicationError, RxChannel, Timer, TxChannel,
+};
+use chacha20poly1305::aead::{self, AeadInPlace, KeyInit};
+use sha3::{Digest, Sha3_256};
+use typenum::Unsigned;
+use zeroize::Zeroizing;
+
+/// The label hashed before the key and message when deriving a synthetic nonce, to keep synthetic
+/// nonces from being valid hashes for any other use of the same key.
+const SYNTHETIC_NONCE_LABEL: &[u8] = b"ucsc-ectf-2023 synthetic nonce";
+
+/// An AEAD cipher that can be used by an [`AeadRxChannel`] and an [`AeadTxChannel`]. Adding a new
+/// cipher only requires implementing this trait for it.
+pub trait ChannelAead: AeadInPlace + KeyInit {
+    /// Whether the nonce is sent before the ciphertext. If this is false, the nonce is sent after the
+    /// ciphertext. The tag is always sent last.
+    const NONCE_FIRST: bool;
+
+    /// Whether the first byte of each nonce is the [`NonceMode`]. This should only be set for ciphers
+    /// whose nonces have enough random bytes to spare one, such as XChacha20Poly1305, since the mode
+    /// byte takes the place of a random byte. Without it, every nonce is [`NonceMode::Random`].
+    const NONCE_MODE_BYTE: bool;
+}
+
+/// How an [`AeadTxChannel`] fills the part of each nonce after the mode byte.
+/// The mode is chosen when the channel is created and is sent as the first byte of every nonce, so it's
+/// authenticated along with the message. An [`AeadRxChannel`] accepts both modes, since decrypting
+/// only needs the nonce on the wire, and rejects a nonce with any other mode byte. Only ciphers with
+/// [`ChannelAead::NONCE_MODE_BYTE`] set have a mode byte, and the rest always use [`NonceMode::Random`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NonceMode {
+    /// The bytes come from the [`RandomSource`]. This is the default. If the random source repeats
+    /// itself, such as after a reboot early in boot before the random number generator is seeded, a
+    /// nonce is reused with different plaintexts, which breaks confidentiality and lets the
+    /// authentication key be recovered.
+    Random = 0,
+
+    /// The bytes are a synthetic IV, derived from a keyed SHA3-256 hash of the associated data, which
+    /// starts with the sequence number, and the plaintext, as in SIV mode. The random source isn't used.
+    /// A nonce is only reused if the same message is sent with the same sequence number and associated
+    /// data, which only reveals that the message was sent again, so this is safe when the random number
+    /// generator can't be trusted, such as while pairing early in boot.
+    ///
+    /// The extra pass hashes the whole plaintext before it's encrypted. SHA3-256 is several times
+    /// slower per byte than the ciphers on the Cortex-M4, so sending a long message costs several
+    /// times as much as in [`NonceMode::Random`]. Receiving costs the same in both modes. The synthetic
+    /// IV is as long as the random part of the nonce, which is 23 bytes with XChacha20Poly1305.
+    Synthetic = 1,
+}
+
+impl NonceMode {
+    /// Gets the mode that a mode byte stands for, or [`None`] if it doesn't stand for any mode.
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Random),
+            1 => Some(Self::Synthetic),
+            _ => None,
+        }
+    }
+}
+
+/// Fills ``dest`` with the synthetic IV of a message. See [`NonceMode::Synthetic`] for more details.
+/// Every input but the associated data and plaintext has a fixed size, and the length of the associated
+/// data is hashed before it, so no two messages are hashed the same way. A ``dest`` longer than the hash
+/// is padded with zeros.
+fn fill_synthetic_iv<A: ChannelAead>(
+    key: &StoredKey<A>,
+    aad: &[u8],
+    plaintext: &[u8],
+    dest: &mut [u8],
+) {
+    let mut hasher = Sha3_256::new();
+    hasher.update(SYNTHETIC_NONCE_LABEL);
+    hasher.update(&key.0);
+    hasher.update((aad.len() as u64).to_be_bytes());
+    hasher.update(aad);
+    hasher.update(plaintext);
+
+    dest.fill(0);
+    dest.iter_mut()
+        .zip(hasher.finalize())
+        .for_each(|(d, h)| *d = h);
+}
+
+/// The associated data authenticated with each message, which is its sequence number followed by the
+/// associated data given by the caller. The sequence number has a fixed size, so no two pairs of
+/// sequence number and associated data are authenticated the same way.
+struct SequencedAad {
+    buff: [u8; SEQUENCE_NUMBER_SIZE + MAX_AAD_SIZE],
+    len: usize,
+}
+
+impl SequencedAad {
+    /// Puts the sequence number before the associated data, or returns [`None`] if the associated data
+    /// is longer than [`MAX_AAD_SIZE`].
+    fn new(sequence_number: &[u8; SEQUENCE_NUMBER_SIZE], aad: &[u8]) -> Option<Self> {
+        let len = SEQUENCE_NUMBER_SIZE + aad.len();
+        let mut buff = [0; SEQUENCE_NUMBER_SIZE + MAX_AAD_SIZE];
+
+        buff.get_mut(SEQUENCE_NUMBER_SIZE..len)?
+            .copy_from_slice(aad);
+        buff[..SEQUENCE_NUMBER_SIZE].copy_from_slice(sequence_number);
+
+        Some(Self { buff, len })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.buff[..self.len]
+    }
+}
+
+/// This [`RxChannel`] wraps around another [`RxChannel`] to decrypt communications encrypted by an
+/// [`AeadTxChannel`] using the same cipher, providing message authenticity and confidentiality.
+/// Messages are decrypted in place in the receive buffer, so no scratch buffer is needed, but the
+/// buffer must have [`AeadRxChannel::METADATA_SIZE`] bytes of space for the sequence number, nonce, and
+/// tag. If a received message doesn't contain a nonce or authentication tag or has an invalid
+/// authentication tag, a [`CommunicationError::RecvError`] is given. If the underlying channel gives
+/// this error, it will be propagated up. Data sent and received through this channel must be at least
+/// 1 byte long.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - The message didn't contain a nonce of the right size,
+/// didn't match the authentication tag provided, didn't contain an authentication tag, or an error
+/// occurred while receiving the message from the wrapped channel.
+/// - [`CommunicationError::BufferTooSmall`] - The message couldn't be read into the buffer because it
+/// was too small. The needed length includes the [`AeadRxChannel::METADATA_SIZE`] bytes of space for the
+/// sequence number, nonce, and tag, so it's the length of the plaintext plus
+/// [`AeadRxChannel::METADATA_SIZE`].
+/// - [`CommunicationError::Timeout`] - The wrapped channel timed out before a complete message was received.
+/// - [`CommunicationError::ReplayDetected`] - Replay protection is enabled and the message's sequence
+/// number wasn't greater than the sequence number of the last message accepted.
+///
+/// The decryption key is stored in memory that's wiped when this channel is dropped or the key is changed.
+///
+/// See the [`module`](super) documentation for more information on the ciphers available.
+pub struct AeadRxChannel<A: ChannelAead, T: RxChannel> {
+    channel: T,
+    rx_key: StoredKey<A>,
+    replay_guard: ReplayGuard,
+    auth_failures: u32,
+}
+
+impl<A: ChannelAead, T: RxChannel> AeadRxChannel<A, T> {
+    const TAG_SIZE: usize = <A::TagSize as Unsigned>::USIZE;
+    const NONCE_SIZE: usize = <A::NonceSize as Unsigned>::USIZE;
+
+    /// The total metadata size required when receiving on this channel.
+    pub const METADATA_SIZE: usize = Self::TAG_SIZE + Self::NONCE_SIZE + SEQUENCE_NUMBER_SIZE;
+
+    /// Creates a new [`AeadRxChannel`] given an inner [`RxChannel`] and a decryption key.
+    pub fn new(channel: T, rx_key: &aead::Key<A>) -> Self {
+        Self {
+            channel,
+            rx_key: StoredKey::new(rx_key),
+            replay_guard: ReplayGuard::new(),
+            auth_failures: 0,
+        }
+    }
+
+    /// Gets a reference to the wrapped channel.
+    pub fn get_ref(&self) -> &T {
+        &self.channel
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.channel
+    }
+
+    /// Gets the number of received messages that failed to authenticate or were rejected as replays
+    /// since this channel was created. This wraps around on overflow.
+    pub fn auth_failures(&self) -> u32 {
+        self.auth_failures
+    }
+
+    /// Enables or disables replay protection, which is disabled by default. When enabled, a message is
+    /// rejected with [`CommunicationError::ReplayDetected`] unless its sequence number is greater than
+    /// the sequence number of the last message accepted. The sequence number is authenticated along with
+    /// the message, so it can't be changed without the message failing to authenticate. The last
+    /// accepted sequence number is kept for the lifetime of this channel and is only reset by changing
+    /// the key with [`KeyedChannel::change_key`].
+    ///
+    /// The sender's sequence number isn't stored anywhere that survives a reboot, so a sender that
+    /// reboots, or that's recreated or rekeyed, starts again from sequence number 0, and every message
+    /// it sends is rejected until this channel is rekeyed as well. Both sides must therefore change
+    /// their keys at the same points, such as at the start of every session, and the receiver must be
+    /// rekeyed whenever the sender may have restarted. Replay protection is left disabled by default
+    /// for channels where that can't be arranged, such as with host tools that create a new channel for
+    /// every run.
+    ///
+    /// Rekeying with the same key restarts the sequence numbers without changing anything else, so
+    /// messages recorded before the rekey are accepted again afterwards. Replay protection therefore
+    /// only protects a channel for as long as it keeps its key, and it doesn't help channels whose keys
+    /// are fixed for the lifetime of the device and set again for every session. Those need freshness
+    /// from their protocol instead, such as a random challenge.
+    pub fn set_replay_protection(&mut self, enabled: bool) {
+        self.replay_guard.enabled = enabled;
+    }
+
+    /// Receives data from the channel in the same way as [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout),
+    /// additionally authenticating the provided associated data. The associated data isn't transmitted, so
+    /// it must be the same as the associated data given to [`AeadTxChannel::send_with_aad`] by the sender
+    /// or the message will fail to authenticate.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout).
+    /// A [`CommunicationError::RecvError`] is also returned if the associated data doesn't match or is
+    /// longer than [`MAX_AAD_SIZE`], in which case nothing is received.
+    pub fn recv_with_data_timeout_and_aad<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        aad: &[u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        self.recv_with(dest, aad, |ch, d| {
+            ch.channel.recv_with_data_timeout(d, timer)
+        })
+    }
+
+    /// Receives data from the channel in the same way as [`recv_with_timeout`](RxChannel::recv_with_timeout),
+    /// additionally authenticating the provided associated data. The associated data isn't transmitted, so
+    /// it must be the same as the associated data given to [`AeadTxChannel::send_with_aad`] by the sender
+    /// or the message will fail to authenticate.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    /// A [`CommunicationError::RecvError`] is also returned if the associated data doesn't match or is
+    /// longer than [`MAX_AAD_SIZE`], in which case nothing is received.
+    pub fn recv_with_timeout_and_aad<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        aad: &[u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        self.recv_with(dest, aad, |ch, d| ch.channel.recv_with_timeout(d, timer))
+    }
+
+    /// Receives data from the channel in the same way as [`recv_with_timeout_and_aad`](Self::recv_with_timeout_and_aad),
+    /// padding the time taken by every failure so where the message was rejected can't be told from how
+    /// long the rejection took. Without padding, a message with a bad length or nonce mode is rejected as
+    /// soon as it's received, while a message that fails to authenticate is only rejected once it's been
+    /// decrypted.
+    ///
+    /// ``padding`` is reset as soon as the wrapped channel returns, whether it received a message or
+    /// rejected one, and any error is only returned once ``padding`` is up. Its duration is the padded
+    /// time, so it must be longer than the slowest rejection, which is authenticating the longest message
+    /// the buffer can hold, or the slowest rejections still take longer than the rest. A successful
+    /// receive returns as soon as it's done, since the message is accepted no matter how long it took.
+    ///
+    /// ```ignore
+    /// let mut timeout = rt.hib_controller.create_timer(Duration::from_secs(1));
+    /// let mut padding = rt.hib_controller.create_timer(Duration::from_millis(5));
+    ///
+    /// let len = channel.recv_with_timeout_padded(&mut buff, b"", &mut timeout, &mut padding)?;
+    /// ```
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_timeout_and_aad`](Self::recv_with_timeout_and_aad).
+    pub fn recv_with_timeout_padded<U: Timer, P: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        aad: &[u8],
+        timer: &mut U,
+        padding: &mut P,
+    ) -> communication::Result<usize> {
+        // The buffer can be rejected before anything is received, so the padding starts now as well.
+        padding.reset();
+
+        self.recv_with(dest, aad, |ch, d| {
+            let res = ch.channel.recv_with_timeout(d, timer);
+            padding.reset();
+
+            res
+        })
+        .map_err(|err| {
+            while !padding.poll() {}
+
+            err
+        })
+    }
+
+    /// Receives a message with ``read_fn`` and checks it against each key in turn, returning the length
+    /// of the plaintext and the index of the first key that authenticated it. Every key is checked
+    /// against the same ciphertext, even after one has matched, and a key that matches puts the
+    /// ciphertext back by encrypting the plaintext again. This way, the work done for each key doesn't
+    /// depend on whether an earlier key matched, and the total time taken doesn't depend on which key
+    /// matched. The message is only decrypted for good once every key has been checked.
+    ///
+    /// This must only be used with ciphers that leave the message as it was when it fails to
+    /// authenticate. Replay protection isn't applied, since each key has its own sequence numbers.
+    pub(super) fn recv_trial_keys(
+        &mut self,
+        keys: &[&aead::Key<A>],
+        dest: &mut [u8],
+        read_fn: impl FnOnce(&mut Self, &mut [u8]) -> communication::Result<usize>,
+    ) -> communication::Result<(usize, usize)> {
+        let dest = self.read_frame(dest, read_fn)?;
+        let mut matched = None;
+
+        if let Ok(parts) = FrameParts::<A>::split(dest) {
+            let aad = SequencedAad::new(&parts.sequence_number, b"")
+                .ok_or(CommunicationError::InternalError)?;
+            let ciphertext = &mut dest[parts.msg_start..parts.msg_start + parts.msg_len];
+
+            for (idx, key) in keys.iter().enumerate() {
+                let cipher = A::new(key);
+
+                if cipher
+                    .decrypt_in_place_detached(&parts.nonce, aad.as_slice(), ciphertext, &parts.tag)
+                    .is_ok()
+                {
+                    cipher
+                        .encrypt_in_place_detached(&parts.nonce, aad.as_slice(), ciphertext)
+                        .map_err(|_| CommunicationError::InternalError)?;
+                    matched = matched.or(Some(idx));
+                }
+            }
+        }
+
+        let Some(idx) = matched else {
+            self.auth_failures = self.auth_failures.wrapping_add(1);
+
+            return Err(CommunicationError::RecvError);
+        };
+
+        let (msg_len, _) = open_frame(&A::new(keys[idx]), dest, b"")?;
+
+        Ok((msg_len, idx))
+    }
+
+    /// Receives a message with ``read_fn``, returning the part of ``dest`` that the message was received
+    /// into once its length has been checked to hold metadata and at least one byte of message.
+    fn read_frame<'d>(
+        &mut self,
+        dest: &'d mut [u8],
+        read_fn: impl FnOnce(&mut Self, &mut [u8]) -> communication::Result<usize>,
+    ) -> communication::Result<&'d mut [u8]> {
+        // Check that the destination buffer has space for at least one byte of ciphertext.
+        if dest.len() <= Self::METADATA_SIZE {
+            return Err(CommunicationError::BufferTooSmall {
+                needed: Self::METADATA_SIZE + 1,
+            });
+        }
+
+        // Read message from inner channel.
+        let bytes_read = read_fn(self, dest)?;
+
+        // Never trust the length given by the inner channel. A misbehaving channel must not be able
+        // to make us read or write past the end of the destination buffer.
+        if bytes_read > dest.len() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        let dest = &mut dest[..bytes_read];
+
+        // Check we have at least one byte of message.
+        if dest.len() <= Self::METADATA_SIZE {
+            return Err(CommunicationError::RecvError);
+        }
+
+        Ok(dest)
+    }
+
+    fn recv_with(
+        &mut self,
+        dest: &mut [u8],
+        aad: &[u8],
+        read_fn: impl FnOnce(&mut Self, &mut [u8]) -> communication::Result<usize>,
+    ) -> communication::Result<usize> {
+        // Associated data that's too long can never authenticate, so the message is left unread.
+        if aad.len() > MAX_AAD_SIZE {
+            return Err(CommunicationError::RecvError);
+        }
+
+        let dest = self.read_frame(dest, read_fn)?;
+
+        // Any error from here on is the message failing to authenticate, since its length was checked.
+        let (msg_len, sequence_number) =
+            open_frame(&self.rx_key.cipher(), dest, aad).map_err(|e| {
+                self.auth_failures = self.auth_failures.wrapping_add(1);
+                e
+            })?;
+
+        // The sequence number is only trusted once the message has been authenticated.
+        if !self.replay_guard.accept(&sequence_number) {
+            // Don't leave any of the replayed plaintext in the buffer.
+            dest.fill(0);
+            self.auth_failures = self.auth_failures.wrapping_add(1);
+
+            return Err(CommunicationError::ReplayDetected);
+        }
+
+        Ok(msg_len)
+    }
+}
+
+/// Decrypts and authenticates a message sent by an [`AeadTxChannel`] in place in ``frame``, which must
+/// be the whole message as received from the framing layer. Upon success, the message is moved to the
+/// start of ``frame`` without its sequence number and its length is returned. This is what an
+/// [`AeadRxChannel`] does with each message it receives, apart from replay protection, but it needs no
+/// channel, so it can be fuzzed off-target.
+///
+/// No matter what ``frame`` contains, this never panics and never reads or writes outside of ``frame``.
+/// The nonce and tag are only read from inside ``frame`` once its length has been checked to be greater
+/// than the metadata size, so truncated nonces and tags are rejected instead of being read past the end.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - ``frame`` isn't long enough for a nonce, a tag, a sequence
+///   number, and at least one byte of message, the nonce has an unknown [`NonceMode`], ``aad`` is
+///   longer than [`MAX_AAD_SIZE`], or the message couldn't be authenticated with the key and associated
+///   data.
+pub fn decrypt_frame<A: ChannelAead>(
+    key: &aead::Key<A>,
+    frame: &mut [u8],
+    aad: &[u8],
+) -> communication::Result<usize> {
+    open_frame(&A::new(key), frame, aad).map(|(msg_len, _)| msg_len)
+}
+
+/// The nonce, sequence number, and tag of a message sent by an [`AeadTxChannel`], and where its
+/// ciphertext is. The sequence number isn't authenticated until the ciphertext has been decrypted.
+struct FrameParts<A: ChannelAead> {
+    nonce: aead::Nonce<A>,
+    sequence_number: [u8; SEQUENCE_NUMBER_SIZE],
+    tag: aead::Tag<A>,
+    msg_start: usize,
+    msg_len: usize,
+}
+
+impl<A: ChannelAead> FrameParts<A> {
+    /// Finds the nonce, sequence number, ciphertext, and tag of a message, copying out the nonce,
+    /// sequence number, and tag. See [`decrypt_frame`] for more details.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - ``frame`` isn't long enough for a nonce, a tag, a sequence
+    ///   number, and at least one byte of message, or the cipher has a mode byte and the nonce has an
+    ///   unknown [`NonceMode`].
+    fn split(frame: &[u8]) -> communication::Result<Self> {
+        let tag_size = <A::TagSize as Unsigned>::USIZE;
+        let nonce_size = <A::NonceSize as Unsigned>::USIZE;
+
+        // Check we have at least one byte of message after the sequence number.
+        if frame.len() <= tag_size + nonce_size + SEQUENCE_NUMBER_SIZE {
+            return Err(CommunicationError::RecvError);
+        }
+
+        // Find the nonce, sequence number, ciphertext, and tag. The sequence number is always right
+        // before the ciphertext, and the tag is always last.
+        let body_len = frame.len() - tag_size - nonce_size;
+        let (nonce_start, body_start) = if A::NONCE_FIRST {
+            (0, nonce_size)
+        } else {
+            (body_len, 0)
+        };
+        let msg_start = body_start + SEQUENCE_NUMBER_SIZE;
+        let msg_len = body_len - SEQUENCE_NUMBER_SIZE;
+
+        // Take nonce and tag
+        let mut nonce = aead::Nonce::<A>::default();
+        nonce.copy_from_slice(&frame[nonce_start..nonce_start + nonce_size]);
+
+        // The mode byte is authenticated along with the rest of the nonce, but an unknown mode is never
+        // accepted, so it's rejected before decrypting.
+        if A::NONCE_MODE_BYTE && NonceMode::from_byte(nonce[0]).is_none() {
+            return Err(CommunicationError::RecvError);
+        }
+        let mut sequence_number = [0; SEQUENCE_NUMBER_SIZE];
+        sequence_number.copy_from_slice(&frame[body_start..msg_start]);
+        let mut tag = aead::Tag::<A>::default();
+        tag.copy_from_slice(&frame[frame.len() - tag_size..]);
+
+        Ok(Self {
+            nonce,
+            sequence_number,
+            tag,
+            msg_start,
+            msg_len,
+        })
+    }
+}
+
+/// Decrypts a message in place with a cipher, returning the length of the message, which is moved to
+/// the start of ``frame``, and its sequence number. See [`decrypt_frame`] for more details.
+fn open_frame<A: ChannelAead>(
+    cipher: &A,
+    frame: &mut [u8],
+    aad: &[u8],
+) -> communication::Result<(usize, [u8; SEQUENCE_NUMBER_SIZE])> {
+    let FrameParts {
+        nonce,
+        sequence_number,
+        tag,
+        msg_start,
+        msg_len,
+    } = FrameParts::<A>::split(frame)?;
+    let aad = SequencedAad::new(&sequence_number, aad).ok_or(CommunicationError::RecvError)?;
+
+    // Decrypt in place using the ciphertext, nonce, tag, and associated data, which authenticates the
+    // sequence number as well.
+    cipher
+        .decrypt_in_place_detached(
+            &nonce,
+            aad.as_slice(),
+            &mut frame[msg_start..msg_start + msg_len],
+            &tag,
+        )
+        .map_err(|_| CommunicationError::RecvError)?;
+
+    // Move the plaintext to the beginning of our slice.
+    frame.copy_within(msg_start..msg_start + msg_len, 0);
+
+    Ok((msg_len, sequence_number))
+}
+
+impl<A: ChannelAead, T: RxChannel> KeyedChannel for AeadRxChannel<A, T> {
+    type KeyType = aead::Key<A>;
+
+    fn change_key(&mut self, new_key: &Self::KeyType) {
+        self.rx_key = StoredKey::new(new_key);
+        self.replay_guard.reset();
+    }
+}
+
+impl<A: ChannelAead, T: RxChannel> RxChannel for AeadRxChannel<A, T> {
+    /// Receives data from the channel, putting the data received into ``dest``, returning the
+    /// number of bytes written to it upon success. The buffer provided should have enough
+    /// space to store the data that needs to be received along with its metadata size. The provided timeout
+    /// is reset on each byte received. If the timeout has passed and not enough bytes have been received, this
+    /// function returns an error. Upon an error, a [`CommunicationError`] is given.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - This error can occur in the following cases:
+    ///   - If a malformed message was sent. A blank message can neither be sent nor received.
+    ///   - If the message couldn't be authenticated.
+    /// - [`CommunicationError::BufferTooSmall`] - The provided buffer is too small to fit the whole message.
+    ///   In this channel, there must be enough space to accomodate for [`AeadRxChannel::METADATA_SIZE`] bytes
+    ///   and 1 additional byte of message data. The needed length given is the length of the plaintext plus
+    ///   [`AeadRxChannel::METADATA_SIZE`].
+    /// - [`CommunicationError::Timeout`] - The timeout was reached before a complete message was received.
+    /// - [`CommunicationError::ReplayDetected`] - Replay protection is enabled and the message was replayed.
+    ///  - [`CommunicationError::InternalError`]
+    ///    - This can occur if some internal error happens. This should only occur if something is wrong
+    ///      with the implementation.
+    fn recv_with_data_timeout<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        self.recv_with_data_timeout_and_aad(dest, b"", timer)
+    }
+
+    /// Receives data from the channel, putting the data received into ``dest``, returning the
+    /// number of bytes written to it upon success. The buffer provided should have enough
+    /// space to store the data that needs to be received along with its metadata size. The provided time to
+    /// block is for the entire receive operation. If the timeout has passed and not enough bytes have been received,
+    /// this function returns an error. Upon an error, a [`CommunicationError`] is given.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - This error can occur in the following cases:
+    ///   - If a malformed message was sent. A blank message can neither be sent nor received.
+    ///   - If the message couldn't be authenticated.
+    /// - [`CommunicationError::BufferTooSmall`] - The provided buffer is too small to fit the whole message.
+    ///   In this channel, there must be enough space to accomodate for [`AeadRxChannel::METADATA_SIZE`] bytes
+    ///   and 1 additional byte of message data. The needed length given is the length of the plaintext plus
+    ///   [`AeadRxChannel::METADATA_SIZE`].
+    /// - [`CommunicationError::Timeout`] - The timeout was reached before a complete message was received.
+    /// - [`CommunicationError::ReplayDetected`] - Replay protection is enabled and the message was replayed.
+    ///  - [`CommunicationError::InternalError`]
+    ///    - This can occur if some internal error happens. This should only occur if something is wrong
+    ///      with the implementation.
+    fn recv_with_timeout<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        self.recv_with_timeout_and_aad(dest, b"", timer)
+    }
+
+    /// Receives data from the channel in the same way as [`recv_with_timeout`](Self::recv_with_timeout),
+    /// also returning the number of raw bytes the inner channel read. See
+    /// [`RxChannel::recv_progress`] for more details.
+    ///
+    /// # ERRORS:
+    ///
+    /// The result contains the same errors as [`recv_with_timeout`](Self::recv_with_timeout).
+    fn recv_progress<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> (usize, communication::Result<usize>) {
+        let mut bytes_read = 0;
+        let res = self.recv_with(dest, b"", |ch, d| {
+            let (raw, res) = ch.channel.recv_progress(d, timer);
+            bytes_read = raw;
+
+            res
+        });
+
+        (bytes_read, res)
+    }
+
+    /// Receives a message whose plaintext must exactly fill ``dest`` minus the
+    /// [`AeadRxChannel::METADATA_SIZE`] bytes of space needed for the sequence number, nonce, and tag.
+    /// The plaintext is put at the beginning of ``dest``. The provided time to block is for the entire
+    /// receive operation.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - The plaintext received wasn't exactly
+    ///   ``dest.len() - METADATA_SIZE`` bytes long.
+    ///
+    /// This function can also return any error from [`recv_with_timeout`](Self::recv_with_timeout).
+    fn recv_exact<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<()> {
+        if self.recv_with_timeout(dest, timer)? != dest.len().saturating_sub(Self::METADATA_SIZE) {
+            return Err(CommunicationError::RecvError);
+        }
+
+        Ok(())
+    }
+
+    /// Attempts to receive a message from the channel without blocking. Returns ``Ok(None)`` immediately
+    /// unless the inner channel has a complete message buffered. Otherwise, the message is authenticated
+    /// and decrypted like in [`recv_with_data_timeout`](Self::recv_with_data_timeout), returning
+    /// ``Ok(Some(n))`` upon success. See [`RxChannel::try_recv`] for more details.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`recv_with_data_timeout`](Self::recv_with_data_timeout),
+    /// apart from [`CommunicationError::Timeout`].
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        let mut pending = true;
+
+        let res = self.recv_with(dest, b"", |ch, d| match ch.channel.try_recv(d)? {
+            Some(bytes_read) => Ok(bytes_read),
+            None => {
+                pending = false;
+
+                // This error is never returned because we check for pending data below.
+                Err(CommunicationError::RecvError)
+            }
+        });
+
+        if !pending {
+            return Ok(None);
+        }
+
+        res.map(Some)
+    }
+}
+
+/// This [`TxChannel`] wraps around a [`FramedTxChannel`] to encrypt communications with an AEAD cipher,
+/// providing message authenticity and confidentiality. This channel requires a [`RandomSource`] to generate
+/// a random nonce, unless it's created with [`NonceMode::Synthetic`]. For ciphers with
+/// [`ChannelAead::NONCE_MODE_BYTE`] set, the first byte of each nonce is the [`NonceMode`] of the channel.
+///
+/// Each message is sent with an 8-byte big-endian sequence number that's incremented with each message
+/// sent. It's sent in the clear right before the ciphertext and authenticated as associated data along
+/// with the message, which an [`AeadRxChannel`] can use to detect replayed messages. The sequence
+/// number starts at 0 and is kept for the lifetime of this channel. It's only reset by changing the key
+/// with [`KeyedChannel::change_key`], so it also restarts when the sender reboots. See
+/// [`AeadRxChannel::set_replay_protection`] for what this means for the receiver.
+///
+/// The message is encrypted in place in the buffer given to [`send`](TxChannel::send), which holds the
+/// ciphertext afterwards, so no scratch buffer is needed. The nonce, sequence number, and tag are passed
+/// to the framing layer as separate slices of one frame. Only [`send_vectored`](TxChannel::send_vectored)
+/// copies its parts into a buffer on the stack, of the size chosen by the caller, which is wiped before
+/// it returns.
+///
+/// The encryption key is stored in memory that's wiped when this channel is dropped or the key is changed.
+///
+/// As a defense-in-depth check on the [`RandomSource`], the channel remembers the random part of the last
+/// ``NONCE_HISTORY`` nonces it generated, which defaults to [`DEFAULT_NONCE_HISTORY`], and refuses to send
+/// a message if a new nonce repeats one of them. With 23 random bytes, as in XChacha20Poly1305, a healthy
+/// random number generator repeating them is astronomically unlikely, so a repeat means the random
+/// number generator is broken, and sending would reuse the nonce with a different message. Each
+/// remembered nonce takes 8 bytes of memory in the channel, and a ``NONCE_HISTORY`` of 0 disables the
+/// check. Ciphers with fewer than 8 random bytes in each nonce would see repeats from a healthy random
+/// number generator, so the check is skipped for them and the history can be set to 0 to save the
+/// memory. The check is also skipped with [`NonceMode::Synthetic`], which doesn't use the random number
+/// generator.
+///
+/// See the module-level documentation for more information on the ciphers available.
+pub struct AeadTxChannel<
+    A: ChannelAead,
+    T: FramedTxChannel,
+    U: RandomSource,
+    const NONCE_HISTORY: usize = DEFAULT_NONCE_HISTORY,
+> {
+    channel: T,
+    random_source: U,
+    tx_key: StoredKey<A>,
+    nonce_mode: NonceMode,
+    sequence_counter: SequenceCounter,
+    nonce_history: NonceHistory<NONCE_HISTORY>,
+}
+
+impl<A: ChannelAead, T: FramedTxChannel, U: RandomSource> AeadTxChannel<A, T, U> {
+    /// Creates a new [`AeadTxChannel`] given an inner [`FramedTxChannel`] and an encryption key. The
+    /// channel remembers the last [`DEFAULT_NONCE_HISTORY`] nonces. Use
+    /// [`with_nonce_history`](Self::with_nonce_history) to remember a different number of nonces.
+    pub fn new(channel: T, random_source: U, tx_key: &aead::Key<A>) -> Self {
+        Self::with_nonce_history(channel, random_source, tx_key)
+    }
+}
+
+impl<A: ChannelAead, T: FramedTxChannel, U: RandomSource, const NONCE_HISTORY: usize>
+    AeadTxChannel<A, T, U, NONCE_HISTORY>
+{
+    /// Creates a new [`AeadTxChannel`] in the same way as [`new`](AeadTxChannel::new), but remembering
+    /// the last ``NONCE_HISTORY`` nonces, such as
+    /// ``XChacha20Poly1305TxChannel::<_, _, 32>::with_nonce_history(channel, random_source, &key)``.
+    pub fn with_nonce_history(channel: T, random_source: U, tx_key: &aead::Key<A>) -> Self {
+        Self::create(channel, random_source, tx_key, NonceMode::Random)
+    }
+
+    /// Creates a new [`AeadTxChannel`] generating nonces with the given [`NonceMode`]. The mode must be
+    /// [`NonceMode::Random`] unless the cipher has [`ChannelAead::NONCE_MODE_BYTE`] set, since the
+    /// receiver has no other way to tell the modes apart.
+    pub(super) fn create(
+        channel: T,
+        random_source: U,
+        tx_key: &aead::Key<A>,
+        nonce_mode: NonceMode,
+    ) -> Self {
+        Self {
+            channel,
+            random_source,
+            tx_key: StoredKey::new(tx_key),
+            nonce_mode,
+            sequence_counter: SequenceCounter::new(),
+            nonce_history: NonceHistory::new(),
+        }
+    }
+
+    /// Gets the [`NonceMode`] this channel was created with.
+    pub fn nonce_mode(&self) -> NonceMode {
+        self.nonce_mode
+    }
+
+    /// Gets a reference to the wrapped channel.
+    pub fn get_ref(&self) -> &T {
+        &self.channel
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.channel
+    }
+
+    /// Sends the data from ``buff`` through the channel in the same way as [`send`](TxChannel::send),
+    /// additionally authenticating the provided associated data. The associated data isn't transmitted,
+    /// so the receiver must supply the same associated data to authenticate the message. This can be
+    /// used to bind a message to a context, such as a message type or a car ID. At most
+    /// [`MAX_AAD_SIZE`] bytes of associated data can be given.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`send`](TxChannel::send). A
+    /// [`CommunicationError::SendError`] is also returned if the associated data is longer than
+    /// [`MAX_AAD_SIZE`].
+    pub fn send_with_aad(&mut self, buff: &mut [u8], aad: &[u8]) -> communication::Result<()> {
+        // Nothing is sent for these, so no sequence number is used up.
+        if buff.is_empty() || aad.len() > MAX_AAD_SIZE {
+            return Err(CommunicationError::SendError);
+        }
+
+        let sequence_number = self.sequence_counter.next_sequence_number()?;
+        let aad = SequencedAad::new(&sequence_number, aad).ok_or(CommunicationError::SendError)?;
+        let mut nonce = aead::Nonce::<A>::default();
+        let mode_len = usize::from(A::NONCE_MODE_BYTE);
+
+        if A::NONCE_MODE_BYTE {
+            nonce[0] = self.nonce_mode as u8;
+        }
+
+        // Fill the rest of the nonce with random bytes now, so nothing is encrypted if the random
+        // source is broken. A synthetic IV has to wait for the plaintext.
+        if self.nonce_mode == NonceMode::Random {
+            self.random_source.fill_rand_slice(&mut nonce[mode_len..]);
+
+            // A repeated nonce means the random source is broken, so nothing is encrypted with it.
+            if !self.nonce_history.insert(&nonce[mode_len..]) {
+                return Err(CommunicationError::InternalError);
+            }
+        }
+
+        if self.nonce_mode == NonceMode::Synthetic {
+            fill_synthetic_iv(&self.tx_key, aad.as_slice(), buff, &mut nonce[mode_len..]);
+        }
+
+        // Encrypt the buffer completely in place with the associated data, returning the auth tag.
+        let tag = self
+            .tx_key
+            .cipher()
+            .encrypt_in_place_detached(&nonce, aad.as_slice(), buff)
+            .map_err(|_| CommunicationError::SendError)?;
+        let ciphertext = &*buff;
+
+        // Write message as either Nonce + Sequence number + Ciphertext + Tag or
+        // Sequence number + Ciphertext + Nonce + Tag.
+        self.channel.frame::<4>(|| {
+            if A::NONCE_FIRST {
+                Frame::new()
+                    .append(&nonce)?
+                    .append(&sequence_number)?
+                    .append(ciphertext)?
+                    .append(&tag)
+            } else {
+                Frame::new()
+                    .append(&sequence_number)?
+                    .append(ciphertext)?
+                    .append(&nonce)?
+                    .append(&tag)
+            }
+        })
+    }
+}
+
+impl<A: ChannelAead, T: FramedTxChannel, U: RandomSource, const NONCE_HISTORY: usize> KeyedChannel
+    for AeadTxChannel<A, T, U, NONCE_HISTORY>
+{
+    type KeyType = aead::Key<A>;
+
+    fn change_key(&mut self, new_key: &Self::KeyType) {
+        self.tx_key = StoredKey::new(new_key);
+        self.sequence_counter.reset();
+    }
+}
+
+impl<A: ChannelAead, T: FramedTxChannel, U: RandomSource, const NONCE_HISTORY: usize> TxChannel
+    for AeadTxChannel<A, T, U, NONCE_HISTORY>
+{
+    /// Sends the data from ``src`` through the channel. The data is encrypted in place, so ``src`` holds
+    /// the ciphertext afterwards. Upon an error, a [`CommunicationError`] is given.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`]
+    ///   - This could occur if any implementation-based error occurs while sending data.
+    ///     This could be because:
+    ///         - The message was too short. With this channel, at least one byte of data must be sent.
+    ///         - An error occurred during message encryption.
+    /// - [`CommunicationError::SequenceExhausted`] - Every sequence number for the key has been used.
+    /// - [`CommunicationError::InternalError`]
+    ///   - This can occur if some internal error happens. This should only occur if something is wrong
+    ///     with the implementation.
+    ///   - The random part of the nonce repeated one of the last ``NONCE_HISTORY`` nonces, which means
+    ///     the [`RandomSource`] is broken.
+    fn send(&mut self, buff: &mut [u8]) -> communication::Result<()> {
+        self.send_with_aad(buff, b"")
+    }
+
+    /// Sends the parts as one message in the same way as [`send`](Self::send). The parts are copied
+    /// straight into a buffer of ``MAX_LEN`` bytes on the stack and encrypted there, so any number of
+    /// parts can be given, as long as their total length fits. The buffer is wiped before this returns.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`send`](Self::send). A [`CommunicationError::SendError`]
+    /// is also returned if the parts add up to more than ``MAX_LEN`` bytes.
+    fn send_vectored<const MAX_LEN: usize>(
+        &mut self,
+        parts: &[&[u8]],
+    ) -> communication::Result<()> {
+        let msg_len = parts.iter().map(|part| part.len()).sum::<usize>();
+
+        if msg_len > MAX_LEN {
+            return Err(CommunicationError::SendError);
+        }
+
+        let mut buff = Zeroizing::new([0; MAX_LEN]);
+        let mut len = 0;
+
+        for part in parts {
+            buff[len..len + part.len()].copy_from_slice(part);
+            len += part.len();
+        }
+
+        self.send_with_aad(&mut buff[..msg_len], b"")
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.channel.flush()
+    }
+}
+
+/// A bidirectional channel made of an [`AeadRxChannel`] and an [`AeadTxChannel`] using the same
+/// cipher, with separate keys for each direction.
+pub struct AeadChannel<A: ChannelAead, R: RxChannel, T: FramedTxChannel, U: RandomSource> {
+    rx_channel: AeadRxChannel<A, R>,
+    tx_channel: AeadTxChannel<A, T, U>,
+}
+
+impl<A: ChannelAead, R: RxChannel, T: FramedTxChannel, U: RandomSource> AeadChannel<A, R, T, U> {
+    /// Creates a new [`AeadChannel`] given the inner channels for each direction, a [`RandomSource`]
+    /// for sending, and the keys for each direction.
+    pub fn new(
+        rx_channel: R,
+        tx_channel: T,
+        random_source: U,
+        rx_key: &aead::Key<A>,
+        tx_key: &aead::Key<A>,
+    ) -> Self {
+        Self {
+            rx_channel: AeadRxChannel::new(rx_channel, rx_key),
+            tx_channel: AeadTxChannel::new(tx_channel, random_source, tx_key),
+        }
+    }
+
+    /// Gets a mutable reference to the receiving half of this channel.
+    pub fn rx_mut(&mut self) -> &mut AeadRxChannel<A, R> {
+        &mut self.rx_channel
+    }
+
+    /// Gets a mutable reference to the sending half of this channel.
+    pub fn tx_mut(&mut self) -> &mut AeadTxChannel<A, T, U> {
+        &mut self.tx_channel
+    }
+
+    /// Changes the decryption key of the receiving half of this channel.
+    pub fn change_rx_key(&mut self, new_key: &aead::Key<A>) {
+        self.rx_channel.change_key(new_key);
+    }
+
+    /// Changes the encryption key of the sending half of this channel.
+    pub fn change_tx_key(&mut self, new_key: &aead::Key<A>) {
+        self.tx_channel.change_key(new_key);
+    }
+}
+
+impl<A: ChannelAead, R: RxChannel, T: FramedTxChannel, U: RandomSource> RxChannel
+    for AeadChannel<A, R, T, U>
+{
+    fn recv_with_data_timeout<V: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut V,
+    ) -> communication::Result<usize> {
+        self.rx_channel.recv_with_data_timeout(dest, timer)
+    }
+
+    fn recv_with_timeout<V: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut V,
+    ) -> communication::Result<usize> {
+        self.rx_channel.recv_with_timeout(dest, timer)
+    }
+
+    fn recv_progress<V: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut V,
+    ) -> (usize, communication::Result<usize>) {
+        self.rx_channel.recv_progress(dest, timer)
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        self.rx_channel.try_recv(dest)
+    }
+
+    fn recv_exact<V: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut V,
+    ) -> communication::Result<()> {
+        self.rx_channel.recv_exact(dest, timer)
+    }
+}
+
+impl<A: ChannelAead, R: RxChannel, T: FramedTxChannel, U: RandomSource> TxChannel
+    for AeadChannel<A, R, T, U>
+{
+    fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
+        self.tx_channel.send(src)
+    }
+
+    fn send_vectored<const MAX_LEN: usize>(
+        &mut self,
+        parts: &[&[u8]],
+    ) -> communication::Result<()> {
+        self.tx_channel.send_vectored::<MAX_LEN>(parts)
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.tx_channel.flush()
+    }
+}
@@ -0,0 +1,46 @@
+use super::{AeadRxChannel, AeadTxChannel, ChannelAead, SEQUENCE_NUMBER_SIZE};
+use aes_gcm::aead::AeadCore;
+use typenum::Unsigned;
+
+pub use aes_gcm::Aes128Gcm;
+
+/// The key used by the channels in this module.
+pub type Aes128GcmKey = aes_gcm::Key<Aes128Gcm>;
+
+type TagSize = <Aes128Gcm as AeadCore>::TagSize;
+type NonceSize = <Aes128Gcm as AeadCore>::NonceSize;
+
+const TAG_SIZE: usize = <TagSize as Unsigned>::USIZE;
+const NONCE_SIZE: usize = <NonceSize as Unsigned>::USIZE;
+
+/// The total metadata size required when receiving on a [`Aes128GcmRxChannel`].
+pub const AES_128_GCM_METADATA_SIZE: usize = TAG_SIZE + NONCE_SIZE + SEQUENCE_NUMBER_SIZE;
+
+impl ChannelAead for Aes128Gcm {
+    const NONCE_FIRST: bool = true;
+    const NONCE_MODE_BYTE: bool = false;
+}
+
+/// This [`RxChannel`](crate::communication::RxChannel) wraps around another
+/// [`RxChannel`](crate::communication::RxChannel) to decrypt communications encrypted by a
+/// [`Aes128GcmTxChannel`], providing message authenticity and confidentiality. This channel behaves
+/// the same as an [`XChacha20Poly1305RxChannel`](super::XChacha20Poly1305RxChannel), except that it
+/// uses AES-128-GCM and expects the 12-byte nonce before the ciphertext. Buffers used to receive
+/// messages must have [`AES_128_GCM_METADATA_SIZE`] bytes of space for the sequence number, nonce, and
+/// tag.
+///
+/// See the documentation for [`AeadRxChannel`] for the errors this channel can give, and the
+/// [`module`](super) documentation for more information on the cipher used.
+pub type Aes128GcmRxChannel<T> = AeadRxChannel<Aes128Gcm, T>;
+
+/// This [`TxChannel`](crate::communication::TxChannel) wraps around a
+/// [`FramedTxChannel`](crate::communication::lower_layers::framing::FramedTxChannel) to encrypt
+/// communications with AES-128-GCM, providing message authenticity and confidentiality. Each message
+/// is sent as the 12-byte nonce, then the sequence number, then the ciphertext, then the tag. Every
+/// byte of each nonce is random. The nonce is too short to spare a [`NonceMode`](super::NonceMode)
+/// byte, so only random nonces are supported, and a key shouldn't be used for more than 2^32 messages
+/// to keep the chance of a repeated nonce negligible.
+///
+/// See the documentation for [`AeadTxChannel`] for more details, and the module-level documentation
+/// for more information on the cipher used.
+pub type Aes128GcmTxChannel<T, U> = AeadTxChannel<Aes128Gcm, T, U>;
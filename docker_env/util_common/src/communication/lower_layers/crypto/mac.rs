@@ -0,0 +1,316 @@
+use super::{KeyedChannel, ReplayGuard, SequenceCounter, StoredKey, SEQUENCE_NUMBER_SIZE};
+use crate::{
+    communication::{
+        self,
+        lower_layers::framing::{Frame, FramedTxChannel},
+        CommunicationError, RxChannel, Timer, TxChannel,
+    },
+    constant_time_eq,
+};
+use chacha20poly1305::aead::{Key, KeySizeUser};
+use sha3::{Digest, Sha3_256};
+use typenum::U32;
+
+/// The size of the authentication tag at the end of each message sent by a [`MacOnlyTxChannel`].
+pub const MAC_TAG_SIZE: usize = 16;
+
+/// The total metadata size required when receiving on a [`MacOnlyRxChannel`].
+pub const MAC_METADATA_SIZE: usize = SEQUENCE_NUMBER_SIZE + MAC_TAG_SIZE;
+
+/// The label hashed before the key and message, to keep tags from being valid hashes for any other use
+/// of the same key.
+const MAC_LABEL: &[u8] = b"ucsc-ectf-2023 mac-only channel";
+
+/// The keyed SHA3-256 MAC used by the [`MacOnlyRxChannel`] and [`MacOnlyTxChannel`]. Unlike SHA-2, SHA3
+/// isn't open to length extension, so hashing the key before the message is a secure MAC.
+pub struct Sha3Mac;
+
+impl KeySizeUser for Sha3Mac {
+    type KeySize = U32;
+}
+
+/// The key used by the [`MacOnlyRxChannel`] and [`MacOnlyTxChannel`].
+pub type MacKey = Key<Sha3Mac>;
+
+/// Computes the tag of a message with its sequence number. Every input but the payload has a fixed
+/// size, so no two messages are hashed the same way.
+fn compute_tag(
+    key: &StoredKey<Sha3Mac>,
+    sequence_number: &[u8],
+    payload: &[u8],
+) -> [u8; MAC_TAG_SIZE] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(MAC_LABEL);
+    hasher.update(&key.0);
+    hasher.update(sequence_number);
+    hasher.update(payload);
+
+    let mut tag = [0; MAC_TAG_SIZE];
+    tag.copy_from_slice(&hasher.finalize()[..MAC_TAG_SIZE]);
+
+    tag
+}
+
+/// This [`RxChannel`] wraps around another [`RxChannel`] to authenticate messages sent by a
+/// [`MacOnlyTxChannel`] with the same key, providing message authenticity but not confidentiality.
+/// Each message is the payload in the clear, followed by an 8-byte big-endian sequence number and a
+/// 16-byte tag, so the buffer must have [`MAC_METADATA_SIZE`] bytes of space for them. The tag is checked
+/// in constant time, and the payload is left at the start of the buffer.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - The message didn't contain a sequence number, a tag, and at
+/// least one byte of payload, didn't match its tag, or an error occurred while receiving the message
+/// from the wrapped channel. The payload of a message that didn't match its tag is wiped from the buffer.
+/// - [`CommunicationError::BufferTooSmall`] - The message couldn't be read into the buffer because it
+/// was too small. The needed length is the length of the payload plus [`MAC_METADATA_SIZE`].
+/// - [`CommunicationError::Timeout`] - The wrapped channel timed out before a complete message was received.
+/// - [`CommunicationError::ReplayDetected`] - Replay protection is enabled and the message's sequence
+/// number wasn't greater than the sequence number of the last message accepted.
+///
+/// The key is stored in memory that's wiped when this channel is dropped or the key is changed.
+pub struct MacOnlyRxChannel<T: RxChannel> {
+    channel: T,
+    key: StoredKey<Sha3Mac>,
+    replay_guard: ReplayGuard,
+    auth_failures: u32,
+}
+
+impl<T: RxChannel> MacOnlyRxChannel<T> {
+    /// Creates a new [`MacOnlyRxChannel`] given an inner [`RxChannel`] and a key.
+    pub fn new(channel: T, key: &MacKey) -> Self {
+        Self {
+            channel,
+            key: StoredKey::new(key),
+            replay_guard: ReplayGuard::new(),
+            auth_failures: 0,
+        }
+    }
+
+    /// Gets a reference to the wrapped channel.
+    pub fn get_ref(&self) -> &T {
+        &self.channel
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.channel
+    }
+
+    /// Gets the number of received messages that failed to authenticate or were rejected as replays
+    /// since this channel was created. This wraps around on overflow.
+    pub fn auth_failures(&self) -> u32 {
+        self.auth_failures
+    }
+
+    /// Enables or disables replay protection, which is disabled by default. See
+    /// [`AeadRxChannel::set_replay_protection`](super::AeadRxChannel::set_replay_protection) for more
+    /// details, which apply to this channel in the same way.
+    pub fn set_replay_protection(&mut self, enabled: bool) {
+        self.replay_guard.enabled = enabled;
+    }
+
+    fn recv_with(
+        &mut self,
+        dest: &mut [u8],
+        read_fn: impl FnOnce(&mut Self, &mut [u8]) -> communication::Result<usize>,
+    ) -> communication::Result<usize> {
+        // Check that the destination buffer has space for at least one byte of payload.
+        if dest.len() <= MAC_METADATA_SIZE {
+            return Err(CommunicationError::BufferTooSmall {
+                needed: MAC_METADATA_SIZE + 1,
+            });
+        }
+
+        let bytes_read = read_fn(self, dest)?;
+
+        // Never trust the length given by the inner channel.
+        if bytes_read > dest.len() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        let dest = &mut dest[..bytes_read];
+
+        if dest.len() <= MAC_METADATA_SIZE {
+            return Err(CommunicationError::RecvError);
+        }
+
+        let msg_len = dest.len() - MAC_METADATA_SIZE;
+        let (payload, metadata) = dest.split_at_mut(msg_len);
+        let (sequence_number, tag) = metadata.split_at(SEQUENCE_NUMBER_SIZE);
+
+        if !constant_time_eq(&compute_tag(&self.key, sequence_number, payload), tag) {
+            // The payload is in the clear, so don't leave a forged one in the buffer.
+            payload.fill(0);
+            self.auth_failures = self.auth_failures.wrapping_add(1);
+
+            return Err(CommunicationError::RecvError);
+        }
+
+        // The sequence number is only trusted once the message has been authenticated.
+        if !self.replay_guard.accept(sequence_number) {
+            payload.fill(0);
+            self.auth_failures = self.auth_failures.wrapping_add(1);
+
+            return Err(CommunicationError::ReplayDetected);
+        }
+
+        Ok(msg_len)
+    }
+}
+
+impl<T: RxChannel> KeyedChannel for MacOnlyRxChannel<T> {
+    type KeyType = MacKey;
+
+    fn change_key(&mut self, new_key: &Self::KeyType) {
+        self.key = StoredKey::new(new_key);
+        self.replay_guard.reset();
+    }
+}
+
+impl<T: RxChannel> RxChannel for MacOnlyRxChannel<T> {
+    /// Receives a message from the channel, putting its payload at the start of ``dest`` and returning
+    /// the length of the payload upon success. The provided timeout is reset on each byte received.
+    ///
+    /// # ERRORS:
+    ///
+    /// See the documentation for [`MacOnlyRxChannel`] for the errors this can give.
+    fn recv_with_data_timeout<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        self.recv_with(dest, |ch, d| ch.channel.recv_with_data_timeout(d, timer))
+    }
+
+    /// Receives a message from the channel, putting its payload at the start of ``dest`` and returning
+    /// the length of the payload upon success. The provided time to block is for the entire receive
+    /// operation.
+    ///
+    /// # ERRORS:
+    ///
+    /// See the documentation for [`MacOnlyRxChannel`] for the errors this can give.
+    fn recv_with_timeout<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        self.recv_with(dest, |ch, d| ch.channel.recv_with_timeout(d, timer))
+    }
+
+    fn recv_progress<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> (usize, communication::Result<usize>) {
+        let mut bytes_read = 0;
+        let res = self.recv_with(dest, |ch, d| {
+            let (raw, res) = ch.channel.recv_progress(d, timer);
+            bytes_read = raw;
+
+            res
+        });
+
+        (bytes_read, res)
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        let mut pending = true;
+
+        let res = self.recv_with(dest, |ch, d| match ch.channel.try_recv(d)? {
+            Some(bytes_read) => Ok(bytes_read),
+            None => {
+                pending = false;
+
+                // This error is never returned because we check for pending data below.
+                Err(CommunicationError::RecvError)
+            }
+        });
+
+        if !pending {
+            return Ok(None);
+        }
+
+        res.map(Some)
+    }
+}
+
+/// This [`TxChannel`] wraps around a [`FramedTxChannel`] to authenticate communications with a keyed
+/// SHA3-256 tag, providing message authenticity but not confidentiality. This suits messages that
+/// aren't secret but mustn't be spoofed, such as a car ID query, and costs [`MAC_METADATA_SIZE`] bytes
+/// per message instead of the larger metadata of the AEAD channels. No [`RandomSource`] is needed, since
+/// nothing is encrypted.
+///
+/// The payload is sent as-is, followed by a big-endian sequence number and the first [`MAC_TAG_SIZE`]
+/// bytes of the tag, as separate slices of one frame. The sequence number starts at 0, is incremented
+/// with each message sent, and is only reset by changing the key with [`KeyedChannel::change_key`], so a
+/// [`MacOnlyRxChannel`] can use it to detect replayed messages.
+///
+/// The key is stored in memory that's wiped when this channel is dropped or the key is changed.
+///
+/// [`RandomSource`]: super::RandomSource
+pub struct MacOnlyTxChannel<T: FramedTxChannel> {
+    channel: T,
+    key: StoredKey<Sha3Mac>,
+    sequence_counter: SequenceCounter,
+}
+
+impl<T: FramedTxChannel> MacOnlyTxChannel<T> {
+    /// Creates a new [`MacOnlyTxChannel`] given an inner [`FramedTxChannel`] and a key.
+    pub fn new(channel: T, key: &MacKey) -> Self {
+        Self {
+            channel,
+            key: StoredKey::new(key),
+            sequence_counter: SequenceCounter::new(),
+        }
+    }
+
+    /// Gets a reference to the wrapped channel.
+    pub fn get_ref(&self) -> &T {
+        &self.channel
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.channel
+    }
+}
+
+impl<T: FramedTxChannel> KeyedChannel for MacOnlyTxChannel<T> {
+    type KeyType = MacKey;
+
+    fn change_key(&mut self, new_key: &Self::KeyType) {
+        self.key = StoredKey::new(new_key);
+        self.sequence_counter.reset();
+    }
+}
+
+impl<T: FramedTxChannel> TxChannel for MacOnlyTxChannel<T> {
+    /// Sends the data from ``src`` through the channel, followed by its sequence number and tag. ``src``
+    /// isn't modified.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - The message was empty or the wrapped channel couldn't send
+    ///   the message.
+    /// - [`CommunicationError::SequenceExhausted`] - Every sequence number for the key has been used.
+    fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
+        if src.is_empty() {
+            return Err(CommunicationError::SendError);
+        }
+
+        let sequence_number = self.sequence_counter.next_sequence_number()?;
+        let tag = compute_tag(&self.key, &sequence_number, src);
+
+        self.channel.frame::<3>(|| {
+            Frame::new()
+                .append(src)?
+                .append(&sequence_number)?
+                .append(&tag)
+        })
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.channel.flush()
+    }
+}
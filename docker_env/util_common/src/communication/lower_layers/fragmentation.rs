@@ -0,0 +1,284 @@
+//! This module contains channels that split messages too large for one frame into fragments and put
+//! them back together on the receiving side. These channels wrap any [`TxChannel`] or
+//! [`RxChannel`], and are meant to be layered above the
+//! [`crypto`](crate::communication::lower_layers::crypto) layer so that each fragment is
+//! independently encrypted and authenticated, which means a tampered fragment is rejected before
+//! any of it is reassembled.
+//!
+//! - Fragment format
+//!     - Each fragment starts with a 2-byte message ID in big-endian byte order. Every fragment of
+//!       a message has the same message ID, and the sender gives each message the next message ID,
+//!       wrapping around after [`u16::MAX`].
+//!     - The message ID is followed by a 2-byte big-endian sequence number. The first fragment of a
+//!       message has sequence number 0, and each following fragment has the next sequence number.
+//!     - The first fragment also has the total length of the message as a 4-byte big-endian integer
+//!       right after its sequence number. The first fragment's header is
+//!       [`FIRST_FRAGMENT_HEADER_SIZE`] bytes, and every other fragment's header is
+//!       [`FRAGMENT_HEADER_SIZE`] bytes.
+//!     - The rest of each fragment is the next part of the message. Every fragment but the last
+//!       carries at least one byte of the message.
+
+use crate::{
+    communication::{self, CommunicationError, RxChannel, TxChannel},
+    timer::Timer,
+};
+use zeroize::Zeroizing;
+
+/// The size of the message ID at the start of every fragment.
+const MESSAGE_ID_SIZE: usize = 2;
+
+/// The size of the header of every fragment but the first, which is the message ID followed by the
+/// sequence number.
+pub const FRAGMENT_HEADER_SIZE: usize = MESSAGE_ID_SIZE + 2;
+
+/// The size of the header of the first fragment of a message, which is the message ID and sequence
+/// number followed by the total length of the message.
+pub const FIRST_FRAGMENT_HEADER_SIZE: usize = FRAGMENT_HEADER_SIZE + 4;
+
+/// A [`TxChannel`] that splits each message into fragments of at most ``FRAGMENT_SIZE`` bytes,
+/// including headers, and sends each fragment through the wrapped [`TxChannel`] as its own message.
+/// Each fragment is copied into a buffer of ``FRAGMENT_SIZE`` bytes on the stack before it's sent,
+/// so ``src`` is never modified, even when the wrapped channel modifies the buffers it sends. The
+/// buffer is wiped before returning, so no part of the message is left on the stack, even when a
+/// fragment fails to send.
+pub struct FragmentingTxChannel<C: TxChannel, const FRAGMENT_SIZE: usize> {
+    channel: C,
+    message_id: u16,
+}
+
+impl<C: TxChannel, const FRAGMENT_SIZE: usize> FragmentingTxChannel<C, FRAGMENT_SIZE> {
+    /// Creates a new [`FragmentingTxChannel`] wrapping the provided channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if ``FRAGMENT_SIZE`` isn't greater than [`FIRST_FRAGMENT_HEADER_SIZE`], since then a
+    /// fragment couldn't carry any of the message.
+    pub fn new(channel: C) -> Self {
+        assert!(
+            FRAGMENT_SIZE > FIRST_FRAGMENT_HEADER_SIZE,
+            "Fragment size too small for the fragment header."
+        );
+
+        Self {
+            channel,
+            message_id: 0,
+        }
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.channel
+    }
+
+    /// Consumes the [`FragmentingTxChannel`], returning the wrapped channel.
+    pub fn into_inner(self) -> C {
+        self.channel
+    }
+}
+
+impl<C: TxChannel, const FRAGMENT_SIZE: usize> TxChannel
+    for FragmentingTxChannel<C, FRAGMENT_SIZE>
+{
+    /// Sends the data from ``src`` through the wrapped channel as one or more fragments with the
+    /// next message ID. An empty message is sent as one fragment with only a header.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - ``src`` is longer than [`u32::MAX`] bytes or would
+    ///   need more fragments than there are sequence numbers.
+    ///
+    /// This function can also return any error from the wrapped channel's
+    /// [`send`](TxChannel::send). If a fragment fails to send, the fragments after it aren't sent.
+    fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
+        let total_len = u32::try_from(src.len()).map_err(|_| CommunicationError::SendError)?;
+        let first_len = src.len().min(FRAGMENT_SIZE - FIRST_FRAGMENT_HEADER_SIZE);
+        let (first, rest) = src.split_at(first_len);
+        let rest = rest.chunks(FRAGMENT_SIZE - FRAGMENT_HEADER_SIZE);
+
+        if rest.len() > u16::MAX as usize {
+            return Err(CommunicationError::SendError);
+        }
+
+        let mut fragment = Zeroizing::new([0; FRAGMENT_SIZE]);
+        let message_id = self.message_id;
+        self.message_id = message_id.wrapping_add(1);
+
+        fragment[..MESSAGE_ID_SIZE].copy_from_slice(&message_id.to_be_bytes());
+        fragment[FRAGMENT_HEADER_SIZE..FIRST_FRAGMENT_HEADER_SIZE]
+            .copy_from_slice(&total_len.to_be_bytes());
+        fragment[FIRST_FRAGMENT_HEADER_SIZE..FIRST_FRAGMENT_HEADER_SIZE + first_len]
+            .copy_from_slice(first);
+        self.channel
+            .send(&mut fragment[..FIRST_FRAGMENT_HEADER_SIZE + first_len])?;
+
+        // The length check above means the sequence numbers can't wrap around.
+        for (sequence_number, part) in (1..=u16::MAX).zip(rest) {
+            fragment[MESSAGE_ID_SIZE..FRAGMENT_HEADER_SIZE]
+                .copy_from_slice(&sequence_number.to_be_bytes());
+            fragment[FRAGMENT_HEADER_SIZE..FRAGMENT_HEADER_SIZE + part.len()].copy_from_slice(part);
+            self.channel
+                .send(&mut fragment[..FRAGMENT_HEADER_SIZE + part.len()])?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.channel.flush()
+    }
+}
+
+/// An [`RxChannel`] that receives fragments sent by a [`FragmentingTxChannel`] from the wrapped
+/// [`RxChannel`] and reassembles them into ``dest``. Each fragment is received into a buffer of
+/// ``BUFFER_SIZE`` bytes on the stack, so ``BUFFER_SIZE`` must be at least the sender's fragment
+/// size plus the metadata size of the wrapped channel, such as
+/// [`METADATA_SIZE`](crate::communication::lower_layers::crypto::METADATA_SIZE) for an
+/// [`XChacha20Poly1305RxChannel`](crate::communication::lower_layers::crypto::XChacha20Poly1305RxChannel).
+/// The buffer holds decrypted fragments, so it's wiped before returning, whether or not the message
+/// was reassembled.
+///
+/// Fragments must arrive in order. Since every fragment is its own message in the wrapped channel,
+/// a missing, repeated, or reordered fragment is detected by its sequence number and fails the
+/// whole message, as does a fragment with a different message ID than the first fragment, such as
+/// one left over from an earlier message. Any fragments of a failed message that arrive afterwards
+/// fail the next receive as well, since they don't start with sequence number 0.
+///
+/// [`try_recv`](RxChannel::try_recv) always returns ``Ok(None)``, since the wrapped channel can only
+/// tell that one fragment has arrived, not the whole message.
+pub struct ReassemblingRxChannel<C: RxChannel, const BUFFER_SIZE: usize> {
+    channel: C,
+}
+
+impl<C: RxChannel, const BUFFER_SIZE: usize> ReassemblingRxChannel<C, BUFFER_SIZE> {
+    /// Creates a new [`ReassemblingRxChannel`] wrapping the provided channel.
+    pub fn new(channel: C) -> Self {
+        Self { channel }
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.channel
+    }
+
+    /// Consumes the [`ReassemblingRxChannel`], returning the wrapped channel.
+    pub fn into_inner(self) -> C {
+        self.channel
+    }
+
+    /// Reassembles a message into ``dest``, given the first fragment, which is the first
+    /// ``first_len`` bytes of ``fragment``. The rest of the fragments are received into
+    /// ``fragment`` with ``recv_fragment``. Returns the length of the message.
+    fn reassemble<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        fragment: &mut [u8; BUFFER_SIZE],
+        first_len: usize,
+        timer: &mut T,
+        recv_fragment: impl Fn(&mut C, &mut [u8], &mut T) -> communication::Result<usize>,
+    ) -> communication::Result<usize> {
+        // The length given by the wrapped channel isn't trusted.
+        let first = fragment
+            .get(..first_len)
+            .ok_or(CommunicationError::RecvError)?;
+
+        if first.len() < FIRST_FRAGMENT_HEADER_SIZE
+            || first[MESSAGE_ID_SIZE..FRAGMENT_HEADER_SIZE] != [0, 0]
+        {
+            return Err(CommunicationError::RecvError);
+        }
+
+        let mut message_id = [0; MESSAGE_ID_SIZE];
+        message_id.copy_from_slice(&first[..MESSAGE_ID_SIZE]);
+
+        let mut total_len_bytes = [0; 4];
+        total_len_bytes.copy_from_slice(&first[FRAGMENT_HEADER_SIZE..FIRST_FRAGMENT_HEADER_SIZE]);
+        let total_len = u32::from_be_bytes(total_len_bytes) as usize;
+
+        let dest = dest
+            .get_mut(..total_len)
+            .ok_or(CommunicationError::BufferTooSmall { needed: total_len })?;
+
+        let mut filled = copy_part(dest, 0, &first[FIRST_FRAGMENT_HEADER_SIZE..])?;
+        let mut expected_sequence_number: u16 = 1;
+
+        while filled < total_len {
+            let len = recv_fragment(&mut self.channel, &mut fragment[..], timer)?;
+            let current = fragment.get(..len).ok_or(CommunicationError::RecvError)?;
+
+            if current.len() <= FRAGMENT_HEADER_SIZE
+                || current[..MESSAGE_ID_SIZE] != message_id
+                || current[MESSAGE_ID_SIZE..FRAGMENT_HEADER_SIZE]
+                    != expected_sequence_number.to_be_bytes()
+            {
+                return Err(CommunicationError::RecvError);
+            }
+
+            filled = copy_part(dest, filled, &current[FRAGMENT_HEADER_SIZE..])?;
+            expected_sequence_number = expected_sequence_number
+                .checked_add(1)
+                .ok_or(CommunicationError::RecvError)?;
+        }
+
+        Ok(total_len)
+    }
+}
+
+/// Copies part of a message into ``dest`` after the first ``filled`` bytes, returning the new
+/// number of filled bytes. Returns [`CommunicationError::RecvError`] if the part goes past the end
+/// of the message.
+fn copy_part(dest: &mut [u8], filled: usize, part: &[u8]) -> communication::Result<usize> {
+    let end = filled + part.len();
+
+    dest.get_mut(filled..end)
+        .ok_or(CommunicationError::RecvError)?
+        .copy_from_slice(part);
+
+    Ok(end)
+}
+
+impl<C: RxChannel, const BUFFER_SIZE: usize> RxChannel for ReassemblingRxChannel<C, BUFFER_SIZE> {
+    /// Receives a fragmented message into ``dest``, returning the length of the message upon
+    /// success. The provided timeout is reset on each byte received, including across fragments.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - A fragment was missing, out of order, malformed, or
+    ///   from another message, or the fragments carried more or less data than the total length in
+    ///   the first fragment.
+    /// - [`CommunicationError::BufferTooSmall`] - ``dest`` is smaller than the total length of the
+    ///   message. The fragments after the first are left in the wrapped channel.
+    ///
+    /// This function can also return any error from the wrapped channel's
+    /// [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout).
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        let mut fragment = Zeroizing::new([0; BUFFER_SIZE]);
+        let len = self
+            .channel
+            .recv_with_data_timeout(&mut fragment[..], timer)?;
+
+        self.reassemble(dest, &mut fragment, len, timer, C::recv_with_data_timeout)
+    }
+
+    /// Receives a fragmented message into ``dest``, returning the length of the message upon
+    /// success. The provided time to block is for receiving every fragment of the message.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as
+    /// [`recv_with_data_timeout`](Self::recv_with_data_timeout), except that errors from the
+    /// wrapped channel come from its [`recv_with_timeout`](RxChannel::recv_with_timeout).
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        let mut fragment = Zeroizing::new([0; BUFFER_SIZE]);
+        let len = self.channel.recv_with_timeout(&mut fragment[..], timer)?;
+
+        self.reassemble(dest, &mut fragment, len, timer, C::recv_with_timeout)
+    }
+}
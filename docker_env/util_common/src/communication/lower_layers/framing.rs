@@ -10,15 +10,32 @@
 //!     - To prevent conflating \1 characters with the underlying data, the underlying data is hex encoded
 //!       and decoded. NULL characters are completely ignored and won't affect the message.
 //!     - Helper functions to implement channels using this type of framing are in the [`bogoframing`] module.
+//! - COBS
+//!     - Consistent Overhead Byte Stuffing removes every zero byte from the underlying data so that each message
+//!       can begin and end with one zero byte. A receiver can always resynchronize at the next zero byte after
+//!       receiving a corrupted frame.
+//!     - Helper functions to implement channels using this type of framing are in the [`cobs`] module.
+//! - CRC framing
+//!     - CRC framing wraps another framing implementation and appends a CRC-16/CCITT to each message so
+//!       that corrupted messages are rejected. Channels for this are in the [`crc`] module.
 //!
 //! See the documentation for [`communication`](crate::communication) for a description of full communication
 //! stack.
 
 pub mod bogoframing;
+pub mod cobs;
+pub mod crc;
 
 use chacha20poly1305::aead::heapless;
+use core::{iter::Chain, option};
 
-use crate::communication::{CommunicationError, TxChannel};
+use crate::communication::{CommunicationError, TxChannel, MAX_VECTORED_PARTS};
+
+/// The most bytes that a received frame longer than ``dest`` is decoded past the end of ``dest`` to
+/// find out how long it is. A frame that's still going once it passes this is rejected right away
+/// with [`CommunicationError::RecvError`], so a peer that sends a frame that never ends can't keep a
+/// receive with a per-byte timeout from returning.
+pub const MAX_OVERSIZED_FRAME_DISCARD: usize = 1024;
 
 /// A trait to be implemented by all transmission channels in framing protocol implementations.
 /// This contains one function to specify the slices that go into the frame to be transmitted.
@@ -36,30 +53,74 @@ pub trait FramedTxChannel: TxChannel {
         &mut self,
         frame: impl FnOnce() -> Result<Frame<'a, FRAME_CT>, CommunicationError>,
     ) -> Result<(), CommunicationError>;
+
+    /// Blocks until every frame transmitted has left the device. This is what
+    /// [`TxChannel::flush`] calls for a [`FramedTxChannel`], since the blanket
+    /// [`TxChannel`] implementation can't be overridden. The default implementation
+    /// does nothing.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - Occurs when some error occurs while
+    /// flushing the channel.
+    fn flush_frames(&mut self) -> Result<(), CommunicationError> {
+        Ok(())
+    }
 }
 
 impl<T: FramedTxChannel> TxChannel for T {
     fn send(&mut self, src: &mut [u8]) -> Result<(), CommunicationError> {
         self.frame::<1>(|| Frame::new().append(src))
     }
+
+    fn flush(&mut self) -> Result<(), CommunicationError> {
+        self.flush_frames()
+    }
+
+    fn send_vectored<const MAX_LEN: usize>(
+        &mut self,
+        parts: &[&[u8]],
+    ) -> Result<(), CommunicationError> {
+        // Nothing is copied, so the length limit is only checked to behave like the other channels.
+        if parts.len() > MAX_VECTORED_PARTS
+            || parts.iter().map(|part| part.len()).sum::<usize>() > MAX_LEN
+        {
+            return Err(CommunicationError::SendError);
+        }
+
+        self.frame::<MAX_VECTORED_PARTS>(|| {
+            parts
+                .iter()
+                .try_fold(Frame::new(), |frame, &part| frame.append(part))
+        })
+    }
 }
 
 /// A struct that keeps track of slices of u8's to write as one frame
 /// in a [`FramedTxChannel`]. This can be used to write discontiguous
 /// pieces of memory into one frame. The const generic ``FRAME_SLICES``
 /// indicates the number of slices in the [`Frame`].
+///
+/// A [`Frame`] can also end with one trailer slice, which doesn't count
+/// towards ``FRAME_SLICES``. This lets a channel that wraps another
+/// [`FramedTxChannel`] add something to the end of a frame with any number
+/// of slices, like the CRC of a [`CrcFramedTxChannel`](crc::CrcFramedTxChannel).
 #[derive(Default)]
 pub struct Frame<'a, const FRAME_SLICES: usize> {
     frame_components: heapless::Vec<&'a [u8], FRAME_SLICES>,
+    trailer: Option<&'a [u8]>,
     total_len: usize,
 }
 
 impl<'a, const FRAME_SLICES: usize> IntoIterator for Frame<'a, FRAME_SLICES> {
     type Item = &'a [u8];
-    type IntoIter = <heapless::Vec<&'a [u8], FRAME_SLICES> as IntoIterator>::IntoIter;
+    type IntoIter = Chain<
+        <heapless::Vec<&'a [u8], FRAME_SLICES> as IntoIterator>::IntoIter,
+        option::IntoIter<&'a [u8]>,
+    >;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.frame_components.into_iter()
+        self.frame_components.into_iter().chain(self.trailer)
     }
 }
 
@@ -69,6 +130,7 @@ impl<'a, const FRAME_CT: usize> Frame<'a, FRAME_CT> {
     pub fn new() -> Self {
         Frame {
             frame_components: heapless::Vec::new(),
+            trailer: None,
             total_len: 0,
         }
     }
@@ -90,6 +152,28 @@ impl<'a, const FRAME_CT: usize> Frame<'a, FRAME_CT> {
         }
     }
 
+    /// Sets the trailer of the frame, which is sent after every other slice.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::InternalError`] - Occurs when the frame already
+    /// has a trailer.
+    pub fn with_trailer(mut self, trailer: &'a [u8]) -> Result<Self, CommunicationError> {
+        if self.trailer.is_some() {
+            return Err(CommunicationError::InternalError);
+        }
+
+        self.trailer = Some(trailer);
+        self.total_len += trailer.len();
+
+        Ok(self)
+    }
+
+    /// Gets an iterator over the slices of the frame, in the order they're sent.
+    pub fn iter(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.frame_components.iter().copied().chain(self.trailer)
+    }
+
     /// Gets the length of the frame in bytes.
     pub fn len(&self) -> usize {
         self.total_len
@@ -6,8 +6,12 @@
 //!     - To prevent conflating \1 characters with the underlying data, the underlying data is hex encoded
 //!       and decoded. NULL characters are completely ignored and won't affect the message.
 //!     - Helper functions to implement channels using this type of framing are in the [`bogoframing`](self) module.
+//!       [`decode_frame`] decodes a frame that's already in memory, such as for fuzzing the decoder off-target.
+//!       [`decode_buffered_frame`] decodes a frame from received bytes that may not have all arrived yet.
+//!     - The ``read_fn`` given to the receive functions returns an error when there's no data to read yet,
+//!       except for [`CommunicationError::LineError`], which aborts the receive with that error.
 
-use super::Frame;
+use super::{Frame, MAX_OVERSIZED_FRAME_DISCARD};
 use crate::communication::{self, CommunicationError, Timer};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -16,6 +20,139 @@ enum TimeoutType {
     FrameLevel,
 }
 
+/// The state of a [`Decoder`].
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum DecodeState {
+    /// Discarding data until a \1 character is found, since any data that's not \1 is garbage.
+    Sync,
+
+    /// A \1 has been found, and the first hex digit of the frame hasn't been found yet. Any more \1
+    /// characters are skipped, since a frame must contain at least 1 character.
+    Start,
+
+    /// Inside the frame, reading hex digits until the closing \1.
+    Body,
+}
+
+/// A decoder for one BogoFrame that's given one byte at a time with [`Decoder::push`]. It has no
+/// dependency on how the bytes are read, so the same decoding is used for bytes read from a channel
+/// and for bytes already in memory.
+///
+/// The decoder upholds the following invariants no matter what bytes it's given:
+/// - It never writes at or past ``dest.len()``. Every write into ``dest`` is bounds-checked against
+///   the number of bytes decoded so far.
+/// - It never panics, and the length it returns is never greater than ``dest.len()``.
+/// - A frame longer than ``dest`` is read to its end and then rejected with
+///   [`CommunicationError::BufferTooSmall`] giving the length of the frame, which can't overflow since
+///   it's at most half the number of bytes given. A frame more than [`MAX_OVERSIZED_FRAME_DISCARD`]
+///   bytes longer than ``dest`` is rejected with [`CommunicationError::RecvError`] as soon as it passes
+///   that length instead of being read to its end.
+struct Decoder {
+    state: DecodeState,
+    high_nibble: Option<u8>,
+    len: usize,
+    min_message_len: usize,
+}
+
+impl Decoder {
+    /// Creates a decoder for a frame to be decoded into a buffer of ``dest_len`` bytes.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::BufferTooSmall`] - ``dest_len`` is less than ``min_message_len``.
+    fn new(dest_len: usize, min_message_len: usize) -> communication::Result<Self> {
+        if dest_len < min_message_len {
+            return Err(CommunicationError::BufferTooSmall {
+                needed: min_message_len,
+            });
+        }
+
+        Ok(Self {
+            state: DecodeState::Sync,
+            high_nibble: None,
+            len: 0,
+            min_message_len,
+        })
+    }
+
+    /// Returns whether the given byte resets a timer with a per-byte timeout. Every byte counts while
+    /// looking for the start of the frame, but NULL characters are ignored after that.
+    fn resets_timer(&self, byte: u8) -> bool {
+        self.state == DecodeState::Sync || byte != b'\0'
+    }
+
+    /// Decodes the next byte into ``dest``, returning the length of the frame once its closing \1 is
+    /// found. ``dest`` must be the same buffer every time.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - A character that's not hex, \1, or NULL was found in the
+    ///   frame, the frame has an odd number of hex digits, the frame is shorter than the minimum
+    ///   message length, or the frame is more than [`MAX_OVERSIZED_FRAME_DISCARD`] bytes longer than
+    ///   ``dest``.
+    /// - [`CommunicationError::BufferTooSmall`] - The frame is longer than ``dest``.
+    fn push(&mut self, byte: u8, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        let nibble = match byte {
+            b'\0' => return Ok(None),
+            1 => None,
+            b'0'..=b'9' => Some(byte - b'0'),
+            b'a'..=b'f' => Some(byte - b'a' + 10),
+            _ if self.state == DecodeState::Sync => return Ok(None),
+            _ => return Err(CommunicationError::RecvError),
+        };
+
+        match (self.state, nibble) {
+            (DecodeState::Sync, None) => {
+                self.state = DecodeState::Start;
+
+                Ok(None)
+            }
+            (DecodeState::Start, Some(high)) => {
+                self.state = DecodeState::Body;
+                self.high_nibble = Some(high);
+
+                Ok(None)
+            }
+            (DecodeState::Sync, Some(_)) | (DecodeState::Start, None) => Ok(None),
+            (DecodeState::Body, Some(low)) => {
+                if let Some(high) = self.high_nibble.take() {
+                    // Bytes past the end of dest aren't stored, but are still counted to find out how
+                    // big dest needs to be.
+                    if let Some(slot) = dest.get_mut(self.len) {
+                        *slot = (high << 4) | low;
+                    }
+
+                    self.len += 1;
+
+                    if self.len > dest.len().saturating_add(MAX_OVERSIZED_FRAME_DISCARD) {
+                        return Err(CommunicationError::RecvError);
+                    }
+                } else {
+                    self.high_nibble = Some(low);
+                }
+
+                Ok(None)
+            }
+            (DecodeState::Body, None) => {
+                // A \1 after the first hex digit of a byte means there's an odd number of hex digits.
+                if self.high_nibble.is_some() {
+                    return Err(CommunicationError::RecvError);
+                }
+
+                if self.len > dest.len() {
+                    return Err(CommunicationError::BufferTooSmall { needed: self.len });
+                }
+
+                if self.len < self.min_message_len {
+                    return Err(CommunicationError::RecvError);
+                }
+
+                Ok(Some(self.len))
+            }
+        }
+    }
+}
+
 /// Receives a bogoframe. [`TimeoutType`] determines whether the timeout resets after
 /// receiving a byte or whether the timemout applies to receiving the entire frame.
 fn recv_bogoframe<T, U: Timer>(
@@ -26,106 +163,89 @@ fn recv_bogoframe<T, U: Timer>(
     min_message_len: usize,
     timeout_type: TimeoutType,
 ) -> communication::Result<usize> {
-    /// Reads a hex nibble, returning ``Ok(None)`` if \1 character is encountered,
-    /// or the hex digit as a number if successful, or an error upon receiving
-    /// an invalid character or timeout. This function ignores any NULL characters
-    /// by reading again.
-    fn read_hex_nibble<T, U: Timer>(
-        read_fn_arg: &mut T,
-        mut read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
-        timer: &mut U,
-        timeout_type: TimeoutType,
-    ) -> communication::Result<Option<u8>> {
-        let nibble = loop {
-            if timer.poll() {
-                break Err(CommunicationError::RecvError);
-            }
-
-            if let Ok(read) = read_fn(read_fn_arg) {
-                match read {
-                    b'\0' => continue,
-                    1 => break Ok(None),
-                    b'0'..=b'9' => break Ok(Some(read - b'0')),
-                    b'a'..=b'f' => break Ok(Some(read - b'a' + 10)),
-                    _ => break Err(CommunicationError::RecvError),
-                };
-            }
-        };
-
-        // Reset the timer if the timeout is per byte only if a valid byte was encountered.
-        if nibble.is_ok() && timeout_type == TimeoutType::ByteLevel {
-            timer.reset();
-        }
-
-        nibble
-    }
-
-    if dest.len() < min_message_len {
-        return Err(CommunicationError::RecvError);
-    }
+    let mut decoder = Decoder::new(dest.len(), min_message_len)?;
 
-    // First, read and discard data until a \1 character is found because any data that's not \1
-    // is garbage, keeping the timeout in mind.
     loop {
         if timer.poll() {
-            return Err(CommunicationError::RecvError);
+            return Err(CommunicationError::Timeout);
         }
 
-        if let Ok(n) = read_fn(read_arg) {
-            // Reset the timer if the timeout is per byte.
-            if timeout_type == TimeoutType::ByteLevel {
-                timer.reset();
-            }
+        match read_fn(read_arg) {
+            Ok(byte) => {
+                // Reset the timer if the timeout is per byte.
+                if timeout_type == TimeoutType::ByteLevel && decoder.resets_timer(byte) {
+                    timer.reset();
+                }
 
-            if n == 1 {
-                break;
+                if let Some(len) = decoder.push(byte, dest)? {
+                    return Ok(len);
+                }
             }
+            // The byte was corrupted on the line, so it's not decoded.
+            Err(CommunicationError::LineError) => return Err(CommunicationError::LineError),
+            // Any other error from the read function means there's no data yet.
+            Err(_) => {}
         }
     }
+}
 
-    // Once a \1 is found, keep reading until we find a non-\1 and non-NULL character to indicate the start of
-    // a message, keeping the timeout in mind. We can do this because a frame must contain at least
-    // 1 character.
-    let mut first_nibble = loop {
-        // If we find an non-hex, non-\1, or non-NULL character, we return an error.
-        // If it's a \1 character, we continue.
-        // If it's a hex character, we return the number it represents.
-        if let Some(n) = read_hex_nibble(read_arg, &mut read_fn, timer, timeout_type)? {
-            break Some(n);
-        }
-    };
-
-    // Start reading bytes into dest until a \1 is found, the buffer is full before a \1 is reached,
-    // a non-hex character is read, or the timeout occurs.
-    for (idx, byte) in dest.iter_mut().enumerate() {
-        let second_nibble = read_hex_nibble(read_arg, &mut read_fn, timer, timeout_type)?;
-
-        let read = if let (Some(first), Some(second)) = (first_nibble, second_nibble) {
-            (first << 4) | second
-        } else {
-            // We've received a \1 character in the second nibble, which means we have an odd
-            // number of hex digits.
-            return Err(CommunicationError::RecvError);
-        };
+/// Decodes the first BogoFrame in ``input`` into ``dest``, returning the number of bytes written to
+/// ``dest`` upon success. Anything after the end of the frame is ignored. This decodes in exactly the
+/// same way as [`recv_frame_with_timeout`] and [`recv_frame_with_data_timeout`], which use it on each
+/// byte as it's read, but needs no channel or timer, so it can be fuzzed off-target.
+///
+/// No matter what ``input`` contains, this never panics, never writes past the end of ``dest``, and
+/// never returns a length greater than ``dest.len()``.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - ``input`` ends before a complete frame, a character that's
+///   not hex, \1, or NULL was found in the frame, the frame has an odd number of hex digits, the frame
+///   is shorter than ``min_message_len``, or the frame is more than [`MAX_OVERSIZED_FRAME_DISCARD`]
+///   bytes longer than ``dest``.
+/// - [`CommunicationError::BufferTooSmall`] - ``dest`` is shorter than the frame or shorter than
+///   ``min_message_len``.
+pub fn decode_frame(
+    input: &[u8],
+    dest: &mut [u8],
+    min_message_len: usize,
+) -> communication::Result<usize> {
+    let mut decoder = Decoder::new(dest.len(), min_message_len)?;
 
-        *byte = read;
+    for &byte in input {
+        if let Some(len) = decoder.push(byte, dest)? {
+            return Ok(len);
+        }
+    }
 
-        first_nibble = read_hex_nibble(read_arg, &mut read_fn, timer, timeout_type)?;
+    Err(CommunicationError::RecvError)
+}
 
-        // We've received a \1 character in the right place.
-        if first_nibble.is_none() {
-            let ct = idx + 1;
+/// Decodes the first BogoFrame in ``input`` into ``dest`` if ``input`` holds enough of it for a receive to
+/// finish, such as bytes buffered by an interrupt handler that haven't been read yet. Returns [`None`] if
+/// the frame hasn't finished arriving. Otherwise, returns the number of bytes of ``input`` that the
+/// receive used, which should be removed from the buffer, and the result of the receive, which is the
+/// same as [`recv_frame_with_timeout`] would give for those bytes. Nothing after the end of the frame is
+/// used, and ``dest`` may have been written to even if [`None`] is returned.
+pub fn decode_buffered_frame(
+    input: impl IntoIterator<Item = u8>,
+    dest: &mut [u8],
+    min_message_len: usize,
+) -> Option<(usize, communication::Result<usize>)> {
+    let mut decoder = match Decoder::new(dest.len(), min_message_len) {
+        Ok(decoder) => decoder,
+        Err(e) => return Some((0, Err(e))),
+    };
 
-            if ct < min_message_len {
-                return Err(CommunicationError::RecvError);
-            } else {
-                return Ok(ct);
-            }
+    for (used, byte) in input.into_iter().enumerate() {
+        match decoder.push(byte, dest) {
+            Ok(None) => {}
+            Ok(Some(len)) => return Some((used + 1, Ok(len))),
+            Err(e) => return Some((used + 1, Err(e))),
         }
     }
 
-    // If we've reached this point, it means we've reached the end of the buffer and haven't read \1
-    Err(CommunicationError::RecvError)
+    None
 }
 
 /// Receives a BogoFrame, blocking until the timer has elapsed from the beginning of this
@@ -149,6 +269,34 @@ pub fn recv_frame_with_timeout<T, U: Timer>(
     )
 }
 
+/// Receives a BogoFrame in the same way as [`recv_frame_with_timeout`], also returning the number of raw
+/// bytes read with ``read_fn``, whether or not a frame was received. This function mirrors
+/// [`RxChannel::recv_progress`](crate::communication::RxChannel::recv_progress()).
+/// See the documentation of that function for more details.
+pub fn recv_frame_with_progress<T, U: Timer>(
+    read_arg: &mut T,
+    dest: &mut [u8],
+    timer: &mut U,
+    mut read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
+    min_message_len: usize,
+) -> (usize, communication::Result<usize>) {
+    let mut bytes_read = 0;
+    let res = recv_frame_with_timeout(
+        read_arg,
+        dest,
+        timer,
+        |arg| {
+            let byte = read_fn(arg)?;
+            bytes_read += 1;
+
+            Ok(byte)
+        },
+        min_message_len,
+    );
+
+    (bytes_read, res)
+}
+
 /// Receives a BogoFrame with the timeout provided by the specified timer.
 /// This timeout resets each time a byte is read. This function mirrors
 /// [`RxChannel::recv_with_data_timeout`](crate::communication::RxChannel::recv_with_data_timeout()).
@@ -0,0 +1,273 @@
+//! The functions in this module are to help implement COBS based [`FramedTxChannels`](super::FramedTxChannel) and
+//! [`RxChannels`](crate::communication::RxChannel).
+//!
+//! - COBS
+//!     - Consistent Overhead Byte Stuffing removes every zero byte from the underlying data, so a zero byte can
+//!       be used as an unambiguous delimiter. Each message begins and ends with one zero byte.
+//!     - The data is split into blocks. Each block starts with a code byte, which is one more than the number of
+//!       non-zero data bytes following it. A block with a code byte less than 0xFF is followed by a zero byte in
+//!       the underlying data unless it's the last block in the message.
+//!     - Because a zero byte can never appear inside an encoded message, every zero byte is a message boundary.
+//!       A receive discards everything up to the first zero byte it reads, and a frame that's cut short by a
+//!       zero byte is dropped in favor of the frame that zero byte starts. This way, a receiver that loses sync
+//!       because of corrupted or dropped bytes is back in sync by the next message boundary.
+//!     - A frame longer than ``dest`` is read to its end so the receive can report how long it is, unless
+//!       it's more than [`MAX_OVERSIZED_FRAME_DISCARD`] bytes longer than ``dest``. Then the receive fails
+//!       with [`CommunicationError::RecvError`] right away, and the rest of the frame is discarded by the
+//!       next receive like any other partial frame.
+//!     - Helper functions to implement channels using this type of framing are in the [`cobs`](self) module.
+//!     - The ``read_fn`` given to the receive functions returns an error when there's no data to read yet,
+//!       except for [`CommunicationError::LineError`], which aborts the receive with that error.
+
+use super::{Frame, MAX_OVERSIZED_FRAME_DISCARD};
+use crate::communication::{self, CommunicationError, Timer};
+
+/// The largest code byte in a COBS block, which indicates that the block has 254 data bytes and isn't
+/// followed by a zero byte.
+const MAX_CODE: u8 = 0xFF;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum TimeoutType {
+    ByteLevel,
+    FrameLevel,
+}
+
+/// Receives a COBS frame. [`TimeoutType`] determines whether the timeout resets after
+/// receiving a byte or whether the timemout applies to receiving the entire frame.
+fn recv_cobs_frame<T, U: Timer>(
+    read_arg: &mut T,
+    dest: &mut [u8],
+    timer: &mut U,
+    mut read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
+    min_message_len: usize,
+    timeout_type: TimeoutType,
+) -> communication::Result<usize> {
+    /// Reads a byte, returning an error upon timeout.
+    fn read_byte<T, U: Timer>(
+        read_fn_arg: &mut T,
+        mut read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
+        timer: &mut U,
+        timeout_type: TimeoutType,
+    ) -> communication::Result<u8> {
+        loop {
+            if timer.poll() {
+                return Err(CommunicationError::Timeout);
+            }
+
+            match read_fn(read_fn_arg) {
+                Ok(read) => {
+                    // Reset the timer if the timeout is per byte.
+                    if timeout_type == TimeoutType::ByteLevel {
+                        timer.reset();
+                    }
+
+                    return Ok(read);
+                }
+                // The byte was corrupted on the line, so it's not decoded.
+                Err(CommunicationError::LineError) => return Err(CommunicationError::LineError),
+                // Any other error from the read function means there's no data yet.
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Writes a decoded byte into dest if there's space for it and counts it either way. Gives up on
+    /// the frame once it's more than MAX_OVERSIZED_FRAME_DISCARD bytes longer than dest.
+    fn push_byte(dest: &mut [u8], ct: &mut usize, byte: u8) -> communication::Result<()> {
+        if let Some(b) = dest.get_mut(*ct) {
+            *b = byte;
+        }
+
+        *ct += 1;
+
+        if *ct > dest.len().saturating_add(MAX_OVERSIZED_FRAME_DISCARD) {
+            return Err(CommunicationError::RecvError);
+        }
+
+        Ok(())
+    }
+
+    if dest.len() < min_message_len {
+        return Err(CommunicationError::BufferTooSmall {
+            needed: min_message_len,
+        });
+    }
+
+    // First, discard everything up to the next zero byte. Anything before it is the rest of a frame that
+    // was cut short or corrupted, or the end of a frame that started before this receive, so it can't be
+    // decoded. In sync, this is the zero byte that starts this frame.
+    while read_byte(read_arg, &mut read_fn, timer, timeout_type)? != 0 {}
+
+    let mut ct;
+
+    'frame: loop {
+        // Skip any more zero bytes. These are the delimiters of this frame and of any empty frames.
+        // The first non-zero byte is the code byte of the first block.
+        let mut code = loop {
+            let read = read_byte(read_arg, &mut read_fn, timer, timeout_type)?;
+
+            if read != 0 {
+                break read;
+            }
+        };
+
+        ct = 0;
+
+        // Decode blocks into dest until the terminating zero byte is found. Every write is bounds checked,
+        // so a frame that decodes to more than dest can hold is only counted instead of overflowing dest.
+        // Such a frame is rejected once it has been fully read, or once it's too long to keep reading.
+        loop {
+            for _ in 1..code {
+                let read = read_byte(read_arg, &mut read_fn, timer, timeout_type)?;
+
+                // A zero byte in the middle of a block means the frame was cut short. A zero byte is
+                // always a delimiter, so it starts the next frame, which is decoded instead.
+                if read == 0 {
+                    continue 'frame;
+                }
+
+                push_byte(dest, &mut ct, read)?;
+            }
+
+            let next_code = read_byte(read_arg, &mut read_fn, timer, timeout_type)?;
+
+            // We've received the terminating zero byte.
+            if next_code == 0 {
+                break 'frame;
+            }
+
+            // Every block except a maximum length block is followed by a zero byte if it's not the last
+            // block.
+            if code != MAX_CODE {
+                push_byte(dest, &mut ct, 0)?;
+            }
+
+            code = next_code;
+        }
+    }
+
+    if ct > dest.len() {
+        Err(CommunicationError::BufferTooSmall { needed: ct })
+    } else if ct < min_message_len {
+        Err(CommunicationError::RecvError)
+    } else {
+        Ok(ct)
+    }
+}
+
+/// Receives a COBS frame, blocking until the timer has elapsed from the beginning of this
+/// function call. This function mirrors
+/// [`RxChannel::recv_with_timeout`](crate::communication::RxChannel::recv_with_timeout()).
+/// See the documentation of that function for more details.
+pub fn recv_frame_with_timeout<T, U: Timer>(
+    read_arg: &mut T,
+    dest: &mut [u8],
+    timer: &mut U,
+    read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
+    min_message_len: usize,
+) -> communication::Result<usize> {
+    recv_cobs_frame(
+        read_arg,
+        dest,
+        timer,
+        read_fn,
+        min_message_len,
+        TimeoutType::FrameLevel,
+    )
+}
+
+/// Receives a COBS frame in the same way as [`recv_frame_with_timeout`], also returning the number of raw
+/// bytes read with ``read_fn``, whether or not a frame was received. This function mirrors
+/// [`RxChannel::recv_progress`](crate::communication::RxChannel::recv_progress()).
+/// See the documentation of that function for more details.
+pub fn recv_frame_with_progress<T, U: Timer>(
+    read_arg: &mut T,
+    dest: &mut [u8],
+    timer: &mut U,
+    mut read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
+    min_message_len: usize,
+) -> (usize, communication::Result<usize>) {
+    let mut bytes_read = 0;
+    let res = recv_frame_with_timeout(
+        read_arg,
+        dest,
+        timer,
+        |arg| {
+            let byte = read_fn(arg)?;
+            bytes_read += 1;
+
+            Ok(byte)
+        },
+        min_message_len,
+    );
+
+    (bytes_read, res)
+}
+
+/// Receives a COBS frame with the timeout provided by the specified timer.
+/// This timeout resets each time a byte is read. This function mirrors
+/// [`RxChannel::recv_with_data_timeout`](crate::communication::RxChannel::recv_with_data_timeout()).
+/// See the documentation of that function for more details.
+pub fn recv_frame_with_data_timeout<T, U: Timer>(
+    read_arg: &mut T,
+    dest: &mut [u8],
+    timer: &mut U,
+    read_fn: impl FnMut(&mut T) -> communication::Result<u8>,
+    min_message_len: usize,
+) -> communication::Result<usize> {
+    recv_cobs_frame(
+        read_arg,
+        dest,
+        timer,
+        read_fn,
+        min_message_len,
+        TimeoutType::ByteLevel,
+    )
+}
+
+/// Sends a COBS frame with the given [`Frame`]. This function mirrors
+/// [`FramedTxChannel::frame`](super::FramedTxChannel::frame()). See the documentation of
+/// that function for more details.
+pub fn frame_cobs<const FRAME_CT: usize, T>(
+    write_arg: &mut T,
+    frame: Frame<FRAME_CT>,
+    mut write_fn: impl FnMut(&mut T, &[u8]) -> communication::Result<()>,
+    min_message_len: usize,
+) -> communication::Result<()> {
+    if frame.len() < min_message_len {
+        return Err(CommunicationError::SendError);
+    }
+
+    // The current block. The first byte is the code byte, which is filled in when the block is written.
+    let mut block = [0; MAX_CODE as usize];
+    let mut code = 1;
+
+    write_fn(write_arg, &[0])?;
+
+    for frame_piece in frame {
+        for &byte in frame_piece {
+            if byte == 0 {
+                // End the current block. The zero byte is implied by the code byte.
+                block[0] = code;
+                write_fn(write_arg, &block[..code as usize])?;
+                code = 1;
+            } else {
+                block[code as usize] = byte;
+                code += 1;
+
+                // The block is full, so write it out without an implied zero byte.
+                if code == MAX_CODE {
+                    block[0] = code;
+                    write_fn(write_arg, &block)?;
+                    code = 1;
+                }
+            }
+        }
+    }
+
+    block[0] = code;
+    write_fn(write_arg, &block[..code as usize])?;
+    write_fn(write_arg, &[0])?;
+
+    Ok(())
+}
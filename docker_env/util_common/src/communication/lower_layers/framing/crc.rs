@@ -0,0 +1,161 @@
+//! This module contains channels that add CRC-16/CCITT integrity checking on top of another framing
+//! implementation. These channels detect accidental corruption only. They provide no protection against
+//! an attacker, who can simply recompute the CRC, so channels in the [`crypto`](crate::communication::lower_layers::crypto)
+//! layer should still be used for anything that needs integrity against tampering.
+//!
+//! - CRC framing
+//!     - A CRC-16/CCITT over the message is appended to the end of the message in big-endian byte order
+//!       before it's passed to the inner framing channel.
+//!     - The CRC uses the polynomial [`CRC16_CCITT_POLYNOMIAL`], an initial value of [`CRC16_CCITT_INIT`],
+//!       no input or output reflection, and no final XOR.
+
+use super::{Frame, FramedTxChannel};
+use crate::{
+    communication::{self, CommunicationError, RxChannel},
+    timer::Timer,
+};
+
+/// The CRC-16/CCITT generator polynomial, x^16 + x^12 + x^5 + 1.
+pub const CRC16_CCITT_POLYNOMIAL: u16 = 0x1021;
+
+/// The initial value of the CRC register.
+pub const CRC16_CCITT_INIT: u16 = 0xFFFF;
+
+/// The number of bytes a CRC adds to each message.
+pub const CRC_SIZE: usize = 2;
+
+/// Updates a CRC-16/CCITT with the given data, returning the new CRC.
+pub fn crc16_ccitt_update(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ CRC16_CCITT_POLYNOMIAL
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+/// Computes the CRC-16/CCITT of the given data.
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    crc16_ccitt_update(CRC16_CCITT_INIT, data)
+}
+
+/// A [`FramedTxChannel`] that appends a CRC-16/CCITT to every frame before sending it through the
+/// wrapped [`FramedTxChannel`]. A [`Frame`] sent through this channel can have any number of slices,
+/// but can't have a trailer, since the CRC is sent as the trailer.
+pub struct CrcFramedTxChannel<T: FramedTxChannel> {
+    channel: T,
+}
+
+impl<T: FramedTxChannel> CrcFramedTxChannel<T> {
+    /// Creates a new [`CrcFramedTxChannel`] wrapping the provided channel.
+    pub fn new(channel: T) -> Self {
+        Self { channel }
+    }
+
+    /// Consumes the [`CrcFramedTxChannel`], returning the wrapped channel.
+    pub fn into_inner(self) -> T {
+        self.channel
+    }
+}
+
+impl<T: FramedTxChannel> FramedTxChannel for CrcFramedTxChannel<T> {
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        let frame = frame()?;
+        let crc = frame.iter().fold(CRC16_CCITT_INIT, crc16_ccitt_update);
+        let crc_bytes = crc.to_be_bytes();
+
+        self.channel.frame(|| frame.with_trailer(&crc_bytes))
+    }
+
+    fn flush_frames(&mut self) -> communication::Result<()> {
+        self.channel.flush()
+    }
+}
+
+/// An [`RxChannel`] that checks and strips the CRC-16/CCITT at the end of every message received through
+/// the wrapped [`RxChannel`]. The destination buffer passed to the receive functions must have
+/// [`CRC_SIZE`] extra bytes of space for the CRC.
+pub struct CrcFramedRxChannel<T: RxChannel> {
+    channel: T,
+}
+
+impl<T: RxChannel> CrcFramedRxChannel<T> {
+    /// Creates a new [`CrcFramedRxChannel`] wrapping the provided channel.
+    pub fn new(channel: T) -> Self {
+        Self { channel }
+    }
+
+    /// Consumes the [`CrcFramedRxChannel`], returning the wrapped channel.
+    pub fn into_inner(self) -> T {
+        self.channel
+    }
+
+    /// Checks the CRC at the end of a received message, returning the length of the message without
+    /// the CRC.
+    fn check_crc(dest: &[u8], bytes_read: usize) -> communication::Result<usize> {
+        // Don't trust the inner channel's length.
+        if bytes_read < CRC_SIZE || bytes_read > dest.len() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        let (msg, crc) = dest[..bytes_read].split_at(bytes_read - CRC_SIZE);
+
+        if !crate::constant_time_eq(&crc16_ccitt(msg).to_be_bytes(), crc) {
+            return Err(CommunicationError::RecvError);
+        }
+
+        Ok(msg.len())
+    }
+}
+
+impl<T: RxChannel> RxChannel for CrcFramedRxChannel<T> {
+    fn recv_with_data_timeout<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        let bytes_read = self.channel.recv_with_data_timeout(dest, timer)?;
+
+        Self::check_crc(dest, bytes_read)
+    }
+
+    fn recv_with_timeout<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<usize> {
+        let bytes_read = self.channel.recv_with_timeout(dest, timer)?;
+
+        Self::check_crc(dest, bytes_read)
+    }
+
+    fn recv_exact<U: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut U,
+    ) -> communication::Result<()> {
+        // The CRC takes up the end of dest, so the message must fill the rest of it.
+        if self.recv_with_timeout(dest, timer)? != dest.len().saturating_sub(CRC_SIZE) {
+            return Err(CommunicationError::RecvError);
+        }
+
+        Ok(())
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        match self.channel.try_recv(dest)? {
+            Some(bytes_read) => Self::check_crc(dest, bytes_read).map(Some),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! This module contains the [`ChannelWriter`] adapter, which lets formatted text be written to any
+//! [`TxChannel`] with [`write!`] and [`writeln!`].
+
+use super::{CommunicationError, Result, TxChannel};
+use core::fmt::{self, Write};
+
+/// The default size of the buffer of a [`ChannelWriter`].
+pub const DEFAULT_CHANNEL_WRITER_SIZE: usize = 128;
+
+/// A [`Write`] adapter for a [`TxChannel`]. Formatted text is collected into a buffer of ``N`` bytes,
+/// and each line is sent as one message, including its newline, once the newline is written. A line
+/// longer than the buffer is split into messages of ``N`` bytes. Text that doesn't end with a newline
+/// is held until [`ChannelWriter::flush()`] is called or the writer is dropped.
+///
+/// ```ignore
+/// let mut writer = ChannelWriter::new(&mut channel);
+///
+/// if writeln!(writer, "car {car_id}: {} features", features.len()).is_err() {
+///     return writer.take_error();
+/// }
+/// ```
+///
+/// [`fmt::Error`] carries no information, so when sending fails, the [`CommunicationError`] is kept
+/// until it's taken with [`ChannelWriter::take_error()`]. The buffered bytes of the message that failed
+/// to send are discarded.
+pub struct ChannelWriter<'a, T: TxChannel, const N: usize = DEFAULT_CHANNEL_WRITER_SIZE> {
+    channel: &'a mut T,
+    buff: [u8; N],
+    len: usize,
+    error: Option<CommunicationError>,
+}
+
+impl<'a, T: TxChannel, const N: usize> ChannelWriter<'a, T, N> {
+    /// Creates a new [`ChannelWriter`] that sends to the provided channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if ``N`` is zero.
+    pub fn new(channel: &'a mut T) -> Self {
+        assert!(N != 0, "ChannelWriter buffer can't be empty.");
+
+        Self {
+            channel,
+            buff: [0; N],
+            len: 0,
+            error: None,
+        }
+    }
+
+    /// Gets a mutable reference to the wrapped channel. Bytes still in the buffer aren't sent first.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.channel
+    }
+
+    /// Takes the error from the last send that failed, if there is one. Once taken, this returns
+    /// ``None`` until another send fails.
+    pub fn take_error(&mut self) -> Option<CommunicationError> {
+        self.error.take()
+    }
+
+    /// Sends any buffered bytes as one message, then flushes the wrapped channel.
+    ///
+    /// # ERRORS:
+    ///
+    /// - Any error from [`TxChannel::send()`] or [`TxChannel::flush()`] of the wrapped channel. The
+    ///   buffered bytes are discarded if they couldn't be sent.
+    pub fn flush(&mut self) -> Result<()> {
+        self.send_buffered()?;
+        self.channel.flush()
+    }
+
+    /// Sends the buffered bytes as one message, if there are any.
+    fn send_buffered(&mut self) -> Result<()> {
+        if self.len == 0 {
+            return Ok(());
+        }
+
+        let len = self.len;
+        self.len = 0;
+
+        self.channel.send(&mut self.buff[..len])
+    }
+}
+
+impl<T: TxChannel, const N: usize> Write for ChannelWriter<'_, T, N> {
+    /// Buffers the string, sending a message at each newline and whenever the buffer fills up.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            self.buff[self.len] = byte;
+            self.len += 1;
+
+            if byte == b'\n' || self.len == N {
+                self.send_buffered().map_err(|err| {
+                    self.error = Some(err);
+                    fmt::Error
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: TxChannel, const N: usize> Drop for ChannelWriter<'_, T, N> {
+    /// Sends any buffered bytes. Errors can't be reported here, so call [`ChannelWriter::flush()`]
+    /// before dropping the writer to find out whether they were sent.
+    fn drop(&mut self) {
+        let _ = self.send_buffered();
+    }
+}
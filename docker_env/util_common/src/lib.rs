@@ -6,3 +6,58 @@
 pub mod communication;
 pub mod messages;
 pub mod timer;
+
+use subtle::ConstantTimeEq;
+
+/// Compares two byte slices for equality in time that doesn't depend on their contents, so it's safe
+/// to use on secrets such as challenges, PINs, and authentication tags. Returns false immediately if
+/// the slices have different lengths, so the length itself isn't hidden; only compare values whose
+/// length is public.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// The reflected CRC-32 (IEEE 802.3) generator polynomial.
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// The CRC-32 of every 4-bit value, so [`crc32()`] can process data a nibble at a time instead of a bit
+/// at a time. A nibble table is used instead of a byte table to keep it small in flash.
+const CRC32_NIBBLE_TABLE: [u32; 16] = {
+    let mut table = [0; 16];
+    let mut i = 0;
+
+    while i < 16 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 4 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// Computes the CRC-32 (IEEE 802.3) of the given data, such as to detect corrupted EEPROM records. This
+/// detects accidental corruption only and provides no protection against an attacker.
+///
+/// The TM4C123 has no CRC peripheral, so this is computed in software on every platform.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0;
+
+    for &byte in data {
+        crc ^= u32::from(byte);
+        crc = (crc >> 4) ^ CRC32_NIBBLE_TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) ^ CRC32_NIBBLE_TABLE[(crc & 0xF) as usize];
+    }
+
+    !crc
+}
@@ -1,6 +1,20 @@
 //! This module is responsible for providing [`serde`] serializable/deserializable structs
 //! for messages sent between the car, key fob, and host tools.
-
+//!
+//! ## Encoding
+//!
+//! Messages are encoded with [`postcard`] by [`encode`] and decoded by [`decode`], which are also
+//! provided as methods on [`Uart0Message`] and [`Uart1Message`]. [`send_message`] and [`recv_message`]
+//! do the same over a [`TxChannel`] or [`RxChannel`]. The first byte of an encoded [`Uart0Message`] or
+//! [`Uart1Message`] is the index of its variant, which acts as the message type. This means new variants
+//! must only be added at the end of these enums, and existing variants must never be removed or
+//! reordered, or devices built from different versions would disagree on what a message is. A message
+//! with an unknown type fails to decode instead of being mistaken for another one.
+
+use crate::{
+    communication::{self, CommunicationError, RxChannel, TxChannel},
+    timer::Timer,
+};
 use k256::{
     ecdsa::{signature::Verifier, Signature, VerifyingKey},
     elliptic_curve::sec1::FromEncodedPoint,
@@ -60,6 +74,18 @@ pub enum Uart0Message<'a> {
     PairingPinResponse(HostToolAck),
 }
 
+impl<'a> Uart0Message<'a> {
+    /// Encodes this message into ``dest``. See [`encode`] for more info.
+    pub fn encode(&self, dest: &mut [u8]) -> communication::Result<usize> {
+        encode(self, dest)
+    }
+
+    /// Decodes a message from ``src``. See [`decode`] for more info.
+    pub fn decode(src: &'a [u8]) -> communication::Result<Self> {
+        decode(src)
+    }
+}
+
 /// This enum represents all possible messages that can be sent across UART1 between
 /// a car and its paired key fob or between a paired key fob and an unpaired key fob.
 #[non_exhaustive]
@@ -110,6 +136,80 @@ pub enum Uart1Message<'a> {
     PairingChallengeResponse(PairingChallengeResponse),
 }
 
+impl<'a> Uart1Message<'a> {
+    /// Encodes this message into ``dest``. See [`encode`] for more info.
+    pub fn encode(&self, dest: &mut [u8]) -> communication::Result<usize> {
+        encode(self, dest)
+    }
+
+    /// Decodes a message from ``src``. See [`decode`] for more info.
+    pub fn decode(src: &'a [u8]) -> communication::Result<Self> {
+        decode(src)
+    }
+}
+
+/// Encodes a message into ``dest``, returning the number of bytes written to it upon success. See the
+/// module-level documentation for the encoding used.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::SendError`] - The encoded message doesn't fit in ``dest``.
+pub fn encode<M: Serialize>(msg: &M, dest: &mut [u8]) -> communication::Result<usize> {
+    postcard::to_slice(msg, dest)
+        .map(|encoded| encoded.len())
+        .map_err(|_| CommunicationError::SendError)
+}
+
+/// Decodes a message from ``src``. The decoded message can borrow from ``src``. Anything after the end
+/// of the message is ignored. See the module-level documentation for the encoding used.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - ``src`` doesn't start with a valid message, such as if the
+///   message type is unknown or the message is cut short.
+pub fn decode<'a, M: Deserialize<'a>>(src: &'a [u8]) -> communication::Result<M> {
+    postcard::from_bytes(src).map_err(|_| CommunicationError::RecvError)
+}
+
+/// Encodes a message into ``buf`` and sends it through ``channel``. ``buf`` is only used to hold the
+/// encoded message, and channels in the crypto layer encrypt it in place, so its contents should be
+/// considered garbage once this function returns.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::SendError`] - The encoded message doesn't fit in ``buf``.
+///
+/// This function also returns any error from [`TxChannel::send`].
+pub fn send_message<M: Serialize>(
+    channel: &mut impl TxChannel,
+    msg: &M,
+    buf: &mut [u8],
+) -> communication::Result<()> {
+    let len = encode(msg, buf)?;
+
+    channel.send(&mut buf[..len])
+}
+
+/// Receives a message from ``channel`` into ``buf`` with [`RxChannel::recv_with_timeout`] and decodes
+/// it. The decoded message can borrow from ``buf``, so ``buf`` must be large enough for the received
+/// message along with any metadata of the channel.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - The message received couldn't be decoded. See [`decode`].
+///
+/// This function also returns any error from [`RxChannel::recv_with_timeout`].
+pub fn recv_message<'a, M: Deserialize<'a>, T: Timer>(
+    channel: &mut impl RxChannel,
+    buf: &'a mut [u8],
+    timer: &mut T,
+) -> communication::Result<M> {
+    let len = channel.recv_with_timeout(buf, timer)?;
+    let buf: &'a [u8] = buf;
+
+    decode(&buf[..len])
+}
+
 /// The message to send to a car to signal the start of an unlock seequence.
 /// It contains the car ID of the car to be unlocked.
 #[derive(Serialize, Deserialize)]
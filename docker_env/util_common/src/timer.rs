@@ -13,4 +13,28 @@ pub trait Timer {
 
     /// Gets the total duration of the timer.
     fn duration(&self) -> Duration;
+
+    /// Gets how much time has passed since the timer was created or last reset, saturating at the
+    /// duration of the timer.
+    fn elapsed(&self) -> Duration;
+
+    /// Gets how much time is left before time is up, or zero if time is already up.
+    fn remaining(&self) -> Duration {
+        self.duration().saturating_sub(self.elapsed())
+    }
+}
+
+/// Spins until ``predicate`` returns ``true`` or the timer is up, whichever comes first, returning
+/// whether the predicate returned ``true``. The predicate is checked before the timer on each pass, so
+/// it's checked at least once, even with a timer that's already up. The timer isn't reset first.
+pub fn spin_until<T: Timer>(timer: &mut T, mut predicate: impl FnMut() -> bool) -> bool {
+    loop {
+        if predicate() {
+            return true;
+        }
+
+        if timer.poll() {
+            return false;
+        }
+    }
 }
@@ -1,74 +1,291 @@
-//! A button module containing an interface to use the onboard SW1 button.
+//! A button module containing an interface to use the onboard SW1 button and a debounced [`Button`]
+//! type for any button connected to a pull-up GPIO input pin, such as the onboard SW2 button. Presses
+//! of both onboard buttons are also reported as [`RuntimeEvent::ButtonPressed`] events.
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use crate::{
+    event::{self, RuntimeEvent},
+    hib::HibController,
+    timer::Timer,
+    Sw2ButtonPin,
+};
+use core::{
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
+    time::Duration,
+};
 use cortex_m::peripheral::NVIC;
+use embedded_hal::digital::v2::InputPin;
 use tm4c123x_hal::{
     bb,
     gpio::{gpiof::PF4, Input, InterruptMode, PullUp},
     interrupt,
-    tm4c123x::GPIO_PORTF,
+    sysctl::{self, Clocks, Domain, PowerControl, PowerState, RunMode},
+    tm4c123x::{gpio_porta, timer0, Interrupt, GPIO_PORTF, TIMER5},
 };
 
+/// The pin number of PF0.
+const PF0_PIN_NUMBER: u8 = 0;
+
 /// The pin number of PF4.
 const PF4_PIN_NUMBER: u8 = 4;
 
+/// How long a button's interrupt stays masked after it's pressed, and again after it's released, so
+/// its bounces aren't reported as more presses.
+pub const BUTTON_DEBOUNCE: Duration = Duration::from_millis(20);
+
+/// The timer enable bit of the GPTMCTL register.
+const GPTMCTL_TAEN: u32 = 1 << 0;
+
+/// The bit of the GPTMCTL register that stops the timer while the processor is halted by a debugger.
+const GPTMCTL_TASTALL: u32 = 1 << 1;
+
+/// The one-shot mode value of the GPTMTAMR register.
+const GPTMTAMR_ONE_SHOT: u32 = 0x1;
+
+/// The timeout interrupt bit of the GPTMIMR and GPTMICR registers.
+const GPTM_TATO: u32 = 1 << 0;
+
 /// Whether the Sw1ButtonController is initialized.
 static SW1_BUTTON_CONTROLLER_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 /// Whether the PF4 pin interrupt has been triggered.
 static PF4_ACTIVATED: AtomicBool = AtomicBool::new(false);
 
+/// The buttons whose interrupts are masked while they're debounced, as a bit for each pin number.
+static DEBOUNCING: AtomicU8 = AtomicU8::new(0);
+
+/// The debouncing buttons that were released when the debounce timer last ran out, as a bit for each
+/// pin number.
+static RELEASED: AtomicU8 = AtomicU8::new(0);
+
+/// The number of system clock cycles in [`BUTTON_DEBOUNCE`].
+static DEBOUNCE_CYCLES: AtomicU32 = AtomicU32::new(0);
+
+/// One of the onboard buttons, as given by [`RuntimeEvent::ButtonPressed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonId {
+    /// The SW1 button on PF4.
+    Sw1,
+
+    /// The SW2 button on PF0.
+    Sw2,
+}
+
+impl ButtonId {
+    /// Every onboard button.
+    const ALL: [Self; 2] = [Self::Sw1, Self::Sw2];
+
+    /// Gets the number of the port F pin the button is on.
+    fn pin_number(self) -> u8 {
+        match self {
+            Self::Sw1 => PF4_PIN_NUMBER,
+            Self::Sw2 => PF0_PIN_NUMBER,
+        }
+    }
+}
+
+/// Gets the port F registers.
+///
+/// # Safety
+///
+/// This must only be called in an interrupt-free context while there is an instance of
+/// Sw1ButtonController. Sw1ButtonController is created only by Runtime, which requires a mutable
+/// reference to RuntimePeripherals. RuntimePeripherals can only be created once because it takes
+/// ownership of Peripherals and CorePeripherals. RuntimePeripherals also splits GPIO_PORTF into
+/// individual pins, destroying GPIO_PORTF in the process, thus making any way of getting and
+/// dereferencing the GPIO port F register block unsafe from that point and on. This ensures that the
+/// definition of LLVM noalias is satisfied.
+unsafe fn gpio_portf<'a>() -> &'a gpio_porta::RegisterBlock {
+    &*GPIO_PORTF::ptr()
+}
+
+/// Gets the TIMER5 registers.
+///
+/// # Safety
+///
+/// This must only be called in an interrupt-free context while there is an instance of
+/// Sw1ButtonController, which holds a mutable reference to TIMER5, so nothing else can access it.
+unsafe fn timer5<'a>() -> &'a timer0::RegisterBlock {
+    &*TIMER5::ptr()
+}
+
+/// Starts the debounce timer from the full [`BUTTON_DEBOUNCE`], restarting it if it's running.
+fn start_debounce_timer(timer: &timer0::RegisterBlock) {
+    // SAFETY: Every value is valid for these registers. The timer is stopped before it's reloaded.
+    unsafe {
+        timer.ctl.modify(|r, w| w.bits(r.bits() & !GPTMCTL_TAEN));
+        timer
+            .tailr
+            .write(|w| w.bits(DEBOUNCE_CYCLES.load(Ordering::SeqCst)));
+        timer.ctl.modify(|r, w| w.bits(r.bits() | GPTMCTL_TAEN));
+    }
+}
+
 #[interrupt]
 fn GPIOF() {
     cortex_m::interrupt::free(|_| {
-        // Check that Sw1ButtonController is initialized to uphold the safety comment below.
+        // Check that Sw1ButtonController is initialized to uphold the safety comments below.
         if !SW1_BUTTON_CONTROLLER_INITIALIZED.load(Ordering::SeqCst) {
             return;
         }
 
-        // SAFETY: This is safe because this is run in an interrupt-free context and this code is run
-        // only when there is an instance of Sw1ButtonController. Sw1ButtonController is created only
-        // by Runtime, which requires a mutable reference to RuntimePeripherals. RuntimePeripherals
-        // can only be created once because it takes ownership of Peripherals and CorePeripherals.
-        // RuntimePeripherals also splits GPIO_PORTF into individual pins, destroying GPIO_PORTF in
-        // the process, thus making any way of getting and dereferencing the GPIO port F register
-        // block unsafe from that point and on. This ensures that the definition of LLVM noalias is
-        // satisfied.
-        let gpio_portf = unsafe { &*GPIO_PORTF::ptr() };
-
-        // Check that the interrupt was actually triggered by PF4.
-        if !bb::read_bit(&gpio_portf.mis, PF4_PIN_NUMBER) {
+        // SAFETY: This is run in an interrupt-free context while there is an instance of
+        // Sw1ButtonController.
+        let (gpio_portf, timer) = unsafe { (gpio_portf(), timer5()) };
+
+        for button in ButtonId::ALL {
+            let pin = button.pin_number();
+
+            // Check that the interrupt was actually triggered by this button.
+            if !bb::read_bit(&gpio_portf.mis, pin) {
+                continue;
+            }
+
+            if button == ButtonId::Sw1 {
+                PF4_ACTIVATED.store(true, Ordering::SeqCst);
+            }
+
+            // Mask the button's interrupt so its bounces aren't reported. The debounce timer unmasks it
+            // once the button has been released for a whole debounce period.
+            //
+            // SAFETY: This is safe because the pointer is guaranteed to be valid. The guarantees from
+            // the earlier safety comment apply here as well.
+            unsafe {
+                bb::change_bit(&gpio_portf.im, pin, false);
+                bb::change_bit(&gpio_portf.icr, pin, true);
+            }
+
+            DEBOUNCING.fetch_or(1 << pin, Ordering::SeqCst);
+            RELEASED.fetch_and(!(1 << pin), Ordering::SeqCst);
+            event::push_event(RuntimeEvent::ButtonPressed(button));
+            start_debounce_timer(timer);
+        }
+    });
+}
+
+#[interrupt]
+fn TIMER5A() {
+    cortex_m::interrupt::free(|_| {
+        // Check that Sw1ButtonController is initialized to uphold the safety comments below.
+        if !SW1_BUTTON_CONTROLLER_INITIALIZED.load(Ordering::SeqCst) {
             return;
         }
 
-        PF4_ACTIVATED.store(true, Ordering::SeqCst);
+        // SAFETY: This is run in an interrupt-free context while there is an instance of
+        // Sw1ButtonController.
+        let (gpio_portf, timer) = unsafe { (gpio_portf(), timer5()) };
+
+        // SAFETY: Every value is valid for the interrupt clear register.
+        timer.icr.write(|w| unsafe { w.bits(GPTM_TATO) });
+
+        let mut debouncing = DEBOUNCING.load(Ordering::SeqCst);
+        let mut released = RELEASED.load(Ordering::SeqCst);
+
+        for button in ButtonId::ALL {
+            let bit = 1 << button.pin_number();
+
+            if debouncing & bit == 0 {
+                continue;
+            }
+
+            if !bb::read_bit(&gpio_portf.data, button.pin_number()) {
+                // The button is still held down, so wait for it to be released.
+                released &= !bit;
+            } else if released & bit == 0 {
+                // The button was just released, so wait out the bounces of the release.
+                released |= bit;
+            } else {
+                // The button has stayed released for a whole debounce period. Clear the edges the
+                // bounces left behind before unmasking, so they aren't reported as a press.
+                //
+                // SAFETY: This is safe because the pointer is guaranteed to be valid. The guarantees
+                // from the earlier safety comment apply here as well.
+                unsafe {
+                    bb::change_bit(&gpio_portf.icr, button.pin_number(), true);
+                    bb::change_bit(&gpio_portf.im, button.pin_number(), true);
+                }
+
+                debouncing &= !bit;
+                released &= !bit;
+            }
+        }
+
+        DEBOUNCING.store(debouncing, Ordering::SeqCst);
+        RELEASED.store(released, Ordering::SeqCst);
 
-        // SAFETY: This is safe because the pointer is guaranteed to be valid. The guarantees from
-        // the earlier safety comment apply here as well.
-        unsafe { bb::change_bit(&gpio_portf.icr, PF4_PIN_NUMBER, true) };
+        if debouncing != 0 {
+            start_debounce_timer(timer);
+        }
     });
 }
 
-/// A struct for the SW1 button controller. The button controller does not provide any debouncing.
+/// A struct for the SW1 button controller. This also owns the edge interrupts of both onboard
+/// buttons, which push a [`RuntimeEvent::ButtonPressed`] into the [`event`](crate::event) queue when
+/// a button is pressed, so a low-power loop can sleep until a button is pressed.
+///
+/// Presses are debounced without polling. When a button is pressed, its interrupt is masked and the
+/// TIMER5 one-shot timer is started. When the timer runs out, the interrupt is unmasked if the button
+/// has been released for a whole [`BUTTON_DEBOUNCE`] period, and the timer is started again otherwise.
+/// TIMER5 is used by this controller for as long as the runtime exists.
 pub struct Sw1ButtonController<'a> {
     _pf4: &'a mut PF4<Input<PullUp>>,
+    timer5: &'a mut TIMER5,
 }
 
 impl<'a> Sw1ButtonController<'a> {
-    /// Initializes the SW1 button controller.
-    pub(crate) fn new(pf4: &'a mut PF4<Input<PullUp>>, nvic: &mut NVIC) -> Self {
+    /// Initializes the SW1 button controller, the edge interrupts of both buttons, and the debounce
+    /// timer. The SW2 pin is only borrowed to configure its interrupt, so it can still be used by a
+    /// [`Button`].
+    pub(crate) fn new(
+        pf4: &'a mut PF4<Input<PullUp>>,
+        pf0: &mut Sw2ButtonPin,
+        timer5: &'a mut TIMER5,
+        power_control: &PowerControl,
+        clocks: &Clocks,
+        nvic: &mut NVIC,
+    ) -> Self {
         const NVIC_GPIOF_ISER_BYTE: usize = 0; // Interrupt number 30 is in byte 0.
         const NVIC_GPIOF_ISER_BIT: u32 = 30; // Interrupt number 30.
+
+        sysctl::control_power(power_control, Domain::Timer5, RunMode::Run, PowerState::On);
+        sysctl::reset(power_control, Domain::Timer5);
+
+        let debounce_cycles: u32 = (BUTTON_DEBOUNCE.as_micros() * clocks.sysclk.0 as u128
+            / 1_000_000)
+            .try_into()
+            .expect("Button debounce is too long.");
+
+        DEBOUNCE_CYCLES.store(debounce_cycles, Ordering::SeqCst);
+        DEBOUNCING.store(0, Ordering::SeqCst);
+        RELEASED.store(0, Ordering::SeqCst);
+
+        // Configure TIMER5 as a 32-bit one-shot timer that raises an interrupt when it runs out, and
+        // stop it while the processor is halted by a debugger.
+        //
+        // SAFETY: Every value is valid for these registers.
+        unsafe {
+            timer5.cfg.write(|w| w.bits(0));
+            timer5.tamr.write(|w| w.bits(GPTMTAMR_ONE_SHOT));
+            timer5.imr.write(|w| w.bits(GPTM_TATO));
+            timer5.ctl.write(|w| w.bits(GPTMCTL_TASTALL));
+        }
+
         SW1_BUTTON_CONTROLLER_INITIALIZED.store(true, Ordering::SeqCst);
-        pf4.set_interrupt_mode(InterruptMode::EdgeRising);
 
-        // SAFETY: Unmasking the interrupt is safe because the interrupt handler for GPIOF defined
-        // in this file only relies on data local to this module, and GPIO_PORTF. The safety of using
-        // GPIO_PORTF is explained in the safety comment of the GPIOF interrupt handler. Since nothing
-        // in this module relies on a mask-based critical section, this write is safe.
-        unsafe { nvic.iser[NVIC_GPIOF_ISER_BYTE].write(1 << NVIC_GPIOF_ISER_BIT) };
+        // The buttons are active low, so a falling edge is a press.
+        pf4.set_interrupt_mode(InterruptMode::EdgeFalling);
+        pf0.set_interrupt_mode(InterruptMode::EdgeFalling);
 
-        Self { _pf4: pf4 }
+        // SAFETY: Unmasking the interrupts is safe because the interrupt handlers for GPIOF and
+        // TIMER5A defined in this file only rely on data local to this module, GPIO_PORTF, and TIMER5.
+        // The safety of using GPIO_PORTF and TIMER5 is explained in the safety comments of
+        // gpio_portf() and timer5(). Since nothing in this module relies on a mask-based critical
+        // section, these writes are safe.
+        unsafe {
+            nvic.iser[NVIC_GPIOF_ISER_BYTE].write(1 << NVIC_GPIOF_ISER_BIT);
+            NVIC::unmask(Interrupt::TIMER5A);
+        }
+
+        Self { _pf4: pf4, timer5 }
     }
 
     /// Returns whether an activation has been occurred for the SW1 button. Will continue to return
@@ -86,5 +303,58 @@ impl<'a> Sw1ButtonController<'a> {
 impl<'a> Drop for Sw1ButtonController<'a> {
     fn drop(&mut self) {
         SW1_BUTTON_CONTROLLER_INITIALIZED.store(false, Ordering::SeqCst);
+
+        // Stop the debounce timer and mask both interrupts, since their handlers do nothing without a
+        // controller. A new controller unmasks them again.
+        //
+        // SAFETY: Every value is valid for the control register.
+        self.timer5.ctl.write(|w| unsafe { w.bits(0) });
+        NVIC::mask(Interrupt::GPIOF);
+        NVIC::mask(Interrupt::TIMER5A);
+    }
+}
+
+/// A button connected to a GPIO pin configured as a pull-up input, such as the onboard SW2 button on
+/// PF0. The button is active low, meaning the pin reads low while the button is pressed. Unlike the
+/// [`Sw1ButtonController`], this doesn't use interrupts and instead debounces the button by polling.
+pub struct Button<'a, P: InputPin> {
+    pin: &'a mut P,
+}
+
+impl<'a, P: InputPin> Button<'a, P> {
+    /// Creates a new button from a GPIO pin configured as a pull-up input.
+    pub fn new(pin: &'a mut P) -> Self {
+        Self { pin }
+    }
+
+    /// Returns whether the button is currently pressed. This reads the pin once and does no debouncing.
+    pub fn is_pressed(&self) -> bool {
+        self.pin.is_low().unwrap_or(false)
+    }
+
+    /// Blocks until the button is pressed. A press is only reported once the line has stayed low for
+    /// the entire debounce window, so bounces and glitches shorter than the window are ignored. If the
+    /// button is already held down when this is called, it must be released and pressed again so that
+    /// one press is never reported twice.
+    pub fn wait_for_press(&mut self, debounce: Duration, hib_controller: &HibController) {
+        // Wait for the button to be released.
+        while self.is_pressed() {}
+
+        let mut debounce_timer = hib_controller.create_timer(debounce);
+
+        loop {
+            // Wait for the line to go low.
+            while !self.is_pressed() {}
+
+            debounce_timer.reset();
+
+            // Report the press once the line has stayed low for the debounce window. If the line goes
+            // high before then, it was a bounce, so start over.
+            while self.is_pressed() {
+                if debounce_timer.poll() {
+                    return;
+                }
+            }
+        }
     }
 }
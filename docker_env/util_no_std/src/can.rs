@@ -0,0 +1,328 @@
+//! This module contains a controller for sending and receiving classic CAN frames on CAN0, such as
+//! to emit unlock events onto the vehicle bus. CAN0 uses PE4 as CAN0Rx and PE5 as CAN0Tx, which must
+//! be connected to a CAN transceiver.
+
+use crate::communication::{self, CommunicationError};
+use heapless::Vec;
+use tm4c123x_hal::{
+    sysctl::{self, Clocks, Domain, PowerControl, PowerState, RunMode},
+    tm4c123x::CAN0,
+};
+use ucsc_ectf_util_common::timer::Timer;
+
+/// The maximum number of data bytes in a classic CAN frame.
+pub const MAX_CAN_DATA_SIZE: usize = 8;
+
+/// The largest 11-bit standard identifier. Frames with identifiers up to this are standard frames.
+pub const MAX_STANDARD_CAN_ID: u32 = 0x7FF;
+
+/// The largest 29-bit extended identifier. Frames with identifiers above [`MAX_STANDARD_CAN_ID`] are
+/// extended frames.
+pub const MAX_EXTENDED_CAN_ID: u32 = 0x1FFF_FFFF;
+
+/// The message object used to transmit frames.
+const TX_OBJECT: u32 = 1;
+
+/// The message object used to receive frames. It accepts every identifier.
+const RX_OBJECT: u32 = 2;
+
+// CANCTL bits.
+const CTL_INIT: u32 = 1 << 0;
+const CTL_CCE: u32 = 1 << 6;
+const CTL_TEST: u32 = 1 << 7;
+
+// CANSTS bits.
+const STS_EPASS: u32 = 1 << 5;
+const STS_BOFF: u32 = 1 << 7;
+
+// CANTST bits.
+const TST_LBACK: u32 = 1 << 4;
+
+// CANIFnCRQ bits.
+const CRQ_BUSY: u32 = 1 << 15;
+
+// CANIFnCMSK bits.
+const CMSK_WRNRD: u32 = 1 << 7;
+const CMSK_MASK: u32 = 1 << 6;
+const CMSK_ARB: u32 = 1 << 5;
+const CMSK_CONTROL: u32 = 1 << 4;
+const CMSK_CLRINTPND: u32 = 1 << 3;
+const CMSK_NEWDAT: u32 = 1 << 2;
+const CMSK_DATAA: u32 = 1 << 1;
+const CMSK_DATAB: u32 = 1 << 0;
+
+// CANIFnARB2 bits.
+const ARB2_MSGVAL: u32 = 1 << 15;
+const ARB2_XTD: u32 = 1 << 14;
+const ARB2_DIR: u32 = 1 << 13;
+
+// CANIFnMCTL bits.
+const MCTL_NEWDAT: u32 = 1 << 15;
+const MCTL_UMASK: u32 = 1 << 12;
+const MCTL_TXRQST: u32 = 1 << 8;
+const MCTL_EOB: u32 = 1 << 7;
+const MCTL_DLC_MASK: u32 = 0xF;
+
+/// The bit timing of the CAN controller, in time quanta.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct BitTiming {
+    /// The number of system clock cycles per time quantum.
+    prescaler: u32,
+    /// The number of time quanta before the sample point, not counting the synchronization segment.
+    tseg1: u32,
+    /// The number of time quanta after the sample point.
+    tseg2: u32,
+    /// The synchronization jump width.
+    sjw: u32,
+}
+
+impl BitTiming {
+    /// Finds a bit timing for a bitrate at the given clocks with the sample point at around 75% of the
+    /// bit, preferring more time quanta per bit. Returns `None` if the bitrate can't be generated
+    /// exactly.
+    pub(crate) fn new(bitrate: u32, clocks: &Clocks) -> Option<Self> {
+        if bitrate == 0 {
+            return None;
+        }
+
+        let sysclk = clocks.sysclk.0;
+
+        // A bit is 8 to 25 time quanta long, and the prescaler is 1 to 1024.
+        (8..=25).rev().find_map(|quanta: u32| {
+            let cycles_per_bit = bitrate.checked_mul(quanta)?;
+
+            if sysclk % cycles_per_bit != 0 {
+                return None;
+            }
+
+            let prescaler = sysclk / cycles_per_bit;
+
+            if !(1..=1024).contains(&prescaler) {
+                return None;
+            }
+
+            // The synchronization segment is always one time quantum.
+            let tseg1 = (quanta * 3 / 4 - 1).min(16);
+            let tseg2 = quanta - 1 - tseg1;
+
+            if !(1..=8).contains(&tseg2) {
+                return None;
+            }
+
+            Some(Self {
+                prescaler,
+                tseg1,
+                tseg2,
+                sjw: tseg2.min(4),
+            })
+        })
+    }
+}
+
+/// Initializes CAN0 with a bitrate and sets up the transmit and receive message objects. CAN0 is left
+/// running, so frames are received as soon as this returns.
+pub(crate) fn initialize_can0(can: &CAN0, power_control: &PowerControl, timing: BitTiming) {
+    sysctl::control_power(power_control, Domain::Can0, RunMode::Run, PowerState::On);
+    sysctl::reset(power_control, Domain::Can0);
+
+    let bit = (timing.prescaler - 1) & 0x3F
+        | (timing.sjw - 1) << 6
+        | (timing.tseg1 - 1) << 8
+        | (timing.tseg2 - 1) << 12;
+
+    // SAFETY: Every value written below is a valid combination of the bits documented in the
+    // datasheet for each register.
+    unsafe {
+        // The bit timing can only be changed while the controller is in its initialization state
+        // with configuration changes enabled.
+        can.ctl.write(|w| w.bits(CTL_INIT | CTL_CCE));
+        can.bit.write(|w| w.bits(bit));
+        can.brpe.write(|w| w.bits((timing.prescaler - 1) >> 6));
+
+        // Set up the transmit message object. Its identifier and data are set for each frame.
+        wait_if1(can);
+        can.if1cmsk
+            .write(|w| w.bits(CMSK_WRNRD | CMSK_ARB | CMSK_CONTROL));
+        can.if1arb1.write(|w| w.bits(0));
+        can.if1arb2.write(|w| w.bits(0));
+        can.if1mctl.write(|w| w.bits(MCTL_EOB));
+        can.if1crq.write(|w| w.bits(TX_OBJECT));
+        wait_if1(can);
+
+        // Set up the receive message object with an empty mask so it accepts every identifier, both
+        // standard and extended.
+        wait_if2(can);
+        can.if2cmsk
+            .write(|w| w.bits(CMSK_WRNRD | CMSK_MASK | CMSK_ARB | CMSK_CONTROL));
+        can.if2msk1.write(|w| w.bits(0));
+        can.if2msk2.write(|w| w.bits(0));
+        can.if2arb1.write(|w| w.bits(0));
+        can.if2arb2.write(|w| w.bits(ARB2_MSGVAL));
+        can.if2mctl.write(|w| w.bits(MCTL_UMASK | MCTL_EOB));
+        can.if2crq.write(|w| w.bits(RX_OBJECT));
+        wait_if2(can);
+
+        // Leave the initialization state to join the bus.
+        can.ctl.write(|w| w.bits(0));
+    }
+}
+
+/// Waits for the IF1 message interface to finish transferring to or from a message object.
+fn wait_if1(can: &CAN0) {
+    while can.if1crq.read().bits() & CRQ_BUSY != 0 {}
+}
+
+/// Waits for the IF2 message interface to finish transferring to or from a message object.
+fn wait_if2(can: &CAN0) {
+    while can.if2crq.read().bits() & CRQ_BUSY != 0 {}
+}
+
+/// The CAN0 controller. This sends frames with one message object and receives frames with another,
+/// so a frame must be received with [`CanController::recv_frame()`] before the next one arrives or
+/// it's overwritten.
+pub struct CanController<'a> {
+    can: &'a mut CAN0,
+}
+
+impl<'a> CanController<'a> {
+    /// Creates the CAN0 controller. CAN0 must already be initialized with [`initialize_can0()`].
+    pub(crate) fn new(can: &'a mut CAN0) -> Self {
+        Self { can }
+    }
+
+    /// Sends a classic CAN frame. Identifiers up to [`MAX_STANDARD_CAN_ID`] are sent in standard
+    /// frames and larger identifiers are sent in extended frames. This waits for the previous frame
+    /// to be sent before queuing this one, but doesn't wait for this one to be sent.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - There are more than [`MAX_CAN_DATA_SIZE`] bytes of data,
+    ///   the identifier is greater than [`MAX_EXTENDED_CAN_ID`], or the controller is error passive or
+    ///   bus off while waiting for the previous frame to be sent, such as when no other node is
+    ///   acknowledging frames.
+    pub fn send_frame(&mut self, id: u32, data: &[u8]) -> communication::Result<()> {
+        if data.len() > MAX_CAN_DATA_SIZE || id > MAX_EXTENDED_CAN_ID {
+            return Err(CommunicationError::SendError);
+        }
+
+        // Wait for the previous frame to be sent. The controller retransmits a frame until it's
+        // acknowledged, so give up once errors pile up instead of waiting forever.
+        while self.can.txrq1.read().bits() & (1 << (TX_OBJECT - 1)) != 0 {
+            if self.can.sts.read().bits() & (STS_EPASS | STS_BOFF) != 0 {
+                return Err(CommunicationError::SendError);
+            }
+        }
+
+        let (arb1, arb2) = if id > MAX_STANDARD_CAN_ID {
+            (id & 0xFFFF, ARB2_XTD | id >> 16)
+        } else {
+            (0, id << 2)
+        };
+
+        let mut padded = [0; MAX_CAN_DATA_SIZE];
+        padded[..data.len()].copy_from_slice(data);
+        let data_reg = |i: usize| padded[i] as u32 | (padded[i + 1] as u32) << 8;
+
+        wait_if1(self.can);
+
+        // SAFETY: Every value written below is a valid combination of the bits documented in the
+        // datasheet for each register.
+        unsafe {
+            self.can
+                .if1cmsk
+                .write(|w| w.bits(CMSK_WRNRD | CMSK_ARB | CMSK_CONTROL | CMSK_DATAA | CMSK_DATAB));
+            self.can.if1arb1.write(|w| w.bits(arb1));
+            self.can
+                .if1arb2
+                .write(|w| w.bits(ARB2_MSGVAL | ARB2_DIR | arb2));
+            self.can
+                .if1mctl
+                .write(|w| w.bits(MCTL_NEWDAT | MCTL_TXRQST | MCTL_EOB | data.len() as u32));
+            self.can.if1da1.write(|w| w.bits(data_reg(0)));
+            self.can.if1da2.write(|w| w.bits(data_reg(2)));
+            self.can.if1db1.write(|w| w.bits(data_reg(4)));
+            self.can.if1db2.write(|w| w.bits(data_reg(6)));
+            self.can.if1crq.write(|w| w.bits(TX_OBJECT));
+        }
+
+        wait_if1(self.can);
+
+        Ok(())
+    }
+
+    /// Receives a classic CAN frame, returning its identifier and data. The timer is polled while
+    /// waiting for a frame. Standard and extended identifiers are both returned as-is.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::Timeout`] - The timer expired before a frame was received.
+    pub fn recv_frame<T: Timer>(
+        &mut self,
+        timer: &mut T,
+    ) -> communication::Result<(u32, Vec<u8, MAX_CAN_DATA_SIZE>)> {
+        while self.can.nwda1.read().bits() & (1 << (RX_OBJECT - 1)) == 0 {
+            if timer.poll() {
+                return Err(CommunicationError::Timeout);
+            }
+        }
+
+        wait_if2(self.can);
+
+        // SAFETY: Every value written below is a valid combination of the bits documented in the
+        // datasheet for each register. Reading the message object clears its new data flag.
+        unsafe {
+            self.can.if2cmsk.write(|w| {
+                w.bits(
+                    CMSK_ARB
+                        | CMSK_CONTROL
+                        | CMSK_CLRINTPND
+                        | CMSK_NEWDAT
+                        | CMSK_DATAA
+                        | CMSK_DATAB,
+                )
+            });
+            self.can.if2crq.write(|w| w.bits(RX_OBJECT));
+        }
+
+        wait_if2(self.can);
+
+        let arb1 = self.can.if2arb1.read().bits();
+        let arb2 = self.can.if2arb2.read().bits();
+        let id = if arb2 & ARB2_XTD != 0 {
+            (arb2 & 0x1FFF) << 16 | (arb1 & 0xFFFF)
+        } else {
+            (arb2 >> 2) & MAX_STANDARD_CAN_ID
+        };
+
+        let data_regs = [
+            self.can.if2da1.read().bits(),
+            self.can.if2da2.read().bits(),
+            self.can.if2db1.read().bits(),
+            self.can.if2db2.read().bits(),
+        ];
+        let len = (self.can.if2mctl.read().bits() & MCTL_DLC_MASK).min(MAX_CAN_DATA_SIZE as u32);
+
+        let data = data_regs
+            .iter()
+            .flat_map(|reg| [*reg as u8, (*reg >> 8) as u8])
+            .take(len as usize)
+            .collect();
+
+        Ok((id, data))
+    }
+
+    /// Enables or disables loopback mode, where frames sent are received by this controller instead
+    /// of going out on the bus, and no acknowledgement is needed. This is useful for self-tests.
+    pub fn set_loopback(&mut self, enabled: bool) {
+        // SAFETY: Only the test mode bits are changed.
+        unsafe {
+            if enabled {
+                self.can.ctl.modify(|r, w| w.bits(r.bits() | CTL_TEST));
+                self.can.tst.write(|w| w.bits(TST_LBACK));
+            } else {
+                self.can.tst.write(|w| w.bits(0));
+                self.can.ctl.modify(|r, w| w.bits(r.bits() & !CTL_TEST));
+            }
+        }
+    }
+}
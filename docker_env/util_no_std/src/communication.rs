@@ -16,9 +16,28 @@
 //!     - The application layer is the layer responsible for incorporating the lower two layers together.
 //!       This crate provides an implementation of this layer through the [`Uart0Controller`] and
 //!       [`Uart1Controller`] structs.
+//!
+//! The [`Rs485Channel`] can wrap a controller to run it over a half-duplex RS-485 transceiver, and
+//! [`select()`] waits for the first of several channels, buttons, or a timeout. The [`BitBangUart`] is a
+//! software UART over two GPIO pins for boards without a free UART peripheral. [`send_retry()`] retries
+//! a send with backoff jittered by the secondary CSPRNG, feeding the watchdog while it waits.
 
+mod bitbang;
+mod retry;
+mod rs485;
 mod secure_uart;
+mod select;
 mod uart;
 
+pub use bitbang::{BitBangUart, MAX_BIT_BANG_BAUD, MIN_BIT_BANG_MESSAGE};
+pub use retry::send_retry;
+pub use rs485::Rs485Channel;
 pub use secure_uart::*;
+pub use select::{select, Pollable, RecvSource, Selected};
+pub use uart::{
+    CobsRxChannel, CobsTxChannel, LineErrorStats, RxFifoTrigger, MAX_BUFFERED_UART1_FRAME,
+    MIN_COBS_UART_MESSAGE,
+};
 pub use ucsc_ectf_util_common::communication::*;
+
+pub(crate) use uart::set_uart1_rx_fifo_trigger;
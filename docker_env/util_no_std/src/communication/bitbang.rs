@@ -0,0 +1,226 @@
+use cortex_m::interrupt;
+use embedded_hal::{
+    blocking::delay::DelayUs,
+    digital::v2::{InputPin, OutputPin},
+};
+use ucsc_ectf_util_common::{
+    communication::{
+        self,
+        lower_layers::framing::{bogoframing, Frame, FramedTxChannel},
+        CommunicationError, RxChannel,
+    },
+    timer::Timer,
+};
+
+/// The highest baud rate a [`BitBangUart`] can be created with. See the struct documentation for the
+/// baud rates that work reliably.
+pub const MAX_BIT_BANG_BAUD: u32 = 38_400;
+
+/// The minimum size a message sent or received by a [`BitBangUart`] can be.
+pub const MIN_BIT_BANG_MESSAGE: usize = 1;
+
+/// A software UART that bit-bangs 8N1 serial over a pair of GPIO pins, as a fallback for boards that
+/// can't route a UART peripheral to the link, such as for the debug console. Messages are framed with
+/// BogoFraming in the same way as the [`Uart0Controller`](super::Uart0Controller), but without
+/// encryption, so it should be wrapped in a channel from the [`crypto`](super::lower_layers::crypto)
+/// layer for anything but debugging.
+///
+/// # Timing
+///
+/// Each bit is timed with ``delay``, rounded to a whole number of microseconds, so the baud rate is off
+/// by the rounding plus the few cycles it takes to drive or read a pin. At 80 MHz, that's well under 1%
+/// for the standard baud rates up to [`MAX_BIT_BANG_BAUD`], and 9600 baud has the most margin. The delay
+/// must be precise to the microsecond, as the SysTick-based [`Delay`](tm4c123x_hal::delay::Delay) is.
+///
+/// Each byte is sent and received with interrupts disabled, since an interrupt in the middle of a byte
+/// would stretch its bits. This blocks the core for the whole transfer, about 1 ms per byte at 9600
+/// baud, and delays every interrupt by up to one byte time.
+///
+/// Reception is polled, so the RX pin has to be checked within half a bit of a start bit, or the byte
+/// is sampled off-center. Between bytes of a frame, the framing layer only polls the timer, so this
+/// holds at 9600 baud, but bytes sent while no receive is running are lost.
+pub struct BitBangUart<TX, RX, D>
+where
+    TX: OutputPin,
+    RX: InputPin,
+    D: DelayUs<u32>,
+{
+    tx: TX,
+    rx: RX,
+    delay: D,
+    bit_us: u32,
+}
+
+impl<TX, RX, D> BitBangUart<TX, RX, D>
+where
+    TX: OutputPin,
+    RX: InputPin,
+    D: DelayUs<u32>,
+{
+    /// Creates a new [`BitBangUart`] that sends on ``tx`` and receives on ``rx`` at the given baud
+    /// rate, timing each bit with ``delay``. ``tx`` is driven high, since the line idles high.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::InternalError`] - ``tx`` couldn't be driven high.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate is zero or greater than [`MAX_BIT_BANG_BAUD`].
+    pub fn new(mut tx: TX, rx: RX, delay: D, baud: u32) -> communication::Result<Self> {
+        assert!(
+            (1..=MAX_BIT_BANG_BAUD).contains(&baud),
+            "The baud rate is out of range."
+        );
+
+        tx.set_high()
+            .map_err(|_| CommunicationError::InternalError)?;
+
+        Ok(Self {
+            tx,
+            rx,
+            delay,
+            bit_us: (1_000_000 + baud / 2) / baud,
+        })
+    }
+
+    /// Consumes the [`BitBangUart`], returning the pins and the delay.
+    pub fn into_inner(self) -> (TX, RX, D) {
+        (self.tx, self.rx, self.delay)
+    }
+
+    /// Sends one byte, least significant bit first, between a start bit and a stop bit.
+    fn write_byte(&mut self, byte: u8) -> communication::Result<()> {
+        interrupt::free(|_| {
+            // The start bit is low, and the stop bit is high.
+            let bits = (u16::from(byte) << 1) | (1 << 9);
+
+            for i in 0..10 {
+                if bits & (1 << i) == 0 {
+                    self.tx.set_low()
+                } else {
+                    self.tx.set_high()
+                }
+                .map_err(|_| CommunicationError::SendError)?;
+
+                self.delay.delay_us(self.bit_us);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Checks whether the line is low, which is the start of a byte if no byte is being received.
+    fn rx_low(&self) -> communication::Result<bool> {
+        self.rx.is_low().map_err(|_| CommunicationError::RecvError)
+    }
+
+    /// Receives one byte if its start bit has begun, returning after the middle of its stop bit.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - The line is idle, or the start bit was too short, so
+    ///   there's no byte yet.
+    /// - [`CommunicationError::LineError`] - The stop bit was low, so the byte was corrupted or the line
+    ///   is being held low. The byte is discarded.
+    fn read_byte(&mut self) -> communication::Result<u8> {
+        if !self.rx_low()? {
+            return Err(CommunicationError::RecvError);
+        }
+
+        interrupt::free(|_| {
+            // Sample in the middle of each bit, and make sure the start bit wasn't a glitch.
+            self.delay.delay_us(self.bit_us / 2);
+
+            if !self.rx_low()? {
+                return Err(CommunicationError::RecvError);
+            }
+
+            let mut byte = 0;
+
+            for i in 0..8 {
+                self.delay.delay_us(self.bit_us);
+
+                if !self.rx_low()? {
+                    byte |= 1 << i;
+                }
+            }
+
+            self.delay.delay_us(self.bit_us);
+
+            if self.rx_low()? {
+                return Err(CommunicationError::LineError);
+            }
+
+            Ok(byte)
+        })
+    }
+}
+
+impl<TX, RX, D> FramedTxChannel for BitBangUart<TX, RX, D>
+where
+    TX: OutputPin,
+    RX: InputPin,
+    D: DelayUs<u32>,
+{
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        bogoframing::frame_bogoframe(
+            self,
+            frame()?,
+            |ch, s| s.iter().try_for_each(|&byte| ch.write_byte(byte)),
+            MIN_BIT_BANG_MESSAGE,
+        )
+    }
+}
+
+impl<TX, RX, D> RxChannel for BitBangUart<TX, RX, D>
+where
+    TX: OutputPin,
+    RX: InputPin,
+    D: DelayUs<u32>,
+{
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_data_timeout(
+            self,
+            dest,
+            timer,
+            Self::read_byte,
+            MIN_BIT_BANG_MESSAGE,
+        )
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_timeout(
+            self,
+            dest,
+            timer,
+            Self::read_byte,
+            MIN_BIT_BANG_MESSAGE,
+        )
+    }
+
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, communication::Result<usize>) {
+        bogoframing::recv_frame_with_progress(
+            self,
+            dest,
+            timer,
+            Self::read_byte,
+            MIN_BIT_BANG_MESSAGE,
+        )
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{random::secondary_rng, watchdog::WatchdogController};
+use core::time::Duration;
+use rand_chacha::rand_core::RngCore;
+use ucsc_ectf_util_common::{
+    communication::{self, TxChannel},
+    timer::Timer,
+};
+
+/// Sends ``src`` through ``channel`` with [`TxChannel::send_retry`], jittering the backoff with the
+/// secondary CSPRNG and feeding ``watchdog`` while waiting between attempts, so a long backoff doesn't
+/// trigger a watchdog reset. See [`TxChannel::send_retry`] for how the attempts and waits work.
+///
+/// ```ignore
+/// let mut timer = rt.hib_controller.create_timer(Duration::from_secs(1));
+///
+/// communication::send_retry::<64, _, _>(
+///     &mut rt.uart1_controller,
+///     &msg,
+///     3,
+///     Duration::from_millis(10),
+///     &mut timer,
+///     &rt.watchdog_controller,
+/// )?;
+/// ```
+///
+/// # Panics
+///
+/// Panics if a retry is needed and the secondary CSPRNG hasn't been initialized yet, which
+/// [`Runtime::new()`](crate::Runtime::new) does.
+///
+/// # ERRORS:
+///
+/// This function returns the same errors as [`TxChannel::send_retry`].
+pub fn send_retry<const MAX_LEN: usize, C: TxChannel, T: Timer>(
+    channel: &mut C,
+    src: &[u8],
+    attempts: u32,
+    base_delay: Duration,
+    timer: &mut T,
+    watchdog: &WatchdogController,
+) -> communication::Result<()> {
+    channel.send_retry::<MAX_LEN, T>(
+        src,
+        attempts,
+        base_delay,
+        timer,
+        || secondary_rng().next_u32(),
+        || watchdog.feed(),
+    )
+}
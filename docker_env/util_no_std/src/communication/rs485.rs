@@ -0,0 +1,173 @@
+use core::sync::atomic::AtomicBool;
+use embedded_hal::digital::v2::OutputPin;
+use ucsc_ectf_util_common::{
+    communication::{self, CommunicationError, RxChannel, TxChannel},
+    timer::Timer,
+};
+
+/// A channel for running another channel, such as a [`Uart1Controller`](super::Uart1Controller), over a
+/// half-duplex RS-485 transceiver. The transceiver's driver enable (DE) pin is driven high before each
+/// message is sent and driven low once the last byte has completely left the UART, so the transceiver
+/// only drives the bus while sending and listens the rest of the time. Receiving is passed straight
+/// through to the wrapped channel.
+///
+/// The wrapped channel's [`flush`](TxChannel::flush) must block until the last stop bit has been
+/// transmitted, as the UART controllers' does, or the end of each frame will be cut off.
+pub struct Rs485Channel<C, P>
+where
+    C: RxChannel + TxChannel,
+    P: OutputPin,
+{
+    channel: C,
+    de_pin: P,
+}
+
+impl<C, P> Rs485Channel<C, P>
+where
+    C: RxChannel + TxChannel,
+    P: OutputPin,
+{
+    /// Creates a new [`Rs485Channel`] wrapping the provided channel and using the provided pin as the
+    /// driver enable pin. The pin is driven low so the transceiver starts out listening.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::InternalError`] - The driver enable pin couldn't be driven low.
+    pub fn new(channel: C, mut de_pin: P) -> communication::Result<Self> {
+        de_pin
+            .set_low()
+            .map_err(|_| CommunicationError::InternalError)?;
+
+        Ok(Self { channel, de_pin })
+    }
+
+    /// Gets a mutable reference to the wrapped channel.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.channel
+    }
+
+    /// Consumes the [`Rs485Channel`], returning the wrapped channel and the driver enable pin.
+    pub fn into_inner(self) -> (C, P) {
+        (self.channel, self.de_pin)
+    }
+
+    /// Drives the driver enable pin high, sends through the wrapped channel with ``send_fn``, waits for
+    /// the transmission to finish, and drives the pin low again, even if sending fails.
+    fn with_driver_enabled(
+        &mut self,
+        send_fn: impl FnOnce(&mut C) -> communication::Result<()>,
+    ) -> communication::Result<()> {
+        self.de_pin
+            .set_high()
+            .map_err(|_| CommunicationError::SendError)?;
+
+        // The last byte has to be completely shifted out before the driver is disabled, or the end
+        // of the frame is lost.
+        let res = send_fn(&mut self.channel).and_then(|_| self.channel.flush());
+
+        let de_res = self
+            .de_pin
+            .set_low()
+            .map_err(|_| CommunicationError::SendError);
+
+        res.and(de_res)
+    }
+}
+
+impl<C, P> RxChannel for Rs485Channel<C, P>
+where
+    C: RxChannel + TxChannel,
+    P: OutputPin,
+{
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.channel.recv_with_data_timeout(dest, timer)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.channel.recv_with_timeout(dest, timer)
+    }
+
+    fn recv_until<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        deadline: &mut T,
+    ) -> communication::Result<usize> {
+        self.channel.recv_until(dest, deadline)
+    }
+
+    fn recv_cancellable<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+        cancel: &AtomicBool,
+    ) -> communication::Result<usize> {
+        self.channel.recv_cancellable(dest, timer, cancel)
+    }
+
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, communication::Result<usize>) {
+        self.channel.recv_progress(dest, timer)
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        self.channel.try_recv(dest)
+    }
+
+    fn recv_exact<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<()> {
+        self.channel.recv_exact(dest, timer)
+    }
+}
+
+impl<C, P> TxChannel for Rs485Channel<C, P>
+where
+    C: RxChannel + TxChannel,
+    P: OutputPin,
+{
+    /// Drives the driver enable pin high, sends the data from ``src`` through the wrapped channel, waits
+    /// for it to finish transmitting, and drives the driver enable pin low again. The pin is driven low
+    /// even if sending fails, so a failed send never leaves the transceiver holding the bus.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - The driver enable pin couldn't be driven.
+    ///
+    /// This function can also return any error from the wrapped channel's [`send`](TxChannel::send) and
+    /// [`flush`](TxChannel::flush).
+    fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
+        self.with_driver_enabled(|ch| ch.send(src))
+    }
+
+    /// Sends the concatenation of ``parts`` in the same way as [`send`](Self::send), with the driver
+    /// enable pin high for the whole message.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as [`send`](Self::send), and any error from the wrapped
+    /// channel's [`send_vectored`](TxChannel::send_vectored).
+    fn send_vectored<const MAX_LEN: usize>(
+        &mut self,
+        parts: &[&[u8]],
+    ) -> communication::Result<()> {
+        self.with_driver_enabled(|ch| ch.send_vectored::<MAX_LEN>(parts))
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        // Every send already waits for the wrapped channel to finish transmitting.
+        Ok(())
+    }
+}
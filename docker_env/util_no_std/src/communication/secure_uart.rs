@@ -2,11 +2,14 @@ use super::{
     lower_layers::crypto::{
         KeyedChannel, RandomSource, XChacha20Poly1305RxChannel, XChacha20Poly1305TxChannel,
     },
-    uart::{FramedUartRxChannel, FramedUartTxChannel},
-    RxChannel, TxChannel,
+    uart::{FramedUartRxChannel, FramedUartTxChannel, LineErrorStats},
+    CommunicationError, RxChannel, TxChannel, MAX_BUFFERED_UART1_FRAME,
 };
-use crate::random::fill_rand_slice;
+use crate::{random::fill_rand_slice, timer};
 use chacha20poly1305::Key;
+use core::{convert::Infallible, time::Duration};
+use embedded_hal::serial;
+use rand_chacha::rand_core::CryptoRng;
 use tm4c123x_hal::{
     serial::{Rx, RxPin, Tx, TxPin},
     tm4c123x::{UART0, UART1},
@@ -19,7 +22,9 @@ type EncryptedUartTxChannel<'a, UART, TX> =
 type EncryptedUartRxChannel<'a, UART, RX> =
     XChacha20Poly1305RxChannel<FramedUartRxChannel<'a, UART, RX>>;
 
-/// The [`RandomSource`] used for encrypted UART channels.
+/// The [`RandomSource`] used for encrypted UART channels. It draws from the main CSPRNG, so a send that
+/// crosses the reseed threshold blocks for around a second while the main CSPRNG reseeds itself. See
+/// [`Runtime::fill_rand_slice()`](crate::Runtime::fill_rand_slice) for more details.
 pub struct UartRandomSource {
     _not_constructible: (), // Makes this not publicly constructible.
 }
@@ -30,6 +35,42 @@ impl RandomSource for UartRandomSource {
     }
 }
 
+// The random bytes come from the main CSPRNG.
+impl CryptoRng for UartRandomSource {}
+
+/// Counters for the frames sent and received by a UART controller, for diagnostics. Every counter wraps
+/// around on overflow.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// The number of frames sent successfully.
+    pub frames_sent: u32,
+
+    /// The number of frames received successfully.
+    pub frames_recv: u32,
+
+    /// The number of receive operations that timed out.
+    pub timeouts: u32,
+
+    /// The number of received frames that failed to authenticate or were rejected as replays.
+    pub auth_failures: u32,
+
+    /// The number of receive operations that failed for any other reason, such as a badly framed
+    /// message, a message that didn't fit in the buffer, or a message of the wrong length.
+    pub malformed: u32,
+
+    /// The physical-layer errors found in received bytes, by class. A receive operation that fails
+    /// because of one isn't counted in any of the other counters.
+    pub line_errors: LineErrorStats,
+}
+
+/// The counters of the RX channel of a UART controller, taken before a receive operation to tell what
+/// kind of error it failed with.
+#[derive(Copy, Clone)]
+struct RxCounters {
+    auth_failures: u32,
+    line_errors: LineErrorStats,
+}
+
 macro_rules! uart_impl {
     ($ctr_ty:ident, $uart_typ:ty, $fn_name:ident,$keyless_fn_name:ident, $tx_ctor:ident, $rx_ctor:ident) => {
         /// An optionally bi-directionally encrypted and authenticated way to send and
@@ -43,11 +84,23 @@ macro_rules! uart_impl {
         /// for how message confidentiality and integrity is guaranteed for transmissions
         /// in this struct.
         ///
+        /// A keyless controller still sends and receives with the constant key, but refuses the
+        /// operations that only make sense with a secret key, such as
+        /// [`send_with_aad`](Self::send_with_aad), with [`CommunicationError::KeyNotSet`] until a key
+        /// is set for that direction.
+        ///
         /// To frame the UART data sent and received, BogoFraming is used.
         ///
         /// ## BogoFraming
         /// Each message sent/received will be hex encoded and decoded, delimited by a NULL (\0) character
         /// at the start and at the end. Messages must be at least 1 character long.
+        ///
+        /// ## Pacing
+        /// Frames are sent back to back by default. A peer that polls for data instead of receiving
+        /// it with interrupts can lose bytes to receive FIFO overruns when frames arrive faster than
+        /// it reads them, such as the fragments of a large message. A minimum gap between frames can
+        /// be set with [`set_min_interframe_gap`](Self::set_min_interframe_gap) to give the peer time
+        /// to catch up.
         pub struct $ctr_ty<'a, TX, RX>
         where
             TX: TxPin<$uart_typ>,
@@ -55,6 +108,11 @@ macro_rules! uart_impl {
         {
             tx_channel: EncryptedUartTxChannel<'a, $uart_typ, TX>,
             rx_channel: EncryptedUartRxChannel<'a, $uart_typ, RX>,
+            stats: ChannelStats,
+            tx_keyed: bool,
+            rx_keyed: bool,
+            min_interframe_gap: Duration,
+            last_send: Option<Duration>,
         }
 
         impl<'a, TX, RX> $ctr_ty<'a, TX, RX>
@@ -85,6 +143,11 @@ macro_rules! uart_impl {
                 Self {
                     tx_channel,
                     rx_channel,
+                    stats: ChannelStats::default(),
+                    tx_keyed: true,
+                    rx_keyed: true,
+                    min_interframe_gap: Duration::ZERO,
+                    last_send: None,
                 }
             }
 
@@ -96,7 +159,11 @@ macro_rules! uart_impl {
                 tx: &'a mut Tx<$uart_typ, TX, ()>,
                 rx: &'a mut Rx<$uart_typ, RX, ()>,
             ) -> Self {
-                Self::$fn_name(tx, rx, &Default::default(), &Default::default())
+                Self {
+                    tx_keyed: false,
+                    rx_keyed: false,
+                    ..Self::$fn_name(tx, rx, &Default::default(), &Default::default())
+                }
             }
 
             /// Changes the encryption key used for the UART TX channel to the provided key.
@@ -105,6 +172,7 @@ macro_rules! uart_impl {
                 new_key: &<EncryptedUartTxChannel<$uart_typ, TX> as KeyedChannel>::KeyType,
             ) {
                 self.tx_channel.change_key(new_key);
+                self.tx_keyed = true;
             }
 
             /// Changes the decryption key used for the UART RX channel to the provided key.
@@ -113,6 +181,228 @@ macro_rules! uart_impl {
                 new_key: &<EncryptedUartRxChannel<$uart_typ, RX> as KeyedChannel>::KeyType,
             ) {
                 self.rx_channel.change_key(new_key);
+                self.rx_keyed = true;
+            }
+
+            /// Replaces both the decryption key used for the UART RX channel and the encryption
+            /// key used for the UART TX channel in place, resetting the sequence number state of
+            /// both channels. This allows the same controller to switch keys mid-conversation, such
+            /// as when moving from a pairing key to a session key.
+            pub fn rekey(&mut self, new_rx_key: &Key, new_tx_key: &Key) {
+                self.rx_channel.change_key(new_rx_key);
+                self.tx_channel.change_key(new_tx_key);
+                self.tx_keyed = true;
+                self.rx_keyed = true;
+            }
+
+            /// Checks whether this controller has a secret key for sending and for receiving, in that
+            /// order. A controller created with ``without_key()`` has neither until one is set.
+            pub fn is_keyed(&self) -> (bool, bool) {
+                (self.tx_keyed, self.rx_keyed)
+            }
+
+            /// Enables or disables replay protection on the UART RX channel. See
+            /// [`XChacha20Poly1305RxChannel::set_replay_protection`] for more info.
+            pub fn set_replay_protection(&mut self, enabled: bool) {
+                self.rx_channel.set_replay_protection(enabled);
+            }
+
+            /// Sets the minimum time between the end of one frame sent by this controller and the start
+            /// of the next. Each send waits out whatever is left of the gap before it starts, so a
+            /// [`FragmentingTxChannel`](super::lower_layers::fragmentation::FragmentingTxChannel)
+            /// wrapping this controller spaces its fragments automatically. A gap of zero, the default,
+            /// sends frames back to back.
+            ///
+            /// This trades throughput for reliability with peers that poll for data instead of
+            /// receiving it with interrupts, which can otherwise lose bytes to receive FIFO overruns.
+            /// It's meant as a stopgap until both ends use interrupt-driven reception, which doesn't
+            /// need it. The gap is timed with the RTC and has an accuracy of 1/32768 seconds. If the RTC
+            /// isn't running, or is set to an earlier time, no gap is waited.
+            pub fn set_min_interframe_gap(&mut self, gap: Duration) {
+                self.min_interframe_gap = gap;
+            }
+
+            /// Gets the minimum time between frames sent by this controller. See
+            /// [`set_min_interframe_gap`](Self::set_min_interframe_gap) for more info.
+            pub fn min_interframe_gap(&self) -> Duration {
+                self.min_interframe_gap
+            }
+
+            /// Sends the data from ``src`` through the UART TX channel, additionally authenticating
+            /// the provided associated data, which isn't transmitted. See
+            /// [`XChacha20Poly1305TxChannel::send_with_aad`] for more info.
+            ///
+            /// # ERRORS:
+            ///
+            /// - [`CommunicationError::KeyNotSet`] - This controller has no secret key for sending.
+            ///
+            /// This function can also return any error from
+            /// [`XChacha20Poly1305TxChannel::send_with_aad`].
+            pub fn send_with_aad(&mut self, src: &mut [u8], aad: &[u8]) -> super::Result<()> {
+                if !self.tx_keyed {
+                    return Err(CommunicationError::KeyNotSet);
+                }
+
+                self.wait_interframe_gap();
+                let res = self.tx_channel.send_with_aad(src, aad);
+                self.record_send(res)
+            }
+
+            /// Receives data from the UART RX channel with a timeout that resets on each byte received,
+            /// additionally authenticating the provided associated data. See
+            /// [`XChacha20Poly1305RxChannel::recv_with_data_timeout_and_aad`] for more info.
+            ///
+            /// # ERRORS:
+            ///
+            /// - [`CommunicationError::KeyNotSet`] - This controller has no secret key for receiving.
+            ///
+            /// This function can also return any error from
+            /// [`XChacha20Poly1305RxChannel::recv_with_data_timeout_and_aad`].
+            pub fn recv_with_data_timeout_and_aad<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                aad: &[u8],
+                timer: &mut T,
+            ) -> super::Result<usize> {
+                if !self.rx_keyed {
+                    return Err(CommunicationError::KeyNotSet);
+                }
+
+                let before = self.rx_counters();
+                let res = self
+                    .rx_channel
+                    .recv_with_data_timeout_and_aad(dest, aad, timer);
+                self.record_recv(before, res)
+            }
+
+            /// Receives data from the UART RX channel with a timeout for the entire receive operation,
+            /// additionally authenticating the provided associated data. See
+            /// [`XChacha20Poly1305RxChannel::recv_with_timeout_and_aad`] for more info.
+            ///
+            /// # ERRORS:
+            ///
+            /// - [`CommunicationError::KeyNotSet`] - This controller has no secret key for receiving.
+            ///
+            /// This function can also return any error from
+            /// [`XChacha20Poly1305RxChannel::recv_with_timeout_and_aad`].
+            pub fn recv_with_timeout_and_aad<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                aad: &[u8],
+                timer: &mut T,
+            ) -> super::Result<usize> {
+                if !self.rx_keyed {
+                    return Err(CommunicationError::KeyNotSet);
+                }
+
+                let before = self.rx_counters();
+                let res = self.rx_channel.recv_with_timeout_and_aad(dest, aad, timer);
+                self.record_recv(before, res)
+            }
+
+            /// Receives data from the UART RX channel with a timeout for the entire receive operation,
+            /// additionally authenticating the provided associated data and padding the time taken by
+            /// every failure to the duration of ``padding``. See
+            /// [`XChacha20Poly1305RxChannel::recv_with_timeout_padded`] for more info.
+            ///
+            /// # ERRORS:
+            ///
+            /// - [`CommunicationError::KeyNotSet`] - This controller has no secret key for receiving.
+            ///   This isn't padded, since it doesn't depend on anything received.
+            ///
+            /// This function can also return any error from
+            /// [`XChacha20Poly1305RxChannel::recv_with_timeout_padded`].
+            pub fn recv_with_timeout_padded<T: Timer, P: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                aad: &[u8],
+                timer: &mut T,
+                padding: &mut P,
+            ) -> super::Result<usize> {
+                if !self.rx_keyed {
+                    return Err(CommunicationError::KeyNotSet);
+                }
+
+                let before = self.rx_counters();
+                let res = self
+                    .rx_channel
+                    .recv_with_timeout_padded(dest, aad, timer, padding);
+                self.record_recv(before, res)
+            }
+
+            /// Gets the send and receive statistics of this controller.
+            pub fn stats(&self) -> &ChannelStats {
+                &self.stats
+            }
+
+            /// Resets every send and receive statistic of this controller to zero.
+            pub fn reset_stats(&mut self) {
+                self.stats = ChannelStats::default();
+            }
+
+            /// Spins until the minimum interframe gap has passed since the last frame was sent.
+            fn wait_interframe_gap(&self) {
+                let last_send = match self.last_send {
+                    Some(last_send) if !self.min_interframe_gap.is_zero() => last_send,
+                    _ => return,
+                };
+
+                while let Some(now) = timer::rtc_now() {
+                    // The RTC was set to an earlier time, so the gap can't be measured.
+                    if now < last_send || now - last_send >= self.min_interframe_gap {
+                        break;
+                    }
+                }
+            }
+
+            /// Updates the statistics with the result of a send operation and records when it
+            /// finished for the interframe gap. A failed send still counts for the gap, since part of
+            /// its frame may have been sent.
+            fn record_send(&mut self, res: super::Result<()>) -> super::Result<()> {
+                self.last_send = timer::rtc_now();
+
+                if res.is_ok() {
+                    self.stats.frames_sent = self.stats.frames_sent.wrapping_add(1);
+                }
+
+                res
+            }
+
+            /// Takes the counters of the RX channel, to be passed to
+            /// [`record_recv`](Self::record_recv) after a receive operation.
+            fn rx_counters(&self) -> RxCounters {
+                RxCounters {
+                    auth_failures: self.rx_channel.auth_failures(),
+                    line_errors: self.rx_channel.get_ref().line_errors(),
+                }
+            }
+
+            /// Updates the statistics with the result of a receive operation. The counters of the
+            /// RX channel before the operation are needed to tell authentication failures and line
+            /// errors apart from other receive errors.
+            fn record_recv<R>(
+                &mut self,
+                before: RxCounters,
+                res: super::Result<R>,
+            ) -> super::Result<R> {
+                let line_errors = self.rx_channel.get_ref().line_errors();
+                self.stats
+                    .line_errors
+                    .add(&line_errors.since(&before.line_errors));
+
+                let counter = match &res {
+                    Ok(_) => &mut self.stats.frames_recv,
+                    Err(CommunicationError::Timeout) => &mut self.stats.timeouts,
+                    // Line errors were already counted by class.
+                    Err(CommunicationError::LineError) => return res,
+                    Err(_) if self.rx_channel.auth_failures() != before.auth_failures => {
+                        &mut self.stats.auth_failures
+                    }
+                    Err(_) => &mut self.stats.malformed,
+                };
+                *counter = counter.wrapping_add(1);
+
+                res
             }
         }
 
@@ -126,7 +416,9 @@ macro_rules! uart_impl {
                 dest: &mut [u8],
                 timer: &mut T,
             ) -> super::Result<usize> {
-                self.rx_channel.recv_with_timeout(dest, timer)
+                let before = self.rx_counters();
+                let res = self.rx_channel.recv_with_timeout(dest, timer);
+                self.record_recv(before, res)
             }
 
             fn recv_with_data_timeout<T: Timer>(
@@ -134,7 +426,41 @@ macro_rules! uart_impl {
                 dest: &mut [u8],
                 timer: &mut T,
             ) -> super::Result<usize> {
-                self.rx_channel.recv_with_data_timeout(dest, timer)
+                let before = self.rx_counters();
+                let res = self.rx_channel.recv_with_data_timeout(dest, timer);
+                self.record_recv(before, res)
+            }
+
+            fn recv_progress<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                timer: &mut T,
+            ) -> (usize, super::Result<usize>) {
+                let before = self.rx_counters();
+                let (bytes_read, res) = self.rx_channel.recv_progress(dest, timer);
+                (bytes_read, self.record_recv(before, res))
+            }
+
+            fn try_recv(&mut self, dest: &mut [u8]) -> super::Result<Option<usize>> {
+                let before = self.rx_counters();
+                let res = self.rx_channel.try_recv(dest);
+
+                // Nothing was received if no complete message was buffered.
+                if let Ok(None) = res {
+                    return res;
+                }
+
+                self.record_recv(before, res)
+            }
+
+            fn recv_exact<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                timer: &mut T,
+            ) -> super::Result<()> {
+                let before = self.rx_counters();
+                let res = self.rx_channel.recv_exact(dest, timer);
+                self.record_recv(before, res)
             }
         }
 
@@ -144,12 +470,120 @@ macro_rules! uart_impl {
             RX: RxPin<$uart_typ>,
         {
             fn send(&mut self, src: &mut [u8]) -> super::Result<()> {
-                self.tx_channel.send(src)
+                self.wait_interframe_gap();
+                let res = self.tx_channel.send(src);
+                self.record_send(res)
+            }
+
+            fn flush(&mut self) -> super::Result<()> {
+                self.tx_channel.flush()
+            }
+
+            fn send_vectored<const MAX_LEN: usize>(
+                &mut self,
+                parts: &[&[u8]],
+            ) -> super::Result<()> {
+                self.wait_interframe_gap();
+                let res = self.tx_channel.send_vectored::<MAX_LEN>(parts);
+                self.record_send(res)
             }
         }
     };
 }
 
+impl<'a, TX, RX> Uart1Controller<'a, TX, RX>
+where
+    TX: TxPin<UART1>,
+    RX: RxPin<UART1>,
+{
+    /// Switches the controller to interrupt-driven reception, where the UART1 interrupt handler moves
+    /// received bytes into a ring buffer that receive operations read from. This prevents bytes from
+    /// being dropped when they arrive faster than they're read, such as while the CPU is busy with
+    /// cryptography. Since each received byte raises an interrupt, an event loop can also sleep with
+    /// WFI until data arrives. The [`Runtime`](crate::Runtime) unmasks the UART1 interrupt, so this
+    /// works on the controller it provides.
+    ///
+    /// This is also what lets [`try_recv`](RxChannel::try_recv) receive anything, since it only
+    /// receives a message once the whole frame is in the ring buffer. See [`MAX_BUFFERED_UART1_FRAME`]
+    /// for how long that message can be.
+    pub fn enable_rx_interrupt(&mut self) {
+        self.rx_channel.get_mut().enable_rx_interrupt();
+    }
+
+    /// Switches the controller back to polled reception. Any bytes that the interrupt handler
+    /// received but weren't read yet are dropped.
+    pub fn disable_rx_interrupt(&mut self) {
+        self.rx_channel.get_mut().disable_rx_interrupt();
+    }
+}
+
+impl<'a, TX, RX> Uart0Controller<'a, TX, RX>
+where
+    TX: TxPin<UART0>,
+    RX: RxPin<UART0>,
+{
+    /// Gets a raw byte view of UART0, with no framing or encryption, for drivers written against
+    /// [`embedded_hal::serial`], such as a console or AT-command parser. See [`RawUart0`] for more
+    /// info.
+    pub fn raw(&mut self) -> RawUart0<'_, 'a, TX, RX> {
+        RawUart0 { controller: self }
+    }
+}
+
+/// A raw byte view of a [`Uart0Controller`], created with [`Uart0Controller::raw()`], which implements
+/// [`serial::Read`] and [`serial::Write`] directly on the UART0 FIFOs. Bytes are sent and received as
+/// they are, without BogoFraming or encryption, so a peer using the controller's channels sees them
+/// as garbage between frames. Only UART0 has this view, since it's the unencrypted link to the host.
+///
+/// The view borrows the controller mutably, so the two can't be used at the same time. Line errors
+/// found by [`serial::Read::read`] are counted in the controller's [`ChannelStats`].
+pub struct RawUart0<'b, 'a, TX, RX>
+where
+    TX: TxPin<UART0>,
+    RX: RxPin<UART0>,
+{
+    controller: &'b mut Uart0Controller<'a, TX, RX>,
+}
+
+impl<TX, RX> serial::Read<u8> for RawUart0<'_, '_, TX, RX>
+where
+    TX: TxPin<UART0>,
+    RX: RxPin<UART0>,
+{
+    /// The error from a byte received with a framing, parity, break, or overrun error, which is
+    /// always [`CommunicationError::LineError`]. The byte is discarded.
+    type Error = CommunicationError;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        let before = self.controller.rx_counters();
+        let res = self.controller.rx_channel.get_mut().read_raw();
+        let line_errors = self.controller.rx_channel.get_ref().line_errors();
+
+        self.controller
+            .stats
+            .line_errors
+            .add(&line_errors.since(&before.line_errors));
+
+        res
+    }
+}
+
+impl<TX, RX> serial::Write<u8> for RawUart0<'_, '_, TX, RX>
+where
+    TX: TxPin<UART0>,
+    RX: RxPin<UART0>,
+{
+    type Error = Infallible;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.controller.tx_channel.get_mut().write_raw(word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.controller.tx_channel.get_mut().flush_raw()
+    }
+}
+
 uart_impl!(
     Uart0Controller,
     UART0,
@@ -0,0 +1,121 @@
+//! This module contains [`select()`], which waits for the first of several sources, such as a channel
+//! receiving a message or a button being pressed, or for a timeout, whichever comes first.
+//!
+//! Each source implements [`Pollable`], which checks for an event without blocking. A channel is turned
+//! into a source with [`RecvSource`], which receives with [`RxChannel::try_recv`], so it never blocks
+//! and is only ready once a whole message has arrived. Only channels that buffer received messages can
+//! be sources, such as the [`Uart1Controller`](super::Uart1Controller) with interrupt-driven reception.
+//! The [`Sw1ButtonController`] and [`Button`] are sources as they are.
+//!
+//! ```ignore
+//! rt.uart1_controller.enable_rx_interrupt();
+//!
+//! let mut frame = RecvSource::new(&mut rt.uart1_controller, &mut buf);
+//!
+//! match select(&mut [&mut frame, &mut rt.sw1_button_controller], &mut timeout) {
+//!     Selected::Source(0) => handle_frame(frame.result()),
+//!     Selected::Source(_) => handle_button(),
+//!     Selected::Timeout => return,
+//! }
+//! ```
+
+use super::{Result, RxChannel};
+use crate::button::{Button, Sw1ButtonController};
+use embedded_hal::digital::v2::InputPin;
+use ucsc_ectf_util_common::timer::Timer;
+
+/// A source of events that [`select()`] can wait on.
+pub trait Pollable {
+    /// Checks whether the source has an event, without blocking. Once this returns true, it should keep
+    /// returning true until the event is handled, such as by clearing the activation of a button.
+    fn poll_ready(&mut self) -> bool;
+}
+
+/// The result of a [`select()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Selected {
+    /// The source at the contained index in the sources given to [`select()`] has an event.
+    Source(usize),
+
+    /// The timer expired before any source had an event.
+    Timeout,
+}
+
+/// Waits until one of the given sources has an event or the timer expires, whichever comes first. The
+/// sources are polled in order, so if several have an event, the first of them is returned. The timer
+/// is checked after the sources on each pass, so a source that's already ready is returned even if the
+/// timer has already expired. The timer is never reset.
+///
+/// This polls in a busy loop instead of sleeping until an interrupt, since polled UART reception, the
+/// [`Button`], and the HIB timers don't raise interrupts.
+pub fn select<T: Timer>(sources: &mut [&mut dyn Pollable], timer: &mut T) -> Selected {
+    loop {
+        if let Some(i) = sources.iter_mut().position(|source| source.poll_ready()) {
+            return Selected::Source(i);
+        }
+
+        if timer.poll() {
+            return Selected::Timeout;
+        }
+    }
+}
+
+/// A [`Pollable`] that receives a message from a channel with [`RxChannel::try_recv`]. It's ready once a
+/// receive has finished, successfully or not, and keeps the result until it's taken with
+/// [`take_result`](RecvSource::take_result), so no receive is started until then.
+pub struct RecvSource<'a, C: RxChannel> {
+    channel: &'a mut C,
+    dest: &'a mut [u8],
+    result: Option<Result<usize>>,
+}
+
+impl<'a, C: RxChannel> RecvSource<'a, C> {
+    /// Creates a new [`RecvSource`] that receives into ``dest``.
+    pub fn new(channel: &'a mut C, dest: &'a mut [u8]) -> Self {
+        Self {
+            channel,
+            dest,
+            result: None,
+        }
+    }
+
+    /// Gets the result of the finished receive without taking it, or [`None`] if no receive has
+    /// finished. Upon success, the result is the number of bytes written into ``dest``.
+    pub fn result(&self) -> Option<Result<usize>> {
+        self.result
+    }
+
+    /// Takes the result of the finished receive, so the next poll starts another receive. See
+    /// [`result`](RecvSource::result) for more info.
+    pub fn take_result(&mut self) -> Option<Result<usize>> {
+        self.result.take()
+    }
+}
+
+impl<C: RxChannel> Pollable for RecvSource<'_, C> {
+    fn poll_ready(&mut self) -> bool {
+        if self.result.is_some() {
+            return true;
+        }
+
+        self.result = self.channel.try_recv(self.dest).transpose();
+
+        self.result.is_some()
+    }
+}
+
+impl Pollable for Sw1ButtonController<'_> {
+    /// Checks whether SW1 has been activated. The activation must be cleared with
+    /// [`Sw1ButtonController::clear_activation()`] once it's handled.
+    fn poll_ready(&mut self) -> bool {
+        self.poll_for_activation()
+    }
+}
+
+impl<P: InputPin> Pollable for Button<'_, P> {
+    /// Checks whether the button is currently pressed, with no debouncing. See
+    /// [`Button::is_pressed()`] for more info.
+    fn poll_ready(&mut self) -> bool {
+        self.is_pressed()
+    }
+}
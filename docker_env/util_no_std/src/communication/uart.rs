@@ -1,19 +1,32 @@
-use core::ops::Deref;
+use core::{
+    cell::RefCell,
+    convert::Infallible,
+    ops::Deref,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-use cortex_m::prelude::_embedded_hal_serial_Read;
+use cortex_m::interrupt::Mutex;
+use heapless::spsc::Queue;
 use tm4c123x_hal::{
+    interrupt,
     serial::{Rx, RxPin, Tx, TxPin},
     tm4c123x::{uart0, UART0, UART1},
 };
 use ucsc_ectf_util_common::{
-    communication::{lower_layers::framing::bogoframing, CommunicationError},
+    communication::{
+        lower_layers::framing::{bogoframing, cobs},
+        CommunicationError,
+    },
     timer::Timer,
 };
 
-use crate::communication::{
-    self,
-    lower_layers::framing::{Frame, FramedTxChannel},
-    RxChannel,
+use crate::{
+    communication::{
+        self,
+        lower_layers::framing::{Frame, FramedTxChannel},
+        RxChannel,
+    },
+    event::{self, RuntimeEvent},
 };
 
 const UART_FIFO_LEN: usize = 16;
@@ -21,6 +34,269 @@ const UART_FIFO_LEN: usize = 16;
 /// The minimum size a framed UART message can be.
 pub const MIN_FRAMED_UART_MESSAGE: usize = UART_FIFO_LEN;
 
+/// The minimum size a COBS framed UART message can be.
+pub const MIN_COBS_UART_MESSAGE: usize = 1;
+
+/// The size of the buffer that UART1 bytes are received into when interrupt-driven reception is enabled.
+const UART1_RX_BUFFER_LEN: usize = 256;
+
+/// The longest frame that [`RxChannel::try_recv`] can receive over UART1, not counting the framing, since
+/// the whole frame must fit in the ring buffer. The ring buffer holds one byte less than its size, and
+/// BogoFraming doubles the size of each message, plus two bytes for the frame delimiters.
+/// For the [`Uart1Controller`](crate::communication::Uart1Controller), this includes the
+/// [`METADATA_SIZE`](crate::communication::lower_layers::crypto::METADATA_SIZE) bytes of encryption
+/// metadata.
+pub const MAX_BUFFERED_UART1_FRAME: usize = (UART1_RX_BUFFER_LEN - 1 - 2) / 2;
+
+/// Whether UART1 reception is interrupt-driven.
+static UART1_RX_INTERRUPT_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The ring buffer that the UART1 interrupt handler puts received bytes into.
+static UART1_RX_BUFFER: Mutex<RefCell<Queue<u8, UART1_RX_BUFFER_LEN>>> =
+    Mutex::new(RefCell::new(Queue::new()));
+
+/// The line errors that the UART1 interrupt handler found since they were last taken by a receive
+/// operation.
+static UART1_PENDING_LINE_ERRORS: Mutex<RefCell<LineErrorStats>> =
+    Mutex::new(RefCell::new(LineErrorStats::new()));
+
+#[interrupt]
+fn UART1() {
+    cortex_m::interrupt::free(|cs| {
+        let uart1 = UART1::registers();
+        let mut rx_buffer = UART1_RX_BUFFER.borrow(cs).borrow_mut();
+        let mut line_errors = UART1_PENDING_LINE_ERRORS.borrow(cs).borrow_mut();
+
+        // Move everything in the hardware FIFO into the ring buffer. If the ring buffer is full, the byte
+        // is dropped, just like it would be if the hardware FIFO overflowed. Bytes with line errors are
+        // dropped too, and their errors are left for the next receive operation to report.
+        let mut received = false;
+
+        while uart1.fr.read().rxfe().bit_is_clear() {
+            let data = uart1.dr.read();
+
+            if line_errors.record(&data) {
+                clear_line_errors(uart1);
+            } else {
+                received |= rx_buffer.enqueue(data.data().bits()).is_ok();
+            }
+        }
+
+        if received {
+            event::push_event(RuntimeEvent::Uart1Rx);
+        }
+
+        // Clear the receive and receive timeout interrupts.
+        uart1.icr.write(|w| w.rxic().set_bit().rtic().set_bit());
+    });
+}
+
+/// The position of the RX interrupt FIFO level select field in the IFLS register.
+const IFLS_RX_SHIFT: u32 = 3;
+
+/// The mask for the RX interrupt FIFO level select field in the IFLS register.
+const IFLS_RX_MASK: u32 = 0b111 << IFLS_RX_SHIFT;
+
+/// How full the receive FIFO of UART1 must be before the receive interrupt is raised when
+/// interrupt-driven reception is enabled. Bytes that don't reach the level are still moved into the
+/// ring buffer by the receive timeout interrupt, which is raised once the line has been idle for 32
+/// bit periods, so no bytes are left behind at any level.
+///
+/// A low level keeps latency low, since a short frame, such as a handshake frame, is handed to the
+/// ring buffer as soon as it arrives instead of waiting for the receive timeout. The cost is an
+/// interrupt every few bytes. A high level raises fewer interrupts during bulk transfers, but the end of
+/// each frame waits for the receive timeout, which is about 280 us at 115200 baud, and the FIFO has
+/// less room left to keep bytes from being lost while interrupts are disabled, such as during a flash
+/// write. The FIFO holds 16 bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RxFifoTrigger {
+    /// The interrupt is raised once the FIFO holds 2 bytes.
+    OneEighth,
+
+    /// The interrupt is raised once the FIFO holds 4 bytes.
+    OneQuarter,
+
+    /// The interrupt is raised once the FIFO holds 8 bytes. This is the hardware default.
+    Half,
+
+    /// The interrupt is raised once the FIFO holds 12 bytes.
+    ThreeQuarters,
+
+    /// The interrupt is raised once the FIFO holds 14 bytes.
+    SevenEighths,
+}
+
+impl RxFifoTrigger {
+    /// Gets the value of the RX interrupt FIFO level select field for this level.
+    fn ifls_bits(self) -> u32 {
+        match self {
+            Self::OneEighth => 0x0,
+            Self::OneQuarter => 0x1,
+            Self::Half => 0x2,
+            Self::ThreeQuarters => 0x3,
+            Self::SevenEighths => 0x4,
+        }
+    }
+}
+
+/// Sets the receive FIFO level that raises the UART1 receive interrupt. See [`RxFifoTrigger`] for
+/// more info.
+pub(crate) fn set_uart1_rx_fifo_trigger(trigger: RxFifoTrigger) {
+    // SAFETY: Only the RX level select field is changed, and every value from RxFifoTrigger is valid
+    // for it.
+    UART1::registers().ifls.modify(|r, w| unsafe {
+        w.bits((r.bits() & !IFLS_RX_MASK) | (trigger.ifls_bits() << IFLS_RX_SHIFT))
+    });
+}
+
+/// Provides access to the register block of a UART peripheral for status checks that the HAL doesn't
+/// expose.
+trait UartRegisters {
+    /// Gets the register block of the UART peripheral.
+    fn registers() -> &'static uart0::RegisterBlock;
+}
+
+impl UartRegisters for UART0 {
+    fn registers() -> &'static uart0::RegisterBlock {
+        // SAFETY: The UART0 peripheral is owned by a split Serial, whose halves can only be used through
+        // the channels in this module. The register block is made up of volatile cells, so shared access
+        // is sound. This is the same way the HAL accesses the registers of a split Serial.
+        unsafe { &*UART0::ptr() }
+    }
+}
+
+impl UartRegisters for UART1 {
+    fn registers() -> &'static uart0::RegisterBlock {
+        // SAFETY: The UART1 peripheral is owned by a split Serial, whose halves can only be used through
+        // the channels in this module. The register block is made up of volatile cells, so shared access
+        // is sound. This is the same way the HAL accesses the registers of a split Serial.
+        unsafe { &*UART1::ptr() }
+    }
+}
+
+/// Counters for the physical-layer errors that a UART reported for received bytes. A byte with any of
+/// these errors is discarded instead of being given to the framing decoder, and the receive operation
+/// fails with [`CommunicationError::LineError`]. A byte is counted once for each error it had. Every
+/// counter wraps around on overflow.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LineErrorStats {
+    /// The number of bytes received without a valid stop bit, which is usually caused by noise or a
+    /// baud rate mismatch.
+    pub framing: u32,
+
+    /// The number of bytes received with the wrong parity. The UARTs are configured without parity, so
+    /// this should always be zero.
+    pub parity: u32,
+
+    /// The number of breaks received, where the line was held low for longer than a whole byte, such as
+    /// when the other end is disconnected or reset.
+    pub breaks: u32,
+
+    /// The number of times a byte arrived while the receive FIFO was full, losing that byte.
+    pub overruns: u32,
+}
+
+impl LineErrorStats {
+    /// Creates counters with no errors.
+    const fn new() -> Self {
+        Self {
+            framing: 0,
+            parity: 0,
+            breaks: 0,
+            overruns: 0,
+        }
+    }
+
+    /// Counts the errors in the receive status that's read along with a byte from the data register of a
+    /// UART, returning whether there were any.
+    fn record(&mut self, data: &uart0::dr::R) -> bool {
+        let errors = [
+            (data.fe().bit_is_set(), &mut self.framing),
+            (data.pe().bit_is_set(), &mut self.parity),
+            (data.be().bit_is_set(), &mut self.breaks),
+            (data.oe().bit_is_set(), &mut self.overruns),
+        ];
+        let mut any = false;
+
+        for (is_set, counter) in errors {
+            if is_set {
+                *counter = counter.wrapping_add(1);
+                any = true;
+            }
+        }
+
+        any
+    }
+
+    /// Adds the counts of another set of counters to these.
+    pub(crate) fn add(&mut self, other: &Self) {
+        self.framing = self.framing.wrapping_add(other.framing);
+        self.parity = self.parity.wrapping_add(other.parity);
+        self.breaks = self.breaks.wrapping_add(other.breaks);
+        self.overruns = self.overruns.wrapping_add(other.overruns);
+    }
+
+    /// Gets the errors counted since these counters had the values in ``earlier``.
+    pub(crate) fn since(&self, earlier: &Self) -> Self {
+        Self {
+            framing: self.framing.wrapping_sub(earlier.framing),
+            parity: self.parity.wrapping_sub(earlier.parity),
+            breaks: self.breaks.wrapping_sub(earlier.breaks),
+            overruns: self.overruns.wrapping_sub(earlier.overruns),
+        }
+    }
+}
+
+/// Clears the line error interrupt flags of a UART, so its raw interrupt status only shows errors that
+/// haven't been handled yet.
+fn clear_line_errors(uart: &uart0::RegisterBlock) {
+    uart.icr.write(|w| {
+        w.feic()
+            .set_bit()
+            .peic()
+            .set_bit()
+            .beic()
+            .set_bit()
+            .oeic()
+            .set_bit()
+    });
+}
+
+/// Reads a byte from the receive FIFO of a UART. The data register is read directly instead of through
+/// the HAL, which throws away the receive status that comes with each byte.
+///
+/// # ERRORS:
+///
+/// - [`CommunicationError::RecvError`] - The receive FIFO is empty.
+/// - [`CommunicationError::LineError`] - The byte was received with a framing, parity, break, or overrun
+///   error. The byte is discarded, and its errors are counted in ``line_errors``.
+fn read_checked<UART: UartRegisters>(
+    line_errors: &mut LineErrorStats,
+) -> communication::Result<u8> {
+    let uart = UART::registers();
+
+    if uart.fr.read().rxfe().bit_is_set() {
+        return Err(CommunicationError::RecvError);
+    }
+
+    let data = uart.dr.read();
+
+    if line_errors.record(&data) {
+        clear_line_errors(uart);
+        return Err(CommunicationError::LineError);
+    }
+
+    Ok(data.data().bits())
+}
+
+/// Blocks until the transmit FIFO of a UART is empty and the last byte, including its stop bits, has
+/// been shifted out.
+fn wait_tx_done<UART: UartRegisters>() {
+    let uart = UART::registers();
+
+    while uart.fr.read().txfe().bit_is_clear() || uart.fr.read().busy().bit_is_set() {}
+}
+
 /// A [`FramedTxChannel`] for transmitting UART data. This channel is unreliable and can lose bytes
 /// in transmission. It is also insecure and should be wrapped around one of the channels in the
 /// [`crypto`](crate::communication::lower_layers::crypto) layer for confidentiality and/or integrity.
@@ -43,6 +319,33 @@ where
     pub fn new_uart0_tx_channel(tx: &'a mut Tx<UART0, TX, ()>) -> Self {
         Self { tx }
     }
+
+    /// Writes one byte to the transmit FIFO without framing, for the raw byte view of the
+    /// [`Uart0Controller`](crate::communication::Uart0Controller).
+    pub(crate) fn write_raw(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        let uart = UART0::registers();
+
+        if uart.fr.read().txff().bit_is_set() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // SAFETY: Any byte can be written to the data register.
+        uart.dr.write(|w| unsafe { w.data().bits(byte) });
+
+        Ok(())
+    }
+
+    /// Checks whether every byte written has left the device, without blocking. See
+    /// [`write_raw`](Self::write_raw) for more info.
+    pub(crate) fn flush_raw(&mut self) -> nb::Result<(), Infallible> {
+        let fr = UART0::registers().fr.read();
+
+        if fr.txfe().bit_is_clear() || fr.busy().bit_is_set() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(())
+    }
 }
 
 impl<'a, TX> FramedUartTxChannel<'a, UART1, TX>
@@ -66,7 +369,20 @@ where
     UART: Deref<Target = uart0::RegisterBlock>,
     RX: RxPin<UART>,
 {
-    rx: &'a mut Rx<UART, RX, ()>,
+    // Held so nothing else can read from the UART. Bytes are read with read_checked instead of the HAL.
+    _rx: &'a mut Rx<UART, RX, ()>,
+    line_errors: LineErrorStats,
+}
+
+impl<'a, UART, RX> FramedUartRxChannel<'a, UART, RX>
+where
+    UART: Deref<Target = uart0::RegisterBlock>,
+    RX: RxPin<UART>,
+{
+    /// Gets the line errors that were found in the bytes received by this channel.
+    pub fn line_errors(&self) -> LineErrorStats {
+        self.line_errors
+    }
 }
 
 impl<'a, RX> FramedUartRxChannel<'a, UART0, RX>
@@ -76,7 +392,20 @@ where
     /// Creates a new [`FramedUartRxChannel`] for UART0 tranmission given the [`Rx`] end
     /// of a split [`Serial`](tm4c123x_hal::serial::Serial).
     pub fn new_uart0_rx_channel(rx: &'a mut Rx<UART0, RX, ()>) -> Self {
-        Self { rx }
+        Self {
+            _rx: rx,
+            line_errors: LineErrorStats::new(),
+        }
+    }
+
+    /// Reads one byte from the receive FIFO without framing, for the raw byte view of the
+    /// [`Uart0Controller`](crate::communication::Uart0Controller). Line errors are counted in the same
+    /// way as for a framed receive.
+    pub(crate) fn read_raw(&mut self) -> nb::Result<u8, CommunicationError> {
+        read_checked::<UART0>(&mut self.line_errors).map_err(|e| match e {
+            CommunicationError::RecvError => nb::Error::WouldBlock,
+            e => nb::Error::Other(e),
+        })
     }
 }
 
@@ -87,7 +416,65 @@ where
     /// Creates a new [`FramedUartRxChannel`] for UART1 tranmission given the [`Tx`] end
     /// of a split [`Serial`](tm4c123x_hal::serial::Serial).
     pub fn new_uart1_rx_channel(rx: &'a mut Rx<UART1, RX, ()>) -> Self {
-        Self { rx }
+        Self {
+            _rx: rx,
+            line_errors: LineErrorStats::new(),
+        }
+    }
+
+    /// Switches UART1 to interrupt-driven reception. From then on, the UART1 interrupt handler moves
+    /// received bytes from the hardware FIFO into a ring buffer, and this channel reads from that
+    /// buffer instead of the hardware FIFO. This keeps bytes from being lost while the CPU is busy
+    /// with something else. The UART1 interrupt must be unmasked in the NVIC for this to work.
+    pub fn enable_rx_interrupt(&mut self) {
+        UART1_RX_INTERRUPT_ENABLED.store(true, Ordering::SeqCst);
+
+        UART1::registers()
+            .im
+            .modify(|_, w| w.rxim().set_bit().rtim().set_bit());
+    }
+
+    /// Switches UART1 back to reading from the hardware FIFO, masking its receive interrupts. Any
+    /// bytes and line errors left in the ring buffer are dropped.
+    pub fn disable_rx_interrupt(&mut self) {
+        UART1::registers()
+            .im
+            .modify(|_, w| w.rxim().clear_bit().rtim().clear_bit());
+
+        UART1_RX_INTERRUPT_ENABLED.store(false, Ordering::SeqCst);
+
+        cortex_m::interrupt::free(|cs| {
+            *UART1_RX_BUFFER.borrow(cs).borrow_mut() = Queue::new();
+            UART1_PENDING_LINE_ERRORS
+                .borrow(cs)
+                .replace(LineErrorStats::new());
+        });
+    }
+
+    /// Reads a byte from the ring buffer if interrupt-driven reception is enabled, or from the hardware
+    /// FIFO otherwise. With interrupt-driven reception, any line errors the interrupt handler found
+    /// since the last read are reported before the bytes after them are read.
+    fn read_byte(&mut self) -> communication::Result<u8> {
+        if !UART1_RX_INTERRUPT_ENABLED.load(Ordering::SeqCst) {
+            return read_checked::<UART1>(&mut self.line_errors);
+        }
+
+        cortex_m::interrupt::free(|cs| {
+            let pending = UART1_PENDING_LINE_ERRORS
+                .borrow(cs)
+                .replace(LineErrorStats::new());
+
+            if pending != LineErrorStats::new() {
+                self.line_errors.add(&pending);
+                return Err(CommunicationError::LineError);
+            }
+
+            UART1_RX_BUFFER
+                .borrow(cs)
+                .borrow_mut()
+                .dequeue()
+                .ok_or(CommunicationError::RecvError)
+        })
     }
 }
 
@@ -109,6 +496,11 @@ where
             MIN_FRAMED_UART_MESSAGE,
         )
     }
+
+    fn flush_frames(&mut self) -> communication::Result<()> {
+        wait_tx_done::<UART0>();
+        Ok(())
+    }
 }
 
 impl<'a, TX> FramedTxChannel for FramedUartTxChannel<'a, UART1, TX>
@@ -129,6 +521,11 @@ where
             MIN_FRAMED_UART_MESSAGE,
         )
     }
+
+    fn flush_frames(&mut self) -> communication::Result<()> {
+        wait_tx_done::<UART1>();
+        Ok(())
+    }
 }
 
 impl<'a, RX> RxChannel for FramedUartRxChannel<'a, UART0, RX>
@@ -144,7 +541,7 @@ where
             self,
             dest,
             timer,
-            |s| s.rx.read().map_err(|_| CommunicationError::RecvError),
+            |s| read_checked::<UART0>(&mut s.line_errors),
             MIN_FRAMED_UART_MESSAGE,
         )
     }
@@ -158,7 +555,21 @@ where
             self,
             dest,
             timer,
-            |s| s.rx.read().map_err(|_| CommunicationError::RecvError),
+            |s| read_checked::<UART0>(&mut s.line_errors),
+            MIN_FRAMED_UART_MESSAGE,
+        )
+    }
+
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, communication::Result<usize>) {
+        bogoframing::recv_frame_with_progress(
+            self,
+            dest,
+            timer,
+            |s| read_checked::<UART0>(&mut s.line_errors),
             MIN_FRAMED_UART_MESSAGE,
         )
     }
@@ -177,7 +588,7 @@ where
             self,
             dest,
             timer,
-            |s| s.rx.read().map_err(|_| CommunicationError::RecvError),
+            |s| s.read_byte(),
             MIN_FRAMED_UART_MESSAGE,
         )
     }
@@ -191,8 +602,218 @@ where
             self,
             dest,
             timer,
-            |s| s.rx.read().map_err(|_| CommunicationError::RecvError),
+            |s| s.read_byte(),
+            MIN_FRAMED_UART_MESSAGE,
+        )
+    }
+
+    fn recv_progress<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> (usize, communication::Result<usize>) {
+        bogoframing::recv_frame_with_progress(
+            self,
+            dest,
+            timer,
+            |s| s.read_byte(),
             MIN_FRAMED_UART_MESSAGE,
         )
     }
+
+    /// Attempts to receive a message without blocking, returning ``Ok(None)`` unless a complete frame is
+    /// waiting in the ring buffer. This only works with interrupt-driven reception, since the hardware
+    /// FIFO can't hold a whole frame, so this always returns ``Ok(None)`` until
+    /// [`enable_rx_interrupt`](Self::enable_rx_interrupt) is called. A frame must fit in the ring buffer
+    /// to be received this way, so it can be at most [`MAX_BUFFERED_UART1_FRAME`] bytes long. If the ring
+    /// buffer fills up before a frame ends, the buffered bytes are dropped and a
+    /// [`CommunicationError::RecvError`] is returned, so that the buffer has space for the next frame.
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the same errors as
+    /// [`recv_with_data_timeout`](RxChannel::recv_with_data_timeout), apart from
+    /// [`CommunicationError::Timeout`].
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        if !UART1_RX_INTERRUPT_ENABLED.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        cortex_m::interrupt::free(|cs| {
+            let pending = UART1_PENDING_LINE_ERRORS
+                .borrow(cs)
+                .replace(LineErrorStats::new());
+
+            if pending != LineErrorStats::new() {
+                self.line_errors.add(&pending);
+                return Err(CommunicationError::LineError);
+            }
+
+            let mut rx_buffer = UART1_RX_BUFFER.borrow(cs).borrow_mut();
+
+            let Some((used, res)) = bogoframing::decode_buffered_frame(
+                rx_buffer.iter().copied(),
+                dest,
+                MIN_FRAMED_UART_MESSAGE,
+            ) else {
+                if rx_buffer.is_full() {
+                    *rx_buffer = Queue::new();
+                    return Err(CommunicationError::RecvError);
+                }
+
+                return Ok(None);
+            };
+
+            for _ in 0..used {
+                rx_buffer.dequeue();
+            }
+
+            res.map(Some)
+        })
+    }
+}
+
+/// A [`FramedTxChannel`] for transmitting UART data using COBS framing. See the
+/// [`cobs`](ucsc_ectf_util_common::communication::lower_layers::framing::cobs) module for details on
+/// how framing works for this channel. Like [`FramedUartTxChannel`], this channel is unreliable and
+/// insecure and should be wrapped around one of the channels in the
+/// [`crypto`](crate::communication::lower_layers::crypto) layer for confidentiality and/or integrity.
+/// A message sent by this channel must be at least [`MIN_COBS_UART_MESSAGE`] bytes long.
+pub struct CobsTxChannel<'a, UART, TX>
+where
+    UART: Deref<Target = uart0::RegisterBlock>,
+    TX: TxPin<UART>,
+{
+    tx: &'a mut Tx<UART, TX, ()>,
 }
+
+/// An [`RxChannel`] for receiving UART data using COBS framing. See the
+/// [`cobs`](ucsc_ectf_util_common::communication::lower_layers::framing::cobs) module for details on
+/// how framing works for this channel. Unlike [`FramedUartRxChannel`], this channel resynchronizes on
+/// the next message boundary after receiving a corrupted frame. It is still unreliable and insecure and
+/// should be wrapped around one of the channels in the [`crypto`](crate::communication::lower_layers::crypto)
+/// layer for confidentiality and/or integrity.
+pub struct CobsRxChannel<'a, UART, RX>
+where
+    UART: Deref<Target = uart0::RegisterBlock>,
+    RX: RxPin<UART>,
+{
+    // Held so nothing else can read from the UART. Bytes are read with read_checked instead of the HAL.
+    _rx: &'a mut Rx<UART, RX, ()>,
+    line_errors: LineErrorStats,
+}
+
+impl<'a, UART, RX> CobsRxChannel<'a, UART, RX>
+where
+    UART: Deref<Target = uart0::RegisterBlock>,
+    RX: RxPin<UART>,
+{
+    /// Gets the line errors that were found in the bytes received by this channel.
+    pub fn line_errors(&self) -> LineErrorStats {
+        self.line_errors
+    }
+}
+
+macro_rules! cobs_impl {
+    ($uart_typ:ident, $tx_ctor:ident, $rx_ctor:ident) => {
+        impl<'a, TX> CobsTxChannel<'a, $uart_typ, TX>
+        where
+            TX: TxPin<$uart_typ>,
+        {
+            #[doc = concat!("Creates a new [`CobsTxChannel`] for ", stringify!($uart_typ))]
+            /// transmission given the [`Tx`] end of a split [`Serial`](tm4c123x_hal::serial::Serial).
+            pub fn $tx_ctor(tx: &'a mut Tx<$uart_typ, TX, ()>) -> Self {
+                Self { tx }
+            }
+        }
+
+        impl<'a, RX> CobsRxChannel<'a, $uart_typ, RX>
+        where
+            RX: RxPin<$uart_typ>,
+        {
+            #[doc = concat!("Creates a new [`CobsRxChannel`] for ", stringify!($uart_typ))]
+            /// reception given the [`Rx`] end of a split [`Serial`](tm4c123x_hal::serial::Serial).
+            pub fn $rx_ctor(rx: &'a mut Rx<$uart_typ, RX, ()>) -> Self {
+                Self {
+                    _rx: rx,
+                    line_errors: LineErrorStats::new(),
+                }
+            }
+        }
+
+        impl<'a, TX> FramedTxChannel for CobsTxChannel<'a, $uart_typ, TX>
+        where
+            TX: TxPin<$uart_typ>,
+        {
+            fn frame<'b, const FRAME_CT: usize>(
+                &mut self,
+                frame: impl FnOnce() -> communication::Result<Frame<'b, FRAME_CT>>,
+            ) -> communication::Result<()> {
+                cobs::frame_cobs(
+                    self,
+                    frame()?,
+                    |ch, s| {
+                        ch.tx.write_all(s);
+                        Ok(())
+                    },
+                    MIN_COBS_UART_MESSAGE,
+                )
+            }
+
+            fn flush_frames(&mut self) -> communication::Result<()> {
+                wait_tx_done::<$uart_typ>();
+                Ok(())
+            }
+        }
+
+        impl<'a, RX> RxChannel for CobsRxChannel<'a, $uart_typ, RX>
+        where
+            RX: RxPin<$uart_typ>,
+        {
+            fn recv_with_data_timeout<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                timer: &mut T,
+            ) -> communication::Result<usize> {
+                cobs::recv_frame_with_data_timeout(
+                    self,
+                    dest,
+                    timer,
+                    |s| read_checked::<$uart_typ>(&mut s.line_errors),
+                    MIN_COBS_UART_MESSAGE,
+                )
+            }
+
+            fn recv_with_timeout<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                timer: &mut T,
+            ) -> communication::Result<usize> {
+                cobs::recv_frame_with_timeout(
+                    self,
+                    dest,
+                    timer,
+                    |s| read_checked::<$uart_typ>(&mut s.line_errors),
+                    MIN_COBS_UART_MESSAGE,
+                )
+            }
+
+            fn recv_progress<T: Timer>(
+                &mut self,
+                dest: &mut [u8],
+                timer: &mut T,
+            ) -> (usize, communication::Result<usize>) {
+                cobs::recv_frame_with_progress(
+                    self,
+                    dest,
+                    timer,
+                    |s| read_checked::<$uart_typ>(&mut s.line_errors),
+                    MIN_COBS_UART_MESSAGE,
+                )
+            }
+        }
+    };
+}
+
+cobs_impl!(UART0, new_uart0_tx_channel, new_uart0_rx_channel);
+cobs_impl!(UART1, new_uart1_tx_channel, new_uart1_rx_channel);
@@ -0,0 +1,157 @@
+//! This module contains cryptographic helpers that aren't provided by the communication stack, such as
+//! key derivation and signature verification.
+
+use chacha20poly1305::Key;
+use ed25519_compact::{PublicKey, Signature};
+use sha3::{Digest, Sha3_256};
+use zeroize::Zeroizing;
+
+/// The output size of SHA3-256 in bytes.
+pub const HASH_SIZE: usize = 32;
+
+/// The block size of SHA3-256 in bytes, which is the rate of the sponge. This is the block size used
+/// by HMAC.
+const BLOCK_SIZE: usize = 136;
+
+/// The maximum output size of [`hkdf_sha3()`] in bytes.
+pub const HKDF_MAX_OUTPUT_SIZE: usize = 255 * HASH_SIZE;
+
+/// The `info` label used to derive the key for messages sent by the initiator of a session.
+const INITIATOR_KEY_LABEL: &[u8] = b"ucsc-ectf-2023 initiator to responder key";
+
+/// The `info` label used to derive the key for messages sent by the responder of a session.
+const RESPONDER_KEY_LABEL: &[u8] = b"ucsc-ectf-2023 responder to initiator key";
+
+/// A SHA3-256 hasher that's fed its input in pieces, such as the fragments of a feature token as they're
+/// received, so the whole input never has to be held in memory. Feeding the same bytes in any split
+/// gives the same hash as hashing them all at once.
+#[derive(Clone, Default)]
+pub struct Sha3Hasher {
+    hasher: Sha3_256,
+}
+
+impl Sha3Hasher {
+    /// Creates a new [`Sha3Hasher`] that hasn't been fed anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the hasher the next piece of its input.
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /// Consumes the hasher, returning the SHA3-256 hash of everything it was fed.
+    pub fn finalize(self) -> [u8; HASH_SIZE] {
+        self.hasher.finalize().into()
+    }
+}
+
+/// An error from a key derivation function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KdfError {
+    /// The requested output is longer than [`HKDF_MAX_OUTPUT_SIZE`].
+    OutputTooLong,
+}
+
+/// Computes HMAC-SHA3-256 over the concatenation of the given data slices.
+fn hmac_sha3(key: &[u8], data: &[&[u8]]) -> [u8; HASH_SIZE] {
+    let mut block_key = Zeroizing::new([0; BLOCK_SIZE]);
+
+    // Keys longer than the block size are hashed first.
+    if key.len() > BLOCK_SIZE {
+        block_key[..HASH_SIZE].copy_from_slice(&Sha3_256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut pad = Zeroizing::new([0; BLOCK_SIZE]);
+
+    // Inner hash.
+    for (p, k) in pad.iter_mut().zip(block_key.iter()) {
+        *p = k ^ 0x36;
+    }
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(pad.as_slice());
+
+    for d in data {
+        hasher.update(d);
+    }
+
+    let mut inner = Zeroizing::new([0; HASH_SIZE]);
+    inner.copy_from_slice(&hasher.finalize());
+
+    // Outer hash.
+    for (p, k) in pad.iter_mut().zip(block_key.iter()) {
+        *p = k ^ 0x5C;
+    }
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(pad.as_slice());
+    hasher.update(inner.as_slice());
+
+    hasher.finalize().into()
+}
+
+/// Derives key material from input keying material with HKDF (RFC 5869) using HMAC-SHA3-256, filling
+/// all of `okm`. An empty `salt` is the same as a salt of zeros. The `info` label should be different
+/// for each key derived from the same input keying material.
+///
+/// # ERRORS:
+///
+/// - [`KdfError::OutputTooLong`] - `okm` is longer than [`HKDF_MAX_OUTPUT_SIZE`] bytes. `okm` is left
+///   untouched.
+pub fn hkdf_sha3(ikm: &[u8], salt: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), KdfError> {
+    if okm.len() > HKDF_MAX_OUTPUT_SIZE {
+        return Err(KdfError::OutputTooLong);
+    }
+
+    // HKDF-Extract.
+    let prk = Zeroizing::new(hmac_sha3(salt, &[ikm]));
+
+    // HKDF-Expand.
+    let mut block = Zeroizing::new([0; HASH_SIZE]);
+
+    for (i, chunk) in okm.chunks_mut(HASH_SIZE).enumerate() {
+        let counter = [i as u8 + 1];
+        let previous: &[u8] = if i == 0 { &[] } else { block.as_slice() };
+
+        let next = Zeroizing::new(hmac_sha3(prk.as_slice(), &[previous, info, &counter]));
+        block.copy_from_slice(next.as_slice());
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+
+    Ok(())
+}
+
+/// Derives the two session keys for a session from a shared secret, such as the output of a key
+/// exchange. The first key is for messages sent by the initiator of the session and the second key is
+/// for messages sent by the responder, so the initiator uses the first key as its transmit key and the
+/// second as its receive key, and the responder does the opposite.
+///
+/// The returned keys aren't wiped when they're dropped, so they should be zeroized once they've been
+/// given to a channel.
+pub fn derive_session_keys(shared_secret: &[u8]) -> (Key, Key) {
+    let mut initiator_key = Key::default();
+    let mut responder_key = Key::default();
+
+    // Neither of these can fail because the keys are shorter than the maximum output size.
+    hkdf_sha3(shared_secret, &[], INITIATOR_KEY_LABEL, &mut initiator_key).unwrap();
+    hkdf_sha3(shared_secret, &[], RESPONDER_KEY_LABEL, &mut responder_key).unwrap();
+
+    (initiator_key, responder_key)
+}
+
+/// Verifies an Ed25519 signature over a message with the given public key. Returns false if the
+/// signature is invalid, or if the public key or signature is malformed, such as a public key that
+/// isn't a valid curve point or a non-canonical signature.
+///
+/// The public key is usually stored in EEPROM, so it should be read with
+/// [`EepromController::read_slice()`](crate::eeprom::EepromController::read_slice) and copied into
+/// an array before calling this function.
+pub fn verify_ed25519(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    PublicKey::new(*public_key)
+        .verify(message, &Signature::new(*signature))
+        .is_ok()
+}
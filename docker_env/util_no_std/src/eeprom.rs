@@ -1,18 +1,60 @@
 //! This module contains an interface to read from and write to the EEPROM.
 
+mod region;
+mod transaction;
+mod wear_leveling;
+
+pub use region::{EepromRegion, EepromRegionHandle, EepromRegionTable};
+pub use transaction::EepromTransaction;
+pub use wear_leveling::WearLeveledCounter;
+
+use crate::random;
+use core::{
+    mem::{self, MaybeUninit},
+    ops::{Deref, DerefMut},
+    slice,
+};
 use cortex_m::asm::delay;
+use heapless::Vec;
 use tm4c123x_hal::sysctl::{self, Domain, PowerControl, PowerState, RunMode};
 use tm4c123x_hal::tm4c123x::EEPROM;
-use ucsc_ectf_eeprom_layout::EepromFieldBounds;
+use ucsc_ectf_util_common::crc32;
+use zeroize::{Zeroize, Zeroizing};
 
 pub use ucsc_ectf_eeprom_layout::EepromReadField;
 pub use ucsc_ectf_eeprom_layout::EepromReadOnlyField;
 pub use ucsc_ectf_eeprom_layout::EepromReadWriteField;
 pub use ucsc_ectf_eeprom_layout::{
-    BYTE_FIELD_SIZE, CAR_ID_SIZE, MESSAGE_SIZE, PACKAGED_FEATURE_SIGNED_SIZE, PAIRING_PIN_SIZE,
-    PUBLIC_KEY_SIZE, SECRET_SIZE, SIGNATURE_SIZE,
+    BYTE_FIELD_SIZE, CAR_ID_SIZE, FREE_SPACE_BOUNDS, JOURNAL_BOUNDS, JOURNAL_CAPACITY,
+    MESSAGE_SIZE, PACKAGED_FEATURE_SIGNED_SIZE, PAIRING_PIN_SIZE, PUBLIC_KEY_SIZE, SECRET_SIZE,
+    SIGNATURE_SIZE,
 };
 
+/// The read-only fields wiped by a factory reset. These are the provisioned secrets. The verifying keys
+/// and messages are public, so they're left alone, and the device has to be provisioned again before
+/// it can pair or unlock.
+pub const FACTORY_RESET_READ_ONLY_FIELDS: [EepromReadOnlyField; 2] = [
+    EepromReadOnlyField::PairedFobPairingSigningKey,
+    EepromReadOnlyField::SecretSeed,
+];
+
+/// The read-write fields wiped by a factory reset. This is every read-write field, which covers the
+/// secrets and keys written during pairing, the pairing byte, the pairing cooldown, the car ID, and the
+/// packaged features. Zeros are read back as unpaired, with no cooldown and no features.
+pub const FACTORY_RESET_READ_WRITE_FIELDS: [EepromReadWriteField; 11] = [
+    EepromReadWriteField::UnpairedFobPairingSigningKey,
+    EepromReadWriteField::UnpairedFobPairingPublicKeySignature,
+    EepromReadWriteField::KeyFobEncryptionKey,
+    EepromReadWriteField::CarEncryptionKey,
+    EepromReadWriteField::CarId,
+    EepromReadWriteField::PairingByte,
+    EepromReadWriteField::PairingPin,
+    EepromReadWriteField::PairingLongerCooldownByte,
+    EepromReadWriteField::FeatureOneSignedPackaged,
+    EepromReadWriteField::FeatureTwoSignedPackaged,
+    EepromReadWriteField::FeatureThreeSignedPackaged,
+];
+
 /// The EEPROM controller. Holds a mutable reference to the EEPROM peripheral.
 pub struct EepromController<'a> {
     /// The EEPROM peripheral.
@@ -30,8 +72,85 @@ pub enum EepromError {
     SizeError,
     /// An error for when a write is performed without permission.
     WritePermissionError,
+    /// An error for when an access doesn't start on a word boundary.
+    AlignmentError,
+    /// An error for when an access goes past the end of the EEPROM.
+    BoundsError,
+    /// An error for when a transaction can't stage any more writes.
+    TransactionFullError,
+    /// An error for when the CRC stored with a record doesn't match its payload.
+    CrcMismatchError,
+    /// An error for when a [`WearLeveledCounter`] can't be incremented any further.
+    CounterOverflowError,
+}
+
+/// A buffer of up to `N` secret bytes read from the EEPROM. Its contents are wiped by [`Zeroize`],
+/// which is called on drop when the buffer is wrapped in [`Zeroizing`], as it is when returned by
+/// [`EepromController::read_secret()`].
+pub struct SecretBytes<const N: usize>(Vec<u8, N>);
+
+impl<const N: usize> Zeroize for SecretBytes<N> {
+    fn zeroize(&mut self) {
+        self.0.as_mut_slice().zeroize();
+        self.0.clear();
+    }
+}
+
+impl<const N: usize> Deref for SecretBytes<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for SecretBytes<N> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+/// Types that can be written to the EEPROM as their raw in-memory bytes.
+///
+/// # Safety
+///
+/// Implementors must not contain any padding or otherwise uninitialized bytes.
+pub unsafe trait AsBytes: Sized {
+    /// Returns the raw in-memory bytes of the value.
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: The contract of AsBytes guarantees that every byte of the value is initialized. The
+        // slice covers exactly the memory of the value and borrows the value for its lifetime.
+        unsafe { slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>()) }
+    }
 }
 
+/// Types that can be read from the EEPROM from raw bytes.
+///
+/// # Safety
+///
+/// Every bit pattern must be a valid value of the implementing type.
+pub unsafe trait FromBytes: Sized {}
+
+macro_rules! impl_bytes_traits {
+    ($($typ:ty),*) => {
+        $(
+            // SAFETY: Integers have no padding and every bit pattern is a valid integer.
+            unsafe impl AsBytes for $typ {}
+            // SAFETY: Integers have no padding and every bit pattern is a valid integer.
+            unsafe impl FromBytes for $typ {}
+        )*
+    };
+}
+
+impl_bytes_traits!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+// SAFETY: Arrays have no padding between elements, and elements have no padding by the contract of
+// AsBytes.
+unsafe impl<T: AsBytes, const N: usize> AsBytes for [T; N] {}
+
+// SAFETY: Every bit pattern of an array is valid if every bit pattern of its elements is valid.
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+
 impl<'a> EepromController<'a> {
     /// The number of bytes in a word.
     const BYTES_PER_WORD: usize = 4;
@@ -39,7 +158,9 @@ impl<'a> EepromController<'a> {
     /// The number of words in a block.
     const WORDS_PER_BLOCK: usize = 16;
 
-    /// Creates a new EEPROM controller.
+    /// Creates a new EEPROM controller. If a transaction was interrupted by a power loss, it's rolled
+    /// forward if it had been committed and discarded otherwise. See the documentation for
+    /// [`EepromTransaction`] for more details.
     ///
     /// Errors:
     /// - [EepromError::InitError] if the EEPROM controller fails to initialize.
@@ -48,7 +169,7 @@ impl<'a> EepromController<'a> {
         power_control: &'a PowerControl,
     ) -> Result<Self, EepromError> {
         // Create the EEPROM controller.
-        let controller = EepromController {
+        let mut controller = EepromController {
             eeprom,
             power_control,
         };
@@ -79,6 +200,11 @@ impl<'a> EepromController<'a> {
             return Err(EepromError::InitError);
         }
 
+        // Finish or discard any transaction that was interrupted by a power loss.
+        controller
+            .recover_journal()
+            .map_err(|_| EepromError::InitError)?;
+
         Ok(controller)
     }
 
@@ -87,26 +213,38 @@ impl<'a> EepromController<'a> {
         while self.eeprom.eedone.read().working().bit_is_set() {}
     }
 
-    /// Checks that the field is within the EEPROM and that the address is word-aligned. Returns the
-    /// word count.
-    fn checked_get_word_count(&self, field_bounds: &EepromFieldBounds) -> usize {
+    /// Returns the size of the EEPROM in bytes.
+    fn size_in_bytes(&self) -> usize {
+        usize::from(self.eeprom.eesize.read().wordcnt().bits()) * Self::BYTES_PER_WORD
+    }
+
+    /// Checks that an access of `size` bytes at the byte address `address` is word-aligned and within
+    /// the EEPROM. Returns the word count.
+    ///
+    /// # Errors:
+    /// - [EepromError::AlignmentError] if the address is not word-aligned.
+    /// - [EepromError::BoundsError] if the access goes past the end of the EEPROM.
+    fn checked_get_word_count(&self, address: usize, size: usize) -> Result<usize, EepromError> {
         // Check that the address is word-aligned.
-        debug_assert!((field_bounds.address % Self::BYTES_PER_WORD) == 0);
+        if (address % Self::BYTES_PER_WORD) != 0 {
+            return Err(EepromError::AlignmentError);
+        }
 
-        // Check that the field is within the EEPROM.
-        let end_address = field_bounds.address + field_bounds.size - 1;
-        let eeprom_size = self.eeprom.eesize.read().wordcnt().bits();
-        debug_assert!((end_address / Self::BYTES_PER_WORD) < eeprom_size.into());
+        // Check that the access is within the EEPROM.
+        match address.checked_add(size) {
+            Some(end_address) if end_address <= self.size_in_bytes() => {}
+            _ => return Err(EepromError::BoundsError),
+        }
 
         // Find word count.
-        let mut word_count = field_bounds.size / Self::BYTES_PER_WORD;
+        let mut word_count = size / Self::BYTES_PER_WORD;
 
-        // Field size is not a multiple of a word size.
-        if (field_bounds.size % Self::BYTES_PER_WORD) != 0 {
+        // Size is not a multiple of a word size.
+        if (size % Self::BYTES_PER_WORD) != 0 {
             word_count += 1;
         }
 
-        word_count
+        Ok(word_count)
     }
 
     /// Sets the EEPROM block and offset to the given byte address.
@@ -126,37 +264,29 @@ impl<'a> EepromController<'a> {
         });
     }
 
-    /// Reads a slice of bytes from the EEPROM. Returns the number of bytes read.
+    /// Reads `dest.len()` bytes from the EEPROM, starting at the byte address `address`.
     ///
     /// # Errors:
-    /// - [EepromError::SizeError] if the destination buffer is too small to hold the EEPROM field.
-    pub fn read_slice<T: EepromReadField>(
-        &mut self,
-        field: T,
-        dest: &mut [u8],
-    ) -> Result<usize, EepromError> {
-        // Check that the destination buffer is large enough.
-        let field_bounds = field.get_field_bounds();
-
-        if dest.len() < field_bounds.size {
-            return Err(EepromError::SizeError);
-        }
+    /// - [EepromError::AlignmentError] if the address is not word-aligned.
+    /// - [EepromError::BoundsError] if the read goes past the end of the EEPROM.
+    fn read_bytes(&mut self, address: usize, dest: &mut [u8]) -> Result<(), EepromError> {
+        let size = dest.len();
 
         // Perform sanity checks and get word count.
-        let word_count = self.checked_get_word_count(&field_bounds);
+        let word_count = self.checked_get_word_count(address, size)?;
 
         // Read from the EEPROM.
-        self.set_address(field_bounds.address);
+        self.set_address(address);
 
         for i in 0..word_count {
             // Read the word and increment offset.
             let word = self.eeprom.eerdwrinc.read().bits().to_le_bytes();
 
-            // On last word and the field size is not a multiple of a word size.
-            if (i == (word_count - 1)) && ((field_bounds.size % Self::BYTES_PER_WORD) != 0) {
+            // On last word and the size is not a multiple of a word size.
+            if (i == (word_count - 1)) && ((size % Self::BYTES_PER_WORD) != 0) {
                 // Copy the partial word to the destination buffer.
-                dest[i * Self::BYTES_PER_WORD..field_bounds.size]
-                    .copy_from_slice(&word[..(field_bounds.size % Self::BYTES_PER_WORD)]);
+                dest[i * Self::BYTES_PER_WORD..size]
+                    .copy_from_slice(&word[..(size % Self::BYTES_PER_WORD)]);
             } else {
                 // Copy the full word to the destination buffer.
                 dest[(i * Self::BYTES_PER_WORD)..((i + 1) * Self::BYTES_PER_WORD)]
@@ -173,46 +303,40 @@ impl<'a> EepromController<'a> {
             }
         }
 
-        Ok(field_bounds.size)
+        Ok(())
     }
 
-    /// Writes a slice of bytes to the EEPROM.
+    /// Writes `src` to the EEPROM, starting at the byte address `address`. If `src` doesn't end on a
+    /// word boundary, the rest of the last word is left unchanged.
     ///
     /// # Errors:
-    /// - [EepromError::SizeError] if the source buffer is not the size of the EEPROM field.
-    pub fn write_slice(
-        &mut self,
-        field: EepromReadWriteField,
-        src: &[u8],
-    ) -> Result<(), EepromError> {
-        // Check that the source buffer is the correct size.
-        let field_bounds = field.get_field_bounds();
-
-        if src.len() != field_bounds.size {
-            return Err(EepromError::SizeError);
-        }
+    /// - [EepromError::AlignmentError] if the address is not word-aligned.
+    /// - [EepromError::BoundsError] if the write goes past the end of the EEPROM.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    fn write_bytes(&mut self, address: usize, src: &[u8]) -> Result<(), EepromError> {
+        let size = src.len();
 
         // Perform sanity checks and get word count.
-        let word_count = self.checked_get_word_count(&field_bounds);
+        let word_count = self.checked_get_word_count(address, size)?;
 
         // Write to the EEPROM.
         self.wait_for_done();
-        self.set_address(field_bounds.address);
+        self.set_address(address);
 
         for i in 0..word_count {
-            // On last word and the field size is not a multiple of a word size.
-            if (i == (word_count - 1)) && ((field_bounds.size % Self::BYTES_PER_WORD) != 0) {
+            // On last word and the size is not a multiple of a word size.
+            if (i == (word_count - 1)) && ((size % Self::BYTES_PER_WORD) != 0) {
                 // Grab existing word without incrementing offset.
                 let existing_word = self.eeprom.eerdwr.read().bits().to_le_bytes();
 
                 // Copy the partial word to the write register.
                 self.eeprom.eerdwrinc.write(|w| {
                     let mut word_le_bytes = [0; Self::BYTES_PER_WORD];
-                    let bytes_left = field_bounds.size % Self::BYTES_PER_WORD;
+                    let bytes_left = size % Self::BYTES_PER_WORD;
 
                     // Copy bytes from the source buffer.
                     word_le_bytes[..bytes_left]
-                        .copy_from_slice(&src[i * Self::BYTES_PER_WORD..field_bounds.size]);
+                        .copy_from_slice(&src[i * Self::BYTES_PER_WORD..size]);
 
                     // Copy bytes from the existing word.
                     word_le_bytes[bytes_left..].copy_from_slice(&existing_word[bytes_left..]);
@@ -258,6 +382,298 @@ impl<'a> EepromController<'a> {
         Ok(())
     }
 
+    /// Reads a slice of bytes from the EEPROM. Returns the number of bytes read.
+    ///
+    /// # Errors:
+    /// - [EepromError::SizeError] if the destination buffer is too small to hold the EEPROM field.
+    pub fn read_slice<T: EepromReadField>(
+        &mut self,
+        field: T,
+        dest: &mut [u8],
+    ) -> Result<usize, EepromError> {
+        // Check that the destination buffer is large enough.
+        let field_bounds = field.get_field_bounds();
+
+        if dest.len() < field_bounds.size {
+            return Err(EepromError::SizeError);
+        }
+
+        self.read_bytes(field_bounds.address, &mut dest[..field_bounds.size])?;
+
+        Ok(field_bounds.size)
+    }
+
+    /// Writes a slice of bytes to the EEPROM.
+    ///
+    /// # Errors:
+    /// - [EepromError::SizeError] if the source buffer is not the size of the EEPROM field.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn write_slice(
+        &mut self,
+        field: EepromReadWriteField,
+        src: &[u8],
+    ) -> Result<(), EepromError> {
+        // Check that the source buffer is the correct size.
+        let field_bounds = field.get_field_bounds();
+
+        if src.len() != field_bounds.size {
+            return Err(EepromError::SizeError);
+        }
+
+        self.write_bytes(field_bounds.address, src)
+    }
+
+    /// Reads a value of type `T` from the EEPROM, starting at the given word offset.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the value doesn't fit in the EEPROM at the given word offset.
+    pub fn read_struct<T: FromBytes>(&mut self, word_offset: usize) -> Result<T, EepromError> {
+        let address = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+        let mut value = MaybeUninit::<T>::zeroed();
+
+        // SAFETY: The memory of value is zeroed, so all of its bytes are initialized. The slice covers
+        // exactly the memory of value, which is not otherwise accessed while the slice is alive.
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, mem::size_of::<T>())
+        };
+        self.read_bytes(address, bytes)?;
+
+        // SAFETY: Every byte of value has been initialized, and the contract of FromBytes guarantees
+        // that any bit pattern is a valid T.
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Writes a value of type `T` to the EEPROM, starting at the given word offset. This is a raw
+    /// write, so it's up to the caller to make sure it doesn't overlap any EEPROM fields that are in
+    /// use.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the value doesn't fit in the EEPROM at the given word offset.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn write_struct<T: AsBytes>(
+        &mut self,
+        word_offset: usize,
+        value: &T,
+    ) -> Result<(), EepromError> {
+        let address = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+
+        self.write_bytes(address, value.as_bytes())
+    }
+
+    /// Returns the number of bytes a record with a payload of `len` bytes takes up in the EEPROM. The
+    /// payload is padded to a word boundary and followed by a one word CRC-32.
+    pub const fn record_size(len: usize) -> usize {
+        (len + Self::BYTES_PER_WORD - 1) / Self::BYTES_PER_WORD * Self::BYTES_PER_WORD
+            + Self::BYTES_PER_WORD
+    }
+
+    /// Writes a CRC-protected record, starting at the given word offset. The payload is followed by
+    /// its CRC-32 in the next word-aligned word, so the record takes up [`record_size()`] bytes. This
+    /// is a raw write, so it's up to the caller to make sure it doesn't overlap any EEPROM fields that
+    /// are in use.
+    ///
+    /// [`record_size()`]: EepromController::record_size()
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the record doesn't fit in the EEPROM at the given word offset.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn write_record(&mut self, word_offset: usize, data: &[u8]) -> Result<(), EepromError> {
+        let address = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+
+        // Check the bounds of the whole record before writing any of it.
+        self.checked_get_word_count(address, Self::record_size(data.len()))?;
+        let crc_address = address + Self::record_size(data.len()) - Self::BYTES_PER_WORD;
+
+        self.write_bytes(address, data)?;
+        self.write_bytes(crc_address, &crc32(data).to_le_bytes())
+    }
+
+    /// Reads a CRC-protected record written with [`write_record()`], starting at the given word offset.
+    /// `dest` must be the size of the record's payload.
+    ///
+    /// [`write_record()`]: EepromController::write_record()
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the record doesn't fit in the EEPROM at the given word offset.
+    /// - [EepromError::CrcMismatchError] if the stored CRC doesn't match the payload. The contents of
+    ///   `dest` are unspecified in this case.
+    pub fn read_record(&mut self, word_offset: usize, dest: &mut [u8]) -> Result<(), EepromError> {
+        let address = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+
+        self.checked_get_word_count(address, Self::record_size(dest.len()))?;
+        let crc_address = address + Self::record_size(dest.len()) - Self::BYTES_PER_WORD;
+        let mut crc = [0; Self::BYTES_PER_WORD];
+
+        self.read_bytes(address, dest)?;
+        self.read_bytes(crc_address, &mut crc)?;
+
+        if u32::from_le_bytes(crc) != crc32(dest) {
+            return Err(EepromError::CrcMismatchError);
+        }
+
+        Ok(())
+    }
+
+    /// Reads a CRC-protected record like [`read_record()`], but returns [`None`] if the record is
+    /// corrupted or can't be read, so that callers can fall back to a default, such as re-pairing.
+    ///
+    /// [`read_record()`]: EepromController::read_record()
+    pub fn read_record_or_default<'b>(
+        &mut self,
+        word_offset: usize,
+        dest: &'b mut [u8],
+    ) -> Option<&'b mut [u8]> {
+        self.read_record(word_offset, dest).ok()?;
+
+        Some(dest)
+    }
+
+    /// Reads `len` secret bytes from the EEPROM, starting at the given word offset. The bytes are read
+    /// directly into a buffer that is wiped when it's dropped, so no other copy of the secret is left
+    /// in RAM.
+    ///
+    /// # Errors:
+    /// - [EepromError::SizeError] if `len` is greater than `N`.
+    /// - [EepromError::BoundsError] if the secret doesn't fit in the EEPROM at the given word offset.
+    pub fn read_secret<const N: usize>(
+        &mut self,
+        word_offset: usize,
+        len: usize,
+    ) -> Result<Zeroizing<SecretBytes<N>>, EepromError> {
+        let address = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+        let mut secret = Zeroizing::new(SecretBytes(Vec::new()));
+
+        secret
+            .0
+            .resize_default(len)
+            .map_err(|_| EepromError::SizeError)?;
+        self.read_bytes(address, &mut secret)?;
+
+        Ok(secret)
+    }
+
+    /// Writes secret bytes to the EEPROM, starting at the given word offset. `src` is wiped after it's
+    /// written, whether or not the write succeeds. This is a raw write, so it's up to the caller to
+    /// make sure it doesn't overlap any EEPROM fields that are in use.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the secret doesn't fit in the EEPROM at the given word offset.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn write_secret(&mut self, word_offset: usize, src: &mut [u8]) -> Result<(), EepromError> {
+        let result = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)
+            .and_then(|address| self.write_bytes(address, src));
+
+        src.zeroize();
+
+        result
+    }
+
+    /// Erases `len` bytes of secret storage, starting at the given word offset, by overwriting them
+    /// with zeros. Used to de-provision keys so that they can't be recovered from the EEPROM.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the secret doesn't fit in the EEPROM at the given word offset.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn erase_secret(&mut self, word_offset: usize, len: usize) -> Result<(), EepromError> {
+        let address = word_offset
+            .checked_mul(Self::BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+
+        // Check the bounds of the whole secret before erasing any of it.
+        self.checked_get_word_count(address, len)?;
+
+        let zeros = [0; Self::BYTES_PER_WORD];
+
+        for word_address in (address..address + len).step_by(Self::BYTES_PER_WORD) {
+            let word_len = (address + len - word_address).min(Self::BYTES_PER_WORD);
+            self.write_bytes(word_address, &zeros[..word_len])?;
+        }
+
+        Ok(())
+    }
+
+    /// Wipes every field a factory reset covers, which are the fields in
+    /// [`FACTORY_RESET_READ_ONLY_FIELDS`] and [`FACTORY_RESET_READ_WRITE_FIELDS`], along with the
+    /// entries of the transaction journal at [`JOURNAL_BOUNDS`], which can still hold words of secrets
+    /// from the last transaction, and the raw words at [`FREE_SPACE_BOUNDS`], such as a
+    /// [`WearLeveledCounter`] or a stored [`FeatureSet`](crate::features::FeatureSet). Each of these is
+    /// overwritten with random bytes from the main CSPRNG and then with zeros. Zeros read back as a
+    /// counter of 0 and an empty feature set. The public fields are left alone.
+    ///
+    /// The commit marker of the journal is cleared before its entries are wiped, so random entries are
+    /// never rolled forward. If power is lost partway through, the wipe should be run again.
+    ///
+    /// This may block for around a second if the main CSPRNG automatically reseeds itself.
+    ///
+    /// # Errors:
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write. Fields before the one that
+    ///   failed have already been wiped.
+    pub(crate) fn factory_reset(&mut self) -> Result<(), EepromError> {
+        for field in FACTORY_RESET_READ_ONLY_FIELDS {
+            let bounds = field.get_field_bounds();
+            self.scrub(bounds.address, bounds.size)?;
+        }
+
+        for field in FACTORY_RESET_READ_WRITE_FIELDS {
+            let bounds = field.get_field_bounds();
+            self.scrub(bounds.address, bounds.size)?;
+        }
+
+        self.scrub(FREE_SPACE_BOUNDS.address, FREE_SPACE_BOUNDS.size)?;
+
+        // Clear the commit marker first, then wipe the rest of the journal.
+        self.write_bytes(JOURNAL_BOUNDS.address, &[0; Self::BYTES_PER_WORD])?;
+        self.scrub(
+            JOURNAL_BOUNDS.address + Self::BYTES_PER_WORD,
+            JOURNAL_BOUNDS.size - Self::BYTES_PER_WORD,
+        )
+    }
+
+    /// Runs the wipe of [`Runtime::factory_reset()`](crate::Runtime::factory_reset()) without asking
+    /// for a confirmation or resetting the chip afterwards, so tests can check what's wiped.
+    ///
+    /// This only exists in debug builds, so it can never be called from a release build.
+    ///
+    /// # Errors:
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write.
+    #[cfg(debug_assertions)]
+    pub fn factory_reset_for_test(&mut self) -> Result<(), EepromError> {
+        self.factory_reset()
+    }
+
+    /// Overwrites `size` bytes at the word-aligned byte address `address` with random bytes from the
+    /// main CSPRNG, and then with zeros.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the bytes don't fit in the EEPROM at the given address.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write.
+    fn scrub(&mut self, address: usize, size: usize) -> Result<(), EepromError> {
+        const CHUNK_SIZE: usize = 32;
+
+        let mut chunk = [0; CHUNK_SIZE];
+
+        for chunk_address in (address..address + size).step_by(CHUNK_SIZE) {
+            let chunk = &mut chunk[..(address + size - chunk_address).min(CHUNK_SIZE)];
+
+            random::fill_rand_slice(chunk);
+            self.write_bytes(chunk_address, chunk)?;
+        }
+
+        self.erase_secret(address / Self::BYTES_PER_WORD, size)
+    }
+
     /// Dumps the contents of the EEPROM. Destination buffer should be large enough to hold the entire
     /// EEPROM.
     ///
@@ -0,0 +1,230 @@
+//! This module contains EEPROM regions, which split the raw words of the EEPROM into named, bounds
+//! checked parts, such as one for keys, one for features, and one for counters.
+//!
+//! An [`EepromRegion`] is a start word and a length in words. Regions are meant to be defined as
+//! constants in an [`EepromRegionTable`], which is the only way to get a handle to a region:
+//!
+//! ```ignore
+//! const KEYS: usize = 0;
+//! const COUNTERS: usize = 1;
+//! const REGIONS: EepromRegionTable<2> =
+//!     EepromRegionTable::new([EepromRegion::new(300, 32), EepromRegion::new(332, 16)]);
+//!
+//! let mut keys = REGIONS.handle(&mut eeprom_controller, KEYS)?;
+//! keys.write(8, &key)?;
+//! ```
+//!
+//! Every region must fit in the free space at [`FREE_SPACE_BOUNDS`], clear of the EEPROM fields and
+//! the transaction journal, and no two regions in a table can overlap. A table that breaks either rule
+//! panics when it's built in debug builds, or fails to compile if it's a constant, and
+//! [`EepromRegionTable::handle()`] refuses regions outside the free space in every build.
+//!
+//! Every access through an [`EepromRegionHandle`] takes a word offset relative to the start of its
+//! region and is checked against the length of the region, so a write to one region can't reach into
+//! another.
+
+use super::{AsBytes, EepromController, EepromError, FromBytes, FREE_SPACE_BOUNDS};
+use core::mem;
+
+/// The number of bytes in a word.
+const BYTES_PER_WORD: usize = EepromController::<'static>::BYTES_PER_WORD;
+
+/// The word offset of the start of the free space.
+const FREE_SPACE_START_WORD: usize = FREE_SPACE_BOUNDS.address / BYTES_PER_WORD;
+
+/// The word offset just past the end of the free space.
+const FREE_SPACE_END_WORD: usize =
+    (FREE_SPACE_BOUNDS.address + FREE_SPACE_BOUNDS.size) / BYTES_PER_WORD;
+
+/// A region of the EEPROM, made up of `len_words` words starting at the word offset `start_word`. See
+/// the module-level documentation for how regions are defined and used.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EepromRegion {
+    start_word: usize,
+    len_words: usize,
+}
+
+impl EepromRegion {
+    /// Creates a region of `len_words` words, starting at the word offset `start_word`. The EEPROM isn't
+    /// accessed until the region is read or written, so a region that doesn't fit in the EEPROM is only
+    /// caught then.
+    pub const fn new(start_word: usize, len_words: usize) -> Self {
+        Self {
+            start_word,
+            len_words,
+        }
+    }
+
+    /// Returns the word offset of the start of the region.
+    pub const fn start_word(&self) -> usize {
+        self.start_word
+    }
+
+    /// Returns the length of the region in words.
+    pub const fn len_words(&self) -> usize {
+        self.len_words
+    }
+
+    /// Returns whether every word of the region is in the free space at [`FREE_SPACE_BOUNDS`], so it
+    /// doesn't touch any EEPROM field or the transaction journal.
+    pub const fn in_free_space(&self) -> bool {
+        self.len_words == 0
+            || (self.start_word >= FREE_SPACE_START_WORD
+                && self.start_word <= FREE_SPACE_END_WORD
+                && self.len_words <= FREE_SPACE_END_WORD - self.start_word)
+    }
+
+    /// Returns whether the region shares any words with `other`. Empty regions never overlap.
+    pub const fn overlaps(&self, other: &EepromRegion) -> bool {
+        self.len_words != 0
+            && other.len_words != 0
+            && self.start_word < other.start_word.saturating_add(other.len_words)
+            && other.start_word < self.start_word.saturating_add(self.len_words)
+    }
+}
+
+/// A set of `N` regions that don't overlap, all in the free space. See the module-level documentation
+/// for more details.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EepromRegionTable<const N: usize> {
+    regions: [EepromRegion; N],
+}
+
+impl<const N: usize> EepromRegionTable<N> {
+    /// Creates a table of the given regions. In debug builds, this panics if any two regions overlap or
+    /// any region is outside the free space, or fails to compile if the table is a constant.
+    pub const fn new(regions: [EepromRegion; N]) -> Self {
+        let mut i = 0;
+
+        while i < N {
+            debug_assert!(
+                regions[i].in_free_space(),
+                "EEPROM region overlaps a field or the transaction journal."
+            );
+
+            let mut j = i + 1;
+
+            while j < N {
+                debug_assert!(!regions[i].overlaps(&regions[j]), "EEPROM regions overlap.");
+
+                j += 1;
+            }
+
+            i += 1;
+        }
+
+        Self { regions }
+    }
+
+    /// Returns the regions in the table.
+    pub const fn regions(&self) -> &[EepromRegion; N] {
+        &self.regions
+    }
+
+    /// Gets a handle for reading and writing the region at `index` in the table. See the documentation
+    /// for [`EepromRegionHandle`] for more details.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if there's no region at `index`, or if the region is outside the
+    ///   free space. The latter is only possible in release builds, where the table isn't checked when
+    ///   it's built.
+    pub fn handle<'a, 'b>(
+        &self,
+        controller: &'a mut EepromController<'b>,
+        index: usize,
+    ) -> Result<EepromRegionHandle<'a, 'b>, EepromError> {
+        let region = *self.regions.get(index).ok_or(EepromError::BoundsError)?;
+
+        if !region.in_free_space() {
+            return Err(EepromError::BoundsError);
+        }
+
+        Ok(EepromRegionHandle { controller, region })
+    }
+}
+
+/// A handle for reading and writing one region of the EEPROM, created with
+/// [`EepromRegionTable::handle()`]. Word offsets are relative to the start of the region.
+pub struct EepromRegionHandle<'a, 'b> {
+    controller: &'a mut EepromController<'b>,
+    region: EepromRegion,
+}
+
+impl<'a, 'b> EepromRegionHandle<'a, 'b> {
+    /// Returns the region this handle accesses.
+    pub fn region(&self) -> EepromRegion {
+        self.region
+    }
+
+    /// Checks that an access of `size` bytes at the word offset `word_offset` stays within the region.
+    /// Returns the byte address of the access in the EEPROM.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the access goes past the end of the region.
+    fn checked_address(&self, word_offset: usize, size: usize) -> Result<usize, EepromError> {
+        let region_size = self.region.len_words.saturating_mul(BYTES_PER_WORD);
+        let offset = word_offset
+            .checked_mul(BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+
+        // Check that the access is within the region.
+        match offset.checked_add(size) {
+            Some(end) if end <= region_size => {}
+            _ => return Err(EepromError::BoundsError),
+        }
+
+        self.region
+            .start_word
+            .checked_mul(BYTES_PER_WORD)
+            .and_then(|start| start.checked_add(offset))
+            .ok_or(EepromError::BoundsError)
+    }
+
+    /// Reads `dest.len()` bytes from the region, starting at the given word offset in the region.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the read goes past the end of the region or the EEPROM.
+    pub fn read(&mut self, word_offset: usize, dest: &mut [u8]) -> Result<(), EepromError> {
+        let address = self.checked_address(word_offset, dest.len())?;
+
+        self.controller.read_bytes(address, dest)
+    }
+
+    /// Writes `src` to the region, starting at the given word offset in the region. If `src` doesn't end
+    /// on a word boundary, the rest of the last word is left unchanged.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the write goes past the end of the region or the EEPROM.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn write(&mut self, word_offset: usize, src: &[u8]) -> Result<(), EepromError> {
+        let address = self.checked_address(word_offset, src.len())?;
+
+        self.controller.write_bytes(address, src)
+    }
+
+    /// Reads a value of type `T` from the region, starting at the given word offset in the region.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the value doesn't fit in the region at the given word offset.
+    pub fn read_struct<T: FromBytes>(&mut self, word_offset: usize) -> Result<T, EepromError> {
+        let address = self.checked_address(word_offset, mem::size_of::<T>())?;
+
+        self.controller.read_struct(address / BYTES_PER_WORD)
+    }
+
+    /// Writes a value of type `T` to the region, starting at the given word offset in the region.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the value doesn't fit in the region at the given word offset.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn write_struct<T: AsBytes>(
+        &mut self,
+        word_offset: usize,
+        value: &T,
+    ) -> Result<(), EepromError> {
+        let address = self.checked_address(word_offset, mem::size_of::<T>())?;
+
+        self.controller
+            .write_struct(address / BYTES_PER_WORD, value)
+    }
+}
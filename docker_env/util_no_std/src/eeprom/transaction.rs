@@ -0,0 +1,271 @@
+//! This module contains transactions, which make multi-word EEPROM updates power-fail safe.
+//!
+//! The transaction journal lives at [`JOURNAL_BOUNDS`] and is laid out as follows, one word each:
+//!
+//! - The commit marker. This is [`JOURNAL_COMMITTED`] once a transaction has been fully written to
+//!   the journal and anything else otherwise.
+//! - The number of entries in the journal.
+//! - The entries. Each entry is a byte address followed by the word to write there.
+//!
+//! A transaction is committed by writing its entries and their count to the journal, setting the
+//! commit marker, applying the entries to their destinations, and then clearing the commit marker.
+//! Setting the commit marker is a single word write, so it's the point at which a transaction takes
+//! effect. If power is lost before then, the partial journal is ignored on the next boot. If power is
+//! lost after then, the next boot rolls the transaction forward by applying the journal again.
+
+use super::{AsBytes, EepromController, EepromError, EepromReadField, EepromReadWriteField};
+use heapless::Vec;
+use ucsc_ectf_eeprom_layout::{
+    JOURNAL_BOUNDS, JOURNAL_CAPACITY, JOURNAL_ENTRY_WORDS, JOURNAL_HEADER_WORDS,
+};
+
+/// The number of bytes in a word.
+const BYTES_PER_WORD: usize = EepromController::<'static>::BYTES_PER_WORD;
+
+/// The value of the commit marker when the journal holds a committed transaction. "CMIT" in ASCII.
+const JOURNAL_COMMITTED: u32 = 0x434D_4954;
+
+/// The value of the commit marker when the journal holds no committed transaction.
+const JOURNAL_EMPTY: u32 = 0;
+
+/// The byte address of the commit marker.
+const JOURNAL_MARKER_ADDRESS: usize = JOURNAL_BOUNDS.address;
+
+/// The byte address of the entry count.
+const JOURNAL_COUNT_ADDRESS: usize = JOURNAL_BOUNDS.address + BYTES_PER_WORD;
+
+/// The byte address of the first entry.
+const JOURNAL_ENTRIES_ADDRESS: usize =
+    JOURNAL_BOUNDS.address + JOURNAL_HEADER_WORDS * BYTES_PER_WORD;
+
+/// Returns whether an access of `size` bytes at the byte address `address` overlaps the journal.
+fn overlaps_journal(address: usize, size: usize) -> bool {
+    address < JOURNAL_BOUNDS.address + JOURNAL_BOUNDS.size
+        && address + size > JOURNAL_BOUNDS.address
+}
+
+/// A staged write of one word.
+#[derive(Clone, Copy)]
+struct JournalEntry {
+    /// The byte address of the word.
+    address: u32,
+    /// The word to write.
+    word: u32,
+}
+
+/// An EEPROM transaction. Writes are staged in RAM and only reach the EEPROM once [`commit()`] is
+/// called, at which point either all of them take effect or, if power is lost, none of them do until
+/// the next boot finishes them. Dropping a transaction without committing it discards its writes.
+///
+/// Reads made through the EEPROM controller while a transaction is open don't see its staged writes.
+/// A transaction can write at most [`JOURNAL_CAPACITY`] distinct words.
+///
+/// [`commit()`]: EepromTransaction::commit()
+pub struct EepromTransaction<'a, 'b> {
+    controller: &'a mut EepromController<'b>,
+    entries: Vec<JournalEntry, JOURNAL_CAPACITY>,
+}
+
+impl<'a, 'b> EepromTransaction<'a, 'b> {
+    /// Creates an empty transaction.
+    pub(super) fn new(controller: &'a mut EepromController<'b>) -> Self {
+        Self {
+            controller,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Stages a write of `src` at the byte address `address`.
+    ///
+    /// # Errors:
+    /// - [EepromError::AlignmentError] if the address is not word-aligned.
+    /// - [EepromError::BoundsError] if the write goes past the end of the EEPROM or into the journal.
+    /// - [EepromError::TransactionFullError] if the transaction can't stage any more words.
+    fn stage_bytes(&mut self, address: usize, src: &[u8]) -> Result<(), EepromError> {
+        self.controller.checked_get_word_count(address, src.len())?;
+
+        // Writes to the journal itself would be overwritten by the commit.
+        if overlaps_journal(address, src.len()) {
+            return Err(EepromError::BoundsError);
+        }
+
+        for (i, chunk) in src.chunks(BYTES_PER_WORD).enumerate() {
+            let word_address = address + i * BYTES_PER_WORD;
+            let mut word_bytes = [0; BYTES_PER_WORD];
+
+            // Fill in the rest of a partial word from the EEPROM, or from an earlier staged write to
+            // the same word.
+            if chunk.len() != BYTES_PER_WORD {
+                word_bytes = match self.find_entry(word_address) {
+                    Some(entry) => entry.word.to_le_bytes(),
+                    None => {
+                        self.controller.read_bytes(word_address, &mut word_bytes)?;
+                        word_bytes
+                    }
+                };
+            }
+
+            word_bytes[..chunk.len()].copy_from_slice(chunk);
+            let word = u32::from_le_bytes(word_bytes);
+
+            match self.find_entry(word_address) {
+                Some(entry) => entry.word = word,
+                None => self
+                    .entries
+                    .push(JournalEntry {
+                        address: word_address as u32,
+                        word,
+                    })
+                    .map_err(|_| EepromError::TransactionFullError)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the staged write to the word at the given byte address, if there is one.
+    fn find_entry(&mut self, address: usize) -> Option<&mut JournalEntry> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.address as usize == address)
+    }
+
+    /// Stages a write of a slice of bytes to an EEPROM field.
+    ///
+    /// # Errors:
+    /// - [EepromError::SizeError] if the source buffer is not the size of the EEPROM field.
+    /// - [EepromError::TransactionFullError] if the transaction can't stage any more words.
+    pub fn write_slice(
+        &mut self,
+        field: EepromReadWriteField,
+        src: &[u8],
+    ) -> Result<(), EepromError> {
+        let field_bounds = field.get_field_bounds();
+
+        if src.len() != field_bounds.size {
+            return Err(EepromError::SizeError);
+        }
+
+        self.stage_bytes(field_bounds.address, src)
+    }
+
+    /// Stages a write of a value of type `T`, starting at the given word offset.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the value doesn't fit in the EEPROM at the given word offset
+    ///   or overlaps the journal.
+    /// - [EepromError::TransactionFullError] if the transaction can't stage any more words.
+    pub fn write_struct<T: AsBytes>(
+        &mut self,
+        word_offset: usize,
+        value: &T,
+    ) -> Result<(), EepromError> {
+        let address = word_offset
+            .checked_mul(BYTES_PER_WORD)
+            .ok_or(EepromError::BoundsError)?;
+
+        self.stage_bytes(address, value.as_bytes())
+    }
+
+    /// Commits the transaction, writing all of its staged writes to the EEPROM.
+    ///
+    /// # Errors:
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write. If this happens after the
+    ///   commit marker is set, the transaction is retried on the next boot.
+    pub fn commit(self) -> Result<(), EepromError> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        // Write the journal. The commit marker is already clear, so none of this takes effect yet.
+        for (i, entry) in self.entries.iter().enumerate() {
+            let entry_address = JOURNAL_ENTRIES_ADDRESS + i * JOURNAL_ENTRY_WORDS * BYTES_PER_WORD;
+
+            self.controller
+                .write_bytes(entry_address, &entry.address.to_le_bytes())?;
+            self.controller
+                .write_bytes(entry_address + BYTES_PER_WORD, &entry.word.to_le_bytes())?;
+        }
+
+        self.controller.write_bytes(
+            JOURNAL_COUNT_ADDRESS,
+            &(self.entries.len() as u32).to_le_bytes(),
+        )?;
+
+        // This is the point at which the transaction takes effect.
+        self.controller
+            .write_bytes(JOURNAL_MARKER_ADDRESS, &JOURNAL_COMMITTED.to_le_bytes())?;
+
+        self.controller.apply_journal()
+    }
+}
+
+impl<'a> EepromController<'a> {
+    /// Begins a transaction. See the documentation for [`EepromTransaction`] for more details.
+    pub fn begin_transaction(&mut self) -> EepromTransaction<'_, 'a> {
+        EepromTransaction::new(self)
+    }
+
+    /// Rolls a committed transaction forward if one is in the journal. A journal without the commit
+    /// marker set holds at most a partial transaction, which is discarded by ignoring it.
+    ///
+    /// # Errors:
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write.
+    pub(super) fn recover_journal(&mut self) -> Result<(), EepromError> {
+        let mut marker = [0; BYTES_PER_WORD];
+        self.read_bytes(JOURNAL_MARKER_ADDRESS, &mut marker)?;
+
+        if u32::from_le_bytes(marker) == JOURNAL_COMMITTED {
+            self.apply_journal()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs the same transaction recovery that [`EepromController::new()`] runs on boot. Used to test
+    /// recovery from a power loss.
+    ///
+    /// # Errors:
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write.
+    #[cfg(debug_assertions)]
+    pub fn run_boot_recovery(&mut self) -> Result<(), EepromError> {
+        self.recover_journal()
+    }
+
+    /// Applies the entries in the journal to their destinations and then clears the commit marker.
+    /// Applying the same journal more than once has the same effect as applying it once.
+    ///
+    /// # Errors:
+    /// - [EepromError::WritePermissionError] if the EEPROM denies a write.
+    fn apply_journal(&mut self) -> Result<(), EepromError> {
+        let mut word = [0; BYTES_PER_WORD];
+        self.read_bytes(JOURNAL_COUNT_ADDRESS, &mut word)?;
+        let count = u32::from_le_bytes(word) as usize;
+
+        // A count this large means the journal is corrupted. There's nothing sensible to apply.
+        if count <= JOURNAL_CAPACITY {
+            for i in 0..count {
+                let entry_address =
+                    JOURNAL_ENTRIES_ADDRESS + i * JOURNAL_ENTRY_WORDS * BYTES_PER_WORD;
+
+                self.read_bytes(entry_address, &mut word)?;
+                let address = u32::from_le_bytes(word) as usize;
+                self.read_bytes(entry_address + BYTES_PER_WORD, &mut word)?;
+
+                // Skip entries that a transaction could never have staged. These can only come from
+                // a corrupted journal.
+                if self
+                    .checked_get_word_count(address, BYTES_PER_WORD)
+                    .is_err()
+                    || overlaps_journal(address, BYTES_PER_WORD)
+                {
+                    continue;
+                }
+
+                self.write_bytes(address, &word)?;
+            }
+        }
+
+        self.write_bytes(JOURNAL_MARKER_ADDRESS, &JOURNAL_EMPTY.to_le_bytes())
+    }
+}
@@ -0,0 +1,119 @@
+//! This module contains wear-leveled counters, which spread the writes of a frequently updated counter
+//! across a block of EEPROM words.
+//!
+//! A [`WearLeveledCounter`] with `N` slots takes up `N` consecutive words, starting at its word offset.
+//! Each slot holds one counter value, and the value `v` is only ever written to slot `v % N`. The slot
+//! a value is in acts as its valid marker, which rotates through the block as the counter goes up:
+//!
+//! - A slot is valid if it's not erased (all 1s) and its value belongs in it, that is, if its value
+//!   modulo `N` is its index in the block.
+//! - The current value of the counter is the greatest value in a valid slot, or 0 if no slot is valid.
+//! - Incrementing the counter writes the next value to the slot after the current one, wrapping around
+//!   to the start of the block. The stale value left in that slot is smaller, so it's never mistaken
+//!   for the current one.
+//!
+//! Each increment is a single word write, and a word write either replaces the old word or leaves it
+//! alone if power is lost, so an interrupted increment leaves the counter at its old value.
+//!
+//! The EEPROM is rated for about 500,000 writes per word. A counter that's stored in one word wears
+//! that word out after 500,000 increments, but a counter with `N` slots only writes each slot once
+//! every `N` increments, so it lasts for about `N * 500,000` increments. For example, a 16 slot counter
+//! lasts for about 8,000,000 increments.
+
+use super::{EepromController, EepromError};
+
+/// The value of a slot that hasn't been written since the EEPROM was erased.
+const ERASED_SLOT: u32 = u32::MAX;
+
+/// A counter that's wear-leveled across `N` words of the EEPROM. See the module-level documentation
+/// for its layout and endurance. The counter holds no state in RAM, so any number of them can be made
+/// for the same block.
+///
+/// The block must be erased before the counter is first used, with either
+/// [`EepromController::erase_mem()`] or [`clear()`](WearLeveledCounter::clear()), since leftover data
+/// can look like valid slots.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WearLeveledCounter<const N: usize> {
+    word_offset: usize,
+}
+
+impl<const N: usize> WearLeveledCounter<N> {
+    /// Fails to compile if the counter has no slots.
+    const NONZERO_SLOTS: () = assert!(N > 0, "a wear-leveled counter needs at least one slot");
+
+    /// Creates a counter whose block starts at the given word offset. The block takes up `N` words. The
+    /// EEPROM isn't accessed until the counter is read or written, so it's up to the caller to make
+    /// sure the block fits in the EEPROM and doesn't overlap any EEPROM fields that are in use.
+    pub const fn new(word_offset: usize) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::NONZERO_SLOTS;
+
+        Self { word_offset }
+    }
+
+    /// Reads the current value of the counter.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the block doesn't fit in the EEPROM at the counter's word
+    ///   offset.
+    pub fn read(&self, eeprom_controller: &mut EepromController) -> Result<u32, EepromError> {
+        self.current(eeprom_controller)
+            .map(|current| current.map_or(0, |(_, value)| value))
+    }
+
+    /// Increments the counter, returning its new value.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the block doesn't fit in the EEPROM at the counter's word
+    ///   offset.
+    /// - [EepromError::CounterOverflowError] if the counter can't go any higher. The counter is left
+    ///   unchanged.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn increment(&self, eeprom_controller: &mut EepromController) -> Result<u32, EepromError> {
+        let (slot, value) = match self.current(eeprom_controller)? {
+            Some((slot, value)) => (slot, value),
+            // With no valid slot, the counter is 0, which belongs in slot 0.
+            None => (0, 0),
+        };
+
+        // The last value is the erased value, so it can't be stored.
+        let next = value
+            .checked_add(1)
+            .filter(|&next| next != ERASED_SLOT)
+            .ok_or(EepromError::CounterOverflowError)?;
+
+        eeprom_controller.write_struct(self.word_offset + (slot + 1) % N, &next)?;
+
+        Ok(next)
+    }
+
+    /// Resets the counter to 0 by erasing every slot in its block. This writes every slot once, so
+    /// it's meant for provisioning, not for regular use.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the block doesn't fit in the EEPROM at the counter's word
+    ///   offset.
+    /// - [EepromError::WritePermissionError] if the EEPROM denies the write.
+    pub fn clear(&self, eeprom_controller: &mut EepromController) -> Result<(), EepromError> {
+        eeprom_controller.write_struct(self.word_offset, &[ERASED_SLOT; N])
+    }
+
+    /// Finds the slot holding the current value of the counter, returning its index and value, or
+    /// [`None`] if no slot is valid.
+    ///
+    /// # Errors:
+    /// - [EepromError::BoundsError] if the block doesn't fit in the EEPROM at the counter's word
+    ///   offset.
+    fn current(
+        &self,
+        eeprom_controller: &mut EepromController,
+    ) -> Result<Option<(usize, u32)>, EepromError> {
+        let slots: [u32; N] = eeprom_controller.read_struct(self.word_offset)?;
+
+        Ok(slots
+            .into_iter()
+            .enumerate()
+            .filter(|&(slot, value)| value != ERASED_SLOT && value as usize % N == slot)
+            .max_by_key(|&(_, value)| value))
+    }
+}
@@ -0,0 +1,88 @@
+//! This module contains the queue that interrupt handlers use to tell the main loop that something
+//! happened, such as a button being pressed or UART1 receiving bytes. Interrupt handlers push a
+//! [`RuntimeEvent`] with [`push_event()`], and the main loop takes them in order with
+//! [`Runtime::next_event()`](crate::Runtime::next_event).
+//!
+//! Events are notifications, not data. An event says which controller has something new, and the data
+//! stays with the controller, such as the bytes in the UART1 ring buffer. Because of this, an event is
+//! only queued if an equal event isn't already waiting, so a busy source, such as UART1 raising an
+//! interrupt every few bytes, takes one slot instead of filling the queue.
+//!
+//! The queue holds [`EVENT_QUEUE_CAPACITY`] events. If it's full, the new event is dropped and counted,
+//! which can be checked with [`dropped_events()`]. Since equal events are merged, this only happens with
+//! more distinct events waiting than the capacity, such as many distinct [`RuntimeEvent::User`] events.
+
+use crate::button::ButtonId;
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use cortex_m::interrupt::{self, Mutex};
+use heapless::spsc::Queue;
+
+/// The number of events the queue can hold.
+pub const EVENT_QUEUE_CAPACITY: usize = 16;
+
+/// The length of the backing array of the queue, which holds one less event than its length.
+const EVENT_QUEUE_LEN: usize = EVENT_QUEUE_CAPACITY + 1;
+
+/// The events waiting for the main loop.
+static EVENT_QUEUE: Mutex<RefCell<Queue<RuntimeEvent, EVENT_QUEUE_LEN>>> =
+    Mutex::new(RefCell::new(Queue::new()));
+
+/// The number of events dropped because the queue was full.
+static DROPPED_EVENTS: AtomicU32 = AtomicU32::new(0);
+
+/// An event pushed by an interrupt handler for the main loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RuntimeEvent {
+    /// An onboard button was pressed. This is only pushed once per press, after which the button is
+    /// debounced. A press of SW1 must still be cleared with
+    /// [`Sw1ButtonController::clear_activation()`](crate::button::Sw1ButtonController::clear_activation).
+    ButtonPressed(ButtonId),
+
+    /// UART1 received bytes into its ring buffer with interrupt-driven reception enabled. See
+    /// [`Uart1Controller::enable_rx_interrupt()`](crate::communication::Uart1Controller::enable_rx_interrupt).
+    Uart1Rx,
+
+    /// An event from an interrupt handler defined by the firmware, such as for a CAN interrupt. The
+    /// meaning of the value is up to the firmware.
+    User(u8),
+}
+
+/// Pushes an event for the main loop, returning whether it was queued or an equal event was already
+/// waiting. If the queue is full, the event is dropped, counted in [`dropped_events()`], and ``false`` is
+/// returned. This can be called from interrupt handlers and the main loop alike.
+pub fn push_event(event: RuntimeEvent) -> bool {
+    interrupt::free(|cs| {
+        let mut queue = EVENT_QUEUE.borrow(cs).borrow_mut();
+
+        if queue.iter().any(|&waiting| waiting == event) {
+            return true;
+        }
+
+        if queue.enqueue(event).is_err() {
+            DROPPED_EVENTS.fetch_add(1, Ordering::SeqCst);
+            return false;
+        }
+
+        true
+    })
+}
+
+/// Takes the oldest waiting event, if there is one.
+pub(crate) fn pop_event() -> Option<RuntimeEvent> {
+    interrupt::free(|cs| EVENT_QUEUE.borrow(cs).borrow_mut().dequeue())
+}
+
+/// Drops every waiting event. The count of dropped events isn't changed.
+pub(crate) fn clear_events() {
+    interrupt::free(|cs| *EVENT_QUEUE.borrow(cs).borrow_mut() = Queue::new());
+}
+
+/// Gets the number of events dropped because the queue was full since boot. This wraps around on
+/// overflow.
+pub fn dropped_events() -> u32 {
+    DROPPED_EVENTS.load(Ordering::SeqCst)
+}
@@ -1,6 +1,7 @@
-//! This module provides a function for verifying signed packaged features.
+//! This module provides a function for verifying signed packaged features and a set of enabled
+//! features that can be persisted to the EEPROM.
 
-use crate::eeprom::EepromController;
+use crate::eeprom::{EepromController, EepromError};
 use k256::{
     ecdsa::{signature::Verifier, Signature, VerifyingKey},
     pkcs8::DecodePublicKey,
@@ -8,7 +9,108 @@ use k256::{
 use ucsc_ectf_eeprom_layout::{
     EepromReadOnlyField, EepromReadWriteField, CAR_ID_SIZE, PUBLIC_KEY_SIZE,
 };
-use ucsc_ectf_util_common::messages::PackagedFeatureSigned;
+use ucsc_ectf_util_common::messages::{FeatureNumber, PackagedFeatureSigned};
+
+/// The number of feature numbers a [`FeatureSet`] can hold. Feature numbers must be less than this.
+pub const MAX_FEATURES: FeatureNumber = 64;
+
+/// An error from a [`FeatureSet`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FeatureSetError {
+    /// The feature number is [`MAX_FEATURES`] or greater.
+    OutOfRange,
+}
+
+/// A set of enabled feature numbers, stored as a bitmap with one bit per feature number.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FeatureSet(u64);
+
+impl FeatureSet {
+    /// Creates a set with no features enabled.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Gets the bit of a feature number.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`FeatureSetError::OutOfRange`] - The feature number is [`MAX_FEATURES`] or greater.
+    fn bit(id: FeatureNumber) -> Result<u64, FeatureSetError> {
+        if id >= MAX_FEATURES {
+            return Err(FeatureSetError::OutOfRange);
+        }
+
+        Ok(1 << id)
+    }
+
+    /// Enables a feature.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`FeatureSetError::OutOfRange`] - The feature number is [`MAX_FEATURES`] or greater. The set
+    ///   is left unchanged.
+    pub fn enable(&mut self, id: FeatureNumber) -> Result<(), FeatureSetError> {
+        self.0 |= Self::bit(id)?;
+
+        Ok(())
+    }
+
+    /// Disables a feature.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`FeatureSetError::OutOfRange`] - The feature number is [`MAX_FEATURES`] or greater. The set
+    ///   is left unchanged.
+    pub fn disable(&mut self, id: FeatureNumber) -> Result<(), FeatureSetError> {
+        self.0 &= !Self::bit(id)?;
+
+        Ok(())
+    }
+
+    /// Checks whether a feature is enabled.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`FeatureSetError::OutOfRange`] - The feature number is [`MAX_FEATURES`] or greater.
+    pub fn is_enabled(&self, id: FeatureNumber) -> Result<bool, FeatureSetError> {
+        Ok(self.0 & Self::bit(id)? != 0)
+    }
+
+    /// Returns an iterator over the enabled feature numbers in ascending order.
+    pub fn iter_enabled(&self) -> impl Iterator<Item = FeatureNumber> {
+        let bits = self.0;
+
+        (0..MAX_FEATURES).filter(move |id| bits & 1 << id != 0)
+    }
+
+    /// Loads a set from the EEPROM, starting at the given word offset. The set takes up two words.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`EepromError::BoundsError`] - The set doesn't fit in the EEPROM at the given word offset.
+    pub fn load_from_eeprom(
+        eeprom_controller: &mut EepromController,
+        word_offset: usize,
+    ) -> Result<Self, EepromError> {
+        eeprom_controller.read_struct(word_offset).map(Self)
+    }
+
+    /// Stores the set to the EEPROM, starting at the given word offset. This is a raw write, so it's
+    /// up to the caller to make sure it doesn't overlap any EEPROM fields that are in use.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`EepromError::BoundsError`] - The set doesn't fit in the EEPROM at the given word offset.
+    /// - [`EepromError::WritePermissionError`] - The EEPROM denied the write.
+    pub fn store_to_eeprom(
+        &self,
+        eeprom_controller: &mut EepromController,
+        word_offset: usize,
+    ) -> Result<(), EepromError> {
+        eeprom_controller.write_struct(word_offset, &self.0)
+    }
+}
 
 /// Verifies the signature of a [`PackagedFeatureSigned`] and checks the car
 /// ID and feature number associated with it. This function should not be
@@ -1,9 +1,205 @@
-//! This module contains an interface to use the hibernation clock.
+//! This module contains an interface to use the hibernation clock and to hibernate.
+//!
+//! # Hibernation
+//!
+//! [`HibController::hibernate()`] powers down everything but the hibernation module, with VDD3ON set
+//! so the GPIO pins keep their state, until the RTC reaches a wake time or the WAKE pin is asserted.
+//! The device then cold-boots from the reset vector, and [`ResetCause::Hibernation`] is reported as
+//! the reset cause. After a wake:
+//!
+//! - The battery-backed data registers of the hibernation module keep their contents, so small state
+//!   stored with [`HibController::store_data()`] can be loaded again without an EEPROM write. The
+//!   EEPROM and flash are non-volatile as always.
+//! - SRAM, including every static, the CSPRNGs, and any session keys, is lost. The runtime gathers
+//!   fresh entropy and reseeds the main CSPRNG as on any boot, and session state has to be derived
+//!   again, such as by running the unlock sequence from the start.
+//! - The RTC keeps counting while hibernating, but gathering entropy at boot restarts it, so any time
+//!   set with [`HibController::set_rtc()`] has to be set again.
+//! - Every other peripheral, including the UARTs and the watchdog, starts from its reset state.
+//!
+//! # Data registers
+//!
+//! The hibernation module has [`HIB_DATA_WORDS`] battery-backed data registers. The first
+//! [`HIB_NV_WORDS`] are for callers, through [`HibController::store_data()`] and
+//! [`HibController::load_data()`]. The next holds a CRC-32 of those words, and the last holds the
+//! marker used to recognize a wake from hibernation.
+//!
+//! After a wake from hibernation, the registers hold what was stored before hibernating. After a cold
+//! boot, they're only valid if the hibernation module kept power, such as from a battery on VBAT,
+//! and the CRC catches the case where it didn't. Loads then fail with [`HibDataError::Corrupted`]
+//! until the registers are cleared with [`HibController::clear_data()`].
 
-use crate::{timer::HibTimer, HibPool};
+use crate::{
+    crc32,
+    timer::{HibTimer, PeriodicTimer},
+    HibPool, ResetCause,
+};
 use core::time::Duration;
+use cortex_m::asm;
 use heapless::Arc;
-use tm4c123x_hal::sysctl::{self, Domain, PowerControl, PowerState, RunMode};
+use tm4c123x_hal::{
+    sysctl::{self, Domain, PowerControl, PowerState, RunMode},
+    tm4c123x::HIB,
+};
+
+/// The number of cycles to delay between polls of the hibernation module's write complete bit.
+const WRC_POLL_CYCLES: u32 = 1000;
+
+/// The number of polls of the hibernation module's write complete bit before giving up. Together
+/// with [`WRC_POLL_CYCLES`], this allows at least 2.5 seconds at 80 MHz, which covers the start-up
+/// time of the 32.768 kHz oscillator, and longer at lower system clock frequencies.
+const WRC_MAX_POLLS: u32 = 200_000;
+
+/// The number of 32-bit battery-backed data registers in the hibernation module.
+pub const HIB_DATA_WORDS: usize = 16;
+
+/// The number of data registers available through [`HibController::store_data()`] and
+/// [`HibController::load_data()`].
+pub const HIB_NV_WORDS: usize = HIB_DATA_WORDS - 2;
+
+/// The index of the data register holding the CRC-32 of the first [`HIB_NV_WORDS`] data registers.
+const NV_CRC_INDEX: usize = HIB_NV_WORDS;
+
+/// The index of the data register holding [`HIBERNATE_MARKER`].
+const HIBERNATE_MARKER_INDEX: usize = HIB_DATA_WORDS - 1;
+
+/// The value of the data register at [`HIBERNATE_MARKER_INDEX`] while the device hibernates. "HIBR" in
+/// ASCII.
+const HIBERNATE_MARKER: u32 = 0x4849_4252;
+
+/// An error for when the hibernation module doesn't become ready for writes in time, such as when
+/// its oscillator is faulty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HibTimeout;
+
+/// Waits for the hibernation module to be ready for register writes.
+///
+/// # ERRORS:
+///
+/// - [`HibTimeout`] - The module didn't become ready within [`WRC_MAX_POLLS`] polls.
+fn wait_for_write_complete(hib: &HIB) -> Result<(), HibTimeout> {
+    for _ in 0..WRC_MAX_POLLS {
+        if hib.ctl.read().wrc().bit_is_set() {
+            return Ok(());
+        }
+
+        asm::delay(WRC_POLL_CYCLES);
+    }
+
+    Err(HibTimeout)
+}
+
+/// An error from the battery-backed data registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HibDataError {
+    /// The index is [`HIB_NV_WORDS`] or greater.
+    OutOfRange,
+
+    /// The CRC of the data registers doesn't match their contents, such as after the hibernation
+    /// module lost power.
+    Corrupted,
+
+    /// The hibernation module didn't become ready for writes in time.
+    Timeout,
+}
+
+impl From<HibTimeout> for HibDataError {
+    fn from(_: HibTimeout) -> Self {
+        Self::Timeout
+    }
+}
+
+/// Gets a pointer to a battery-backed data register. The data registers are consecutive words, but the
+/// register block only names the first.
+///
+/// # Panics
+///
+/// Panics if the index is [`HIB_DATA_WORDS`] or greater.
+fn data_register(hib: &HIB, index: usize) -> *mut u32 {
+    assert!(
+        index < HIB_DATA_WORDS,
+        "HIB data register index out of range."
+    );
+
+    (&hib.data as *const _ as *mut u32).wrapping_add(index)
+}
+
+/// Reads a battery-backed data register.
+///
+/// # Panics
+///
+/// Panics if the index is [`HIB_DATA_WORDS`] or greater.
+fn read_data(hib: &HIB, index: usize) -> u32 {
+    // SAFETY: The pointer is to one of the data registers, which are always readable.
+    unsafe { data_register(hib, index).read_volatile() }
+}
+
+/// Writes a battery-backed data register, waiting for the write to complete.
+///
+/// # Panics
+///
+/// Panics if the index is [`HIB_DATA_WORDS`] or greater.
+///
+/// # ERRORS:
+///
+/// - [`HibTimeout`] - The module didn't become ready for the write in time.
+fn write_data(hib: &HIB, index: usize, value: u32) -> Result<(), HibTimeout> {
+    wait_for_write_complete(hib)?;
+
+    // SAFETY: The pointer is to one of the data registers, which accept any value. Every write to
+    // them is made while the hibernation module is ready for writes.
+    unsafe { data_register(hib, index).write_volatile(value) };
+
+    wait_for_write_complete(hib)
+}
+
+/// Calculates the CRC-32 of the given contents of the first [`HIB_NV_WORDS`] data registers.
+fn nv_crc(words: &[u32; HIB_NV_WORDS]) -> u32 {
+    let mut bytes = [0; HIB_NV_WORDS * 4];
+
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+
+    crc32(&bytes)
+}
+
+/// Reads the first [`HIB_NV_WORDS`] data registers, checking them against their CRC.
+///
+/// # ERRORS:
+///
+/// - [`HibDataError::Corrupted`] - The CRC doesn't match.
+fn read_nv_words(hib: &HIB) -> Result<[u32; HIB_NV_WORDS], HibDataError> {
+    let mut words = [0; HIB_NV_WORDS];
+
+    for (index, word) in words.iter_mut().enumerate() {
+        *word = read_data(hib, index);
+    }
+
+    if read_data(hib, NV_CRC_INDEX) != nv_crc(&words) {
+        return Err(HibDataError::Corrupted);
+    }
+
+    Ok(words)
+}
+
+/// Returns whether the device just woke from [`HibController::hibernate()`], clearing the marker that
+/// recognizes a wake so a later reset isn't mistaken for one. This must be called before anything
+/// resets the hibernation module.
+pub(crate) fn take_hibernation_wake(hib: &HIB) -> bool {
+    // The hibernation clock stays enabled through hibernation, and is disabled after a power loss.
+    if hib.ctl.read().clk32en().bit_is_clear()
+        || read_data(hib, HIBERNATE_MARKER_INDEX) != HIBERNATE_MARKER
+    {
+        return false;
+    }
+
+    // If this times out, the next reset is mistaken for a wake, which only affects the reported reset
+    // cause.
+    let _ = write_data(hib, HIBERNATE_MARKER_INDEX, 0);
+
+    true
+}
 
 /// The hibernation controller.
 #[derive(Clone)]
@@ -13,7 +209,15 @@ pub struct HibController {
 
 impl HibController {
     /// Creates a new hibernation controller.
-    pub(crate) fn new(hib: Arc<HibPool>, power_control: &PowerControl) -> Self {
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`HibTimeout`] - The hibernation module didn't become ready for writes in time.
+    pub(crate) fn new(
+        hib: Arc<HibPool>,
+        power_control: &PowerControl,
+        reset_cause: ResetCause,
+    ) -> Result<Self, HibTimeout> {
         // Enable hibernation module. This is enabled by default, but we enable it here just in case.
         sysctl::control_power(
             power_control,
@@ -22,8 +226,10 @@ impl HibController {
             PowerState::On,
         );
 
-        // Reset hibernation module for good measure.
-        sysctl::reset(power_control, Domain::Hibernation);
+        // Reset hibernation module for good measure, unless it's holding state from before hibernation.
+        if reset_cause != ResetCause::Hibernation {
+            sysctl::reset(power_control, Domain::Hibernation);
+        }
 
         // Initialize hibernation clock.
         hib.ctl.write(|w| {
@@ -32,7 +238,7 @@ impl HibController {
         });
 
         // Wait for hibernation module to be ready.
-        while hib.ctl.read().wrc().bit_is_clear() {}
+        wait_for_write_complete(&hib)?;
 
         // Enable RTC.
         // SAFETY: Writing to this register is safe because it is data-race free. This guarantee
@@ -41,13 +247,173 @@ impl HibController {
             .modify(|r, w| unsafe { w.bits(r.bits()).rtcen().set_bit() });
 
         // Wait for hibernation module to be ready.
-        while hib.ctl.read().wrc().bit_is_clear() {}
+        wait_for_write_complete(&hib)?;
+
+        Ok(Self { hib })
+    }
+
+    /// Gets the time since the RTC was enabled at boot, or since the time it was last set to with
+    /// [`HibController::set_rtc()`]. The time has an accuracy of 1/32768 seconds and never goes
+    /// backwards unless the RTC is set to an earlier time.
+    pub fn now(&self) -> Duration {
+        HibTimer::subseconds_to_duration(HibTimer::time_to_subseconds(HibTimer::get_time_hib(
+            &self.hib,
+        )))
+    }
+
+    /// Sets the RTC to a time, such as a provisioned timestamp. The RTC can only be loaded with whole
+    /// seconds, so any fractional part of the time is dropped and the subsecond count restarts from
+    /// zero.
+    ///
+    /// Timers compare their deadlines against the RTC, so setting it moves the deadlines of any
+    /// running timers. Setting it forward makes them expire early, and setting it backward delays
+    /// them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time is 2^32 seconds or longer, or if the hibernation module doesn't become
+    /// ready for writes in time.
+    pub fn set_rtc(&self, time: Duration) {
+        let seconds = time.as_secs().try_into().expect("Time is too long.");
+
+        // Wait for hibernation module to be ready.
+        wait_for_write_complete(&self.hib).expect("Hibernation module timed out.");
 
-        Self { hib }
+        // SAFETY: Every value is valid for the RTC load register. Writing to this register is
+        // data-race free because register writes are atomic and the RTC is only loaded here.
+        self.hib.rtcld.write(|w| unsafe { w.bits(seconds) });
+
+        // Wait for the load to complete.
+        wait_for_write_complete(&self.hib).expect("Hibernation module timed out.");
     }
 
     /// Creates a timer from a duration using the hibernation clock.
     pub fn create_timer(&self, duration: Duration) -> HibTimer {
         HibTimer::new(&self.hib, duration)
     }
+
+    /// Creates a timer that fires once every period using the hibernation clock. See the
+    /// documentation for [`PeriodicTimer`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period is shorter than the 1/32768 second accuracy of the timer.
+    pub fn create_periodic_timer(&self, period: Duration) -> PeriodicTimer {
+        PeriodicTimer::new(&self.hib, period)
+    }
+
+    /// Stores a word in one of the first [`HIB_NV_WORDS`] battery-backed data registers, updating their
+    /// CRC. See the module-level documentation for when stored words survive.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`HibDataError::OutOfRange`] - The index is [`HIB_NV_WORDS`] or greater.
+    /// - [`HibDataError::Corrupted`] - The registers were already corrupted, so nothing is stored.
+    ///   Clear them with [`HibController::clear_data()`] first.
+    /// - [`HibDataError::Timeout`] - The hibernation module didn't become ready for writes in time.
+    ///   The registers might be left corrupted.
+    pub fn store_data(&self, index: usize, value: u32) -> Result<(), HibDataError> {
+        if index >= HIB_NV_WORDS {
+            return Err(HibDataError::OutOfRange);
+        }
+
+        let mut words = read_nv_words(&self.hib)?;
+        words[index] = value;
+
+        write_data(&self.hib, index, value)?;
+        write_data(&self.hib, NV_CRC_INDEX, nv_crc(&words))?;
+
+        Ok(())
+    }
+
+    /// Loads a word stored with [`HibController::store_data()`]. Words that were never stored since the
+    /// registers were last cleared are zero.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`HibDataError::OutOfRange`] - The index is [`HIB_NV_WORDS`] or greater.
+    /// - [`HibDataError::Corrupted`] - The CRC of the registers doesn't match, such as after the
+    ///   hibernation module lost power.
+    pub fn load_data(&self, index: usize) -> Result<u32, HibDataError> {
+        if index >= HIB_NV_WORDS {
+            return Err(HibDataError::OutOfRange);
+        }
+
+        read_nv_words(&self.hib).map(|words| words[index])
+    }
+
+    /// Sets the first [`HIB_NV_WORDS`] data registers to zero with a matching CRC, such as after
+    /// [`HibController::load_data()`] reports that they're corrupted.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`HibTimeout`] - The hibernation module didn't become ready for writes in time.
+    pub fn clear_data(&self) -> Result<(), HibTimeout> {
+        let words = [0; HIB_NV_WORDS];
+
+        for index in 0..HIB_NV_WORDS {
+            write_data(&self.hib, index, 0)?;
+        }
+
+        write_data(&self.hib, NV_CRC_INDEX, nv_crc(&words))
+    }
+
+    /// Hibernates until the RTC is `wake_after` past the current time or, if `wake_on_pin` is set,
+    /// until the WAKE pin is asserted, whichever comes first. This never returns, since the device
+    /// cold-boots on waking. See the module-level documentation for what survives hibernation.
+    ///
+    /// The RTC can only wake the device on a whole second, so the wake time is rounded up to the next
+    /// second, and is at least the next second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no wake source, if the wake time is 2^32 seconds or later, or if the
+    /// hibernation module doesn't become ready for writes in time.
+    pub fn hibernate(&self, wake_after: Option<Duration>, wake_on_pin: bool) -> ! {
+        assert!(
+            wake_after.is_some() || wake_on_pin,
+            "Hibernating without a wake source would never wake."
+        );
+
+        if let Some(wake_after) = wake_after {
+            let wake_at = self.now() + wake_after;
+            let seconds = (wake_at.as_secs() + u64::from(wake_at.subsec_nanos() != 0))
+                .max(u64::from(self.hib.rtcc.read().bits()) + 1)
+                .try_into()
+                .expect("Wake time is too far away.");
+
+            wait_for_write_complete(&self.hib).expect("Hibernation module timed out.");
+
+            // SAFETY: Every value is valid for the RTC match register. Writing to this register is
+            // data-race free because register writes are atomic and it's only written here.
+            self.hib.rtcm0.write(|w| unsafe { w.bits(seconds) });
+        }
+
+        write_data(&self.hib, HIBERNATE_MARKER_INDEX, HIBERNATE_MARKER)
+            .expect("Hibernation module timed out.");
+
+        // SAFETY: Writing to this register is data-race free because this never returns, so nothing
+        // else modifies it afterwards.
+        self.hib.ctl.modify(|r, w| unsafe {
+            w.bits(r.bits())
+                .rtcwen()
+                .bit(wake_after.is_some())
+                .pinwen()
+                .bit(wake_on_pin)
+                .vdd3on()
+                .set_bit()
+        });
+
+        wait_for_write_complete(&self.hib).expect("Hibernation module timed out.");
+
+        // SAFETY: See above.
+        self.hib
+            .ctl
+            .modify(|r, w| unsafe { w.bits(r.bits()).hibreq().set_bit() });
+
+        // Power is removed shortly after the request.
+        loop {
+            asm::wfi();
+        }
+    }
 }
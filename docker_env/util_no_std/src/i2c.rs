@@ -0,0 +1,348 @@
+//! This module contains a blocking controller for an I2C master, such as to talk to an external
+//! secure element or temperature sensor.
+
+use crate::{
+    communication::{self, CommunicationError},
+    timer::{spin_until, HibTimer},
+    HibPool,
+};
+use core::time::Duration;
+use heapless::Arc;
+use tm4c123x_hal::{
+    sysctl::{self, Clocks, Domain, PowerControl, PowerState, RunMode},
+    tm4c123x::i2c0,
+};
+
+/// The default time to wait for each step of a transfer, such as a byte being sent, before giving
+/// up. This includes any time the target stretches the clock.
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(10);
+
+// I2CMCR bits.
+const MCR_MFE: u32 = 1 << 4;
+
+// I2CMCS bits when written.
+const MCS_RUN: u32 = 1 << 0;
+const MCS_START: u32 = 1 << 1;
+const MCS_STOP: u32 = 1 << 2;
+const MCS_ACK: u32 = 1 << 3;
+
+// I2CMCS bits when read.
+const MCS_BUSY: u32 = 1 << 0;
+const MCS_ERROR: u32 = 1 << 1;
+const MCS_ARBLST: u32 = 1 << 4;
+const MCS_BUSBSY: u32 = 1 << 6;
+const MCS_CLKTO: u32 = 1 << 7;
+
+// I2CMRIS and I2CMICR bits.
+const MRIS_RIS: u32 = 1 << 0;
+
+/// The I2C bus used by the I2C controller, along with its pins. I2C2 isn't available because its
+/// pins, PE4 and PE5, are used by CAN0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2cBus {
+    /// I2C0, with PB2 as SCL and PB3 as SDA.
+    I2c0,
+
+    /// I2C1, with PA6 as SCL and PA7 as SDA.
+    I2c1,
+}
+
+/// The speed of the I2C bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2cSpeed {
+    /// Standard mode, at 100 kHz.
+    Standard,
+
+    /// Fast mode, at 400 kHz.
+    Fast,
+
+    /// Fast mode plus, at 1 MHz.
+    FastPlus,
+}
+
+impl I2cSpeed {
+    /// Gets the SCL frequency of the speed in Hz.
+    fn hz(self) -> u32 {
+        match self {
+            Self::Standard => 100_000,
+            Self::Fast => 400_000,
+            Self::FastPlus => 1_000_000,
+        }
+    }
+
+    /// Gets the timer period for the speed at the given clocks, rounded so the bus runs no faster
+    /// than the speed. Returns `None` if the system clock is too slow or too fast for the speed.
+    pub(crate) fn timer_period(self, clocks: &Clocks) -> Option<u32> {
+        // Each SCL period is 20 timer periods, 6 low and 4 high, each of which is TPR + 1 cycles.
+        let cycles = 20 * self.hz();
+        let tpr = (clocks.sysclk.0 + cycles - 1) / cycles;
+
+        (2..=128).contains(&tpr).then_some(tpr - 1)
+    }
+}
+
+/// The configuration of the I2C controller in a [`RuntimeConfig`](crate::RuntimeConfig).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct I2cConfig {
+    /// The I2C bus to use.
+    pub bus: I2cBus,
+
+    /// The speed of the bus.
+    pub speed: I2cSpeed,
+}
+
+/// Initializes an I2C peripheral as a master with a timer period from
+/// [`I2cSpeed::timer_period()`]. The pins must already be configured.
+pub(crate) fn initialize_i2c(
+    i2c: &i2c0::RegisterBlock,
+    domain: Domain,
+    power_control: &PowerControl,
+    timer_period: u32,
+) {
+    sysctl::control_power(power_control, domain, RunMode::Run, PowerState::On);
+    sysctl::reset(power_control, domain);
+
+    // SAFETY: Every value written below is a valid combination of the bits documented in the
+    // datasheet for each register.
+    unsafe {
+        i2c.mcr.write(|w| w.bits(MCR_MFE));
+        i2c.mtpr.write(|w| w.bits(timer_period));
+    }
+}
+
+/// The direction of a transfer, used to pick which error to return.
+#[derive(Clone, Copy)]
+enum Direction {
+    Write,
+    Read,
+}
+
+impl Direction {
+    /// Gets the error for a NAK or lost arbitration in this direction.
+    fn error(self) -> CommunicationError {
+        match self {
+            Self::Write => CommunicationError::SendError,
+            Self::Read => CommunicationError::RecvError,
+        }
+    }
+}
+
+/// A blocking I2C master controller.
+///
+/// The TM4C's I2C master doesn't reliably set its busy bit right after a command is written, so
+/// each command waits on the raw interrupt status instead, which is only set once the command
+/// finishes. When a command fails without generating a STOP, such as when a burst is NAKed part way
+/// through, the master holds the bus until a STOP is written, so this writes one before returning
+/// the error. Targets can stretch the clock for as long as they need within the timeout.
+pub struct I2cController<'a> {
+    i2c: &'a i2c0::RegisterBlock,
+    hib: Arc<HibPool>,
+    timeout: Duration,
+}
+
+impl<'a> I2cController<'a> {
+    /// Creates an I2C controller for a peripheral that was initialized with [`initialize_i2c()`].
+    pub(crate) fn new(i2c: &'a i2c0::RegisterBlock, hib: Arc<HibPool>) -> Self {
+        Self {
+            i2c,
+            hib,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the time to wait for each step of a transfer, such as a byte being sent, before giving up
+    /// with [`CommunicationError::Timeout`]. This includes any time the target stretches the clock.
+    /// Defaults to 10 ms.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Writes bytes to the target with a 7-bit address.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::SendError`] - The address or a byte was NAKed, the bus was lost to
+    ///   another master, the address is wider than 7 bits, or there were no bytes to write.
+    /// - [`CommunicationError::Timeout`] - The bus stayed busy or a step of the transfer didn't finish
+    ///   within the timeout, such as when the target stretches the clock for too long.
+    pub fn write(&mut self, addr: u8, src: &[u8]) -> communication::Result<()> {
+        self.write_impl(addr, src, true)
+    }
+
+    /// Reads bytes from the target with a 7-bit address. Every byte but the last is ACKed.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`CommunicationError::RecvError`] - The address was NAKed, the bus was lost to another
+    ///   master, the address is wider than 7 bits, or there were no bytes to read.
+    /// - [`CommunicationError::Timeout`] - The bus stayed busy or a step of the transfer didn't finish
+    ///   within the timeout, such as when the target stretches the clock for too long.
+    pub fn read(&mut self, addr: u8, dest: &mut [u8]) -> communication::Result<()> {
+        self.read_impl(addr, dest, true)
+    }
+
+    /// Writes bytes to the target with a 7-bit address, then reads bytes from it after a repeated
+    /// START, such as to read a register. If there are no bytes to write, this is the same as
+    /// [`I2cController::read()`].
+    ///
+    /// # ERRORS:
+    ///
+    /// This function returns the errors of [`I2cController::write()`] while writing and the errors of
+    /// [`I2cController::read()`] while reading.
+    pub fn write_read(
+        &mut self,
+        addr: u8,
+        src: &[u8],
+        dest: &mut [u8],
+    ) -> communication::Result<()> {
+        // Check this before writing, so a write isn't left holding the bus without a read after it.
+        if dest.is_empty() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        if !src.is_empty() {
+            self.write_impl(addr, src, false)?;
+        }
+
+        self.read_impl(addr, dest, src.is_empty())
+    }
+
+    /// Writes bytes to the target, generating a STOP at the end if ``stop`` is true.
+    fn write_impl(&mut self, addr: u8, src: &[u8], stop: bool) -> communication::Result<()> {
+        if addr > 0x7F || src.is_empty() {
+            return Err(CommunicationError::SendError);
+        }
+
+        self.wait_bus_idle()?;
+
+        // SAFETY: The address is 7 bits, and the low bit selects a write.
+        self.i2c
+            .msa
+            .write(|w| unsafe { w.bits((addr as u32) << 1) });
+
+        for (i, byte) in src.iter().enumerate() {
+            let mut cmd = MCS_RUN;
+
+            if i == 0 {
+                cmd |= MCS_START;
+            }
+
+            if stop && i == src.len() - 1 {
+                cmd |= MCS_STOP;
+            }
+
+            // SAFETY: Any byte is valid data.
+            self.i2c.mdr.write(|w| unsafe { w.bits(*byte as u32) });
+            self.run_command(cmd, Direction::Write)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads bytes from the target, always generating a STOP at the end. The first command
+    /// generates a START, which is a repeated START if the bus is already held by a write.
+    fn read_impl(
+        &mut self,
+        addr: u8,
+        dest: &mut [u8],
+        bus_idle: bool,
+    ) -> communication::Result<()> {
+        if addr > 0x7F || dest.is_empty() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        if bus_idle {
+            self.wait_bus_idle()?;
+        }
+
+        // SAFETY: The address is 7 bits, and the low bit selects a read.
+        self.i2c
+            .msa
+            .write(|w| unsafe { w.bits((addr as u32) << 1 | 1) });
+
+        let len = dest.len();
+
+        for (i, byte) in dest.iter_mut().enumerate() {
+            let mut cmd = MCS_RUN;
+
+            if i == 0 {
+                cmd |= MCS_START;
+            }
+
+            // The last byte is NAKed to tell the target the read is over.
+            if i == len - 1 {
+                cmd |= MCS_STOP;
+            } else {
+                cmd |= MCS_ACK;
+            }
+
+            self.run_command(cmd, Direction::Read)?;
+            *byte = self.i2c.mdr.read().bits() as u8;
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the bus to be idle before starting a transfer, such as while another master is
+    /// using it.
+    fn wait_bus_idle(&self) -> communication::Result<()> {
+        let mut timer = HibTimer::new(&self.hib, self.timeout);
+
+        if !spin_until(&mut timer, || self.i2c.mcs.read().bits() & MCS_BUSBSY == 0) {
+            return Err(CommunicationError::Timeout);
+        }
+
+        Ok(())
+    }
+
+    /// Runs one command on the master and waits for it to finish. If the command fails without a
+    /// STOP being generated, a STOP is generated to release the bus.
+    fn run_command(&self, cmd: u32, direction: Direction) -> communication::Result<()> {
+        // SAFETY: Clearing the raw interrupt status and writing a command are valid for these
+        // registers.
+        unsafe {
+            self.i2c.micr.write(|w| w.bits(MRIS_RIS));
+            self.i2c.mcs.write(|w| w.bits(cmd));
+        }
+
+        let mut timer = HibTimer::new(&self.hib, self.timeout);
+
+        if !spin_until(&mut timer, || self.i2c.mris.read().bits() & MRIS_RIS != 0) {
+            self.stop();
+            return Err(CommunicationError::Timeout);
+        }
+
+        // SAFETY: Clearing the raw interrupt status is valid for this register.
+        self.i2c.micr.write(|w| unsafe { w.bits(MRIS_RIS) });
+
+        let status = self.i2c.mcs.read().bits();
+
+        if status & MCS_CLKTO != 0 {
+            self.stop();
+            return Err(CommunicationError::Timeout);
+        }
+
+        if status & MCS_ERROR != 0 {
+            // The master doesn't own the bus after losing arbitration, and a command that included
+            // a STOP already generated one.
+            if status & MCS_ARBLST == 0 && cmd & MCS_STOP == 0 {
+                self.stop();
+            }
+
+            return Err(direction.error());
+        }
+
+        Ok(())
+    }
+
+    /// Generates a STOP to release the bus after an error, waiting up to the timeout for it to be
+    /// sent.
+    fn stop(&self) {
+        // SAFETY: A lone STOP is a valid command.
+        self.i2c.mcs.write(|w| unsafe { w.bits(MCS_STOP) });
+
+        let mut timer = HibTimer::new(&self.hib, self.timeout);
+
+        spin_until(&mut timer, || self.i2c.mcs.read().bits() & MCS_BUSY == 0);
+    }
+}
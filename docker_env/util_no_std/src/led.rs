@@ -0,0 +1,132 @@
+//! This module contains an interface to use the onboard RGB LED as a status indicator.
+//!
+//! The LED shares GPIO port F with the SW1 and SW2 buttons on PF4 and PF0. Each pin is configured
+//! through its own bit-banded alias of the port registers when the [`RuntimePeripherals`] split the
+//! port, so configuring or driving the LED pins never touches the button pins, and the SW1 interrupt
+//! handler only acknowledges PF4.
+//!
+//! [`RuntimePeripherals`]: crate::RuntimePeripherals
+
+use crate::{hib::HibController, timer::Timer, LedBluePin, LedGreenPin, LedRedPin};
+use core::{convert::Infallible, time::Duration};
+use embedded_hal::digital::v2::OutputPin;
+
+/// A state to show on the [`StatusLed`]. Each state is shown as its own color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedState {
+    /// The LED is off.
+    Off,
+
+    /// Waiting for something to do. The LED is blue.
+    Idle,
+
+    /// Pairing is in progress. The LED is yellow.
+    Pairing,
+
+    /// The car is unlocked. The LED is green.
+    Unlocked,
+
+    /// Something went wrong. The LED is red, as it is while the panic handler resets the chip.
+    Error,
+}
+
+impl LedState {
+    /// Gets whether the red, green, and blue parts of the LED are on in this state.
+    fn rgb(self) -> (bool, bool, bool) {
+        match self {
+            Self::Off => (false, false, false),
+            Self::Idle => (false, false, true),
+            Self::Pairing => (true, true, false),
+            Self::Unlocked => (false, true, false),
+            Self::Error => (true, false, false),
+        }
+    }
+}
+
+/// A pattern for [`StatusLed::blink()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlinkPattern {
+    /// The state shown while the LED is on.
+    pub state: LedState,
+
+    /// How long the LED stays on for each blink.
+    pub on_time: Duration,
+
+    /// How long the LED stays off after each blink.
+    pub off_time: Duration,
+
+    /// The number of blinks.
+    pub count: u32,
+}
+
+/// A driver for the onboard RGB LED on PF1 (red), PF2 (blue), and PF3 (green). The LED starts off.
+pub struct StatusLed<'a> {
+    red: &'a mut LedRedPin,
+    blue: &'a mut LedBluePin,
+    green: &'a mut LedGreenPin,
+    state: LedState,
+}
+
+impl<'a> StatusLed<'a> {
+    /// Initializes the status LED and turns it off.
+    pub(crate) fn new(
+        red: &'a mut LedRedPin,
+        blue: &'a mut LedBluePin,
+        green: &'a mut LedGreenPin,
+    ) -> Self {
+        let mut led = Self {
+            red,
+            blue,
+            green,
+            state: LedState::Off,
+        };
+
+        led.set_state(LedState::Off);
+
+        led
+    }
+
+    /// Gets the state the LED is showing.
+    pub fn state(&self) -> LedState {
+        self.state
+    }
+
+    /// Shows the given state on the LED until another state is set.
+    pub fn set_state(&mut self, state: LedState) {
+        let (red, green, blue) = state.rgb();
+
+        set_pin(self.red, red);
+        set_pin(self.green, green);
+        set_pin(self.blue, blue);
+
+        self.state = state;
+    }
+
+    /// Blinks the LED with the given pattern, timed by the hibernation clock, and then shows the state
+    /// it was showing before. This blocks until the pattern is done, so the watchdog must not be
+    /// enabled with a timeout shorter than the whole pattern.
+    pub fn blink(&mut self, pattern: BlinkPattern, hib_controller: &HibController) {
+        let previous = self.state;
+        let mut on_timer = hib_controller.create_timer(pattern.on_time);
+        let mut off_timer = hib_controller.create_timer(pattern.off_time);
+
+        for _ in 0..pattern.count {
+            self.set_state(pattern.state);
+            on_timer.reset();
+            while !on_timer.poll() {}
+
+            self.set_state(LedState::Off);
+            off_timer.reset();
+            while !off_timer.poll() {}
+        }
+
+        self.set_state(previous);
+    }
+}
+
+/// Drives an LED pin high to turn its color on, or low to turn it off.
+fn set_pin(pin: &mut impl OutputPin<Error = Infallible>, on: bool) {
+    let result = if on { pin.set_high() } else { pin.set_low() };
+
+    result.unwrap_or_else(|err| match err {})
+}
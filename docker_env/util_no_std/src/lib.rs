@@ -4,15 +4,34 @@
 #![no_std]
 
 pub mod button;
+pub mod can;
 pub mod communication;
+pub mod crypto;
 pub mod eeprom;
+pub mod event;
 pub mod features;
 pub mod hib;
+pub mod i2c;
+pub mod led;
+pub mod log;
+pub mod measurement;
+#[cfg(feature = "panic-handler")]
+pub mod panic;
+pub mod rate_limit;
+pub mod stack_guard;
 pub mod timer;
+pub mod watchdog;
 
 pub(crate) mod random;
 
 mod runtime;
 
+pub use random::{
+    bytes_since_reseed, entropy_report, last_entropy_health, main_rng, random_key, secondary_rng,
+    BitEstimate, EntropyHealth, EntropyReport, EntropySourceKind, MainRng, ReadingEstimate,
+    SecondaryRng,
+};
+#[cfg(all(feature = "test-rng", debug_assertions))]
+pub use random::{health_test_bits, health_test_readings, seed_main_for_test};
 pub use runtime::*;
-pub use ucsc_ectf_util_common::messages;
+pub use ucsc_ectf_util_common::{constant_time_eq, crc32, messages};
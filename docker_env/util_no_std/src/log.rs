@@ -0,0 +1,328 @@
+//! This module contains the [`debug!`](crate::debug), [`info!`](crate::info), and
+//! [`warn!`](crate::warn) macros for emitting debug output, along with the
+//! [`debug_hex!`](crate::debug_hex) macro for dumping bytes with [`hexdump()`].
+//!
+//! Each macro writes one line, prefixed with its level. The line is formatted into a buffer on the
+//! stack first and then written with interrupts enabled, so a slow destination never holds off
+//! interrupts. A line longer than the buffer is written in pieces. Messages below [`MAX_LEVEL`]
+//! compile to nothing, including their arguments, so they can be left in the code. [`MAX_LEVEL`] is
+//! [`Level::Debug`] in debug builds and [`Level::Warn`] in release builds, and the `log-off`
+//! feature strips every message.
+//!
+//! Nothing is written until the logger is set up by [`Runtime::new()`](crate::Runtime::new). After
+//! that, messages go to the debugger through semihosting in debug builds running under a debugger,
+//! and to UART0 otherwise. Semihosting halts a chip with no debugger attached, so it's never used
+//! without one.
+//!
+//! UART0 is also used by the [`Uart0Controller`](crate::communication::Uart0Controller), so a message
+//! written from an interrupt handler while a frame is being sent lands in the middle of the frame.
+//! Messages written between frames are skipped by the framing layer on the receiving end. In the
+//! same way, a message written from an interrupt handler can land in the middle of another message,
+//! but never in the middle of a piece of one.
+
+use core::{
+    fmt::{self, Arguments, Write},
+    sync::atomic::{AtomicU8, Ordering},
+};
+use cortex_m::interrupt;
+use tm4c123x_hal::tm4c123x::{SYSCTL, UART0};
+
+#[cfg(debug_assertions)]
+use cortex_m::peripheral::DCB;
+#[cfg(debug_assertions)]
+use cortex_m_semihosting::hio;
+
+/// The level of a log message, from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Detailed information for debugging, written with [`debug!`](crate::debug).
+    Debug,
+    /// Notable events, written with [`info!`](crate::info).
+    Info,
+    /// Unexpected conditions that the firmware recovered from, written with [`warn!`](crate::warn).
+    Warn,
+    /// No messages are written at this level. Only used as a [`MAX_LEVEL`].
+    Off,
+}
+
+impl Level {
+    /// Gets the prefix written before messages of this level.
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Debug => "[DEBUG] ",
+            Self::Info => "[INFO] ",
+            Self::Warn => "[WARN] ",
+            Self::Off => "",
+        }
+    }
+}
+
+/// The lowest level of message that's written. Messages below this level compile to nothing.
+pub const MAX_LEVEL: Level = if cfg!(feature = "log-off") {
+    Level::Off
+} else if cfg!(debug_assertions) {
+    Level::Debug
+} else {
+    Level::Warn
+};
+
+/// The number of bytes on each line of a [`hexdump()`].
+const HEXDUMP_LINE_LEN: usize = 16;
+
+/// The size of the buffer that each line of a log message is formatted into before it's written. This
+/// fits a whole line of a [`hexdump()`].
+const LINE_BUFFER_LEN: usize = 128;
+
+/// The mask for UART0 in the UART clock gating register.
+const UART0_MASK: u32 = 1 << 0;
+
+// The destinations of log messages, stored in SINK.
+const SINK_NONE: u8 = 0;
+const SINK_UART0: u8 = 1;
+#[cfg(debug_assertions)]
+const SINK_SEMIHOSTING: u8 = 2;
+
+/// Where log messages are written. Nothing is written until [`init()`] is called.
+static SINK: AtomicU8 = AtomicU8::new(SINK_NONE);
+
+/// Returns whether messages of the given level are written. This is a constant, so messages that
+/// aren't written are optimized out.
+#[doc(hidden)]
+pub const fn enabled(level: Level) -> bool {
+    !matches!(level, Level::Off) && level as u8 >= MAX_LEVEL as u8
+}
+
+/// Sets up the logger, picking semihosting in debug builds with a debugger attached and UART0
+/// otherwise. UART0 must have been initialized by the
+/// [`RuntimePeripherals`](crate::RuntimePeripherals) already, or nothing is written to it.
+pub(crate) fn init() {
+    #[cfg(debug_assertions)]
+    if DCB::is_debugger_attached() {
+        SINK.store(SINK_SEMIHOSTING, Ordering::Relaxed);
+        return;
+    }
+
+    // SAFETY: These registers are only read.
+    let (sysctl, uart0) = unsafe { (&*SYSCTL::ptr(), &*UART0::ptr()) };
+
+    // Accessing UART0 before it's clocked faults, and writing to it before it's enabled hangs.
+    if sysctl.rcgcuart.read().bits() & UART0_MASK != 0 && uart0.ctl.read().uarten().bit_is_set() {
+        SINK.store(SINK_UART0, Ordering::Relaxed);
+    }
+}
+
+/// Writes bytes to UART0, blocking until each byte fits in the transmit FIFO.
+fn write_uart0(bytes: &[u8]) {
+    // SAFETY: Only the transmit FIFO is touched, and each byte is written in a critical section
+    // right after checking that it fits, so a message written from an interrupt handler can't fill
+    // the FIFO in between. The UART0 controller only writes to the FIFO too, so its bytes are never
+    // lost, only interleaved with the message.
+    let uart0 = unsafe { &*UART0::ptr() };
+
+    for &byte in bytes {
+        while !interrupt::free(|_| {
+            if uart0.fr.read().txff().bit_is_set() {
+                return false;
+            }
+
+            // SAFETY: Every byte is valid for the data register.
+            uart0.dr.write(|w| unsafe { w.bits(byte as u32) });
+
+            true
+        }) {}
+    }
+}
+
+/// Writes bytes to the destination of log messages, if there is one.
+fn write_to_sink(bytes: &[u8]) {
+    match SINK.load(Ordering::Relaxed) {
+        SINK_UART0 => write_uart0(bytes),
+        #[cfg(debug_assertions)]
+        SINK_SEMIHOSTING => {
+            if let Ok(mut stdout) = hio::hstdout() {
+                let _ = stdout.write_all(bytes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A writer that formats into a buffer of [`LINE_BUFFER_LEN`] bytes on the stack and writes the
+/// buffer to the destination of log messages at the end of each line, or whenever it fills up.
+struct LineWriter {
+    buf: [u8; LINE_BUFFER_LEN],
+    len: usize,
+}
+
+impl LineWriter {
+    /// Creates a new, empty [`LineWriter`].
+    fn new() -> Self {
+        Self {
+            buf: [0; LINE_BUFFER_LEN],
+            len: 0,
+        }
+    }
+
+    /// Writes whatever is in the buffer and empties it.
+    fn flush(&mut self) {
+        write_to_sink(&self.buf[..self.len]);
+        self.len = 0;
+    }
+}
+
+impl Write for LineWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            if self.len == LINE_BUFFER_LEN {
+                self.flush();
+            }
+
+            self.buf[self.len] = byte;
+            self.len += 1;
+
+            if byte == b'\n' {
+                self.flush();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes one log message. Use the [`debug!`](crate::debug), [`info!`](crate::info), and
+/// [`warn!`](crate::warn) macros instead, which skip this entirely for levels that aren't written.
+#[doc(hidden)]
+pub fn write(level: Level, args: Arguments) {
+    if SINK.load(Ordering::Relaxed) == SINK_NONE {
+        return;
+    }
+
+    write_line(&mut LineWriter::new(), level, args);
+}
+
+/// Writes one log message followed by a [`hexdump()`] of the given bytes. Use the
+/// [`debug_hex!`](crate::debug_hex) macro instead, which skips this entirely if debug messages
+/// aren't written.
+#[doc(hidden)]
+pub fn write_hex(level: Level, bytes: &[u8], args: Arguments) {
+    if SINK.load(Ordering::Relaxed) == SINK_NONE {
+        return;
+    }
+
+    let mut writer = LineWriter::new();
+    write_line(&mut writer, level, args);
+    let _ = hexdump(bytes, &mut writer);
+}
+
+/// Writes a message with the prefix of its level and a newline. Errors are ignored, since there's
+/// nowhere to report them.
+fn write_line(writer: &mut LineWriter, level: Level, args: Arguments) {
+    let _ = writer.write_str(level.prefix());
+    let _ = writer.write_fmt(args);
+    let _ = writer.write_str("\n");
+}
+
+/// Writes the given bytes to ``out`` in the layout of a classic hex dump, with 16 bytes on each line.
+/// Each line has the offset of its first byte, the bytes in hex, split into two groups of 8, and the
+/// bytes as ASCII, with a dot for each byte that isn't printable:
+///
+/// ```text
+/// 00000000  01 48 65 6c 6c 6f 00 ff  ff ff ff ff ff ff ff ff  |.Hello..........|
+/// 00000010  2a 01                                             |*.|
+/// ```
+///
+/// Nothing is written for an empty slice. No memory is allocated, so this can be used with any
+/// [`Write`] implementation, such as semihosting output or a fixed-size buffer.
+///
+/// # ERRORS:
+///
+/// - [`fmt::Error`] - ``out`` failed to write part of the dump.
+pub fn hexdump(bytes: &[u8], out: &mut impl Write) -> fmt::Result {
+    for (line, chunk) in bytes.chunks(HEXDUMP_LINE_LEN).enumerate() {
+        write!(out, "{:08x} ", line * HEXDUMP_LINE_LEN)?;
+
+        for i in 0..HEXDUMP_LINE_LEN {
+            // An extra space splits the bytes into two groups of 8.
+            if i % 8 == 0 {
+                out.write_char(' ')?;
+            }
+
+            // Pad a short last line so its ASCII column lines up with the others.
+            match chunk.get(i) {
+                Some(byte) => write!(out, "{byte:02x} ")?,
+                None => out.write_str("   ")?,
+            }
+        }
+
+        out.write_str(" |")?;
+
+        for &byte in chunk {
+            let printable = byte.is_ascii_graphic() || byte == b' ';
+            out.write_char(if printable { byte as char } else { '.' })?;
+        }
+
+        out.write_str("|\n")?;
+    }
+
+    Ok(())
+}
+
+/// Writes a [`Level::Debug`] message, formatted like [`format_args!`]. See the [`log`](crate::log)
+/// module for more details.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            $crate::log::write($crate::log::Level::Debug, format_args!($($arg)+));
+        }
+    };
+}
+
+/// Writes a [`Level::Info`] message, formatted like [`format_args!`]. See the [`log`](crate::log)
+/// module for more details.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        if $crate::log::enabled($crate::log::Level::Info) {
+            $crate::log::write($crate::log::Level::Info, format_args!($($arg)+));
+        }
+    };
+}
+
+/// Writes a [`Level::Warn`] message, formatted like [`format_args!`]. See the [`log`](crate::log)
+/// module for more details.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        if $crate::log::enabled($crate::log::Level::Warn) {
+            $crate::log::write($crate::log::Level::Warn, format_args!($($arg)+));
+        }
+    };
+}
+
+/// Writes a [`Level::Debug`] message followed by a [`hexdump()`](crate::log::hexdump) of the given
+/// bytes, which can be anything that implements [`AsRef<[u8]>`](AsRef). The message is formatted like
+/// [`format_args!`], and is the number of bytes if it's left out. See the [`log`](crate::log) module for
+/// more details.
+#[macro_export]
+macro_rules! debug_hex {
+    ($bytes:expr $(,)?) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            let bytes = ::core::convert::AsRef::<[u8]>::as_ref(&$bytes);
+            $crate::log::write_hex(
+                $crate::log::Level::Debug,
+                bytes,
+                format_args!("{} bytes:", bytes.len()),
+            );
+        }
+    };
+    ($bytes:expr, $($arg:tt)+) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            $crate::log::write_hex(
+                $crate::log::Level::Debug,
+                ::core::convert::AsRef::<[u8]>::as_ref(&$bytes),
+                format_args!($($arg)+),
+            );
+        }
+    };
+}
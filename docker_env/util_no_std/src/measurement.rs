@@ -0,0 +1,52 @@
+//! This module contains the firmware measurement, a SHA3-256 hash of the flash image that a
+//! provisioning host can compare against the hash of the image it built to check which firmware is
+//! running. See [`Runtime::measure_firmware()`] for how the bounds of the image are found, and
+//! [`report_measurement()`] for sending the measurement to the host.
+//!
+//! The firmware measures itself, so this only detects firmware that was flashed by mistake or
+//! corrupted. Firmware that was replaced on purpose can report whatever measurement it wants.
+//!
+//! [`Runtime::measure_firmware()`]: crate::Runtime::measure_firmware
+
+use crate::communication::{self, TxChannel};
+use core::slice;
+use sha3::{Digest, Sha3_256};
+
+/// The size of a firmware measurement in bytes.
+pub const MEASUREMENT_SIZE: usize = 32;
+
+/// A firmware measurement, which is the SHA3-256 hash of the flash image.
+pub type Measurement = [u8; MEASUREMENT_SIZE];
+
+/// Hashes the bytes from `start` up to, but not including, `end`.
+///
+/// # Safety
+///
+/// `start` must be at most `end`, and every byte between them must be readable memory that isn't
+/// written to while it's hashed, such as flash.
+pub(crate) unsafe fn measure(start: *const u8, end: *const u8) -> Measurement {
+    assert!(start <= end, "The start of the image is after its end.");
+
+    // SAFETY: The caller guarantees that the range is readable and isn't written to, and its length
+    // can't overflow since start is at most end.
+    let image = unsafe { slice::from_raw_parts(start, end as usize - start as usize) };
+
+    Sha3_256::digest(image).into()
+}
+
+/// Sends a firmware measurement through the given channel, such as a
+/// [`Uart0Controller`](crate::communication::Uart0Controller) to a provisioning host. The measurement
+/// is sent as-is, as one message of [`MEASUREMENT_SIZE`] bytes.
+///
+/// # ERRORS:
+///
+/// This function returns any error from [`TxChannel::send`].
+pub fn report_measurement(
+    measurement: &Measurement,
+    channel: &mut impl TxChannel,
+) -> communication::Result<()> {
+    // Channels in the crypto layer encrypt in place, so send a copy.
+    let mut msg = *measurement;
+
+    channel.send(&mut msg)
+}
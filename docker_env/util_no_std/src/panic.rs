@@ -0,0 +1,186 @@
+//! This module contains a panic handler that wipes registered secrets, turns on the red LED, and
+//! resets the chip. In debug builds, the panic message is also written to UART0 before resetting.
+//!
+//! This module is only available with the `panic-handler` feature. A binary using this feature must
+//! not link another panic handler, such as `panic-halt`.
+
+use core::{
+    panic::PanicInfo,
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicU32, AtomicUsize, Ordering},
+};
+use cortex_m::{asm, interrupt, peripheral::SCB};
+use tm4c123x_hal::tm4c123x::{GPIO_PORTF, SYSCTL};
+
+#[cfg(debug_assertions)]
+use core::fmt::{self, Write};
+#[cfg(debug_assertions)]
+use tm4c123x_hal::tm4c123x::UART0;
+
+/// The maximum number of secret regions that can be registered.
+pub const MAX_SECRET_REGIONS: usize = 8;
+
+/// The mask for the PF1 pin, which drives the red LED.
+const PF1_MASK: u32 = 1 << 1;
+
+/// The number of milliseconds the red LED stays on before resetting.
+const FAULT_LED_MS: u32 = 500;
+
+/// The system clock frequency out of reset, which is the 16 MHz precision internal oscillator. This is
+/// used for panics before the runtime has configured the system clock.
+const RESET_SYSCLK_HZ: u32 = 16_000_000;
+
+/// The mask for GPIO port F in the GPIO clock gating registers.
+const GPIO_PORTF_MASK: u32 = 1 << 5;
+
+/// The mask for UART0 in the UART clock gating register.
+#[cfg(debug_assertions)]
+const UART0_MASK: u32 = 1 << 0;
+
+/// A secret region registered with [`register_secret_region()`].
+struct SecretRegion {
+    ptr: AtomicPtr<u8>,
+    len: AtomicUsize,
+}
+
+/// An unregistered secret region, used to initialize [`SECRET_REGIONS`].
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SECRET_REGION: SecretRegion = SecretRegion {
+    ptr: AtomicPtr::new(ptr::null_mut()),
+    len: AtomicUsize::new(0),
+};
+
+/// The secret regions that are zeroized on panic. Atomics are used instead of a mutex so the panic
+/// handler can read them even if it panicked while the regions were being registered.
+static SECRET_REGIONS: [SecretRegion; MAX_SECRET_REGIONS] =
+    [EMPTY_SECRET_REGION; MAX_SECRET_REGIONS];
+
+/// The number of registered secret regions.
+static SECRET_REGION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The system clock frequency in Hz, set by the runtime once it has configured the system clock.
+static SYSCLK_HZ: AtomicU32 = AtomicU32::new(RESET_SYSCLK_HZ);
+
+/// Records the system clock frequency so the red LED stays on for [`FAULT_LED_MS`] whatever the
+/// configured system clock is.
+pub(crate) fn set_sysclk(hz: u32) {
+    SYSCLK_HZ.store(hz, Ordering::SeqCst);
+}
+
+/// Registers a region of memory holding secrets, such as a key buffer, to be zeroized if the
+/// program panics.
+///
+/// # Safety
+///
+/// The region must be valid for writes of `len` bytes for the rest of the program, such as a buffer
+/// in a `static`, since it can be zeroized at any point after registration.
+///
+/// # Panics
+///
+/// Panics if more than [`MAX_SECRET_REGIONS`] regions are registered.
+pub unsafe fn register_secret_region(ptr: *mut u8, len: usize) {
+    interrupt::free(|_| {
+        let count = SECRET_REGION_COUNT.load(Ordering::SeqCst);
+
+        assert!(count < MAX_SECRET_REGIONS, "Too many secret regions.");
+
+        SECRET_REGIONS[count].ptr.store(ptr, Ordering::SeqCst);
+        SECRET_REGIONS[count].len.store(len, Ordering::SeqCst);
+        SECRET_REGION_COUNT.store(count + 1, Ordering::SeqCst);
+    });
+}
+
+/// Zeroizes all registered secret regions.
+fn zeroize_secret_regions() {
+    let count = SECRET_REGION_COUNT.load(Ordering::SeqCst);
+
+    for region in &SECRET_REGIONS[..count] {
+        let ptr = region.ptr.load(Ordering::SeqCst);
+
+        for i in 0..region.len.load(Ordering::SeqCst) {
+            // SAFETY: The caller of register_secret_region() guaranteed the region is valid for
+            // writes. Volatile writes are used so the zeroization isn't optimized out.
+            unsafe { ptr::write_volatile(ptr.add(i), 0) };
+        }
+    }
+}
+
+/// Turns on the red LED.
+fn turn_on_fault_led() {
+    // SAFETY: Interrupts are disabled and the program never returns from the panic handler, so
+    // nothing else can access these registers concurrently.
+    let sysctl = unsafe { &*SYSCTL::ptr() };
+    // SAFETY: See above. Only the PF1 bits are changed, so the other pins keep working until reset.
+    let gpio_portf = unsafe { &*GPIO_PORTF::ptr() };
+
+    // Make sure port F is clocked, in case the panic happened before it was initialized.
+    // SAFETY: Only the port F bit is set.
+    sysctl
+        .rcgcgpio
+        .modify(|r, w| unsafe { w.bits(r.bits() | GPIO_PORTF_MASK) });
+    while sysctl.prgpio.read().bits() & GPIO_PORTF_MASK == 0 {}
+
+    // SAFETY: Only the PF1 bits are set, which makes PF1 a digital output driven high.
+    unsafe {
+        gpio_portf.dir.modify(|r, w| w.bits(r.bits() | PF1_MASK));
+        gpio_portf.den.modify(|r, w| w.bits(r.bits() | PF1_MASK));
+        gpio_portf.data.modify(|r, w| w.bits(r.bits() | PF1_MASK));
+    }
+}
+
+/// A writer for UART0 that blocks until each byte fits in the transmit FIFO.
+#[cfg(debug_assertions)]
+struct PanicUart0Writer;
+
+#[cfg(debug_assertions)]
+impl Write for PanicUart0Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // SAFETY: Interrupts are disabled and the program never returns from the panic handler, so
+        // nothing else can access UART0 concurrently.
+        let uart0 = unsafe { &*UART0::ptr() };
+
+        for byte in s.bytes() {
+            while uart0.fr.read().txff().bit_is_set() {}
+
+            // SAFETY: Every byte is valid for the data register.
+            uart0.dr.write(|w| unsafe { w.bits(byte as u32) });
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the panic message to UART0 if UART0 has been initialized.
+#[cfg(debug_assertions)]
+fn write_panic_message(info: &PanicInfo) {
+    // SAFETY: This register is only read.
+    let sysctl = unsafe { &*SYSCTL::ptr() };
+    // SAFETY: See PanicUart0Writer::write_str().
+    let uart0 = unsafe { &*UART0::ptr() };
+
+    // Accessing UART0 before it's clocked faults, and writing to it before it's enabled hangs.
+    if sysctl.rcgcuart.read().bits() & UART0_MASK == 0 || uart0.ctl.read().uarten().bit_is_clear() {
+        return;
+    }
+
+    let _ = writeln!(PanicUart0Writer, "{info}");
+
+    // Wait for the message to finish sending before resetting.
+    while uart0.fr.read().busy().bit_is_set() {}
+}
+
+#[panic_handler]
+fn panic(#[cfg_attr(not(debug_assertions), allow(unused_variables))] info: &PanicInfo) -> ! {
+    interrupt::disable();
+
+    zeroize_secret_regions();
+    turn_on_fault_led();
+
+    #[cfg(debug_assertions)]
+    write_panic_message(info);
+
+    // Keep the red LED on long enough to be seen, since the reset turns it off.
+    asm::delay(SYSCLK_HZ.load(Ordering::SeqCst) / 1000 * FAULT_LED_MS);
+
+    SCB::sys_reset()
+}
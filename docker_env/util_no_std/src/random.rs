@@ -3,62 +3,368 @@
 //!
 //! The main CSPRNG is to be used by the runtime to generate random numbers and internally by this
 //! crate when the runtime is known to be initialized. Initialization of this CSPRNG will block to
-//! gather entropy.
+//! gather entropy. The main CSPRNG is automatically reseeded with fresh entropy after it has
+//! generated a configurable number of bytes. See [`fill_rand_slice()`] for more details.
 //!
 //! The secondary CSPRNG is to be used internally by this crate where the main CSPRNG has not been
 //! initialized yet. Initialization of this CSPRNG does not need to block to gather entropy. Uses
-//! the same seed across reboots.
+//! the same seed across reboots, but can be reseeded from the main CSPRNG once the main CSPRNG is
+//! initialized. See [`reseed_secondary_from_main()`] for more details.
+//!
+//! Only the main CSPRNG may be used for anything that must be unpredictable, such as keys, nonces, and
+//! challenges. The secondary CSPRNG is for everything else, such as test data and jitter. This is
+//! enforced by the types of the handles to each CSPRNG:
+//!
+//! - [`MainRng`] implements [`CryptoRng`] and
+//!   [`RandomSource`](ucsc_ectf_util_common::communication::lower_layers::crypto::RandomSource), so it
+//!   can be passed into [`random_key()`] and used to generate the nonces of secure channels.
+//! - [`SecondaryRng`] implements neither, so passing it into either of those fails to compile. Arrays
+//!   drawn from the secondary CSPRNG with [`fill_rand_array_secondary()`] also fail to compile if
+//!   they're large enough to be a key, and filling a slice the size of a key through a
+//!   [`SecondaryRng`] panics.
 
 mod entropy;
 
-use core::cell::RefCell;
+use core::{
+    cell::{Cell, RefCell},
+    num::NonZeroU32,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use cortex_m::interrupt::{self, Mutex};
 use once_cell::sync::OnceCell;
 
 use rand_chacha::{
-    rand_core::{RngCore, SeedableRng},
+    rand_core::{self, impls, CryptoRng, RngCore, SeedableRng},
     ChaCha20Rng,
 };
 
-use self::entropy::{Adc, ClockDrift, EntropyHasher, Secret, UninitMemory};
+pub(crate) use self::entropy::{sample_rx_line, UartNoiseSamples};
+use self::entropy::{Adc, ClockDrift, EntropyHasher, Secret, TempSensor, UartNoise, UninitMemory};
+pub use self::entropy::{
+    BitEstimate, EntropyHealth, EntropyReport, EntropySourceKind, ReadingEstimate,
+};
 use crate::RuntimePeripherals;
+use chacha20poly1305::Key;
+use ucsc_ectf_util_common::communication::lower_layers::crypto::RandomSource;
+use zeroize::Zeroize;
+
+/// The default number of bytes the main CSPRNG generates before it's automatically reseeded. 1 MiB.
+const DEFAULT_RESEED_THRESHOLD: usize = 1024 * 1024;
+
+/// The number of bytes of entropy taken from the main CSPRNG and mixed into its new seed on reseed.
+const RESEED_CARRY_SIZE: usize = 32;
+
+/// The size of the smallest key in use, which is a 16-byte AES-128 key. Arrays this size or larger
+/// can't be drawn from the secondary CSPRNG with [`fill_rand_array_secondary()`].
+const MIN_KEY_SIZE: usize = 16;
+
+/// The sizes of the keys in use: 16-byte AES-128 keys and 32-byte XChaCha20-Poly1305 and signing keys.
+/// Slices of these sizes can't be filled through a [`SecondaryRng`].
+const KEY_SIZES: [usize; 2] = [MIN_KEY_SIZE, 32];
+
+/// The error code returned by [`SecondaryRng::try_fill_bytes()`] for slices the size of a key.
+const KEY_SIZED_SECONDARY_FILL: u32 = rand_core::Error::CUSTOM_START;
 
 static MAIN_CSPRNG: OnceCell<Mutex<RefCell<ChaCha20Rng>>> = OnceCell::new();
+static MAIN_CSPRNG_BYTES_SINCE_RESEED: AtomicUsize = AtomicUsize::new(0);
+static MAIN_CSPRNG_BYTES_SINCE_RESEED_ATTEMPT: AtomicUsize = AtomicUsize::new(0);
+static MAIN_CSPRNG_RESEED_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_RESEED_THRESHOLD);
 static SECONDARY_CSPRNG: OnceCell<Mutex<RefCell<ChaCha20Rng>>> = OnceCell::new();
+static LAST_ENTROPY_HEALTH: Mutex<Cell<EntropyHealth>> =
+    Mutex::new(Cell::new(EntropyHealth::NotTested));
+static ENTROPY_REPORT: Mutex<Cell<EntropyReport>> = Mutex::new(Cell::new(EntropyReport::new()));
 
 /// Initializes the secondary and main CSPRNG. The initialization of the main CSPRNG will block while
 /// gathering entropy. The secondary CSPRNG does not need to block while gathering entropy. The
 /// secondary CSPRNG will have been initialized by the time the main CSPRNG is to be initialized.
 ///
-/// Will do nothing if called more than once.
-pub(crate) fn init_rng(peripherals: &mut RuntimePeripherals) {
+/// The raw samples gathered for the main CSPRNG are health tested before they're hashed. The result
+/// can be retrieved with [`last_entropy_health()`]. Estimates of how much entropy each source
+/// gathered can be retrieved with [`entropy_report()`].
+///
+/// Will do nothing if called more than once after succeeding. If it fails, the main CSPRNG is left
+/// uninitialized, so calling it again gathers fresh entropy.
+///
+/// # ERRORS:
+///
+/// - The [`EntropyHealth`] failure - Occurs if the entropy gathered for the main CSPRNG fails its
+///   health tests. This means an entropy source is broken, and seeding with its output could produce
+///   predictable keys.
+pub(crate) fn init_rng(peripherals: &mut RuntimePeripherals) -> Result<(), EntropyHealth> {
     SECONDARY_CSPRNG.get_or_init(|| {
         Mutex::new(RefCell::new(ChaCha20Rng::from_seed(
-            EntropyHasher::<Secret<()>>::new(peripherals).hash(),
+            EntropyHasher::<Secret<()>>::new(peripherals)
+                .hash()
+                .expect("The secret has no health tests, so hashing it can't fail."),
         )))
     });
 
-    MAIN_CSPRNG.get_or_init(|| {
-        Mutex::new(RefCell::new(ChaCha20Rng::from_seed(
-            EntropyHasher::<UninitMemory<Secret<Adc<ClockDrift<()>>>>>::new(peripherals).hash(),
-        )))
+    MAIN_CSPRNG.get_or_try_init(|| {
+        let hasher = EntropyHasher::<
+            UninitMemory<Secret<Adc<TempSensor<ClockDrift<UartNoise<()>>>>>>,
+        >::new(peripherals);
+
+        // The report is recorded even if the health tests fail, since it helps diagnose the failure.
+        interrupt::free(|c| ENTROPY_REPORT.borrow(c).set(hasher.report()));
+
+        let hash = hasher.hash();
+
+        let seed = match hash {
+            Ok(seed) => {
+                set_entropy_health(EntropyHealth::Healthy);
+                seed
+            }
+            Err(health) => {
+                set_entropy_health(health);
+                return Err(health);
+            }
+        };
+
+        Ok(Mutex::new(RefCell::new(ChaCha20Rng::from_seed(seed))))
+    })?;
+
+    Ok(())
+}
+
+/// Forces the main CSPRNG to a known seed without gathering any entropy, so tests get the same output
+/// from [`fill_rand_slice()`] on every run, such as to check the exact bytes of a nonce. If the main
+/// CSPRNG hasn't been initialized yet, this initializes it, and [`init_rng()`] leaves it alone
+/// afterwards. The count of bytes generated since the last reseed is reset, but an automatic reseed
+/// still mixes in fresh entropy once the threshold is crossed, so tests that generate a lot of bytes
+/// should disable automatic reseeding with [`set_reseed_threshold()`].
+///
+/// This only exists in debug builds with the `test-rng` feature, so it can never be called from a
+/// release build. Call [`reseed_main()`] once the test is done to go back to an unpredictable state.
+#[cfg(all(feature = "test-rng", debug_assertions))]
+pub fn seed_main_for_test(seed: [u8; 32]) {
+    let csprng = MAIN_CSPRNG.get_or_init(|| Mutex::new(RefCell::new(ChaCha20Rng::from_seed(seed))));
+
+    interrupt::free(|c| csprng.borrow(c).replace(ChaCha20Rng::from_seed(seed)));
+    MAIN_CSPRNG_BYTES_SINCE_RESEED.store(0, Ordering::Relaxed);
+    MAIN_CSPRNG_BYTES_SINCE_RESEED_ATTEMPT.store(0, Ordering::Relaxed);
+}
+
+/// Runs the health tests for binary samples, where each bit is one sample, on the given bytes, like
+/// the ADC and clock drift samples are tested before they're used. This lets tests check that crafted
+/// samples fail the repetition count and adaptive proportion tests.
+///
+/// This only exists in debug builds with the `test-rng` feature.
+#[cfg(all(feature = "test-rng", debug_assertions))]
+pub fn health_test_bits(samples: &[u8], source: EntropySourceKind) -> EntropyHealth {
+    entropy::test_bits(samples, source)
+}
+
+/// Runs the health tests for multi-bit readings on the given readings, like the temperature sensor
+/// readings are tested before they're used. Only the repetition count test is run on readings.
+///
+/// This only exists in debug builds with the `test-rng` feature.
+#[cfg(all(feature = "test-rng", debug_assertions))]
+pub fn health_test_readings(readings: &[u16], source: EntropySourceKind) -> EntropyHealth {
+    entropy::test_readings(readings.iter(), source)
+}
+
+/// Records the result of the latest entropy health tests.
+fn set_entropy_health(health: EntropyHealth) {
+    interrupt::free(|c| LAST_ENTROPY_HEALTH.borrow(c).set(health));
+}
+
+/// Returns the result of the latest health tests run on the raw entropy gathered for the main
+/// CSPRNG, either by [`init_rng()`] or by a reseed.
+pub fn last_entropy_health() -> EntropyHealth {
+    interrupt::free(|c| LAST_ENTROPY_HEALTH.borrow(c).get())
+}
+
+/// Returns the number of bytes the main CSPRNG has generated through [`fill_rand_slice()`] since it
+/// was last seeded successfully. A failed reseed doesn't reset this, so it keeps growing while
+/// reseeds keep failing.
+pub fn bytes_since_reseed() -> usize {
+    MAIN_CSPRNG_BYTES_SINCE_RESEED.load(Ordering::Relaxed)
+}
+
+/// Returns diagnostic estimates of how much entropy each source gathered when the main CSPRNG was
+/// initialized, such as for a factory test to catch a floating ADC. Every source is zero if the main
+/// CSPRNG hasn't been initialized yet. Reseeds don't change the report.
+///
+/// These are heuristic estimates computed from simple statistics of the raw samples, not formal
+/// min-entropy bounds. They're never used for seeding, so they can't weaken it. See [`EntropyReport`]
+/// for more details.
+pub fn entropy_report() -> EntropyReport {
+    interrupt::free(|c| ENTROPY_REPORT.borrow(c).get())
+}
+
+/// Fills a slice with random bytes from the main CSPRNG without counting them towards the reseed
+/// threshold.
+fn fill_rand_slice_uncounted(dest: &mut [u8]) {
+    interrupt::free(|c| {
+        MAIN_CSPRNG
+            .get()
+            .expect("The main CSPRNG has not been initialized yet. Initialize it first with init_rng().")
+            .borrow(c)
+            .borrow_mut()
+            .fill_bytes(dest);
     });
 }
 
 /// Fills a slice with random bytes from the main CSPRNG.
 ///
+/// Once the main CSPRNG has generated at least the reseed threshold's worth of bytes since it was
+/// last seeded, or since the last failed attempt to reseed it, this function calls [`reseed_main()`]
+/// before returning. That call blocks for around a second while gathering entropy, so any call to
+/// this function may occasionally take that long, including one made from an interrupt handler, which
+/// then holds off every interrupt of the same or lower priority for that second. The bytes written to
+/// `dest` are generated before the reseed. The threshold defaults to [`DEFAULT_RESEED_THRESHOLD`] and
+/// can be changed with [`set_reseed_threshold()`].
+///
 /// # Panics
 ///
 /// Panics if the main CSPRNG has not been initialized yet.
 pub(crate) fn fill_rand_slice(dest: &mut [u8]) {
+    fill_rand_slice_uncounted(dest);
+
+    let needs_reseed = interrupt::free(|_| {
+        let since_reseed = MAIN_CSPRNG_BYTES_SINCE_RESEED
+            .load(Ordering::Relaxed)
+            .saturating_add(dest.len());
+        MAIN_CSPRNG_BYTES_SINCE_RESEED.store(since_reseed, Ordering::Relaxed);
+
+        // Counting from the last attempt keeps a failing reseed from being retried on every call.
+        let generated = MAIN_CSPRNG_BYTES_SINCE_RESEED_ATTEMPT
+            .load(Ordering::Relaxed)
+            .saturating_add(dest.len());
+        MAIN_CSPRNG_BYTES_SINCE_RESEED_ATTEMPT.store(generated, Ordering::Relaxed);
+
+        let threshold = MAIN_CSPRNG_RESEED_THRESHOLD.load(Ordering::Relaxed);
+        threshold != 0 && generated >= threshold
+    });
+
+    if needs_reseed {
+        // A failed reseed leaves the main CSPRNG in its current, still secure, state. The failure is
+        // recorded for last_entropy_health().
+        if let Err(health) = reseed_main() {
+            crate::warn!("Automatic reseed of the main CSPRNG failed: {health:?}");
+        }
+    }
+}
+
+/// Reseeds the main CSPRNG with fresh entropy from the ADC and clock drift sources, hashed together
+/// with output from the current main CSPRNG so that a reseed can never lose entropy. Resets the count
+/// of bytes generated since the last reseed, as reported by [`bytes_since_reseed()`], if the reseed
+/// succeeds.
+///
+/// This blocks for around a second while gathering entropy. Interrupts stay enabled while entropy is
+/// gathered, and the main CSPRNG can still be used by interrupt handlers until the new seed is swapped
+/// in. ADC0 is used to gather entropy, so if this interrupts an [`AdcReader`](crate::adc::AdcReader)
+/// reading, no entropy is gathered and the reseed fails.
+///
+/// The fresh samples are health tested before they're used. The result can be retrieved with
+/// [`last_entropy_health()`].
+///
+/// # ERRORS:
+///
+/// - The [`EntropyHealth`] failure - Occurs if the fresh samples fail their health tests. The main
+///   CSPRNG keeps its current state and its count of bytes generated in this case. An automatic
+///   reseed is only retried once another threshold's worth of bytes has been generated, so that it
+///   isn't retried on every call.
+/// - [`EntropyHealth::SourceUnavailable`] - Occurs if ADC0 is in use. The main CSPRNG keeps its
+///   current state in the same way as for a health test failure.
+///
+/// # Panics
+///
+/// Panics if the main CSPRNG has not been initialized yet.
+pub(crate) fn reseed_main() -> Result<(), EntropyHealth> {
+    let mut carry = [0; RESEED_CARRY_SIZE];
+    fill_rand_slice_uncounted(&mut carry);
+
+    let seed = entropy::hash_fresh_entropy(&carry);
+    carry.zeroize();
+
+    let result = match seed {
+        Ok(mut seed) => {
+            interrupt::free(|c| {
+                MAIN_CSPRNG
+                    .get()
+                    .expect("The main CSPRNG has not been initialized yet. Initialize it first with init_rng().")
+                    .borrow(c)
+                    .replace(ChaCha20Rng::from_seed(seed));
+            });
+            seed.zeroize();
+            set_entropy_health(EntropyHealth::Healthy);
+            MAIN_CSPRNG_BYTES_SINCE_RESEED.store(0, Ordering::Relaxed);
+
+            Ok(())
+        }
+        Err(health) => {
+            set_entropy_health(health);
+
+            Err(health)
+        }
+    };
+
+    MAIN_CSPRNG_BYTES_SINCE_RESEED_ATTEMPT.store(0, Ordering::Relaxed);
+
+    result
+}
+
+/// Reseeds the secondary CSPRNG with 32 bytes from the main CSPRNG, so later uses of the secondary
+/// CSPRNG no longer depend only on the seed that's the same across reboots. The new seed only lasts
+/// until the next reboot, where the secondary CSPRNG is seeded from the same secret again.
+///
+/// The main CSPRNG must be initialized before this is called, which it always is once the runtime
+/// has been created. The bytes drawn count towards the reseed threshold of the main CSPRNG, so this
+/// may block for around a second like [`fill_rand_slice()`].
+///
+/// # Panics
+///
+/// Panics if the main CSPRNG or the secondary CSPRNG has not been initialized yet.
+pub(crate) fn reseed_secondary_from_main() {
+    let mut seed = [0; 32];
+    fill_rand_slice(&mut seed);
+
     interrupt::free(|c| {
-        MAIN_CSPRNG
+        SECONDARY_CSPRNG
             .get()
-            .expect("The main CSPRNG has not been initialized yet. Initialize it first with init_rng().")
+            .expect("The secondary CSPRNG has not been initialized yet. Initialize it first with init_rng().")
             .borrow(c)
-            .borrow_mut()
-            .fill_bytes(dest);
+            .replace(ChaCha20Rng::from_seed(seed));
     });
+
+    seed.zeroize();
+}
+
+/// Sets the number of bytes the main CSPRNG generates through [`fill_rand_slice()`] before it's
+/// automatically reseeded. A threshold of 0 disables automatic reseeding.
+pub(crate) fn set_reseed_threshold(bytes: usize) {
+    MAIN_CSPRNG_RESEED_THRESHOLD.store(bytes, Ordering::Relaxed);
+}
+
+/// Returns an array of random bytes from the main CSPRNG. This is the same as filling an array with
+/// [`fill_rand_slice()`], but the size comes from the type, so it can't be mismatched.
+///
+/// # Panics
+///
+/// Panics if the main CSPRNG has not been initialized yet.
+pub(crate) fn fill_rand_array<const N: usize>() -> [u8; N] {
+    let mut array = [0; N];
+    fill_rand_slice(&mut array);
+
+    array
+}
+
+/// Returns a random key for XChaCha20-Poly1305, such as for an
+/// [`XChacha20Poly1305TxChannel`](crate::communication::lower_layers::crypto::XChacha20Poly1305TxChannel).
+/// The key is returned by value, so it should be zeroized once it's no longer needed.
+///
+/// `rng` must implement [`CryptoRng`], so a [`MainRng`] can be passed in, but a [`SecondaryRng`]
+/// can't:
+///
+/// ```ignore
+/// let key = random_key(&mut main_rng());
+/// ```
+pub fn random_key<R: RngCore + CryptoRng>(rng: &mut R) -> Key {
+    let mut key = Key::default();
+    rng.fill_bytes(&mut key);
+
+    key
 }
 
 /// Fills a slice with random bytes from the secondary CSPRNG.
@@ -80,3 +386,196 @@ pub(crate) fn fill_rand_slice_secondary(dest: &mut [u8]) {
             .fill_bytes(dest);
     });
 }
+
+/// Fails to compile if an array of `N` bytes is large enough to be a key. See
+/// [`fill_rand_array_secondary()`] for more details.
+struct SecondaryArraySize<const N: usize>;
+
+impl<const N: usize> SecondaryArraySize<N> {
+    /// Fails to compile if `N` is at least [`MIN_KEY_SIZE`].
+    const NOT_KEY_SIZED: () = assert!(
+        N < MIN_KEY_SIZE,
+        "Arrays large enough to be a key must come from the main CSPRNG."
+    );
+}
+
+/// Returns an array of random bytes from the secondary CSPRNG. This is the same as filling an array
+/// with [`fill_rand_slice_secondary()`], but the size comes from the type, so it can't be mismatched.
+///
+/// To keep keys from being drawn from the secondary CSPRNG by mistake, this fails to compile if the
+/// array is [`MIN_KEY_SIZE`] bytes or larger. Use [`fill_rand_array()`] for those instead.
+///
+/// # Danger
+///
+/// The seed for this CSPRNG is the same across reboots.
+///
+/// # Panics
+///
+/// Panics if the secondary CSPRNG has not been initialized yet.
+pub(crate) fn fill_rand_array_secondary<const N: usize>() -> [u8; N] {
+    #[allow(clippy::let_unit_value)]
+    let _ = SecondaryArraySize::<N>::NOT_KEY_SIZED;
+
+    let mut array = [0; N];
+    fill_rand_slice_secondary(&mut array);
+
+    array
+}
+
+/// Generates a uniformly distributed random number in `[low, high)` using random bytes from
+/// `fill_fn`. Uses rejection sampling so that there is no modulo bias.
+///
+/// # Panics
+///
+/// Panics if `low >= high`.
+fn gen_range_u32_with(low: u32, high: u32, mut fill_fn: impl FnMut(&mut [u8])) -> u32 {
+    assert!(low < high, "Empty range passed to gen_range_u32().");
+
+    let range = high - low;
+
+    // The largest multiple of range that fits in a u32, minus 1. Values above this are rejected, since
+    // there's not enough of them to cover every value in the range evenly.
+    let zone = u32::MAX - (u32::MAX - range + 1) % range;
+
+    loop {
+        let mut bytes = [0; 4];
+        fill_fn(&mut bytes);
+        let value = u32::from_le_bytes(bytes);
+
+        if value <= zone {
+            return low + value % range;
+        }
+    }
+}
+
+/// Generates a uniformly distributed random number in `[low, high)` from the main CSPRNG.
+///
+/// # Panics
+///
+/// Panics if `low >= high` or if the main CSPRNG has not been initialized yet.
+pub(crate) fn gen_range_u32(low: u32, high: u32) -> u32 {
+    gen_range_u32_with(low, high, fill_rand_slice)
+}
+
+/// Generates a uniformly distributed random number in `[low, high)` from the secondary CSPRNG.
+///
+/// # Danger
+///
+/// The seed for this CSPRNG is the same across reboots.
+///
+/// # Panics
+///
+/// Panics if `low >= high` or if the secondary CSPRNG has not been initialized yet.
+pub(crate) fn gen_range_u32_secondary(low: u32, high: u32) -> u32 {
+    gen_range_u32_with(low, high, fill_rand_slice_secondary)
+}
+
+/// A handle to the main CSPRNG that implements [`RngCore`] and [`CryptoRng`], so it can be passed into
+/// generic `rand` APIs and [`random_key()`]. It also implements [`RandomSource`], so it can generate the
+/// nonces of secure channels. Every call enters the interrupt-free critical section around the main CSPRNG,
+/// and bytes generated through this handle count towards the automatic reseed threshold, just like
+/// with [`fill_rand_slice()`]. Create one with [`main_rng()`].
+pub struct MainRng {
+    _private: (),
+}
+
+/// Returns a handle to the main CSPRNG.
+///
+/// # Panics
+///
+/// Panics if the main CSPRNG has not been initialized yet.
+pub fn main_rng() -> MainRng {
+    assert!(
+        MAIN_CSPRNG.get().is_some(),
+        "The main CSPRNG has not been initialized yet. Initialize it first with init_rng()."
+    );
+
+    MainRng { _private: () }
+}
+
+impl RngCore for MainRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_rand_slice(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        fill_rand_slice(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for MainRng {}
+
+impl RandomSource for MainRng {
+    fn fill_rand_slice<T: AsMut<[u8]>>(&mut self, mut slice_ref: T) {
+        fill_rand_slice(slice_ref.as_mut());
+    }
+}
+
+/// A handle to the secondary CSPRNG that implements [`RngCore`], so it can be passed into generic
+/// `rand` APIs. Every call enters the interrupt-free critical section around the secondary CSPRNG.
+/// Create one with [`secondary_rng()`].
+///
+/// # Danger
+///
+/// The seed for this CSPRNG is the same across reboots. For this reason, this handle intentionally
+/// doesn't implement [`CryptoRng`] or [`RandomSource`], so it can't be passed into APIs that need
+/// unpredictable output, such as [`random_key()`] or the nonce generation of secure channels.
+///
+/// To catch keys drawn through this handle by mistake, [`RngCore::fill_bytes()`] panics and
+/// [`RngCore::try_fill_bytes()`] returns an error if the slice is the size of a key in use (16 or 32
+/// bytes). Slices of any other size, including large buffers of test data, are filled as usual.
+pub struct SecondaryRng {
+    _private: (),
+}
+
+/// Returns a handle to the secondary CSPRNG.
+///
+/// # Panics
+///
+/// Panics if the secondary CSPRNG has not been initialized yet.
+pub fn secondary_rng() -> SecondaryRng {
+    assert!(
+        SECONDARY_CSPRNG.get().is_some(),
+        "The secondary CSPRNG has not been initialized yet. Initialize it first with init_rng()."
+    );
+
+    SecondaryRng { _private: () }
+}
+
+impl RngCore for SecondaryRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        assert!(
+            !KEY_SIZES.contains(&dest.len()),
+            "Slices the size of a key must be filled from the main CSPRNG."
+        );
+
+        fill_rand_slice_secondary(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        if KEY_SIZES.contains(&dest.len()) {
+            // CUSTOM_START is nonzero, so this never panics.
+            return Err(NonZeroU32::new(KEY_SIZED_SECONDARY_FILL).unwrap().into());
+        }
+
+        fill_rand_slice_secondary(dest);
+        Ok(())
+    }
+}
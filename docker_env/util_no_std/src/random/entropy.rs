@@ -2,12 +2,22 @@
 
 mod adc;
 mod clock_drift;
+mod health;
+mod report;
 mod secret;
+mod temp_sensor;
+mod uart_noise;
 mod uninit_memory;
 
 pub(crate) use adc::Adc;
 pub(crate) use clock_drift::ClockDrift;
+#[cfg(all(feature = "test-rng", debug_assertions))]
+pub(crate) use health::{test_bits, test_readings};
+pub use health::{EntropyHealth, EntropySourceKind};
+pub use report::{BitEstimate, EntropyReport, ReadingEstimate};
 pub(crate) use secret::Secret;
+pub(crate) use temp_sensor::TempSensor;
+pub(crate) use uart_noise::{sample_rx_line, UartNoise, UartNoiseSamples};
 pub(crate) use uninit_memory::UninitMemory;
 
 use crate::RuntimePeripherals;
@@ -27,12 +37,62 @@ pub(crate) trait EntropySource {
     ///
     /// IMPORTANT NOTE: This function must call the next entropy source's `add_to_hasher()` function.
     fn add_to_hasher(&self, hasher: &mut Sha3_256);
+
+    /// Runs health tests over the raw samples of the entropy source, returning the first failure
+    /// along the list of entropy sources. Sources without raw samples to test pass.
+    ///
+    /// IMPORTANT NOTE: This function must call the next entropy source's `health()` function.
+    fn health(&self) -> EntropyHealth;
+
+    /// Adds diagnostic estimates of the entropy in the raw samples of the entropy source to a report.
+    /// Sources without raw samples to estimate add nothing.
+    ///
+    /// IMPORTANT NOTE: This function must call the next entropy source's `report()` function.
+    fn report(&self, report: &mut EntropyReport);
 }
 
 // We implement this trait for () so that we can use it to end the list of entropy sources.
 impl EntropySource for () {
     fn init(_peripherals: &mut RuntimePeripherals) {}
     fn add_to_hasher(&self, _hasher: &mut Sha3_256) {}
+    fn health(&self) -> EntropyHealth {
+        EntropyHealth::Healthy
+    }
+    fn report(&self, _report: &mut EntropyReport) {}
+}
+
+/// Gathers fresh entropy from the ADC and clock drift sources and hashes it together with `input`.
+/// Unlike [`EntropyHasher`], this doesn't need the [`RuntimePeripherals`], so it can be used after
+/// they have been handed off to the runtime. Blocks for around a second while gathering entropy.
+///
+/// ADC0 is taken for the duration of the sampling, so no entropy is gathered if ADC0 is already in
+/// use.
+///
+/// # ERRORS:
+///
+/// - The [`EntropyHealth`] failure - Occurs if the fresh samples fail their health tests.
+/// - [`EntropyHealth::SourceUnavailable`] - Occurs if ADC0 is already in use.
+pub(crate) fn hash_fresh_entropy(input: &[u8]) -> Result<[u8; ENTROPY_HASH_SIZE], EntropyHealth> {
+    let adc_samples =
+        adc::fresh_samples().ok_or(EntropyHealth::SourceUnavailable(EntropySourceKind::Adc))?;
+    let mut health = health::test_bits(&adc_samples, EntropySourceKind::Adc);
+
+    let clock_drift_samples = clock_drift::fresh_samples();
+    if let Some(samples) = &clock_drift_samples {
+        health = health.and(health::test_bits(samples, EntropySourceKind::ClockDrift));
+    }
+
+    if health.is_failure() {
+        return Err(health);
+    }
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(input);
+    hasher.update(adc_samples);
+    if let Some(samples) = clock_drift_samples {
+        hasher.update(samples);
+    }
+    Ok(hasher.finalize().into())
 }
 
 /// A hasher that concatenates entropy sources together and hashes the result.
@@ -49,10 +109,31 @@ impl<T: EntropySource> EntropyHasher<T> {
         }
     }
 
-    /// Concatenates entropy sources together and hashes the result.
-    pub(crate) fn hash(&self) -> [u8; ENTROPY_HASH_SIZE] {
+    /// Estimates how much entropy each source gathered. This is diagnostic only and doesn't affect
+    /// [`EntropyHasher::hash()`].
+    pub(crate) fn report(&self) -> EntropyReport {
+        let mut report = EntropyReport::new();
+        self.entropy.report(&mut report);
+
+        report
+    }
+
+    /// Runs the health tests of every entropy source, then concatenates the entropy sources together
+    /// and hashes the result.
+    ///
+    /// # ERRORS:
+    ///
+    /// - The [`EntropyHealth`] failure - Occurs if any entropy source fails its health tests. Nothing
+    ///   is hashed in this case.
+    pub(crate) fn hash(&self) -> Result<[u8; ENTROPY_HASH_SIZE], EntropyHealth> {
+        let health = self.entropy.health();
+
+        if health.is_failure() {
+            return Err(health);
+        }
+
         let mut hasher = Sha3_256::new();
         self.entropy.add_to_hasher(&mut hasher);
-        hasher.finalize().into()
+        Ok(hasher.finalize().into())
     }
 }
@@ -1,9 +1,10 @@
-use super::EntropySource;
-use crate::RuntimePeripherals;
+use super::{health, BitEstimate, EntropyHealth, EntropyReport, EntropySource, EntropySourceKind};
+use crate::{adc, RuntimePeripherals};
 use bitvec::prelude::*;
-use cortex_m::prelude::_embedded_hal_blocking_delay_DelayMs;
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::{asm, prelude::_embedded_hal_blocking_delay_DelayMs};
 use sha3::{Digest, Sha3_256};
-use tm4c123x_hal::sysctl::{control_power, reset, Domain, PowerState, RunMode};
+use tm4c123x_hal::tm4c123x::adc0;
 
 /// Time to delay in milliseconds.
 const DELAY: u32 = 1;
@@ -11,6 +12,10 @@ const DELAY: u32 = 1;
 /// Amount to sample in bytes.
 const SAMPLE_SIZE: usize = 50;
 
+/// Number of CPU cycles in a millisecond, used to delay when the [`RuntimePeripherals`] delay isn't
+/// available. This is set from the system clock when the main CSPRNG is seeded.
+static CYCLES_PER_MS: AtomicU32 = AtomicU32::new(80_000);
+
 /// This entropy source gathers entropy from the LSBs of the ADC inputs.
 pub(crate) struct Adc<T: EntropySource> {
     next: T,
@@ -19,54 +24,12 @@ pub(crate) struct Adc<T: EntropySource> {
 
 impl<T: EntropySource> EntropySource for Adc<T> {
     fn init(peripherals: &mut RuntimePeripherals) -> Self {
-        // Turn it on!
-        control_power(
-            &peripherals.power_control,
-            Domain::Adc0,
-            RunMode::Run,
-            PowerState::On,
-        );
-        reset(&peripherals.power_control, Domain::Adc0);
-
-        let adc0 = &peripherals.adc0;
-        // Turn off Sample Sequencer 3 while we configure it.
-        adc0.actss.write(|w| w.asen3().clear_bit());
-        // Set it to be triggered by software, this is technically the default, but why not.
-        adc0.emux.write(|w| w.em3().processor());
-        // We want the temperature sensor output, so we're not using any of the GPIO inputs.
-        adc0.ssmux3.reset();
-        // Take samples from the temperature sensor and enable interrupts.
-        // We'll only be using the interrupts as a way to check if the ADC is ready.
-        adc0.ssctl3
-            .write(|w| w.ie0().set_bit().ts0().set_bit().end0().set_bit());
-        // Turn Sample Sequencer 3 back on.
-        adc0.actss.write(|w| w.asen3().set_bit());
-
-        // At 1 ms of delay, we get around 5.301307 bits of entropy per byte if we sample 50 bytes.
-        // We want 256 bits of entropy, so we need `256 bits / (5.301307 bits/byte) ≈ 386.3198264 bits` or 387 bits of raw data.
-        // 397 bits / (8 bits/byte) = 48.375. We round up to 50 bytes.
-        let mut samples = [0u8; SAMPLE_SIZE];
-        for mut bit in samples.as_mut_bits::<Lsb0>() {
-            // Start sampling.
-            adc0.pssi.write(|w| w.ss3().set_bit());
-            // Poll interrupt register to see if ADC is ready.
-            while adc0.ris.read().inr3().bit_is_clear() {}
-            let reading = adc0.ssfifo3.read().data().bits();
-            // We take only the LSB, as the rest doesn't change too much.
-            bit.set((reading & 0x1) == 0x1);
-            // Clear interrupt by writing 1 to it.
-            adc0.isc.write(|w| w.in3().set_bit());
-            peripherals.delay.delay_ms(DELAY);
-        }
+        CYCLES_PER_MS.store(peripherals.clocks.sysclk.0 / 1000, Ordering::Relaxed);
 
-        // Turn it off!
-        reset(&peripherals.power_control, Domain::Adc0);
-        control_power(
-            &peripherals.power_control,
-            Domain::Adc0,
-            RunMode::Run,
-            PowerState::Off,
-        );
+        // Nothing else can be using ADC0 before the runtime is created.
+        let delay = &mut peripherals.delay;
+        let samples =
+            adc::with_adc0(|adc0| sample(adc0, |ms| delay.delay_ms(ms))).expect("ADC0 is in use.");
 
         Adc {
             next: T::init(peripherals),
@@ -78,4 +41,63 @@ impl<T: EntropySource> EntropySource for Adc<T> {
         hasher.update(self.samples);
         self.next.add_to_hasher(hasher);
     }
+
+    fn health(&self) -> EntropyHealth {
+        health::test_bits(&self.samples, EntropySourceKind::Adc).and(self.next.health())
+    }
+
+    fn report(&self, report: &mut EntropyReport) {
+        report.adc = BitEstimate::from_bits(&self.samples);
+        self.next.report(report);
+    }
+}
+
+/// Takes samples from the temperature sensor through ADC0, keeping only the LSB of each reading.
+/// ADC0 must be powered on and reset. `delay_ms` is called between samples to delay by the given number of
+/// milliseconds.
+fn sample(adc0: &adc0::RegisterBlock, mut delay_ms: impl FnMut(u32)) -> [u8; SAMPLE_SIZE] {
+    // Turn off Sample Sequencer 3 while we configure it.
+    adc0.actss.write(|w| w.asen3().clear_bit());
+    // Set it to be triggered by software, this is technically the default, but why not.
+    adc0.emux.write(|w| w.em3().processor());
+    // We want the temperature sensor output, so we're not using any of the GPIO inputs.
+    adc0.ssmux3.reset();
+    // Take samples from the temperature sensor and enable interrupts.
+    // We'll only be using the interrupts as a way to check if the ADC is ready.
+    adc0.ssctl3
+        .write(|w| w.ie0().set_bit().ts0().set_bit().end0().set_bit());
+    // Turn Sample Sequencer 3 back on.
+    adc0.actss.write(|w| w.asen3().set_bit());
+
+    // At 1 ms of delay, we get around 5.301307 bits of entropy per byte if we sample 50 bytes.
+    // We want 256 bits of entropy, so we need `256 bits / (5.301307 bits/byte) ≈ 386.3198264 bits` or 387 bits of raw data.
+    // 397 bits / (8 bits/byte) = 48.375. We round up to 50 bytes.
+    let mut samples = [0u8; SAMPLE_SIZE];
+    for mut bit in samples.as_mut_bits::<Lsb0>() {
+        // Start sampling.
+        adc0.pssi.write(|w| w.ss3().set_bit());
+        // Poll interrupt register to see if ADC is ready.
+        while adc0.ris.read().inr3().bit_is_clear() {}
+        let reading = adc0.ssfifo3.read().data().bits();
+        // We take only the LSB, as the rest doesn't change too much.
+        bit.set((reading & 0x1) == 0x1);
+        // Clear interrupt by writing 1 to it.
+        adc0.isc.write(|w| w.in3().set_bit());
+        delay_ms(DELAY);
+    }
+
+    samples
+}
+
+/// Takes fresh samples from ADC0 after the [`RuntimePeripherals`] have been handed off to the
+/// runtime. Blocks for around 400 ms while sampling.
+///
+/// ADC0 is taken through [`adc::with_adc0()`] for the duration of the sampling and released
+/// afterwards, so this can't corrupt a reading by an [`AdcReader`](crate::adc::AdcReader). If ADC0 is
+/// already in use, such as when this is called from an interrupt handler that interrupted an
+/// [`AdcReader`](crate::adc::AdcReader) reading, no samples are taken and [`None`] is returned.
+pub(super) fn fresh_samples() -> Option<[u8; SAMPLE_SIZE]> {
+    let cycles_per_ms = CYCLES_PER_MS.load(Ordering::Relaxed);
+
+    adc::with_adc0(|adc0| sample(adc0, |ms| asm::delay(ms * cycles_per_ms)))
 }
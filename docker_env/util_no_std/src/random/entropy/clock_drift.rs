@@ -1,8 +1,11 @@
-use super::EntropySource;
-use crate::RuntimePeripherals;
+use super::{health, BitEstimate, EntropyHealth, EntropyReport, EntropySource, EntropySourceKind};
+use crate::{ResetCause, RuntimePeripherals};
 use bitvec::prelude::*;
 use sha3::{digest::Update, Sha3_256};
-use tm4c123x_hal::sysctl::{self, Domain, PowerState, RunMode};
+use tm4c123x_hal::{
+    sysctl::{self, Domain, PowerState, RunMode},
+    tm4c123x::HIB,
+};
 
 /// Number of subseconds to count for clock drift.
 const SUBSECONDS_TO_COUNT: usize = 16;
@@ -28,8 +31,12 @@ impl<T: EntropySource> EntropySource for ClockDrift<T> {
             PowerState::On,
         );
 
-        // Reset hibernation module for good measure.
-        sysctl::reset(&peripherals.power_control, Domain::Hibernation);
+        // Reset hibernation module for good measure, unless it's holding state from before hibernation.
+        let woke_from_hibernation = peripherals.reset_cause == ResetCause::Hibernation;
+
+        if !woke_from_hibernation {
+            sysctl::reset(&peripherals.power_control, Domain::Hibernation);
+        }
 
         // Initialize hibernation clock.
         peripherals.hib.ctl.write(|w| {
@@ -85,8 +92,11 @@ impl<T: EntropySource> EntropySource for ClockDrift<T> {
             bit.set((counter & 1) == 1);
         }
 
-        // Reset hibernation module to disable the hibernation clock.
-        sysctl::reset(&peripherals.power_control, Domain::Hibernation);
+        // Reset hibernation module to disable the hibernation clock. The data registers are kept after
+        // a wake, and the hibernation controller reinitializes the clock either way.
+        if !woke_from_hibernation {
+            sysctl::reset(&peripherals.power_control, Domain::Hibernation);
+        }
 
         ClockDrift {
             next: T::init(peripherals),
@@ -98,4 +108,57 @@ impl<T: EntropySource> EntropySource for ClockDrift<T> {
         hasher.update(&self.entropy_pool);
         self.next.add_to_hasher(hasher);
     }
+
+    fn health(&self) -> EntropyHealth {
+        health::test_bits(&self.entropy_pool, EntropySourceKind::ClockDrift).and(self.next.health())
+    }
+
+    fn report(&self, report: &mut EntropyReport) {
+        report.clock_drift = BitEstimate::from_bits(&self.entropy_pool);
+        self.next.report(report);
+    }
+}
+
+/// The number of subseconds in a second of the RTC. The subsecond counter wraps around to 0 after
+/// reaching this value.
+const SUBSECONDS_PER_SECOND: u16 = 32768;
+
+/// Gathers fresh clock drift entropy after the [`RuntimePeripherals`] have been handed off to the
+/// runtime. Blocks for around half a second while gathering entropy.
+///
+/// Unlike the [`ClockDrift`] entropy source, this doesn't reset the RTC, since the hibernation
+/// controller relies on it. It instead counts between subsecond boundaries of the running RTC. If the
+/// RTC isn't running, no entropy is gathered and [`None`] is returned.
+pub(super) fn fresh_samples() -> Option<[u8; CLOCK_DRIFT_ENTROPY_SIZE]> {
+    // SAFETY: The hibernation module registers are only read here, so this can't interfere with the
+    // hibernation controller.
+    let hib = unsafe { &*HIB::ptr() };
+    let mut entropy_pool: [u8; CLOCK_DRIFT_ENTROPY_SIZE] = [0; CLOCK_DRIFT_ENTROPY_SIZE];
+
+    if hib.ctl.read().rtcen().bit_is_clear() {
+        return None;
+    }
+
+    let subseconds = || hib.rtcss.read().rtcssc().bits();
+
+    for mut bit in entropy_pool.as_mut_bits::<Lsb0>() {
+        // Wait for a subsecond boundary so every count starts at the same point.
+        let prev = subseconds();
+        while subseconds() == prev {}
+        let start = subseconds();
+
+        // Wait for the RTC to advance by SUBSECONDS_TO_COUNT subseconds and count.
+        let mut counter: u32 = 0;
+
+        while usize::from((subseconds() + SUBSECONDS_PER_SECOND - start) % SUBSECONDS_PER_SECOND)
+            < SUBSECONDS_TO_COUNT
+        {
+            counter += 1;
+        }
+
+        // Set bit to 1 if counter LSB is 1.
+        bit.set((counter & 1) == 1);
+    }
+
+    Some(entropy_pool)
 }
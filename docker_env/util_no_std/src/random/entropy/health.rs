@@ -0,0 +1,139 @@
+//! Startup health tests for raw entropy samples, modeled on the continuous health tests in NIST
+//! SP 800-90B section 4.4. These are meant to catch catastrophic failures of an entropy source, such
+//! as an ADC stuck returning a constant, not to estimate how much entropy a source provides.
+//!
+//! The cutoffs are computed for a false positive probability of at most 2^-20 per test of a healthy
+//! source with the assessed min-entropy, using the formulas in SP 800-90B sections 4.4.1 and 4.4.2.
+
+use bitvec::prelude::*;
+
+/// The repetition count test cutoff for binary samples. `1 + ceil(20 / H)` for an assessed
+/// min-entropy of H = 0.5 bits per bit. This is below what the binary sources are measured to
+/// provide, such as the ~0.66 bits per bit of the ADC LSBs, so that healthy hardware never fails.
+const BINARY_REPETITION_CUTOFF: usize = 41;
+
+/// The window size of the adaptive proportion test for binary samples. SP 800-90B uses 1024, but that's
+/// more than the 400 bits of ADC samples gathered at a time, so a smaller window is used to make sure
+/// that every source gets tested.
+const BINARY_PROPORTION_WINDOW: usize = 128;
+
+/// The adaptive proportion test cutoff for binary samples. `1 + CRITBINOM(W, 2^-H, 1 - 2^-20)` for a
+/// window of W = 128 bits and H = 0.5, which is the smallest count that a healthy source reaches with
+/// a probability of at most 2^-20: P(X >= 114) ~= 2^-20.7 for X ~ Binomial(128, 2^-0.5).
+const BINARY_PROPORTION_CUTOFF: usize = 114;
+
+/// The repetition count test cutoff for multi-bit readings, assuming 1 bit of min-entropy per
+/// reading. `1 + ceil(20 / H)`.
+const READING_REPETITION_CUTOFF: usize = 21;
+
+/// An entropy source that is health tested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntropySourceKind {
+    /// The LSBs of temperature sensor readings from ADC0.
+    Adc,
+    /// The full temperature sensor readings from ADC1.
+    TempSensor,
+    /// The drift between the hibernation clock and the system clock.
+    ClockDrift,
+}
+
+/// The result of the health tests run on raw entropy samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntropyHealth {
+    /// No health tests have been run yet.
+    NotTested,
+    /// Every tested source passed all of its health tests.
+    Healthy,
+    /// A source produced too many identical samples in a row.
+    RepetitionCountFailure(EntropySourceKind),
+    /// A source produced one sample value too often within a window of samples.
+    AdaptiveProportionFailure(EntropySourceKind),
+    /// A source couldn't be sampled, such as when ADC0 was already in use.
+    SourceUnavailable(EntropySourceKind),
+}
+
+impl EntropyHealth {
+    /// Returns whether this is a health test failure.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            EntropyHealth::RepetitionCountFailure(_)
+                | EntropyHealth::AdaptiveProportionFailure(_)
+                | EntropyHealth::SourceUnavailable(_)
+        )
+    }
+
+    /// Returns `other` if `self` passed, otherwise returns `self`. Used to report the first failure
+    /// along the chain of entropy sources.
+    pub(crate) fn and(self, other: EntropyHealth) -> EntropyHealth {
+        if self.is_failure() {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+/// Runs the repetition count test over a sequence of samples, returning whether any sample repeats
+/// `cutoff` or more times in a row.
+fn repetition_count_fails<T: PartialEq>(samples: impl Iterator<Item = T>, cutoff: usize) -> bool {
+    let mut last = None;
+    let mut count = 0;
+
+    for sample in samples {
+        if last.as_ref() == Some(&sample) {
+            count += 1;
+
+            if count >= cutoff {
+                return true;
+            }
+        } else {
+            last = Some(sample);
+            count = 1;
+        }
+    }
+
+    false
+}
+
+/// Runs the adaptive proportion test over a slice of bits, returning whether the first bit of any
+/// window appears too often in that window. A trailing partial window isn't tested, like in
+/// SP 800-90B, since the cutoff only holds for a full window.
+fn adaptive_proportion_fails(bits: &BitSlice<u8>) -> bool {
+    bits.chunks_exact(BINARY_PROPORTION_WINDOW).any(|window| {
+        let reference = window[0];
+        let count = if reference {
+            window.count_ones()
+        } else {
+            window.count_zeros()
+        };
+
+        count >= BINARY_PROPORTION_CUTOFF
+    })
+}
+
+/// Health tests raw binary samples, where each bit is one sample.
+pub(crate) fn test_bits(samples: &[u8], source: EntropySourceKind) -> EntropyHealth {
+    let bits = samples.view_bits::<Lsb0>();
+
+    if repetition_count_fails(bits.iter().by_vals(), BINARY_REPETITION_CUTOFF) {
+        EntropyHealth::RepetitionCountFailure(source)
+    } else if adaptive_proportion_fails(bits) {
+        EntropyHealth::AdaptiveProportionFailure(source)
+    } else {
+        EntropyHealth::Healthy
+    }
+}
+
+/// Health tests multi-bit readings. Only the repetition count test is run, since the readings
+/// aren't expected to be uniformly distributed.
+pub(crate) fn test_readings<T: PartialEq>(
+    readings: impl Iterator<Item = T>,
+    source: EntropySourceKind,
+) -> EntropyHealth {
+    if repetition_count_fails(readings, READING_REPETITION_CUTOFF) {
+        EntropyHealth::RepetitionCountFailure(source)
+    } else {
+        EntropyHealth::Healthy
+    }
+}
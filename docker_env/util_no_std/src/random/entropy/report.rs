@@ -0,0 +1,159 @@
+//! Diagnostic estimates of how much entropy each source contributed to the seed of the main CSPRNG,
+//! meant to catch a broken source at provisioning time, such as a floating ADC.
+//!
+//! Every estimate here is a heuristic computed from simple statistics of the raw samples, not a formal
+//! min-entropy bound like those from NIST SP 800-90B. A source can look healthy here and still be
+//! predictable. The estimates are never used for seeding, which always hashes every raw sample.
+
+use bitvec::prelude::*;
+
+/// Statistics of a source whose raw samples are individual bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BitEstimate {
+    /// The number of raw bits gathered.
+    pub bits: u32,
+    /// The number of raw bits that were set.
+    pub ones: u32,
+    /// The number of raw bits that differ from the bit before them.
+    pub transitions: u32,
+    /// A heuristic estimate of the entropy in bits. This is twice the count of the less common bit value
+    /// or twice the count of the less common of transitions and repeats, whichever is lower. Bits from
+    /// an ideal source give around one bit of entropy each, while a stuck or alternating source gives
+    /// around zero.
+    pub estimated_entropy: u32,
+}
+
+impl BitEstimate {
+    /// Creates an estimate for a source that hasn't been sampled.
+    const fn new() -> Self {
+        Self {
+            bits: 0,
+            ones: 0,
+            transitions: 0,
+            estimated_entropy: 0,
+        }
+    }
+
+    /// Estimates the entropy of raw binary samples, where each bit is one sample.
+    pub(super) fn from_bits(samples: &[u8]) -> Self {
+        let bits = samples.view_bits::<Lsb0>();
+        let len = bits.len() as u32;
+        let ones = bits.count_ones() as u32;
+        let transitions = bits.windows(2).filter(|pair| pair[0] != pair[1]).count() as u32;
+        let repeats = len.saturating_sub(1) - transitions;
+
+        Self {
+            bits: len,
+            ones,
+            transitions,
+            estimated_entropy: (2 * ones.min(len - ones)).min(2 * transitions.min(repeats)),
+        }
+    }
+}
+
+/// Statistics of a source whose raw samples are multi-bit readings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadingEstimate {
+    /// The number of readings taken.
+    pub readings: u32,
+    /// The variance of the readings, in squared units of the reading, rounded down.
+    pub variance: u32,
+    /// The number of readings that differ from the reading before them.
+    pub changes: u32,
+    /// A heuristic estimate of the entropy in bits. Each reading that differs from the reading before it
+    /// is credited with one bit, so a stuck source gives zero.
+    pub estimated_entropy: u32,
+}
+
+impl ReadingEstimate {
+    /// Creates an estimate for a source that hasn't been sampled.
+    const fn new() -> Self {
+        Self {
+            readings: 0,
+            variance: 0,
+            changes: 0,
+            estimated_entropy: 0,
+        }
+    }
+
+    /// Estimates the entropy of multi-bit readings.
+    pub(super) fn from_readings(readings: impl Iterator<Item = u16> + Clone) -> Self {
+        let mut count: u64 = 0;
+        let mut sum: u64 = 0;
+        let mut sum_sq: u64 = 0;
+
+        for reading in readings.clone() {
+            count += 1;
+            sum += u64::from(reading);
+            sum_sq += u64::from(reading) * u64::from(reading);
+        }
+
+        let variance = if count == 0 {
+            0
+        } else {
+            (count * sum_sq - sum * sum) / (count * count)
+        };
+
+        let changes = readings
+            .clone()
+            .zip(readings.skip(1))
+            .filter(|(prev, next)| prev != next)
+            .count() as u32;
+
+        Self {
+            readings: count as u32,
+            variance: variance as u32,
+            changes,
+            estimated_entropy: changes,
+        }
+    }
+}
+
+/// Estimates of how much entropy each source contributed to the seed of the main CSPRNG when it was
+/// initialized. See the module-level documentation for the caveats of these estimates. Retrieve the
+/// report with [`entropy_report()`](crate::entropy_report).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntropyReport {
+    /// The LSBs of temperature sensor readings from ADC0.
+    pub adc: BitEstimate,
+    /// The drift between the hibernation clock and the system clock.
+    pub clock_drift: BitEstimate,
+    /// The uninitialized memory copied at startup.
+    pub uninit_memory: BitEstimate,
+    /// The full temperature sensor readings from ADC1.
+    pub temp_sensor: ReadingEstimate,
+    /// The noise on the UART1 RX line while it was idle. This is left empty if the line read a constant.
+    pub uart_noise: BitEstimate,
+}
+
+impl EntropyReport {
+    /// Creates a report with no sources sampled.
+    pub(crate) const fn new() -> Self {
+        Self {
+            adc: BitEstimate::new(),
+            clock_drift: BitEstimate::new(),
+            uninit_memory: BitEstimate::new(),
+            temp_sensor: ReadingEstimate::new(),
+            uart_noise: BitEstimate::new(),
+        }
+    }
+
+    /// Gets the sum of the estimated entropy of every source in bits.
+    pub fn total_estimated_entropy(&self) -> u32 {
+        self.adc.estimated_entropy
+            + self.clock_drift.estimated_entropy
+            + self.uninit_memory.estimated_entropy
+            + self.temp_sensor.estimated_entropy
+            + self.uart_noise.estimated_entropy
+    }
+
+    /// Returns whether every source has a nonzero entropy estimate. This is a pass/fail signal for
+    /// factory tests. A failure means a source is stuck, alternating, or wasn't sampled. The UART1 RX line
+    /// noise isn't checked, since it's only available while nothing is plugged into UART1.
+    pub fn every_source_contributed(&self) -> bool {
+        self.adc.estimated_entropy != 0
+            && self.clock_drift.estimated_entropy != 0
+            && self.uninit_memory.estimated_entropy != 0
+            && self.temp_sensor.estimated_entropy != 0
+    }
+}
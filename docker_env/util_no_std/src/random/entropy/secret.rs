@@ -1,4 +1,4 @@
-use super::EntropySource;
+use super::{EntropyHealth, EntropyReport, EntropySource};
 use crate::{eeprom::EepromController, RuntimePeripherals};
 use sha3::{digest::Update, Sha3_256};
 use ucsc_ectf_eeprom_layout::{EepromReadOnlyField, SECRET_SIZE};
@@ -34,6 +34,14 @@ impl<T: EntropySource> EntropySource for Secret<T> {
         hasher.update(&self.secret);
         self.next.add_to_hasher(hasher);
     }
+
+    fn health(&self) -> EntropyHealth {
+        self.next.health()
+    }
+
+    fn report(&self, report: &mut EntropyReport) {
+        self.next.report(report);
+    }
 }
 
 impl<T: EntropySource> Drop for Secret<T> {
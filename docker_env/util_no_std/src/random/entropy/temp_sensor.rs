@@ -0,0 +1,110 @@
+use super::{
+    health, EntropyHealth, EntropyReport, EntropySource, EntropySourceKind, ReadingEstimate,
+};
+use crate::RuntimePeripherals;
+use cortex_m::prelude::_embedded_hal_blocking_delay_DelayUs;
+use sha3::{Digest, Sha3_256};
+use tm4c123x_hal::sysctl::{control_power, reset, Domain, PowerState, RunMode};
+
+/// Time to delay between readings in microseconds.
+const DELAY_US: u32 = 100;
+
+/// Number of temperature readings to take.
+const READING_COUNT: usize = 64;
+
+/// Size of each reading in bytes. Readings are 12 bits wide.
+const READING_SIZE: usize = 2;
+
+/// This entropy source gathers entropy from full readings of the internal temperature sensor.
+///
+/// Unlike [`Adc`](super::Adc), which keeps only the LSB of each reading from ADC0, this source keeps
+/// every bit of each reading and takes its readings through ADC1 with a much shorter delay. This
+/// makes it sensitive to the thermal noise of the sensor rather than to the drift between readings.
+/// We conservatively credit it with 1 bit of min-entropy per reading, or around 64 bits in total, so
+/// it strengthens the other sources rather than replacing any of them.
+///
+/// ADC1 is only powered on while this source is initializing and is reset and powered off
+/// afterwards, so it doesn't conflict with later uses of ADC1. It also never overlaps with the
+/// [`Adc`](super::Adc) source, since entropy sources are initialized one after another.
+pub(crate) struct TempSensor<T: EntropySource> {
+    next: T,
+    readings: [u8; READING_COUNT * READING_SIZE],
+}
+
+impl<T: EntropySource> EntropySource for TempSensor<T> {
+    fn init(peripherals: &mut RuntimePeripherals) -> Self {
+        // Turn it on!
+        control_power(
+            &peripherals.power_control,
+            Domain::Adc1,
+            RunMode::Run,
+            PowerState::On,
+        );
+        reset(&peripherals.power_control, Domain::Adc1);
+
+        let adc1 = &peripherals.adc1;
+        // Turn off Sample Sequencer 3 while we configure it.
+        adc1.actss.write(|w| w.asen3().clear_bit());
+        // Set it to be triggered by software.
+        adc1.emux.write(|w| w.em3().processor());
+        // We want the temperature sensor output, so we're not using any of the GPIO inputs.
+        adc1.ssmux3.reset();
+        // Take samples from the temperature sensor and enable interrupts.
+        // We'll only be using the interrupts as a way to check if the ADC is ready.
+        adc1.ssctl3
+            .write(|w| w.ie0().set_bit().ts0().set_bit().end0().set_bit());
+        // Turn Sample Sequencer 3 back on.
+        adc1.actss.write(|w| w.asen3().set_bit());
+
+        let mut readings = [0u8; READING_COUNT * READING_SIZE];
+        for reading_bytes in readings.chunks_exact_mut(READING_SIZE) {
+            // Start sampling.
+            adc1.pssi.write(|w| w.ss3().set_bit());
+            // Poll interrupt register to see if ADC is ready.
+            while adc1.ris.read().inr3().bit_is_clear() {}
+            let reading = adc1.ssfifo3.read().data().bits();
+            reading_bytes.copy_from_slice(&reading.to_le_bytes());
+            // Clear interrupt by writing 1 to it.
+            adc1.isc.write(|w| w.in3().set_bit());
+            peripherals.delay.delay_us(DELAY_US);
+        }
+
+        // Turn it off!
+        reset(&peripherals.power_control, Domain::Adc1);
+        control_power(
+            &peripherals.power_control,
+            Domain::Adc1,
+            RunMode::Run,
+            PowerState::Off,
+        );
+
+        TempSensor {
+            next: T::init(peripherals),
+            readings,
+        }
+    }
+
+    fn add_to_hasher(&self, hasher: &mut Sha3_256) {
+        hasher.update(self.readings);
+        self.next.add_to_hasher(hasher);
+    }
+
+    fn health(&self) -> EntropyHealth {
+        health::test_readings(self.readings(), EntropySourceKind::TempSensor)
+            .and(self.next.health())
+    }
+
+    fn report(&self, report: &mut EntropyReport) {
+        report.temp_sensor = ReadingEstimate::from_readings(self.readings());
+        self.next.report(report);
+    }
+}
+
+impl<T: EntropySource> TempSensor<T> {
+    /// Gets an iterator over the readings taken.
+    fn readings(&self) -> impl Iterator<Item = u16> + Clone + '_ {
+        self.readings
+            .chunks_exact(READING_SIZE)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+}
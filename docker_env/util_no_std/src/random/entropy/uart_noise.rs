@@ -0,0 +1,105 @@
+use super::{BitEstimate, EntropyHealth, EntropyReport, EntropySource};
+use crate::RuntimePeripherals;
+use bitvec::prelude::*;
+use cortex_m::asm;
+use embedded_hal::digital::v2::InputPin;
+use sha3::{Digest, Sha3_256};
+
+/// Number of bytes to gather from the UART1 RX line. Each read of the line yields 1 bit.
+pub(crate) const UART_NOISE_ENTROPY_SIZE: usize = 64;
+
+/// Number of reads of the UART1 RX line that are folded into each bit.
+const READS_PER_BIT: u32 = 7;
+
+/// Number of CPU cycles to delay between reads of the UART1 RX line.
+const READ_DELAY_CYCLES: u32 = 13;
+
+/// The samples taken from the UART1 RX line, or [`None`] if the line read a constant.
+pub(crate) type UartNoiseSamples = Option<[u8; UART_NOISE_ENTROPY_SIZE]>;
+
+/// Samples the UART1 RX line while it's idle, before UART1 is configured. Each bit is the XOR of
+/// [`READS_PER_BIT`] fast reads of the line, spaced apart by an odd number of cycles so the reads
+/// don't line up with any periodic interference. This must be called with the RX pin configured as a
+/// floating input, so that the pin isn't held at a level by its own pull resistor.
+///
+/// Returns [`None`] if every read gave the same level, which means the line is being driven, such as
+/// by the idle TX line of the other board. Nothing can be gathered from the line in that case.
+pub(crate) fn sample_rx_line<P: InputPin>(pin: &P) -> UartNoiseSamples {
+    let mut samples = [0; UART_NOISE_ENTROPY_SIZE];
+    let mut first_level = None;
+    let mut constant = true;
+
+    for mut bit in samples.as_mut_bits::<Lsb0>() {
+        let mut folded = false;
+
+        for _ in 0..READS_PER_BIT {
+            // A pin read that fails reads as low, which is caught below if every read failed.
+            let level = pin.is_high().unwrap_or(false);
+
+            match first_level {
+                None => first_level = Some(level),
+                Some(first) => constant &= first == level,
+            }
+
+            folded ^= level;
+            asm::delay(READ_DELAY_CYCLES);
+        }
+
+        bit.set(folded);
+    }
+
+    if constant {
+        None
+    } else {
+        Some(samples)
+    }
+}
+
+/// This entropy source gathers entropy from electrical noise on the UART1 RX line.
+///
+/// This assumes the line is floating when the board boots, such as when nothing is plugged into the
+/// UART1 port. A floating input picks up noise from its surroundings, which is sampled through GPIO
+/// reads in [`RuntimePeripherals::with_config()`] before UART1 is configured. Once another board is
+/// plugged in, its idle TX line holds the RX line high and the line reads a constant. In that case, the
+/// source is skipped. It adds nothing to the hash and nothing to the [`EntropyReport`], and it never
+/// fails the health tests.
+///
+/// A floating line can still be heavily biased or slow to change, so this source isn't health tested
+/// and is never relied on. It only strengthens the other sources when it happens to be available.
+/// Because of this, [`EntropyReport::every_source_contributed()`] doesn't check it.
+///
+/// The samples are taken out of the [`RuntimePeripherals`] when this source is initialized, so they're
+/// only used once.
+pub(crate) struct UartNoise<T: EntropySource> {
+    next: T,
+    samples: UartNoiseSamples,
+}
+
+impl<T: EntropySource> EntropySource for UartNoise<T> {
+    fn init(peripherals: &mut RuntimePeripherals) -> Self {
+        UartNoise {
+            samples: peripherals.uart1_rx_noise.take(),
+            next: T::init(peripherals),
+        }
+    }
+
+    fn add_to_hasher(&self, hasher: &mut Sha3_256) {
+        if let Some(samples) = &self.samples {
+            hasher.update(samples);
+        }
+
+        self.next.add_to_hasher(hasher);
+    }
+
+    fn health(&self) -> EntropyHealth {
+        self.next.health()
+    }
+
+    fn report(&self, report: &mut EntropyReport) {
+        if let Some(samples) = &self.samples {
+            report.uart_noise = BitEstimate::from_bits(samples);
+        }
+
+        self.next.report(report);
+    }
+}
@@ -6,7 +6,7 @@ use rand_chacha::{
     ChaCha20Rng,
 };
 
-use super::EntropySource;
+use super::{BitEstimate, EntropyHealth, EntropyReport, EntropySource};
 use sha3::{Digest, Sha3_256};
 
 /// Gets the size of the uninitialized memory buffer from the rand_uninit_memory library header file.
@@ -160,4 +160,15 @@ impl<T: EntropySource> EntropySource for UninitMemory<T> {
         hasher.update(unsafe { &random_bytes });
         self.next.add_to_hasher(hasher);
     }
+
+    fn health(&self) -> EntropyHealth {
+        self.next.health()
+    }
+
+    fn report(&self, report: &mut EntropyReport) {
+        // SAFETY: This read from random_bytes is safe because UninitMemory is neither Send nor Sync,
+        // and therefore, it can only be accessed on the thread it was initialized on.
+        report.uninit_memory = BitEstimate::from_bits(unsafe { &random_bytes });
+        self.next.report(report);
+    }
 }
@@ -0,0 +1,177 @@
+//! This module contains a rate limiter for attempts that can be brute-forced, such as pairing PIN
+//! attempts.
+//!
+//! A [`RateLimiter`] counts the failures since the last success in a
+//! [`WearLeveledCounter`], so the count survives power cycles and every failure is a single EEPROM
+//! word write. After `f` failures, the next attempt is delayed by `base_delay * 2^(f - 1)`, capped at
+//! `max_delay`, and once `f` reaches `lockout_threshold`, every attempt is refused until the counter
+//! is cleared. See [`RateLimiterConfig`] for the defaults.
+//!
+//! Delays are timed with the HIB RTC, which has an accuracy of 1/32768 seconds but restarts from zero
+//! whenever the runtime is initialized. A deadline from before a power cycle would be meaningless
+//! after it, so only the failure count is stored in the EEPROM. When a [`RateLimiter`] is loaded, the
+//! delay for the stored count starts over from the current time, so power cycling can only lengthen a
+//! delay, never cut it short.
+
+use crate::{
+    eeprom::{EepromController, EepromError, WearLeveledCounter},
+    hib::HibController,
+};
+use core::time::Duration;
+
+/// The configuration of a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimiterConfig {
+    /// The delay after the first failure. Each failure after that doubles the delay.
+    pub base_delay: Duration,
+
+    /// The longest delay between attempts, before the hard lockout.
+    pub max_delay: Duration,
+
+    /// The number of failures since the last success at which every attempt is refused.
+    pub lockout_threshold: u32,
+}
+
+impl Default for RateLimiterConfig {
+    /// A delay of 1 second after the first failure, doubling up to 10 minutes, with a hard lockout
+    /// after 32 failures. Reaching the lockout takes over 3 hours of attempts.
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(600),
+            lockout_threshold: 32,
+        }
+    }
+}
+
+impl RateLimiterConfig {
+    /// Gets the delay after the given number of failures. This is zero with no failures.
+    fn delay(&self, failures: u32) -> Duration {
+        let Some(doublings) = failures.checked_sub(1) else {
+            return Duration::ZERO;
+        };
+
+        2u32.checked_pow(doublings)
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+/// An error for when an attempt is refused by a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockedOut {
+    /// The delay after the last failure hasn't passed yet. Contains the time left.
+    Delayed(Duration),
+
+    /// There have been too many failures, so attempts are refused until the counter's words are
+    /// cleared, such as with [`WearLeveledCounter::clear()`] when the device is provisioned again.
+    Permanently,
+}
+
+/// A rate limiter that delays attempts after each failure and refuses them entirely after too many.
+/// See the module-level documentation for its timing and persistence.
+///
+/// The counter takes up `N` EEPROM words, starting at the word offset it's loaded from. A failure must
+/// be recorded before anything that depends on the attempt is done, such as replying to it, so
+/// cutting power as soon as an attempt is found to be wrong can't skip recording it.
+pub struct RateLimiter<const N: usize> {
+    counter: WearLeveledCounter<N>,
+    hib: HibController,
+    config: RateLimiterConfig,
+    failures: u32,
+    locked_until: Duration,
+}
+
+impl<const N: usize> RateLimiter<N> {
+    /// Loads a rate limiter whose failure counter starts at the given word offset, starting the delay
+    /// for any failures stored there from the current time.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`EepromError::BoundsError`] - The counter doesn't fit in the EEPROM at the given word
+    ///   offset.
+    pub fn load(
+        eeprom_controller: &mut EepromController,
+        hib: &HibController,
+        word_offset: usize,
+        config: RateLimiterConfig,
+    ) -> Result<Self, EepromError> {
+        let counter = WearLeveledCounter::new(word_offset);
+        let failures = counter.read(eeprom_controller)?;
+
+        Ok(Self {
+            counter,
+            hib: hib.clone(),
+            config,
+            failures,
+            locked_until: hib.now() + config.delay(failures),
+        })
+    }
+
+    /// Gets the number of failures since the last success.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Checks whether an attempt is allowed now.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`LockedOut::Permanently`] - There have been at least `lockout_threshold` failures since the
+    ///   last success.
+    /// - [`LockedOut::Delayed`] - The delay after the last failure hasn't passed yet.
+    pub fn check(&self) -> Result<(), LockedOut> {
+        if self.failures >= self.config.lockout_threshold {
+            return Err(LockedOut::Permanently);
+        }
+
+        match self.locked_until.checked_sub(self.hib.now()) {
+            Some(remaining) if !remaining.is_zero() => Err(LockedOut::Delayed(remaining)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records a failed attempt in the EEPROM and starts the delay before the next one.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`EepromError::BoundsError`] - The counter doesn't fit in the EEPROM.
+    /// - [`EepromError::CounterOverflowError`] - The counter can't go any higher. This is far past
+    ///   any lockout threshold, so attempts are already refused.
+    /// - [`EepromError::WritePermissionError`] - The EEPROM denied the write. The delay is still
+    ///   started.
+    pub fn record_failure(
+        &mut self,
+        eeprom_controller: &mut EepromController,
+    ) -> Result<(), EepromError> {
+        // Start the delay even if the write fails, so a failing EEPROM doesn't remove it.
+        let result = self.counter.increment(eeprom_controller);
+        self.failures = self.failures.saturating_add(1);
+        self.locked_until = self.hib.now() + self.config.delay(self.failures);
+
+        result.map(|failures| self.failures = failures)
+    }
+
+    /// Records a successful attempt, clearing the failures in the EEPROM and any delay. Clearing writes
+    /// every word of the counter, so nothing is written if there are no failures to clear.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`EepromError::BoundsError`] - The counter doesn't fit in the EEPROM.
+    /// - [`EepromError::WritePermissionError`] - The EEPROM denied the write. The failures stay
+    ///   recorded.
+    pub fn record_success(
+        &mut self,
+        eeprom_controller: &mut EepromController,
+    ) -> Result<(), EepromError> {
+        if self.failures == 0 {
+            return Ok(());
+        }
+
+        self.counter.clear(eeprom_controller)?;
+        self.failures = 0;
+        self.locked_until = Duration::ZERO;
+
+        Ok(())
+    }
+}
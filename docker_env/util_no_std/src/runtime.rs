@@ -2,13 +2,24 @@
 //! peripherals, provides random number generation, and manage an interrupt loop.
 
 use crate::{
-    button::Sw1ButtonController,
-    communication::{Uart0Controller, Uart1Controller},
-    eeprom::EepromController,
-    hib::HibController,
-    random,
+    button::{Button, Sw1ButtonController},
+    can::{self, BitTiming, CanController},
+    communication::{self, RxFifoTrigger, Uart0Controller, Uart1Controller},
+    eeprom::{EepromController, EepromError},
+    event::{self, RuntimeEvent},
+    hib::{self, HibController, HibDataError},
+    i2c::{self, I2cBus, I2cConfig, I2cController},
+    led::StatusLed,
+    log,
+    measurement::{self, Measurement},
+    random::{self, EntropyHealth, UartNoiseSamples},
+    stack_guard, timer,
+    watchdog::WatchdogController,
 };
 use chacha20poly1305::Key;
+use core::{convert::Infallible, time::Duration};
+use cortex_m::{asm, peripheral::SCB};
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
 use heapless::pool::{
     self,
     singleton::arc::{self, ArcInner, Pool},
@@ -19,27 +30,45 @@ use tm4c123x_hal::{
     delay::Delay,
     gpio::{
         gpioa::{PA0, PA1},
-        gpiob::{PB0, PB1},
-        gpiof::PF4,
-        AlternateFunction, GpioExt, Input, PullUp, PushPull, AF1,
+        gpioe::{PE4, PE5},
+        gpiof::{PF0, PF1, PF2, PF3, PF4},
+        AlternateFunction, GpioExt, Input, Output, PullUp, PushPull, AF1, AF3, AF8,
     },
     serial::{NewlineMode, Rx, RxPin, Serial, Tx, TxPin},
     sysctl::{
-        Clocks, CrystalFrequency, Oscillator, PllOutputFrequency, PowerControl, Sysctl, SysctlExt,
-        SystemClock,
+        Clocks, CrystalFrequency, Domain, Oscillator, PllOutputFrequency, PowerControl, Sysctl,
+        SysctlExt, SystemClock,
     },
     time::Bps,
     tm4c123x::*,
 };
 
+#[cfg(not(feature = "uart1-pc4-pc5"))]
+use tm4c123x_hal::gpio::gpiob::{PB0, PB1};
+#[cfg(feature = "uart1-pc4-pc5")]
+use tm4c123x_hal::gpio::{
+    gpioc::{PC4, PC5},
+    AF2,
+};
+
 #[cfg(debug_assertions)]
 pub use heapless::Arc;
 
+pub mod adc;
+
 /// The size of the memory pool for the hibernation peripheral.
 const HIB_POOL_MEMORY_SIZE: usize = 32;
 
-/// Bits-per-second for UART communications.
-const BPS: u32 = 115200;
+/// The default bits-per-second for UART communications.
+const DEFAULT_BPS: u32 = 115200;
+
+/// The maximum error between a requested baud rate and the baud rate the UART can actually generate,
+/// in tenths of a percent. Receivers tolerate a few percent of error, so this leaves some margin for
+/// the other end's error.
+const MAX_BAUD_ERROR_PERMILLE: u64 = 20;
+
+/// The default bitrate for CAN0, which is the usual bitrate of a vehicle's high-speed CAN bus.
+const DEFAULT_CAN_BITRATE: u32 = 500_000;
 
 /// The TX pin for UART 0.
 pub type Uart0TxPin = PA1<AlternateFunction<AF1, PullUp>>;
@@ -47,12 +76,44 @@ pub type Uart0TxPin = PA1<AlternateFunction<AF1, PullUp>>;
 /// The RX pin for UART 0.
 pub type Uart0RxPin = PA0<AlternateFunction<AF1, PushPull>>;
 
-/// The TX pin for UART 1.
+/// The TX pin for UART 1. This is PB1, or PC5 with the `uart1-pc4-pc5` feature.
+#[cfg(not(feature = "uart1-pc4-pc5"))]
 pub type Uart1TxPin = PB1<AlternateFunction<AF1, PullUp>>;
 
-/// The RX pin for UART 1.
+/// The TX pin for UART 1. This is PB1, or PC5 with the `uart1-pc4-pc5` feature.
+#[cfg(feature = "uart1-pc4-pc5")]
+pub type Uart1TxPin = PC5<AlternateFunction<AF2, PullUp>>;
+
+/// The RX pin for UART 1. This is PB0, or PC4 with the `uart1-pc4-pc5` feature.
+#[cfg(not(feature = "uart1-pc4-pc5"))]
 pub type Uart1RxPin = PB0<AlternateFunction<AF1, PushPull>>;
 
+/// The RX pin for UART 1. This is PB0, or PC4 with the `uart1-pc4-pc5` feature.
+#[cfg(feature = "uart1-pc4-pc5")]
+pub type Uart1RxPin = PC4<AlternateFunction<AF2, PushPull>>;
+
+/// The confirmation that must be passed to [`Runtime::factory_reset()`] for it to do anything. "WIPE"
+/// in ASCII.
+pub const FACTORY_RESET_CONFIRMATION: u32 = 0x5749_5045;
+
+/// The RX pin for CAN0.
+pub type Can0RxPin = PE4<AlternateFunction<AF8, PushPull>>;
+
+/// The TX pin for CAN0.
+pub type Can0TxPin = PE5<AlternateFunction<AF8, PushPull>>;
+
+/// The pin for the SW2 button.
+pub type Sw2ButtonPin = PF0<Input<PullUp>>;
+
+/// The pin for the red part of the onboard RGB LED.
+pub type LedRedPin = PF1<Output<PushPull>>;
+
+/// The pin for the blue part of the onboard RGB LED.
+pub type LedBluePin = PF2<Output<PushPull>>;
+
+/// The pin for the green part of the onboard RGB LED.
+pub type LedGreenPin = PF3<Output<PushPull>>;
+
 /*
 Portions of the below code are adapted from the heapless crate:
 
@@ -122,6 +183,100 @@ impl HibPool {
 
 // Portions of the above code are adapted from the heapless crate.
 
+/// The cause of the last reset of the microcontroller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetCause {
+    /// The microcontroller was powered on.
+    PowerOn,
+
+    /// The supply voltage dropped below the brown-out threshold.
+    Brownout,
+
+    /// WATCHDOG0 timed out.
+    Watchdog0,
+
+    /// WATCHDOG1 timed out.
+    Watchdog1,
+
+    /// The reset pin was asserted.
+    External,
+
+    /// Software requested a reset, such as through the system reset request in the NVIC.
+    Software,
+
+    /// The device woke from hibernation. See [`Runtime::hibernate()`] for more details.
+    Hibernation,
+
+    /// No reset cause was recorded, such as after a reset from a debugger.
+    Unknown,
+}
+
+impl ResetCause {
+    /// Reads the cause of the last reset from the reset cause register and clears the register so
+    /// the next reset can be attributed. More than one cause can be recorded for one reset, such as
+    /// an external reset during power on, so the most fundamental cause is returned. A wake from
+    /// hibernation powers the core on again, so it's checked for first.
+    fn take(sysctl: &SYSCTL, hib: &HIB) -> Self {
+        let resc = sysctl.resc.read();
+
+        let cause = if hib::take_hibernation_wake(hib) {
+            Self::Hibernation
+        } else if resc.por().bit_is_set() {
+            Self::PowerOn
+        } else if resc.bor().bit_is_set() {
+            Self::Brownout
+        } else if resc.wdt0().bit_is_set() {
+            Self::Watchdog0
+        } else if resc.wdt1().bit_is_set() {
+            Self::Watchdog1
+        } else if resc.sw().bit_is_set() {
+            Self::Software
+        } else if resc.ext().bit_is_set() {
+            Self::External
+        } else {
+            Self::Unknown
+        };
+
+        // SAFETY: Writing zero clears every reset cause, which is valid for this register.
+        sysctl.resc.write(|w| unsafe { w.bits(0) });
+
+        cause
+    }
+}
+
+/// An error that can occur while initializing the runtime with [`Runtime::new()`].
+#[derive(Debug)]
+pub enum RuntimeInitError {
+    /// The EEPROM controller failed to initialize.
+    Eeprom(EepromError),
+
+    /// The entropy gathered to seed the main CSPRNG failed its health tests.
+    EntropyHealth(EntropyHealth),
+
+    /// The hibernation module didn't become ready in time.
+    HibTimeout,
+}
+
+/// An error that can occur during [`Runtime::factory_reset()`].
+#[derive(Debug)]
+pub enum FactoryResetError {
+    /// The confirmation wasn't [`FACTORY_RESET_CONFIRMATION`], so nothing was wiped.
+    WrongConfirmation,
+
+    /// The EEPROM failed while it was being wiped.
+    Eeprom(EepromError),
+}
+
+/// Whether a callback run by [`Runtime::start_low_power()`] has work pending.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoopState {
+    /// The callback has more work to do, so it's run again immediately.
+    Busy,
+
+    /// The callback has no work to do until an interrupt occurs, so the core sleeps until then.
+    Idle,
+}
+
 /// The runtime struct.
 pub struct Runtime<'a> {
     /// The EEPROM controller.
@@ -133,38 +288,97 @@ pub struct Runtime<'a> {
     /// The SW1 button controller.
     pub sw1_button_controller: Sw1ButtonController<'a>,
 
+    /// The SW2 button. See the documentation for [`Button`] for more details.
+    pub sw2_button: Button<'a, Sw2ButtonPin>,
+
+    /// The onboard RGB LED. See the documentation for [`StatusLed`] for more details.
+    pub status_led: StatusLed<'a>,
+
     /// The controller for UART0. See the documentation for [`Uart0Controller`] for more details.
     pub uart0_controller: Uart0Controller<'a, Uart0TxPin, Uart0RxPin>,
 
     /// The controller for UART1. See the documentation for [`Uart1Controller`] for more details.
     pub uart1_controller: Uart1Controller<'a, Uart1TxPin, Uart1RxPin>,
+
+    /// The controller for CAN0. See the documentation for [`CanController`] for more details.
+    pub can0_controller: CanController<'a>,
+
+    /// The I2C controller, if an I2C bus is configured in the [`RuntimeConfig`]. See the
+    /// documentation for [`I2cController`] for more details.
+    pub i2c_controller: Option<I2cController<'a>>,
+
+    /// The WATCHDOG0 controller. See the documentation for [`WatchdogController`] for more details.
+    pub watchdog_controller: WatchdogController<'a>,
+
+    /// The MPU, used for the stack guard.
+    mpu: &'a mut MPU,
+
+    /// The SysTick-based delay, used for blocking waits.
+    delay: &'a mut Delay,
+
+    /// The cause of the last reset.
+    reset_cause: ResetCause,
 }
 
 impl<'a> Runtime<'a> {
-    /// Initializes the runtime.
+    /// Initializes the runtime. If this fails, it can be called again with the same peripherals to
+    /// retry, or the chip can be reset.
     ///
-    /// # Panics
+    /// # ERRORS:
     ///
-    /// Panics if the EEPROM controller cannot be initialized.
+    /// - [`RuntimeInitError::EntropyHealth`] - The entropy gathered to seed the main CSPRNG failed its
+    ///   health tests. Retrying gathers fresh entropy.
+    /// - [`RuntimeInitError::Eeprom`] - The EEPROM controller couldn't be initialized.
+    /// - [`RuntimeInitError::HibTimeout`] - The hibernation module didn't become ready in time.
     pub fn new(
         peripherals: &'a mut RuntimePeripherals,
         uart1_rx_key: &Key,
         uart1_tx_key: &Key,
-    ) -> Self {
-        random::init_rng(peripherals);
+    ) -> Result<Self, RuntimeInitError> {
+        log::init();
+
+        // Drop events left by an earlier runtime, since they refer to its controllers.
+        event::clear_events();
+
+        random::init_rng(peripherals).map_err(RuntimeInitError::EntropyHealth)?;
 
         let eeprom_controller =
-            EepromController::new(&mut peripherals.eeprom, &peripherals.power_control).unwrap();
+            EepromController::new(&mut peripherals.eeprom, &peripherals.power_control)
+                .map_err(RuntimeInitError::Eeprom)?;
+
+        let hib_controller = HibController::new(
+            peripherals.hib.clone(),
+            &peripherals.power_control,
+            peripherals.reset_cause,
+        )
+        .map_err(|_| RuntimeInitError::HibTimeout)?;
 
-        let hib_controller =
-            HibController::new(peripherals.hib.clone(), &peripherals.power_control);
+        let sw1_button_controller = Sw1ButtonController::new(
+            &mut peripherals.pf4,
+            &mut peripherals.pf0,
+            &mut peripherals.timer5,
+            &peripherals.power_control,
+            &peripherals.clocks,
+            &mut peripherals.nvic,
+        );
 
-        let sw1_button_controller =
-            Sw1ButtonController::new(&mut peripherals.pf4, &mut peripherals.nvic);
+        let sw2_button = Button::new(&mut peripherals.pf0);
+
+        let status_led = StatusLed::new(
+            &mut peripherals.pf1,
+            &mut peripherals.pf2,
+            &mut peripherals.pf3,
+        );
 
         let uart0_controller =
             Uart0Controller::without_key(&mut peripherals.uart0_tx, &mut peripherals.uart0_rx);
 
+        // SAFETY: Unmasking the UART1 interrupt is safe because the interrupt handler only relies on
+        // data local to the uart module and the UART1 registers, which it accesses in an interrupt-free
+        // context. Nothing relies on a mask-based critical section for this interrupt. The interrupt
+        // is only raised once interrupt-driven reception is enabled on the UART1 controller.
+        unsafe { NVIC::unmask(Interrupt::UART1) };
+
         let uart1_controller = Uart1Controller::new(
             &mut peripherals.uart1_tx,
             &mut peripherals.uart1_rx,
@@ -172,28 +386,590 @@ impl<'a> Runtime<'a> {
             uart1_tx_key,
         );
 
-        Runtime {
+        let can0_controller = CanController::new(&mut peripherals.can0);
+
+        let i2c_controller = match peripherals.i2c_config {
+            Some(I2cConfig {
+                bus: I2cBus::I2c0, ..
+            }) => Some(I2cController::new(
+                &peripherals.i2c0,
+                peripherals.hib.clone(),
+            )),
+            Some(I2cConfig {
+                bus: I2cBus::I2c1, ..
+            }) => Some(I2cController::new(
+                &peripherals.i2c1,
+                peripherals.hib.clone(),
+            )),
+            None => None,
+        };
+
+        // Let every interrupt wake the core from a WFE, including interrupts that are masked or that
+        // occur before the WFE. See Runtime::start_low_power() for more details.
+        peripherals.scb.set_sevonpend();
+
+        // Route MPU faults to their own handler, which panics. See Runtime::configure_stack_guard().
+        stack_guard::enable_mem_manage_fault(&mut peripherals.scb);
+
+        let watchdog_controller = WatchdogController::new(
+            &mut peripherals.watchdog0,
+            &peripherals.power_control,
+            &peripherals.clocks,
+        );
+
+        Ok(Runtime {
             eeprom_controller,
             hib_controller,
             sw1_button_controller,
+            sw2_button,
+            status_led,
             uart0_controller,
             uart1_controller,
+            can0_controller,
+            i2c_controller,
+            watchdog_controller,
+            mpu: &mut peripherals.mpu,
+            delay: &mut peripherals.delay,
+            reset_cause: peripherals.reset_cause,
+        })
+    }
+
+    /// Initializes the runtime, panicking if it can't be initialized. This is for firmware that
+    /// can't do anything useful without the runtime. See [`Runtime::new()`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Runtime::new()`] returns an error.
+    pub fn new_or_panic(
+        peripherals: &'a mut RuntimePeripherals,
+        uart1_rx_key: &Key,
+        uart1_tx_key: &Key,
+    ) -> Self {
+        match Self::new(peripherals, uart1_rx_key, uart1_tx_key) {
+            Ok(rt) => rt,
+            Err(err) => panic!("Runtime initialization failed: {err:?}"),
+        }
+    }
+
+    /// Tears down the runtime so the [`RuntimePeripherals`] it was created from can be reconfigured,
+    /// such as to change the baud rate of UART1, and used to create a new runtime. This disables the
+    /// watchdog, switches UART1 back to polled reception, and masks the UART1 interrupt, so nothing
+    /// the runtime set up keeps running without it. Dropping the controllers also powers off the
+    /// EEPROM and frees SW1 for a new [`Sw1ButtonController`].
+    ///
+    /// The runtime only borrows the peripherals, and each controller holds its part of that borrow
+    /// for `'a`, so the peripherals can't be handed back by value. Instead, the borrow ends once this
+    /// returns, and the peripherals can be used again directly:
+    ///
+    /// ```ignore
+    /// let rt = Runtime::new(&mut rt_peripherals, &uart1_rx_key, &uart1_tx_key)?;
+    ///
+    /// // ...
+    ///
+    /// rt.release();
+    ///
+    /// // Reconfigure rt_peripherals here.
+    ///
+    /// let rt = Runtime::new(&mut rt_peripherals, &uart1_rx_key, &uart1_tx_key)?;
+    /// ```
+    ///
+    /// Anything that borrows from the runtime, such as a channel wrapping one of its controllers,
+    /// must be dropped first. The CSPRNGs and the log sink are global, so they're kept as they are
+    /// instead of being set up again by the new runtime.
+    pub fn release(mut self) {
+        self.watchdog_controller.disable();
+        self.uart1_controller.disable_rx_interrupt();
+        NVIC::mask(Interrupt::UART1);
+    }
+
+    /// Runs a callback in a loop forever, sleeping the core whenever the callback returns
+    /// [`LoopState::Idle`] until the next interrupt, such as UART1 receiving data or a button being
+    /// pressed. The watchdog is fed on each iteration, and its timeout interrupt wakes the core in
+    /// time to be fed again, so an idle loop doesn't trigger a watchdog reset.
+    ///
+    /// UART1 only raises an interrupt once
+    /// [`Uart1Controller::enable_rx_interrupt()`](crate::communication::Uart1Controller::enable_rx_interrupt)
+    /// has been called. Until then, data arriving on UART1 doesn't wake the core, so a callback that
+    /// waits for a message over UART1 must enable it first:
+    ///
+    /// ```ignore
+    /// rt.uart1_controller.enable_rx_interrupt();
+    ///
+    /// rt.start_low_power(|rt| match rt.uart1_controller.try_recv(&mut buf) {
+    ///     Ok(Some(len)) => handle(&buf[..len]),
+    ///     _ => LoopState::Idle,
+    /// });
+    /// ```
+    ///
+    /// Timers don't raise interrupts, so a callback waiting on a timer should return
+    /// [`LoopState::Busy`] until the timer expires.
+    pub fn start_low_power(&mut self, mut to_run: impl FnMut(&mut Self) -> LoopState) -> ! {
+        loop {
+            self.feed_watchdog();
+
+            if to_run(self) == LoopState::Idle {
+                // A WFE is used instead of a WFI because SEVONPEND makes any interrupt that occurred
+                // while the callback was running set the event register, so the WFE returns
+                // immediately instead of sleeping through an event the callback didn't see.
+                asm::wfe();
+            }
+        }
+    }
+
+    /// Runs a callback in a loop until it returns ``true``, then returns, so a sequence such as pairing
+    /// can finish and hand control back to reconfigure the board. The watchdog is fed on each iteration,
+    /// as in [`Runtime::start_low_power()`]. The core never sleeps, so the callback is run again
+    /// immediately after it returns ``false``.
+    ///
+    /// ```ignore
+    /// let mut exchanges = 0;
+    ///
+    /// rt.run_until(|rt| {
+    ///     if exchange(rt).is_ok() {
+    ///         exchanges += 1;
+    ///     }
+    ///
+    ///     exchanges == 3
+    /// });
+    /// ```
+    pub fn run_until(&mut self, mut done: impl FnMut(&mut Self) -> bool) {
+        loop {
+            self.feed_watchdog();
+
+            if done(self) {
+                return;
+            }
         }
     }
 
+    /// Configures the MPU to deny all access to the [`STACK_GUARD_SIZE`](stack_guard::STACK_GUARD_SIZE)
+    /// bytes at the low end of the stack, so a stack overflow faults instead of silently overwriting
+    /// whatever is below the stack. The fault is routed to the panic handler, so the panic handler
+    /// still runs, using the guard as its stack. The guard starts at the first address at or above
+    /// `stack_limit` that's aligned to its size, and those bytes can't be used by the stack anymore.
+    ///
+    /// `stack_limit` is the lowest address of the stack. cortex-m-rt only provides the top of the
+    /// stack, `_stack_start`, so the limit has to come from the memory layout. With a `STACK` region
+    /// below `RAM` in `memory.x`, as the car and fob use, export the bottom of the region with
+    /// `_stack_end = ORIGIN(STACK);` and pass its address:
+    ///
+    /// ```ignore
+    /// extern "C" {
+    ///     static _stack_end: u32;
+    /// }
+    ///
+    /// rt.configure_stack_guard(unsafe { core::ptr::addr_of!(_stack_end) });
+    /// ```
+    ///
+    /// With cortex-m-rt's default layout, where the stack grows down towards the statics, pass the
+    /// address of `__sheap` instead, which is the end of `.bss` and `.uninit`.
+    pub fn configure_stack_guard(&mut self, stack_limit: *const u32) {
+        stack_guard::configure_stack_guard(self.mpu, stack_limit);
+    }
+
+    /// Measures the firmware by hashing the flash from `start` up to, but not including, `end` with
+    /// SHA3-256. The measurement can be sent to a provisioning host with
+    /// [`report_measurement()`](measurement::report_measurement).
+    ///
+    /// The bounds come from symbols in `memory.x`. It's read before cortex-m-rt lays out the sections,
+    /// so it can't refer to the end of the image. Instead, measure the whole flash region, which works
+    /// for any image size. With the car and fob layout, where `TEXT_JUMP` comes before `FLASH`, export
+    /// the bounds with `_firmware_start = ORIGIN(TEXT_JUMP);` and
+    /// `_firmware_end = ORIGIN(FLASH) + LENGTH(FLASH);` and pass their addresses:
+    ///
+    /// ```ignore
+    /// extern "C" {
+    ///     static _firmware_start: u8;
+    ///     static _firmware_end: u8;
+    /// }
+    ///
+    /// let measurement = unsafe {
+    ///     rt.measure_firmware(
+    ///         core::ptr::addr_of!(_firmware_start),
+    ///         core::ptr::addr_of!(_firmware_end),
+    ///     )
+    /// };
+    /// ```
+    ///
+    /// The unused flash after the image is part of the measurement, so it must be erased when the
+    /// firmware is flashed, and the host must hash the image padded with `0xFF` to the length of the
+    /// region.
+    ///
+    /// # Safety
+    ///
+    /// `start` must be at most `end`, and every byte between them must be flash. Both are checked
+    /// only by the linker script, so they must be the addresses of the symbols above.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is after `end`.
+    pub unsafe fn measure_firmware(&self, start: *const u8, end: *const u8) -> Measurement {
+        // SAFETY: The caller guarantees that the range is flash, which isn't written to while the
+        // runtime is running.
+        unsafe { measurement::measure(start, end) }
+    }
+
+    /// Busy-waits for a number of microseconds with SysTick, which counts system clock cycles, so this
+    /// is much more precise than a timer, whose accuracy is 1/32768 seconds. This blocks the event
+    /// loop, including feeding the watchdog, so long waits should use a timer instead.
+    pub fn delay_us(&mut self, us: u32) {
+        self.delay.delay_us(us);
+    }
+
+    /// Busy-waits for a number of milliseconds with SysTick. This blocks the event loop, including
+    /// feeding the watchdog, so long waits should use a timer instead. See [`Runtime::delay_us()`]
+    /// for more details.
+    pub fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms);
+    }
+
+    /// Gets the cause of the last reset. This is latched when [`RuntimePeripherals`] is created.
+    pub fn reset_cause(&self) -> ResetCause {
+        self.reset_cause
+    }
+
+    /// Enables the watchdog so it resets the chip if [`Runtime::feed_watchdog()`] isn't called within
+    /// the timeout. An event loop should feed the watchdog on each iteration. See
+    /// [`WatchdogController::enable()`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timeout is zero or longer than the watchdog can count.
+    pub fn enable_watchdog(&mut self, timeout: Duration) {
+        self.watchdog_controller.enable(timeout);
+    }
+
+    /// Disables the watchdog. This should be used before long blocking operations such as
+    /// [`Runtime::reseed_rng()`].
+    pub fn disable_watchdog(&mut self) {
+        self.watchdog_controller.disable();
+    }
+
+    /// Feeds the watchdog, restarting its timeout. This can be used to service the watchdog inside
+    /// long callbacks.
+    pub fn feed_watchdog(&self) {
+        self.watchdog_controller.feed();
+    }
+
+    /// Spins until ``predicate`` returns ``true`` or ``timeout`` passes, whichever comes first, returning
+    /// whether the predicate returned ``true``. The watchdog is fed on each pass, so this can wait
+    /// longer than the watchdog timeout. The predicate is checked at least once, even with a zero
+    /// timeout. This is timed with the hibernation clock, so it's accurate to 1/32768 seconds.
+    ///
+    /// ```ignore
+    /// if !rt.spin_until(Duration::from_millis(10), || pin.is_high().unwrap_or(false)) {
+    ///     return Err(CommunicationError::Timeout);
+    /// }
+    /// ```
+    pub fn spin_until(&self, timeout: Duration, mut predicate: impl FnMut() -> bool) -> bool {
+        let mut timer = self.hib_controller.create_timer(timeout);
+
+        timer::spin_until(&mut timer, || {
+            self.feed_watchdog();
+            predicate()
+        })
+    }
+
+    /// Waits for the next event pushed by an interrupt handler, returning ``None`` if none arrives
+    /// before ``timeout`` passes. Events are returned in the order they were pushed, and waiting events
+    /// are returned right away, even with a zero timeout. The watchdog is fed while waiting. See the
+    /// [`event`](crate::event) module for the capacity of the queue and what happens when it's full.
+    ///
+    /// ```ignore
+    /// match rt.next_event(Duration::from_millis(100)) {
+    ///     Some(RuntimeEvent::ButtonPressed(ButtonId::Sw2)) => rt.status_led.set_state(LedState::Idle),
+    ///     Some(RuntimeEvent::Uart1Rx) => handle_host_message(&mut rt),
+    ///     _ => {}
+    /// }
+    /// ```
+    pub fn next_event(&self, timeout: Duration) -> Option<RuntimeEvent> {
+        let mut next = None;
+
+        self.spin_until(timeout, || {
+            next = event::pop_event();
+            next.is_some()
+        });
+
+        next
+    }
+
+    /// Hibernates until `wake_after` has passed or, if `wake_on_pin` is set, until the WAKE pin is
+    /// asserted, whichever comes first. This draws far less power than sleeping in
+    /// [`Runtime::start_low_power()`], but the device cold-boots on waking, with
+    /// [`ResetCause::Hibernation`] as the reset cause. Only the battery-backed data registers of the
+    /// hibernation module survive, so everything else, including session keys, must be derived again.
+    /// See the [`hib`](crate::hib) module for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no wake source, or if the hibernation module doesn't become ready for writes
+    /// in time. See [`HibController::hibernate()`] for more details.
+    pub fn hibernate(&mut self, wake_after: Option<Duration>, wake_on_pin: bool) -> ! {
+        self.hib_controller.hibernate(wake_after, wake_on_pin)
+    }
+
+    /// Stores a word in a battery-backed data register of the hibernation module, which survives
+    /// [`Runtime::hibernate()`]. There are [`HIB_NV_WORDS`](hib::HIB_NV_WORDS) of them, guarded by a
+    /// CRC. See [`HibController::store_data()`] for more details.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`HibDataError::OutOfRange`] - The index is [`HIB_NV_WORDS`](hib::HIB_NV_WORDS) or greater.
+    /// - [`HibDataError::Corrupted`] - The registers were already corrupted, so nothing is stored.
+    /// - [`HibDataError::Timeout`] - The hibernation module didn't become ready for writes in time.
+    pub fn hib_store(&self, index: usize, value: u32) -> Result<(), HibDataError> {
+        self.hib_controller.store_data(index, value)
+    }
+
+    /// Loads a word stored with [`Runtime::hib_store()`]. See [`HibController::load_data()`] for more
+    /// details.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`HibDataError::OutOfRange`] - The index is [`HIB_NV_WORDS`](hib::HIB_NV_WORDS) or greater.
+    /// - [`HibDataError::Corrupted`] - The CRC of the registers doesn't match, such as after the
+    ///   hibernation module lost power.
+    pub fn hib_load(&self, index: usize) -> Result<u32, HibDataError> {
+        self.hib_controller.load_data(index)
+    }
+
+    /// Wipes every secret and all pairing state from the EEPROM and then resets the chip, returning the
+    /// device to a de-provisioned state. `confirmation` must be [`FACTORY_RESET_CONFIRMATION`], so that
+    /// this can't be called by accident.
+    ///
+    /// The wiped ranges are listed in
+    /// [`FACTORY_RESET_READ_ONLY_FIELDS`](crate::eeprom::FACTORY_RESET_READ_ONLY_FIELDS) and
+    /// [`FACTORY_RESET_READ_WRITE_FIELDS`](crate::eeprom::FACTORY_RESET_READ_WRITE_FIELDS), along with
+    /// the transaction journal at [`JOURNAL_BOUNDS`](crate::eeprom::JOURNAL_BOUNDS) and the raw words
+    /// at [`FREE_SPACE_BOUNDS`](crate::eeprom::FREE_SPACE_BOUNDS), which hold things like a
+    /// [`WearLeveledCounter`](crate::eeprom::WearLeveledCounter) or a
+    /// [`FeatureSet`](crate::features::FeatureSet). Each range is overwritten with random bytes from
+    /// the main CSPRNG and then with zeros, which leaves the device unpaired with no features and every
+    /// counter at 0. The verifying keys and messages are public and are left alone. Provisioning must
+    /// write every field in these lists again. See [`EepromController`] for more details.
+    ///
+    /// The watchdog is disabled first, since the wipe can take a while.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`FactoryResetError::WrongConfirmation`] - `confirmation` isn't
+    ///   [`FACTORY_RESET_CONFIRMATION`]. Nothing is wiped.
+    /// - [`FactoryResetError::Eeprom`] - An EEPROM write failed partway through. The chip isn't reset,
+    ///   so the wipe can be retried.
+    pub fn factory_reset(&mut self, confirmation: u32) -> Result<Infallible, FactoryResetError> {
+        if confirmation != FACTORY_RESET_CONFIRMATION {
+            return Err(FactoryResetError::WrongConfirmation);
+        }
+
+        self.disable_watchdog();
+        self.eeprom_controller
+            .factory_reset()
+            .map_err(FactoryResetError::Eeprom)?;
+
+        SCB::sys_reset()
+    }
+
+    /// Gets the time since the RTC was enabled at boot, or since the time it was last set to with
+    /// [`Runtime::set_rtc()`]. Reads are guarded against torn values across a second boundary. See
+    /// [`HibController::now()`] for more details.
+    pub fn now(&self) -> Duration {
+        self.hib_controller.now()
+    }
+
+    /// Sets the RTC to a time, such as a provisioned timestamp. This moves the deadlines of any
+    /// running timers. See [`HibController::set_rtc()`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time is 2^32 seconds or longer, or if the hibernation module doesn't become
+    /// ready for writes in time.
+    pub fn set_rtc(&self, time: Duration) {
+        self.hib_controller.set_rtc(time);
+    }
+
     /// Fills a slice with random bytes from the main CSPRNG.
+    ///
+    /// This may occasionally block for around a second while the main CSPRNG automatically reseeds
+    /// itself. See [`Runtime::set_rng_reseed_threshold()`] for more details. The reseed happens on
+    /// whichever call crosses the threshold, even one made from an interrupt handler, so a handler that
+    /// draws random bytes, directly or by sending on an encrypted UART channel, can block for that long
+    /// too.
     pub fn fill_rand_slice(&self, dest: &mut [u8]) {
         random::fill_rand_slice(dest);
     }
+
+    /// Returns an array of random bytes from the main CSPRNG, such as for a nonce or a challenge. The
+    /// size comes from the type of the array, so it can't be mismatched.
+    ///
+    /// This may occasionally block for around a second while the main CSPRNG automatically reseeds
+    /// itself, like [`Runtime::fill_rand_slice()`].
+    pub fn fill_rand_array<const N: usize>(&self) -> [u8; N] {
+        random::fill_rand_array()
+    }
+
+    /// Returns an array of random bytes from the secondary CSPRNG. This CSPRNG uses the same seed
+    /// across reboots, so prefer [`Runtime::fill_rand_array()`]. Arrays of 16 bytes or more fail to
+    /// compile, since they're large enough to be a key, which must come from the main CSPRNG.
+    pub fn fill_rand_array_secondary<const N: usize>(&self) -> [u8; N] {
+        random::fill_rand_array_secondary()
+    }
+
+    /// Returns a random XChaCha20-Poly1305 key from the main CSPRNG. The key is returned by value, so
+    /// it should be zeroized once it's no longer needed.
+    ///
+    /// This may occasionally block for around a second while the main CSPRNG automatically reseeds
+    /// itself, like [`Runtime::fill_rand_slice()`].
+    pub fn random_key(&self) -> Key {
+        random::random_key(&mut random::main_rng())
+    }
+
+    /// Generates a uniformly distributed random number in `[low, high)` from the main CSPRNG.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low >= high`.
+    pub fn gen_range_u32(&self, low: u32, high: u32) -> u32 {
+        random::gen_range_u32(low, high)
+    }
+
+    /// Generates a uniformly distributed random number in `[low, high)` from the secondary CSPRNG.
+    /// This CSPRNG uses the same seed across reboots, so prefer [`Runtime::gen_range_u32()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low >= high`.
+    pub fn gen_range_u32_secondary(&self, low: u32, high: u32) -> u32 {
+        random::gen_range_u32_secondary(low, high)
+    }
+
+    /// Reseeds the main CSPRNG with fresh entropy. Blocks for around a second while gathering entropy.
+    /// ADC0 is used to gather entropy, so this fails if it's called from an interrupt handler that
+    /// interrupted an [`AdcReader`](adc::AdcReader) reading.
+    ///
+    /// # ERRORS:
+    ///
+    /// - The [`EntropyHealth`] failure - Occurs if the fresh entropy fails its health tests. The main
+    ///   CSPRNG keeps its current state in this case.
+    /// - [`EntropyHealth::SourceUnavailable`] - Occurs if ADC0 is in use. The main CSPRNG keeps its
+    ///   current state in this case.
+    pub fn reseed_rng(&self) -> Result<(), EntropyHealth> {
+        random::reseed_main()
+    }
+
+    /// Reseeds the secondary CSPRNG with random bytes from the main CSPRNG, so later uses of the
+    /// secondary CSPRNG, such as [`Runtime::gen_range_u32_secondary()`], no longer depend only on the
+    /// seed that's the same across reboots. The new seed lasts until the next reboot.
+    ///
+    /// This may occasionally block for around a second while the main CSPRNG automatically reseeds
+    /// itself, like [`Runtime::fill_rand_slice()`].
+    pub fn reseed_secondary_rng(&self) {
+        random::reseed_secondary_from_main();
+    }
+
+    /// Sets the number of bytes the main CSPRNG generates before it automatically reseeds itself,
+    /// blocking the call that crosses the threshold for around a second. Defaults to 1 MiB. A
+    /// threshold of 0 disables automatic reseeding.
+    pub fn set_rng_reseed_threshold(&self, bytes: usize) {
+        random::set_reseed_threshold(bytes);
+    }
+}
+
+/// The configuration of the peripherals initialized by [`RuntimePeripherals::with_config()`]. The
+/// default configuration is the one used by [`RuntimePeripherals::from()`].
+#[derive(Copy, Clone)]
+pub struct RuntimeConfig {
+    /// The system clock frequency, which the PLL generates from the 16 MHz crystal. Defaults to 80 MHz.
+    /// The valid frequencies are the variants of [`PllOutputFrequency`], which divide the 400 MHz PLL
+    /// output down from 80 MHz. A lower frequency saves power at the cost of speed, which matters most
+    /// for cryptography and entropy gathering.
+    ///
+    /// The UART baud rate divisors, the [`Delay`], and the watchdog timeouts are all computed from the
+    /// resulting [`Clocks`], so they keep their timing at any frequency. The HIB runs from its own 32.768
+    /// kHz oscillator and the EEPROM waits are counted in cycles, so neither depends on the frequency.
+    pub sysclk: PllOutputFrequency,
+
+    /// The baud rate of UART0. Defaults to 115200.
+    pub uart0_baud: u32,
+
+    /// The baud rate of UART1. Defaults to 115200.
+    pub uart1_baud: u32,
+
+    /// How full the UART1 receive FIFO must be to raise the receive interrupt once interrupt-driven
+    /// reception is enabled. Defaults to [`RxFifoTrigger::Half`], the hardware default. A low level
+    /// suits short handshake frames, and a high level suits bulk transfers. See [`RxFifoTrigger`] for
+    /// the tradeoff.
+    pub uart1_rx_fifo_trigger: RxFifoTrigger,
+
+    /// The bitrate of CAN0. Defaults to 500,000.
+    pub can0_bitrate: u32,
+
+    /// The I2C bus and speed of the I2C controller, or `None` to leave the I2C peripherals and their
+    /// pins unconfigured. Defaults to `None`.
+    pub i2c: Option<I2cConfig>,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            sysclk: PllOutputFrequency::_80_00mhz,
+            uart0_baud: DEFAULT_BPS,
+            uart1_baud: DEFAULT_BPS,
+            uart1_rx_fifo_trigger: RxFifoTrigger::Half,
+            can0_bitrate: DEFAULT_CAN_BITRATE,
+            i2c: None,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Checks whether a UART can generate a baud rate at the given clocks to within 2%. The UARTs
+    /// divide the system clock by 16 times a divisor with a 16-bit integer part and a 6-bit fractional
+    /// part, so at 80 MHz, baud rates from around 80 to 5,000,000 can be generated. The maximum baud
+    /// rate scales down with the system clock.
+    pub fn is_baud_achievable(baud: u32, clocks: &Clocks) -> bool {
+        if baud == 0 {
+            return false;
+        }
+
+        let sysclk = clocks.sysclk.0 as u64;
+        let baud = baud as u64;
+
+        // The divisor in 64ths, rounded to the nearest 64th like the HAL does.
+        let divisor = (sysclk * 4 + baud / 2) / baud;
+
+        if !(64..=u16::MAX as u64 * 64 + 63).contains(&divisor) {
+            return false;
+        }
+
+        let actual_baud = sysclk * 4 / divisor;
+
+        actual_baud.abs_diff(baud) * 1000 <= baud * MAX_BAUD_ERROR_PERMILLE
+    }
+
+    /// Checks whether CAN0 can generate a bitrate exactly at the given clocks. The bit time must be a
+    /// whole number of 8 to 25 time quanta, each of which is 1 to 1024 system clock cycles, so at 80
+    /// MHz, common bitrates such as 125,000, 250,000, 500,000, and 1,000,000 can all be generated.
+    pub fn is_can_bitrate_achievable(bitrate: u32, clocks: &Clocks) -> bool {
+        BitTiming::new(bitrate, clocks).is_some()
+    }
+
+    /// Panics with a clear message if a baud rate in this configuration isn't achievable.
+    fn check_bauds(&self, clocks: &Clocks) {
+        for (name, baud) in [("UART0", self.uart0_baud), ("UART1", self.uart1_baud)] {
+            assert!(
+                Self::is_baud_achievable(baud, clocks),
+                "{name} baud rate of {baud} isn't achievable with a {} Hz system clock.",
+                clocks.sysclk.0
+            );
+        }
+    }
 }
 
-/// Initializes the system clock and power control, and returns them.
-fn initialize_sysctl(mut sysctl: Sysctl) -> (PowerControl, Clocks) {
+/// Initializes the system clock to the given frequency and power control, and returns them.
+fn initialize_sysctl(mut sysctl: Sysctl, frequency: PllOutputFrequency) -> (PowerControl, Clocks) {
     // Setup clock.
-    sysctl.clock_setup.oscillator = Oscillator::Main(
-        CrystalFrequency::_16mhz,
-        SystemClock::UsePll(PllOutputFrequency::_80_00mhz),
-    );
+    sysctl.clock_setup.oscillator =
+        Oscillator::Main(CrystalFrequency::_16mhz, SystemClock::UsePll(frequency));
 
     (sysctl.power_control, sysctl.clock_setup.freeze())
 }
@@ -204,6 +980,7 @@ macro_rules! init_uart {
             uart: $typ,
             tx: TX,
             rx: RX,
+            baud: u32,
             clocks: &Clocks,
             pc: &PowerControl,
         ) -> (Tx<$typ, TX, ()>, Rx<$typ, RX, ()>)
@@ -217,7 +994,7 @@ macro_rules! init_uart {
                 rx,
                 (),
                 (),
-                Bps(BPS),
+                Bps(baud),
                 NewlineMode::Binary,
                 clocks,
                 pc,
@@ -230,8 +1007,17 @@ macro_rules! init_uart {
 init_uart!(UART0, initialize_uart0, uart0);
 init_uart!(UART1, initialize_uart1, uart1);
 
-/// All peripherals and core peripherals, but with the system clock, power control, PF4 GPIO pin,
-/// delay, and UART pins initialized.
+/// All peripherals and core peripherals, but with the system clock, power control, PF0 to PF4 GPIO
+/// pins, delay, UART pins, CAN0, and the configured I2C bus initialized, and the reset cause latched.
+/// The UART1 RX line is sampled for entropy before UART1 is configured, and the samples are kept until
+/// the main CSPRNG is seeded.
+///
+/// UART1 uses one of two pin pairs, which is picked at compile time since the pins are part of the
+/// [`Uart1TxPin`] and [`Uart1RxPin`] types:
+///
+/// - PB1 (TX) and PB0 (RX), with alternate function 1. This is the default.
+/// - PC5 (TX) and PC4 (RX), with alternate function 2, with the `uart1-pc4-pc5` feature. Port C is
+///   split to configure them, so `gpio_portc` isn't provided with this feature.
 #[allow(dead_code, missing_docs)]
 pub struct RuntimePeripherals {
     pub cbp: CBP,
@@ -247,6 +1033,7 @@ pub struct RuntimePeripherals {
     pub tpiu: TPIU,
     pub watchdog0: WATCHDOG0,
     pub watchdog1: WATCHDOG1,
+    #[cfg(not(feature = "uart1-pc4-pc5"))]
     pub gpio_portc: GPIO_PORTC,
     pub gpio_portd: GPIO_PORTD,
     pub ssi0: SSI0,
@@ -263,7 +1050,13 @@ pub struct RuntimePeripherals {
     pub i2c1: I2C1,
     pub i2c2: I2C2,
     pub i2c3: I2C3,
-    pub gpio_porte: GPIO_PORTE,
+    pub can0_rx: Can0RxPin,
+    pub can0_tx: Can0TxPin,
+    pub i2c_config: Option<I2cConfig>,
+    pub pf0: Sw2ButtonPin,
+    pub pf1: LedRedPin,
+    pub pf2: LedBluePin,
+    pub pf3: LedGreenPin,
     pub pf4: PF4<Input<PullUp>>,
     pub pwm0: PWM0,
     pub pwm1: PWM1,
@@ -308,33 +1101,140 @@ pub struct RuntimePeripherals {
     pub uart0_rx: Rx<UART0, Uart0RxPin, ()>,
     pub uart1_tx: Tx<UART1, Uart1TxPin, ()>,
     pub uart1_rx: Rx<UART1, Uart1RxPin, ()>,
+    pub reset_cause: ResetCause,
+    pub(crate) uart1_rx_noise: UartNoiseSamples,
 }
 
 impl From<(CorePeripherals, Peripherals)> for RuntimePeripherals {
-    fn from((core_peripherals, peripherals): (CorePeripherals, Peripherals)) -> Self {
+    /// Initializes the runtime peripherals with the default [`RuntimeConfig`].
+    fn from(peripherals: (CorePeripherals, Peripherals)) -> Self {
+        Self::with_config(peripherals, &RuntimeConfig::default())
+    }
+}
+
+impl RuntimePeripherals {
+    /// Initializes the runtime peripherals with the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a baud rate in the configuration isn't achievable. See
+    /// [`RuntimeConfig::is_baud_achievable()`] for more details.
+    pub fn with_config(
+        (core_peripherals, peripherals): (CorePeripherals, Peripherals),
+        config: &RuntimeConfig,
+    ) -> Self {
         // Initialize the hibernation peripheral memory pool.
         static mut HIB_POOL_MEMORY: [u8; HIB_POOL_MEMORY_SIZE] = [0; HIB_POOL_MEMORY_SIZE];
         // SAFETY: This is safe because this is the only place HIB_POOL_MEMORY is used, thus there
         // are no race conditions. This is only run once since it consumes peripherals.
         HibPool::grow(unsafe { &mut HIB_POOL_MEMORY });
 
-        let sysctl = initialize_sysctl(peripherals.SYSCTL.constrain());
+        // Latch the reset cause before anything else can reset the chip and overwrite it.
+        let reset_cause = ResetCause::take(&peripherals.SYSCTL, &peripherals.HIB);
+
+        let sysctl = initialize_sysctl(peripherals.SYSCTL.constrain(), config.sysclk);
+        config.check_bauds(&sysctl.1);
+
+        #[cfg(feature = "panic-handler")]
+        crate::panic::set_sysclk(sysctl.1.sysclk.0);
+
         let mut porta = peripherals.GPIO_PORTA.split(&sysctl.0);
         let (uart0_tx, uart0_rx) = initialize_uart0(
             peripherals.UART0,
             porta.pa1.into_af_pull_up::<AF1>(&mut porta.control),
             porta.pa0.into_af_push_pull::<AF1>(&mut porta.control),
+            config.uart0_baud,
             &sysctl.1,
             &sysctl.0,
         );
         let mut portb = peripherals.GPIO_PORTB.split(&sysctl.0);
+        // The UART1 RX line is sampled for entropy as a floating input before UART1 takes it over. See
+        // the UartNoise entropy source for more details.
+        #[cfg(not(feature = "uart1-pc4-pc5"))]
+        let (uart1_tx_pin, uart1_rx_pin, uart1_rx_noise) = {
+            let pb0 = portb.pb0.into_floating_input();
+            let uart1_rx_noise = random::sample_rx_line(&pb0);
+
+            (
+                portb.pb1.into_af_pull_up::<AF1>(&mut portb.control),
+                pb0.into_af_push_pull::<AF1>(&mut portb.control),
+                uart1_rx_noise,
+            )
+        };
+        #[cfg(feature = "uart1-pc4-pc5")]
+        let (uart1_tx_pin, uart1_rx_pin, uart1_rx_noise) = {
+            // PC0 to PC3 are the JTAG pins, so they're left alone.
+            let mut portc = peripherals.GPIO_PORTC.split(&sysctl.0);
+            let pc4 = portc.pc4.into_floating_input();
+            let uart1_rx_noise = random::sample_rx_line(&pc4);
+
+            (
+                portc.pc5.into_af_pull_up::<AF2>(&mut portc.control),
+                pc4.into_af_push_pull::<AF2>(&mut portc.control),
+                uart1_rx_noise,
+            )
+        };
         let (uart1_tx, uart1_rx) = initialize_uart1(
             peripherals.UART1,
-            portb.pb1.into_af_pull_up::<AF1>(&mut portb.control),
-            portb.pb0.into_af_push_pull::<AF1>(&mut portb.control),
+            uart1_tx_pin,
+            uart1_rx_pin,
+            config.uart1_baud,
             &sysctl.1,
             &sysctl.0,
         );
+        communication::set_uart1_rx_fifo_trigger(config.uart1_rx_fifo_trigger);
+
+        let mut porte = peripherals.GPIO_PORTE.split(&sysctl.0);
+        let can0_rx = porte.pe4.into_af_push_pull::<AF8>(&mut porte.control);
+        let can0_tx = porte.pe5.into_af_push_pull::<AF8>(&mut porte.control);
+        let can0_timing = match BitTiming::new(config.can0_bitrate, &sysctl.1) {
+            Some(timing) => timing,
+            None => panic!(
+                "CAN0 bitrate of {} isn't achievable with a {} Hz system clock.",
+                config.can0_bitrate, sysctl.1.sysclk.0
+            ),
+        };
+        can::initialize_can0(&peripherals.CAN0, &sysctl.0, can0_timing);
+
+        if let Some(i2c_config) = config.i2c {
+            let timer_period = match i2c_config.speed.timer_period(&sysctl.1) {
+                Some(timer_period) => timer_period,
+                None => panic!(
+                    "I2C speed of {:?} isn't achievable with a {} Hz system clock.",
+                    i2c_config.speed, sysctl.1.sysclk.0
+                ),
+            };
+
+            // SCL is driven by the master, and SDA is open drain so targets can pull it low.
+            match i2c_config.bus {
+                I2cBus::I2c0 => {
+                    portb.pb2.into_af_push_pull::<AF3>(&mut portb.control);
+                    portb
+                        .pb3
+                        .into_af_open_drain::<AF3, PullUp>(&mut portb.control);
+                    i2c::initialize_i2c(&peripherals.I2C0, Domain::I2c0, &sysctl.0, timer_period);
+                }
+                I2cBus::I2c1 => {
+                    porta.pa6.into_af_push_pull::<AF3>(&mut porta.control);
+                    porta
+                        .pa7
+                        .into_af_open_drain::<AF3, PullUp>(&mut porta.control);
+                    i2c::initialize_i2c(&peripherals.I2C1, Domain::I2c1, &sysctl.0, timer_period);
+                }
+            }
+        }
+
+        let mut portf = peripherals.GPIO_PORTF.split(&sysctl.0);
+
+        // PF0 is an NMI pin, so it's locked and must be unlocked through the GPIO lock and commit
+        // registers before it can be configured.
+        let pf0 = portf.pf0.unlock(&mut portf.control).into_pull_up_input();
+
+        // The LED pins are configured through the same split as the button pins. Each pin only
+        // changes its own bits of the port registers, so the buttons keep their configuration.
+        let pf1 = portf.pf1.into_push_pull_output();
+        let pf2 = portf.pf2.into_push_pull_output();
+        let pf3 = portf.pf3.into_push_pull_output();
 
         RuntimePeripherals {
             cbp: core_peripherals.CBP,
@@ -350,6 +1250,7 @@ impl From<(CorePeripherals, Peripherals)> for RuntimePeripherals {
             tpiu: core_peripherals.TPIU,
             watchdog0: peripherals.WATCHDOG0,
             watchdog1: peripherals.WATCHDOG1,
+            #[cfg(not(feature = "uart1-pc4-pc5"))]
             gpio_portc: peripherals.GPIO_PORTC,
             gpio_portd: peripherals.GPIO_PORTD,
             ssi0: peripherals.SSI0,
@@ -366,12 +1267,14 @@ impl From<(CorePeripherals, Peripherals)> for RuntimePeripherals {
             i2c1: peripherals.I2C1,
             i2c2: peripherals.I2C2,
             i2c3: peripherals.I2C3,
-            gpio_porte: peripherals.GPIO_PORTE,
-            pf4: peripherals
-                .GPIO_PORTF
-                .split(&sysctl.0)
-                .pf4
-                .into_pull_up_input(),
+            can0_rx,
+            can0_tx,
+            i2c_config: config.i2c,
+            pf0,
+            pf1,
+            pf2,
+            pf3,
+            pf4: portf.pf4.into_pull_up_input(),
             pwm0: peripherals.PWM0,
             pwm1: peripherals.PWM1,
             qei0: peripherals.QEI0,
@@ -415,6 +1318,8 @@ impl From<(CorePeripherals, Peripherals)> for RuntimePeripherals {
             uart0_rx,
             uart1_tx,
             uart1_rx,
+            reset_cause,
+            uart1_rx_noise,
         }
     }
 }
@@ -0,0 +1,191 @@
+//! This module contains a reader for analog inputs through ADC0, such as a battery voltage divider.
+//!
+//! ADC0 is shared with the entropy sources, which sample the temperature sensor through it while
+//! seeding and reseeding the CSPRNGs. Every user of ADC0 goes through [`with_adc0()`], which powers
+//! ADC0 on and resets it before each use and powers it off afterwards, so neither user sees the
+//! other's sample sequencer configuration, and which refuses to run while ADC0 is already in use.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt;
+use tm4c123x_hal::tm4c123x::{adc0, gpio_porta, ADC0, GPIO_PORTB, GPIO_PORTD, GPIO_PORTE, SYSCTL};
+
+/// Whether ADC0 is in use by [`with_adc0()`].
+static ADC0_IN_USE: AtomicBool = AtomicBool::new(false);
+
+/// An error for when ADC0 is already in use, such as when a reading is taken from an interrupt handler
+/// while the main CSPRNG is reseeding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdcInUse;
+
+/// An analog input channel of ADC0. Each channel is fixed to one pin, which must not be used for
+/// anything else while it's read:
+///
+/// | Channel | Pin |
+/// |---------|-----|
+/// | AIN0    | PE3 |
+/// | AIN1    | PE2 |
+/// | AIN2    | PE1 |
+/// | AIN3    | PE0 |
+/// | AIN4    | PD3 |
+/// | AIN5    | PD2 |
+/// | AIN6    | PD1 |
+/// | AIN7    | PD0 |
+/// | AIN8    | PE5 |
+/// | AIN9    | PE4 |
+/// | AIN10   | PB4 |
+/// | AIN11   | PB5 |
+///
+/// PE4 and PE5 are used by CAN0, so AIN8 and AIN9 can't be read alongside the CAN0 controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum AdcChannel {
+    Ain0,
+    Ain1,
+    Ain2,
+    Ain3,
+    Ain4,
+    Ain5,
+    Ain6,
+    Ain7,
+    Ain8,
+    Ain9,
+    Ain10,
+    Ain11,
+}
+
+impl AdcChannel {
+    /// Gets the registers of the GPIO port of the channel's pin, the port's index in the GPIO clock
+    /// gating registers, and the pin number.
+    fn pin(self) -> (*const gpio_porta::RegisterBlock, u32, u32) {
+        match self {
+            Self::Ain0 => (GPIO_PORTE::ptr(), 4, 3),
+            Self::Ain1 => (GPIO_PORTE::ptr(), 4, 2),
+            Self::Ain2 => (GPIO_PORTE::ptr(), 4, 1),
+            Self::Ain3 => (GPIO_PORTE::ptr(), 4, 0),
+            Self::Ain4 => (GPIO_PORTD::ptr(), 3, 3),
+            Self::Ain5 => (GPIO_PORTD::ptr(), 3, 2),
+            Self::Ain6 => (GPIO_PORTD::ptr(), 3, 1),
+            Self::Ain7 => (GPIO_PORTD::ptr(), 3, 0),
+            Self::Ain8 => (GPIO_PORTE::ptr(), 4, 5),
+            Self::Ain9 => (GPIO_PORTE::ptr(), 4, 4),
+            Self::Ain10 => (GPIO_PORTB::ptr(), 1, 4),
+            Self::Ain11 => (GPIO_PORTB::ptr(), 1, 5),
+        }
+    }
+}
+
+/// The number of samples ADC0 averages in hardware into each reading. More samples give a less
+/// noisy reading at the cost of a longer conversion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum AdcAveraging {
+    None,
+    X2,
+    X4,
+    X8,
+    X16,
+    X32,
+    X64,
+}
+
+/// A reader for one analog input channel through ADC0. Readings are 12-bit, from 0 at 0 V to 4095 at
+/// 3.3 V.
+pub struct AdcReader {
+    channel: AdcChannel,
+    averaging: AdcAveraging,
+}
+
+impl AdcReader {
+    /// Creates a reader for an analog input channel, configuring the channel's pin as an analog
+    /// input. See [`AdcChannel`] for the pin of each channel.
+    pub fn new(channel: AdcChannel, averaging: AdcAveraging) -> Self {
+        let (port, port_index, pin) = channel.pin();
+
+        // SAFETY: Only the bits of the channel's pin and port are touched, and the read-modify-writes
+        // are done in critical sections so they can't race with other users of these registers.
+        let sysctl = unsafe { &*SYSCTL::ptr() };
+        // SAFETY: See above.
+        let port = unsafe { &*port };
+
+        interrupt::free(|_| {
+            // SAFETY: Setting one port's bit in the clock gating register is valid.
+            sysctl
+                .rcgcgpio
+                .modify(|r, w| unsafe { w.bits(r.bits() | 1 << port_index) });
+        });
+        while sysctl.prgpio.read().bits() & 1 << port_index == 0 {}
+
+        interrupt::free(|_| {
+            // SAFETY: Switching one pin to its analog function is valid for these registers.
+            unsafe {
+                port.den.modify(|r, w| w.bits(r.bits() & !(1 << pin)));
+                port.afsel.modify(|r, w| w.bits(r.bits() | 1 << pin));
+                port.amsel.modify(|r, w| w.bits(r.bits() | 1 << pin));
+            }
+        });
+
+        Self { channel, averaging }
+    }
+
+    /// Takes an averaged 12-bit reading of the channel.
+    ///
+    /// # ERRORS:
+    ///
+    /// - [`AdcInUse`] - ADC0 is in use, such as when this is called from an interrupt handler while
+    ///   the main CSPRNG is reseeding.
+    pub fn read(&mut self) -> Result<u16, AdcInUse> {
+        with_adc0(|adc0| {
+            // Use Sample Sequencer 3, which takes one sample, triggered by software.
+            adc0.actss.write(|w| w.asen3().clear_bit());
+            adc0.emux.write(|w| w.em3().processor());
+
+            // SAFETY: The averaging and channel are both in range for these registers.
+            unsafe {
+                adc0.sac.write(|w| w.bits(self.averaging as u32));
+                adc0.ssmux3.write(|w| w.bits(self.channel as u32));
+            }
+
+            adc0.ssctl3.write(|w| w.ie0().set_bit().end0().set_bit());
+            adc0.actss.write(|w| w.asen3().set_bit());
+
+            adc0.pssi.write(|w| w.ss3().set_bit());
+            while adc0.ris.read().inr3().bit_is_clear() {}
+            let reading = adc0.ssfifo3.read().data().bits();
+            adc0.isc.write(|w| w.in3().set_bit());
+
+            reading
+        })
+        .ok_or(AdcInUse)
+    }
+}
+
+/// Powers on and resets ADC0, runs a function with it, and powers it off again. Returns `None`
+/// without running the function if ADC0 is already in use, such as when this is called from an
+/// interrupt handler that interrupted another use of ADC0.
+pub(crate) fn with_adc0<R>(f: impl FnOnce(&adc0::RegisterBlock) -> R) -> Option<R> {
+    if ADC0_IN_USE.swap(true, Ordering::Acquire) {
+        return None;
+    }
+
+    // SAFETY: Only the ADC0 bits of the clock gating, software reset, and peripheral ready registers
+    // are touched, and the read-modify-writes are done in critical sections so they can't race with
+    // other users of these registers.
+    let sysctl = unsafe { &*SYSCTL::ptr() };
+    // SAFETY: ADC0 is only used through this function, which only lets one caller use it at a time.
+    let adc0 = unsafe { &*ADC0::ptr() };
+
+    // Turn it on and reset it, so the previous user's configuration doesn't carry over.
+    interrupt::free(|_| sysctl.rcgcadc.modify(|_, w| w.r0().set_bit()));
+    interrupt::free(|_| sysctl.sradc.modify(|_, w| w.r0().set_bit()));
+    interrupt::free(|_| sysctl.sradc.modify(|_, w| w.r0().clear_bit()));
+    while sysctl.pradc.read().r0().bit_is_clear() {}
+
+    let res = f(adc0);
+
+    // Turn it off!
+    interrupt::free(|_| sysctl.rcgcadc.modify(|_, w| w.r0().clear_bit()));
+
+    ADC0_IN_USE.store(false, Ordering::Release);
+
+    Some(res)
+}
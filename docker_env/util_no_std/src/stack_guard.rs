@@ -0,0 +1,103 @@
+//! This module contains a stack guard that uses the MPU to make a stack overflow fault instead of
+//! silently overwriting whatever is below the stack. See [`Runtime::configure_stack_guard()`] for
+//! more details.
+//!
+//! [`Runtime::configure_stack_guard()`]: crate::Runtime::configure_stack_guard
+
+use cortex_m::{
+    asm,
+    peripheral::{MPU, SCB},
+};
+use cortex_m_rt::{exception, ExceptionFrame};
+
+/// The size of the stack guard in bytes. This must be a power of two of at least 32. Once the guard is
+/// hit, the fault handler turns off the MPU and uses the guard as its stack, so this must be large
+/// enough for the panic handler to run.
+pub const STACK_GUARD_SIZE: u32 = 1024;
+
+/// The MPU region used for the stack guard. Higher regions take priority, so the highest region is
+/// used to keep any other region from granting access to the guard.
+const STACK_GUARD_REGION: u32 = 7;
+
+// MPU_CTRL bits.
+const MPU_CTRL_ENABLE: u32 = 1 << 0;
+const MPU_CTRL_PRIVDEFENA: u32 = 1 << 2;
+
+// MPU_RASR bits. The access permission bits are left as zero, which denies all access.
+const MPU_RASR_ENABLE: u32 = 1 << 0;
+const MPU_RASR_SIZE_SHIFT: u32 = 1;
+const MPU_RASR_XN: u32 = 1 << 28;
+
+// SCB_SHCSR bits.
+const SHCSR_MEMFAULTENA: u32 = 1 << 16;
+
+// SCB_CFSR bits.
+const CFSR_MMARVALID: u32 = 1 << 7;
+
+/// Enables the MemManage fault, so MPU faults run [`MemoryManagement()`] instead of escalating to a
+/// hard fault.
+pub(crate) fn enable_mem_manage_fault(scb: &mut SCB) {
+    // SAFETY: Only the MemManage fault enable bit is set.
+    unsafe { scb.shcsr.modify(|r| r | SHCSR_MEMFAULTENA) };
+}
+
+/// Configures a no-access MPU region of [`STACK_GUARD_SIZE`] bytes at the lowest aligned address at
+/// or above `stack_limit`, and enables the MPU with the default memory map for everything else.
+pub(crate) fn configure_stack_guard(mpu: &mut MPU, stack_limit: *const u32) {
+    let base = (stack_limit as u32 + STACK_GUARD_SIZE - 1) & !(STACK_GUARD_SIZE - 1);
+    let size_field = STACK_GUARD_SIZE.trailing_zeros() - 1;
+
+    // SAFETY: The region number is in range, the base is aligned to the region size, and the size
+    // field encodes a valid power of two. The default memory map is kept for privileged code, which
+    // all of the firmware is, so only the guard is affected.
+    unsafe {
+        mpu.ctrl.write(0);
+        mpu.rnr.write(STACK_GUARD_REGION);
+        mpu.rbar.write(base);
+        mpu.rasr
+            .write(MPU_RASR_XN | size_field << MPU_RASR_SIZE_SHIFT | MPU_RASR_ENABLE);
+        mpu.ctrl.write(MPU_CTRL_PRIVDEFENA | MPU_CTRL_ENABLE);
+    }
+
+    // Make sure the guard is in place before anything else touches the stack.
+    asm::dsb();
+    asm::isb();
+}
+
+/// Turns off the MPU so a fault handler can use the stack guard as its stack.
+fn disable_mpu() {
+    // SAFETY: This is only run in fault handlers, which never return, so nothing relies on the MPU
+    // afterwards.
+    unsafe { (*MPU::PTR).ctrl.write(0) };
+
+    asm::dsb();
+    asm::isb();
+}
+
+/// Routes MPU faults, such as hitting the stack guard, to the panic handler.
+#[exception]
+unsafe fn MemoryManagement() -> ! {
+    disable_mpu();
+
+    // SAFETY: This register is only read.
+    let cfsr = (*SCB::PTR).cfsr.read();
+
+    if cfsr & CFSR_MMARVALID != 0 {
+        // SAFETY: This register is only read.
+        let mmfar = (*SCB::PTR).mmfar.read();
+
+        panic!("Memory protection fault at {mmfar:#010X}, likely a stack overflow.");
+    } else {
+        panic!("Memory protection fault, likely a stack overflow.");
+    }
+}
+
+/// Routes hard faults to the panic handler. Hitting the stack guard while the exception frame is
+/// being pushed escalates to a hard fault, which runs with the MPU ignored, so this also catches
+/// stack overflows.
+#[exception]
+unsafe fn HardFault(ef: &ExceptionFrame) -> ! {
+    disable_mpu();
+
+    panic!("Hard fault at PC {:#010X}.", ef.pc());
+}
@@ -4,29 +4,41 @@
 use crate::HibPool;
 use core::time::Duration;
 use heapless::Arc;
+use tm4c123x_hal::tm4c123x::{hib, HIB};
 
 pub use ucsc_ectf_util_common::timer::*;
 
 /// The timer struct. Used to count a specific amount of time with the hibernation clock. Timers
 /// will only work properly if the uptime of the system is less than 2^32 seconds (~136.2 years)
 /// at the time of timer polling. Timers have an accuracy of 1/32768 seconds.
+///
+/// Timers never program the hibernation module. Each timer captures its deadline when it's created
+/// and compares it against the free-running RTC in software, so any number of timers can run at the
+/// same time, such as a button debounce timer alongside a receive timeout.
 pub struct HibTimer<'a> {
     duration: Duration,
     hib: &'a Arc<HibPool>,
+    start_subseconds: u64,
     end_subseconds: u64,
 }
 
 impl<'a> HibTimer<'a> {
     const SUBSECONDS_PER_SECOND: u64 = 32_768;
     const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
+    const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
 
     /// Converts (seconds, subseconds) to subseconds.
-    fn time_to_subseconds((sec, subsec): (u32, u16)) -> u64 {
+    pub(crate) fn time_to_subseconds((sec, subsec): (u32, u16)) -> u64 {
         (sec as u64) * Self::SUBSECONDS_PER_SECOND + (subsec as u64)
     }
 
     /// Gets the current time from the hibernation clock.
-    fn get_time_hib(hib: &Arc<HibPool>) -> (u32, u16) {
+    pub(crate) fn get_time_hib(hib: &Arc<HibPool>) -> (u32, u16) {
+        Self::read_rtc(hib)
+    }
+
+    /// Gets the current time from the registers of the hibernation module.
+    fn read_rtc(hib: &hib::RegisterBlock) -> (u32, u16) {
         loop {
             // A read from the RTC is only valid when the seconds count is the same before and after
             // retrieving the subseconds count.
@@ -39,14 +51,22 @@ impl<'a> HibTimer<'a> {
         }
     }
 
+    /// Converts subseconds to a [`Duration`].
+    pub(crate) fn subseconds_to_duration(subseconds: u64) -> Duration {
+        Duration::new(
+            subseconds / Self::SUBSECONDS_PER_SECOND,
+            ((subseconds % Self::SUBSECONDS_PER_SECOND) * Self::NANOSECONDS_PER_SECOND
+                / Self::SUBSECONDS_PER_SECOND) as u32,
+        )
+    }
+
     /// Gets the current time from the hibernation clock.
     fn get_time(&self) -> (u32, u16) {
         Self::get_time_hib(self.hib)
     }
 
-    fn new_impl(hib: &'a Arc<HibPool>, duration: Duration) -> Self {
-        let curr_subseconds = Self::time_to_subseconds(Self::get_time_hib(hib));
-
+    /// Converts a [`Duration`] to subseconds.
+    fn duration_to_subseconds(duration: Duration) -> u64 {
         let duration_secs = duration
             .as_secs()
             .try_into()
@@ -55,15 +75,27 @@ impl<'a> HibTimer<'a> {
         let duration_subsecs = (duration.subsec_micros() as u64 * Self::SUBSECONDS_PER_SECOND
             / Self::MICROSECONDS_PER_SECOND) as u16;
 
-        let subsecond_duration = Self::time_to_subseconds((duration_secs, duration_subsecs));
+        Self::time_to_subseconds((duration_secs, duration_subsecs))
+    }
+
+    fn new_impl(hib: &'a Arc<HibPool>, duration: Duration) -> Self {
+        let curr_subseconds = Self::time_to_subseconds(Self::get_time_hib(hib));
+        let subsecond_duration = Self::duration_to_subseconds(duration);
 
         HibTimer {
             hib,
+            start_subseconds: curr_subseconds,
             end_subseconds: curr_subseconds + subsecond_duration,
             duration,
         }
     }
 
+    /// Returns whether time is up by comparing the current RTC time against the deadline of this
+    /// timer. Unlike [`Timer::poll()`], this doesn't need mutable access to the timer.
+    pub fn has_elapsed(&self) -> bool {
+        Self::time_to_subseconds(self.get_time()) >= self.end_subseconds
+    }
+
     #[cfg(not(debug_assertions))]
     /// Initializes a timer that expires after a certain duration.
     pub(crate) fn new(hib: &'a Arc<HibPool>, duration: Duration) -> Self {
@@ -79,7 +111,7 @@ impl<'a> HibTimer<'a> {
 
 impl<'a> Timer for HibTimer<'a> {
     fn poll(&mut self) -> bool {
-        Self::time_to_subseconds(self.get_time()) >= self.end_subseconds
+        self.has_elapsed()
     }
 
     fn reset(&mut self) {
@@ -89,4 +121,127 @@ impl<'a> Timer for HibTimer<'a> {
     fn duration(&self) -> Duration {
         self.duration
     }
+
+    /// Gets how much time has passed since the timer was created or last reset, saturating at the
+    /// duration of the timer. The current time is read from the hibernation clock with the re-read
+    /// required to avoid a torn read across a second boundary.
+    fn elapsed(&self) -> Duration {
+        let curr_subseconds = Self::time_to_subseconds(self.get_time());
+
+        if curr_subseconds >= self.end_subseconds {
+            return self.duration;
+        }
+
+        Self::subseconds_to_duration(curr_subseconds.saturating_sub(self.start_subseconds))
+            .min(self.duration)
+    }
+}
+
+/// A timer that fires once per period. Used for periodic work, such as a heartbeat, without
+/// recreating a timer each time it fires. Like [`HibTimer`], it compares against the free-running RTC
+/// in software, so it can run alongside any number of other timers.
+///
+/// Deadlines are always whole periods after the time the timer was created, so timing doesn't drift
+/// no matter how late [`PeriodicTimer::poll()`] is called. If `poll` is called more than one period
+/// after a deadline, it returns `true` only once and the missed periods are skipped, so the next
+/// deadline is the first one that's still in the future.
+pub struct PeriodicTimer<'a> {
+    period: Duration,
+    hib: &'a Arc<HibPool>,
+    start_subseconds: u64,
+    next_subseconds: u64,
+}
+
+impl<'a> PeriodicTimer<'a> {
+    fn new_impl(hib: &'a Arc<HibPool>, period: Duration) -> Self {
+        assert!(
+            HibTimer::duration_to_subseconds(period) != 0,
+            "Period is too short."
+        );
+
+        let curr_subseconds = HibTimer::time_to_subseconds(HibTimer::get_time_hib(hib));
+        let mut timer = PeriodicTimer {
+            period,
+            hib,
+            start_subseconds: curr_subseconds,
+            next_subseconds: 0,
+        };
+
+        timer.next_subseconds = timer.deadline(1);
+        timer
+    }
+
+    /// Gets the time of the deadline at the end of the given period in subseconds. A period usually
+    /// isn't a whole number of subseconds, so each deadline is computed from the start of the timer
+    /// instead of by adding a rounded period to the last deadline, which would let the rounding error
+    /// build up. The deadline is rounded up, so it's never before the exact time.
+    fn deadline(&self, period: u64) -> u64 {
+        let subseconds =
+            (period as u128 * self.period.as_nanos() * HibTimer::SUBSECONDS_PER_SECOND as u128)
+                .div_ceil(HibTimer::NANOSECONDS_PER_SECOND as u128);
+
+        self.start_subseconds + subseconds as u64
+    }
+
+    #[cfg(not(debug_assertions))]
+    /// Initializes a timer that fires once every period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period is shorter than the 1/32768 second accuracy of the timer.
+    pub(crate) fn new(hib: &'a Arc<HibPool>, period: Duration) -> Self {
+        Self::new_impl(hib, period)
+    }
+
+    #[cfg(debug_assertions)]
+    /// Initializes a timer that fires once every period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period is shorter than the 1/32768 second accuracy of the timer.
+    pub fn new(hib: &'a Arc<HibPool>, period: Duration) -> Self {
+        Self::new_impl(hib, period)
+    }
+
+    /// Polls the timer, returning `true` once for each deadline that has passed since the last time
+    /// it returned `true`, skipping any periods that were missed entirely. See the documentation for
+    /// [`PeriodicTimer`] for more details.
+    pub fn poll(&mut self) -> bool {
+        let curr_subseconds = HibTimer::time_to_subseconds(HibTimer::get_time_hib(self.hib));
+
+        if curr_subseconds < self.next_subseconds {
+            return false;
+        }
+
+        // Advance to the first deadline after the current time, skipping missed periods. This is the
+        // deadline after the number of whole periods that have passed since the start of the timer.
+        let passed_periods = (curr_subseconds - self.start_subseconds) as u128
+            * HibTimer::NANOSECONDS_PER_SECOND as u128
+            / (self.period.as_nanos() * HibTimer::SUBSECONDS_PER_SECOND as u128);
+        self.next_subseconds = self.deadline(passed_periods as u64 + 1);
+
+        true
+    }
+
+    /// Gets the period of the timer.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+/// Gets the time of the RTC without a reference to the hibernation module, for code that runs once the
+/// hibernation module has been handed off to the runtime, such as the UART controllers. Returns
+/// [`None`] if the RTC isn't running, since its time would never change.
+pub(crate) fn rtc_now() -> Option<Duration> {
+    // SAFETY: The hibernation module registers are only read here, so this can't interfere with the
+    // hibernation controller.
+    let hib = unsafe { &*HIB::ptr() };
+
+    if hib.ctl.read().rtcen().bit_is_clear() {
+        return None;
+    }
+
+    Some(HibTimer::subseconds_to_duration(
+        HibTimer::time_to_subseconds(HibTimer::read_rtc(hib)),
+    ))
 }
@@ -0,0 +1,94 @@
+//! This module contains an interface to use the WATCHDOG0 peripheral to reset the chip if the
+//! program hangs.
+
+use core::time::Duration;
+use cortex_m::peripheral::NVIC;
+use tm4c123x_hal::{
+    sysctl::{self, Clocks, Domain, PowerControl, PowerState, RunMode},
+    tm4c123x::{Interrupt, WATCHDOG0},
+};
+
+/// The watchdog controller. The watchdog starts disabled and must be enabled with
+/// [`WatchdogController::enable()`]. Once enabled, it must be fed with
+/// [`WatchdogController::feed()`] before its timeout passes, or the chip resets. A watchdog reset
+/// is reported as such by the reset cause of the next boot.
+pub struct WatchdogController<'a> {
+    watchdog: &'a mut WATCHDOG0,
+    sysclk: u32,
+}
+
+impl<'a> WatchdogController<'a> {
+    /// Initializes the watchdog controller. The watchdog is left disabled.
+    pub(crate) fn new(
+        watchdog: &'a mut WATCHDOG0,
+        power_control: &PowerControl,
+        clocks: &Clocks,
+    ) -> Self {
+        sysctl::control_power(
+            power_control,
+            Domain::Watchdog0,
+            RunMode::Run,
+            PowerState::On,
+        );
+        sysctl::reset(power_control, Domain::Watchdog0);
+
+        // Stop the watchdog while the processor is halted by a debugger so debugging doesn't reset
+        // the chip.
+        watchdog.test.modify(|_, w| w.stall().set_bit());
+
+        Self {
+            watchdog,
+            sysclk: clocks.sysclk.0,
+        }
+    }
+
+    /// Enables the watchdog so it resets the chip if it isn't fed within the timeout. If the watchdog
+    /// is already enabled, this changes its timeout and feeds it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timeout is zero or longer than the watchdog can count, which is around 107
+    /// seconds at an 80 MHz system clock.
+    pub fn enable(&mut self, timeout: Duration) {
+        // The watchdog raises an interrupt the first time it counts down to zero and resets the chip
+        // the second time, so it counts down half of the timeout each time.
+        let load: u32 = (timeout.as_micros() * self.sysclk as u128 / 1_000_000 / 2)
+            .try_into()
+            .expect("Watchdog timeout is too long.");
+
+        assert!(load != 0, "Watchdog timeout is too short.");
+
+        // SAFETY: Every value is valid for the load register.
+        self.watchdog.load.write(|w| unsafe { w.bits(load) });
+        self.feed();
+
+        // The interrupt can't be disabled once it's enabled, but it's masked in the NVIC, so it
+        // only serves to arm the reset and to wake the core from Runtime::start_low_power().
+        self.watchdog
+            .ctl
+            .modify(|_, w| w.inten().set_bit().resen().set_bit());
+    }
+
+    /// Disables the watchdog resetting the chip. This should be used before long blocking operations
+    /// such as entropy gathering. The watchdog can be enabled again with
+    /// [`WatchdogController::enable()`].
+    pub fn disable(&mut self) {
+        self.watchdog.ctl.modify(|_, w| w.resen().clear_bit());
+    }
+
+    /// Returns whether the watchdog is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.watchdog.ctl.read().resen().bit_is_set()
+    }
+
+    /// Feeds the watchdog, restarting its timeout. This does nothing if the watchdog is disabled.
+    pub fn feed(&self) {
+        // SAFETY: Writing any value to the interrupt clear register clears the timeout interrupt and
+        // reloads the counter.
+        self.watchdog.icr.write(|w| unsafe { w.bits(0) });
+
+        // The interrupt is masked, so it stays pending in the NVIC until it's cleared here. It must
+        // be cleared so the next timeout can wake the core again.
+        NVIC::unpend(Interrupt::WATCHDOG0);
+    }
+}
@@ -15,9 +15,14 @@
 //! - The application layer
 //!     - The application layer is the layer responsible for incorporating the lower two layers together.
 //!       This crate provides an implementation of this layer through the [`VerifiedFramedTcpSocket`] struct.
+//!
+//! For testing, [`LoopbackChannel`] provides an in-memory pair of channels with the same framing as the
+//! channels used on the UARTs.
 
 pub(crate) mod framed_tcp;
+mod loopback;
 mod verified_framed_tcp;
 
+pub use loopback::*;
 pub use ucsc_ectf_util_common::communication::*;
 pub use verified_framed_tcp::*;
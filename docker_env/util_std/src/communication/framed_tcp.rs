@@ -128,4 +128,8 @@ impl FramedTxChannel for FramedTcpTxChannel {
             MIN_FRAMED_UART_MESSAGE,
         )
     }
+
+    fn flush_frames(&mut self) -> communication::Result<()> {
+        self.0.flush().map_err(|_| CommunicationError::SendError)
+    }
 }
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use ucsc_ectf_util_common::{
+    communication::{
+        self,
+        lower_layers::framing::{bogoframing, Frame, FramedTxChannel},
+        CommunicationError, RxChannel,
+    },
+    messages::heapless::Deque,
+    timer::Timer,
+};
+
+use super::framed_tcp::MIN_FRAMED_UART_MESSAGE;
+
+/// The default number of bytes that can be waiting in each direction of a [`LoopbackChannel`] pair.
+/// BogoFraming doubles the size of each message, plus two bytes for the frame delimiters.
+pub const DEFAULT_LOOPBACK_CAPACITY: usize = 4096;
+
+/// The bytes sent in one direction of a [`LoopbackChannel`] pair that haven't been received yet.
+type LoopbackQueue<const N: usize> = Arc<Mutex<Deque<u8, N>>>;
+
+/// An in-memory channel for testing code that sends and receives through channels, such as protocol
+/// state machines, without real UART peripherals. Create two connected channels with
+/// [`LoopbackChannel::pair`]. Everything sent through one channel of the pair is received by the other.
+///
+/// Each message is framed with BogoFraming into a queue of bytes, with the same minimum message length
+/// as on the UARTs, so sending and receiving have the same framing and length semantics as the real
+/// channels, including [`CommunicationError::BufferTooSmall`] for messages that don't fit in ``dest``.
+/// Like a real UART, receiving waits for bytes until the timer expires, so each channel of a pair can be
+/// used from its own thread. Each message is queued whole, so [`try_recv`](RxChannel::try_recv)
+/// receives it as soon as it has been sent, like UART1 with interrupt-driven reception. Channels in the
+/// [`crypto`](super::lower_layers::crypto) layer can wrap a [`LoopbackChannel`] to test encrypted
+/// protocols.
+///
+/// Each direction holds up to ``N`` bytes of framed messages. Sending a message that doesn't fit in the
+/// space left fails with [`CommunicationError::SendError`] without queueing any of it.
+pub struct LoopbackChannel<const N: usize = DEFAULT_LOOPBACK_CAPACITY> {
+    tx: LoopbackQueue<N>,
+    rx: LoopbackQueue<N>,
+}
+
+impl<const N: usize> LoopbackChannel<N> {
+    /// Creates two [`LoopbackChannels`](LoopbackChannel) connected back-to-back.
+    pub fn pair() -> (Self, Self) {
+        let a_to_b: LoopbackQueue<N> = Arc::new(Mutex::new(Deque::new()));
+        let b_to_a: LoopbackQueue<N> = Arc::new(Mutex::new(Deque::new()));
+
+        (
+            Self {
+                tx: a_to_b.clone(),
+                rx: b_to_a.clone(),
+            },
+            Self {
+                tx: b_to_a,
+                rx: a_to_b,
+            },
+        )
+    }
+
+    /// Gets the number of bytes sent by the other channel of the pair that haven't been received yet.
+    pub fn pending(&self) -> usize {
+        lock(&self.rx).map_or(0, |rx| rx.len())
+    }
+
+    /// Reads a byte sent by the other channel of the pair, returning an error if there isn't one, which
+    /// the framing layer treats the same as an empty UART FIFO.
+    fn read_byte(&mut self) -> communication::Result<u8> {
+        lock(&self.rx)?
+            .pop_front()
+            .ok_or(CommunicationError::RecvError)
+    }
+}
+
+/// Locks a queue. A queue is only poisoned if a thread panicked while holding the lock, which none of
+/// the code here can do.
+fn lock<const N: usize>(
+    queue: &LoopbackQueue<N>,
+) -> communication::Result<MutexGuard<'_, Deque<u8, N>>> {
+    queue.lock().map_err(|_| CommunicationError::InternalError)
+}
+
+impl<const N: usize> RxChannel for LoopbackChannel<N> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_data_timeout(
+            self,
+            dest,
+            timer,
+            Self::read_byte,
+            MIN_FRAMED_UART_MESSAGE,
+        )
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_timeout(
+            self,
+            dest,
+            timer,
+            Self::read_byte,
+            MIN_FRAMED_UART_MESSAGE,
+        )
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        let mut rx = lock(&self.rx)?;
+
+        let Some((used, res)) =
+            bogoframing::decode_buffered_frame(rx.iter().copied(), dest, MIN_FRAMED_UART_MESSAGE)
+        else {
+            return Ok(None);
+        };
+
+        for _ in 0..used {
+            rx.pop_front();
+        }
+
+        res.map(Some)
+    }
+}
+
+impl<const N: usize> FramedTxChannel for LoopbackChannel<N> {
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        let frame = frame()?;
+        let mut tx = lock(&self.tx)?;
+
+        // Each byte is sent as two hex digits between two delimiters.
+        if N - tx.len() < frame.len() * 2 + 2 {
+            return Err(CommunicationError::SendError);
+        }
+
+        bogoframing::frame_bogoframe(
+            &mut *tx,
+            frame,
+            |queue, s| {
+                s.iter()
+                    .try_for_each(|&b| queue.push_back(b))
+                    .map_err(|_| CommunicationError::SendError)
+            },
+            MIN_FRAMED_UART_MESSAGE,
+        )
+    }
+}
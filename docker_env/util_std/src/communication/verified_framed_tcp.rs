@@ -1,6 +1,6 @@
 use std::net::ToSocketAddrs;
 
-use rand::RngCore;
+use rand::{CryptoRng, RngCore};
 use ucsc_ectf_util_common::{
     communication::{
         self,
@@ -29,6 +29,9 @@ impl RandomSource for StdRandomSource {
     }
 }
 
+// The random bytes come from the thread-local CSPRNG, which is seeded by the OS.
+impl CryptoRng for StdRandomSource {}
+
 /// This struct contains an [`RxChannel`] and [`TxChannel`] for a TCP socket that frames messages
 /// using BogoFraming. See the [`framing`](super::lower_layers::framing) module for more information
 /// on this type of framing.
@@ -80,4 +83,15 @@ impl TxChannel for VerifiedFramedTcpSocket {
     fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
         self.tx_channel.send(src)
     }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.tx_channel.flush()
+    }
+
+    fn send_vectored<const MAX_LEN: usize>(
+        &mut self,
+        parts: &[&[u8]],
+    ) -> communication::Result<()> {
+        self.tx_channel.send_vectored::<MAX_LEN>(parts)
+    }
 }
@@ -9,6 +9,7 @@ pub use ucsc_ectf_util_common::timer::*;
 /// A timer that uses [`Instants`](Instant) to implement a [`Timer`] that works in std environments.
 pub struct StdTimer {
     duration: Duration,
+    start: Instant,
     end: Instant,
 }
 
@@ -16,9 +17,12 @@ impl StdTimer {
     /// Creates a new [`StdTimer`] with a provided [`Duration`]
     /// for when the timer expires.
     pub fn new(duration: Duration) -> Self {
+        let start = Instant::now();
+
         StdTimer {
             duration,
-            end: Instant::now() + duration,
+            start,
+            end: start + duration,
         }
     }
 }
@@ -29,10 +33,15 @@ impl Timer for StdTimer {
     }
 
     fn reset(&mut self) {
-        self.end = Instant::now() + self.duration;
+        self.start = Instant::now();
+        self.end = self.start + self.duration;
     }
 
     fn duration(&self) -> Duration {
         self.duration
     }
+
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed().min(self.duration)
+    }
 }
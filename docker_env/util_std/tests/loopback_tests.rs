@@ -0,0 +1,124 @@
+//! Host-side tests for [`LoopbackChannel`].
+
+use std::{thread, time::Duration};
+use ucsc_ectf_util_std::{
+    communication::{CommunicationError, LoopbackChannel, RxChannel, TxChannel},
+    timer::{StdTimer, Timer},
+};
+
+/// A message long enough to meet the minimum framed message length.
+const MSG: &[u8] = b"Hello from the loopback channel!";
+
+/// How long a receive waits for a message that never comes.
+const TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Tests that a message sent through either channel of a pair is received whole by the other.
+#[test]
+fn round_trip_test() {
+    let (mut a, mut b) = LoopbackChannel::<256>::pair();
+    let mut dest = [0; 64];
+
+    a.send(&mut MSG.to_vec()).unwrap();
+    assert!(b.pending() > 0);
+    assert_eq!(a.pending(), 0);
+
+    let len = b
+        .recv_with_timeout(&mut dest, &mut StdTimer::new(TIMEOUT))
+        .unwrap();
+    assert_eq!(&dest[..len], MSG);
+    assert_eq!(b.pending(), 0);
+
+    let mut reply = *b"A reply going the other way back.";
+    b.send(&mut reply).unwrap();
+
+    let len = a.try_recv(&mut dest).unwrap().unwrap();
+    assert_eq!(&dest[..len], b"A reply going the other way back.");
+}
+
+/// Tests that messages are received in the order they were sent.
+#[test]
+fn order_test() {
+    let (mut a, mut b) = LoopbackChannel::<256>::pair();
+    let mut dest = [0; 64];
+
+    a.send(&mut b"The first message sent through.".to_vec())
+        .unwrap();
+    a.send(&mut b"The second message sent through.".to_vec())
+        .unwrap();
+
+    let len = b
+        .recv_with_timeout(&mut dest, &mut StdTimer::new(TIMEOUT))
+        .unwrap();
+    assert_eq!(&dest[..len], b"The first message sent through.");
+
+    let len = b
+        .recv_with_timeout(&mut dest, &mut StdTimer::new(TIMEOUT))
+        .unwrap();
+    assert_eq!(&dest[..len], b"The second message sent through.");
+}
+
+/// Tests that receiving with nothing sent times out once the timer expires, and that trying to
+/// receive returns straight away.
+#[test]
+fn timeout_test() {
+    let (_a, mut b) = LoopbackChannel::<256>::pair();
+    let mut dest = [0; 64];
+    let mut timer = StdTimer::new(TIMEOUT);
+
+    assert_eq!(
+        b.recv_with_timeout(&mut dest, &mut timer),
+        Err(CommunicationError::Timeout)
+    );
+    assert!(timer.poll());
+
+    assert_eq!(
+        b.recv_with_data_timeout(&mut dest, &mut StdTimer::new(TIMEOUT)),
+        Err(CommunicationError::Timeout)
+    );
+    assert_eq!(b.try_recv(&mut dest), Ok(None));
+}
+
+/// Tests that a receive waits for a message sent from another thread before the timer expires.
+#[test]
+fn threaded_round_trip_test() {
+    let (mut a, mut b) = LoopbackChannel::<256>::pair();
+    let mut dest = [0; 64];
+
+    let sender = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        a.send(&mut MSG.to_vec()).unwrap();
+    });
+
+    let len = b
+        .recv_with_timeout(&mut dest, &mut StdTimer::new(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(&dest[..len], MSG);
+
+    sender.join().unwrap();
+}
+
+/// Tests that a message that doesn't fit in ``dest`` or in the space left in the queue is refused.
+#[test]
+fn size_test() {
+    let (mut a, mut b) = LoopbackChannel::<80>::pair();
+    let mut dest = [0; 16];
+
+    // The framed message takes 2 * 32 + 2 bytes, so a second one doesn't fit.
+    a.send(&mut MSG.to_vec()).unwrap();
+    assert_eq!(
+        a.send(&mut MSG.to_vec()),
+        Err(CommunicationError::SendError)
+    );
+
+    assert_eq!(
+        b.recv_with_timeout(&mut dest, &mut StdTimer::new(TIMEOUT)),
+        Err(CommunicationError::BufferTooSmall { needed: MSG.len() })
+    );
+
+    // Messages shorter than the minimum framed message length aren't sent.
+    assert_eq!(
+        b.send(&mut b"Too short".to_vec()),
+        Err(CommunicationError::SendError)
+    );
+    assert_eq!(a.pending(), 0);
+}
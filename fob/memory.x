@@ -12,3 +12,6 @@ reserved space and flash memory, instead of .data and .bss.
 */
 
 _stack_start = ORIGIN(STACK) + LENGTH(STACK);
+
+/* The bottom of the stack, for the stack guard. See Runtime::configure_stack_guard(). */
+_stack_end = ORIGIN(STACK);
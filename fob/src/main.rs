@@ -4,9 +4,7 @@
 #![no_main]
 #![no_std]
 
-extern crate panic_halt;
-
-use core::{arch::global_asm, time::Duration};
+use core::{arch::global_asm, ptr, time::Duration};
 use cortex_m::interrupt;
 use cortex_m_rt::entry;
 use tm4c123x_hal::{CorePeripherals, Peripherals};
@@ -39,6 +37,11 @@ jump_to_reset:
 "#
 );
 
+extern "C" {
+    /// The bottom of the stack, defined in memory.x.
+    static _stack_end: u32;
+}
+
 #[entry]
 fn main() -> ! {
     // Enable interrupts because the bootloader disables them and leaves them disabled.
@@ -47,18 +50,25 @@ fn main() -> ! {
         interrupt::enable();
     }
 
+    // Wipe the key buffers if anything panics.
+    unlock::register_key_buffers();
+
     // Grab peripherals.
     let core_peripherals = CorePeripherals::take().unwrap();
     let peripherals = Peripherals::take().unwrap();
     let mut rt_peripherals = RuntimePeripherals::from((core_peripherals, peripherals));
 
     // Initialize runtime.
-    let mut rt = Runtime::new(
+    let mut rt = Runtime::new_or_panic(
         &mut rt_peripherals,
         &Default::default(),
         &Default::default(),
     );
 
+    // Fault on a stack overflow instead of running off the bottom of the stack.
+    // SAFETY: _stack_end is defined in memory.x, and only its address is taken.
+    rt.configure_stack_guard(unsafe { ptr::addr_of!(_stack_end) });
+
     // Get pairing status.
     let mut pairing_byte = [0; BYTE_FIELD_SIZE];
 
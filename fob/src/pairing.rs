@@ -2,6 +2,7 @@ use crate::MAX_MESSAGE_SIZE;
 use core::time::Duration;
 use ucsc_ectf_util_no_std::{
     communication::{CommunicationError, TxChannel},
+    constant_time_eq,
     eeprom::{EepromController, EepromReadWriteField, BYTE_FIELD_SIZE, PAIRING_PIN_SIZE},
     hib::HibController,
     messages::{HostToolAck, Uart0Message},
@@ -83,7 +84,10 @@ fn check_pin_attempt(rt: &mut Runtime, pairing_pin_attempt: u32) -> bool {
     pairing_pin_bytes.rotate_right(PAIRING_PIN_SIZE - PAIRING_PIN_REAL_SIZE); // Account for build script's encoding.
     let mut pairing_pin = u32::from_be_bytes(pairing_pin_bytes);
     pairing_pin_bytes.zeroize();
-    let pairing_pin_correct = pairing_pin_attempt == pairing_pin;
+    let pairing_pin_correct = constant_time_eq(
+        &pairing_pin_attempt.to_be_bytes(),
+        &pairing_pin.to_be_bytes(),
+    );
     pairing_pin.zeroize();
 
     pairing_pin_correct
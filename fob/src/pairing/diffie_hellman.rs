@@ -165,8 +165,8 @@ fn diffie_hellman_set_key(
         .expect("Failed to expand session key.");
 
     // Set key for UART1.
-    rt.uart1_controller.change_rx_key(&session_key_bytes.into());
-    rt.uart1_controller.change_tx_key(&session_key_bytes.into());
+    rt.uart1_controller
+        .rekey(&session_key_bytes.into(), &session_key_bytes.into());
     session_key_bytes.zeroize();
 }
 
@@ -261,8 +261,7 @@ fn prepare_and_send_diffie_hellman_message(rt: &mut Runtime, paired: bool) -> Op
 pub(crate) fn run_unpaired(rt: &mut Runtime) -> bool {
     // Set keys to default. Necessary in case of failure after key exchange.
     let default_key: Key = Default::default();
-    rt.uart1_controller.change_rx_key(&default_key);
-    rt.uart1_controller.change_tx_key(&default_key);
+    rt.uart1_controller.rekey(&default_key, &default_key);
 
     // Receive ephemeral public key from paired key fob.
     let Some(paired_ephemeral_public_key) = recv_verified_ephemeral_public_key(
@@ -290,8 +289,7 @@ pub(crate) fn run_unpaired(rt: &mut Runtime) -> bool {
 pub(crate) fn run_paired(rt: &mut Runtime) -> bool {
     // Set keys to default. Necessary in case of failure after key exchange.
     let default_key: Key = Default::default();
-    rt.uart1_controller.change_rx_key(&default_key);
-    rt.uart1_controller.change_tx_key(&default_key);
+    rt.uart1_controller.rekey(&default_key, &default_key);
 
     // Generate ephemeral private key and send Diffie-Hellman message.
     let Some(ephemeral_private_key) = prepare_and_send_diffie_hellman_message(rt, true)
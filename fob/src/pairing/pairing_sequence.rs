@@ -1,7 +1,8 @@
 use crate::MAX_MESSAGE_SIZE;
-use core::{mem, time::Duration};
+use core::time::Duration;
 use ucsc_ectf_util_no_std::{
     communication::{CommunicationError, RxChannel, TxChannel},
+    constant_time_eq,
     eeprom::{
         EepromReadWriteField, BYTE_FIELD_SIZE, CAR_ID_SIZE, PAIRING_PIN_SIZE, SECRET_SIZE,
         SIGNATURE_SIZE,
@@ -16,8 +17,7 @@ use zeroize::Zeroize;
 
 /// Generates a sends a challenge message.
 fn generate_and_send_challenge(rt: &mut Runtime, request_nonce: Nonce) -> Option<Nonce> {
-    let mut challenge = [0; mem::size_of::<Nonce>()];
-    rt.fill_rand_slice(&mut challenge);
+    let challenge: Nonce = rt.fill_rand_array();
 
     // Send pairing challenge.
     let challenge_msg = Uart1Message::PairingChallenge(PairingChallenge {
@@ -117,8 +117,8 @@ fn unpaired_recv_verified_pairing_info(rt: &mut Runtime) -> Option<PairingChalle
     let challenge_response_msg = recv_challenge_response(rt)?;
 
     // Check nonces.
-    if challenge_response_msg.request_nonce != request_nonce
-        || challenge_response_msg.challenge_response != challenge
+    if !constant_time_eq(&challenge_response_msg.request_nonce, &request_nonce)
+        | !constant_time_eq(&challenge_response_msg.challenge_response, &challenge)
     {
         return None;
     }
@@ -246,8 +246,7 @@ fn generate_challenge_response_msg(
 // Pairs an unpaired key fob from a paired key fob. Requires a secure UART1 channel.
 pub(crate) fn run_paired(rt: &mut Runtime) {
     // Generate request nonce.
-    let mut request_nonce = [0; mem::size_of::<Nonce>()];
-    rt.fill_rand_slice(&mut request_nonce);
+    let request_nonce: Nonce = rt.fill_rand_array();
 
     // Send pairing request.
     let pairing_request = Uart1Message::PairingRequest(PairingRequest(request_nonce));
@@ -295,7 +294,7 @@ pub(crate) fn run_paired(rt: &mut Runtime) {
     };
 
     // Verify nonce.
-    if challenge_msg.request_nonce != request_nonce {
+    if !constant_time_eq(&challenge_msg.request_nonce, &request_nonce) {
         return;
     }
 
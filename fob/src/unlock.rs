@@ -1,5 +1,5 @@
 use crate::{features, MAX_MESSAGE_SIZE};
-use core::time::Duration;
+use core::{ptr, time::Duration};
 use ucsc_ectf_util_no_std::{
     communication::{CommunicationError, RxChannel, TxChannel},
     eeprom::{EepromReadWriteField, CAR_ID_SIZE, PACKAGED_FEATURE_SIGNED_SIZE, SECRET_SIZE},
@@ -7,37 +7,56 @@ use ucsc_ectf_util_no_std::{
         heapless::Vec, FeatureNumber, Uart1Message, UnlockChallengeResponse, UnlockRequest,
         NUM_FEATURES,
     },
+    panic::register_secret_region,
     timer::Timer,
     Runtime,
 };
 use zeroize::Zeroize;
 
+/// The buffer the key fob encryption key is read into. This is static so it can be registered with
+/// the panic handler, which wipes it if a panic happens while the key is in it.
+static mut KEY_FOB_ENCRYPTION_KEY: [u8; SECRET_SIZE] = [0; SECRET_SIZE];
+
+/// The buffer the car encryption key is read into. See [`KEY_FOB_ENCRYPTION_KEY`].
+static mut CAR_ENCRYPTION_KEY: [u8; SECRET_SIZE] = [0; SECRET_SIZE];
+
+/// Registers the unlock key buffers with the panic handler. This should be called once at startup.
+pub(crate) fn register_key_buffers() {
+    // SAFETY: The key buffers are statics, so they're valid for writes for the rest of the program.
+    unsafe {
+        register_secret_region(
+            ptr::addr_of_mut!(KEY_FOB_ENCRYPTION_KEY).cast(),
+            SECRET_SIZE,
+        );
+        register_secret_region(ptr::addr_of_mut!(CAR_ENCRYPTION_KEY).cast(), SECRET_SIZE);
+    }
+}
+
 pub(crate) fn process_button_press(rt: &mut Runtime) {
     // Create timer to debounce the button at the end.
     let mut unlock_timer = rt.hib_controller.create_timer(Duration::from_millis(100));
 
     // Transmit and receive on UART1 using unlock keys.
-    let mut key_fob_encryption_key = [0; SECRET_SIZE];
-    let mut car_encryption_key = [0; SECRET_SIZE];
+    // SAFETY: This function is only called from the main loop, never from an interrupt, and doesn't
+    // recurse, so these are the only references to the key buffers.
+    let key_fob_encryption_key = unsafe { &mut *ptr::addr_of_mut!(KEY_FOB_ENCRYPTION_KEY) };
+    let car_encryption_key = unsafe { &mut *ptr::addr_of_mut!(CAR_ENCRYPTION_KEY) };
 
     rt.eeprom_controller
         .read_slice(
             EepromReadWriteField::KeyFobEncryptionKey,
-            &mut key_fob_encryption_key,
+            key_fob_encryption_key,
         )
         .expect("EEPROM read failed: key fob encryption key.");
     rt.eeprom_controller
-        .read_slice(
-            EepromReadWriteField::CarEncryptionKey,
-            &mut car_encryption_key,
-        )
+        .read_slice(EepromReadWriteField::CarEncryptionKey, car_encryption_key)
         .expect("EEPROM read failed: car encryption key.");
 
     rt.uart1_controller
-        .change_rx_key(&car_encryption_key.into());
+        .change_rx_key(&(*car_encryption_key).into());
     car_encryption_key.zeroize();
     rt.uart1_controller
-        .change_tx_key(&key_fob_encryption_key.into());
+        .change_tx_key(&(*key_fob_encryption_key).into());
     key_fob_encryption_key.zeroize();
 
     // Get car ID from EEPROM.
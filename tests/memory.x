@@ -11,3 +11,10 @@ reserved space and flash memory, instead of .data and .bss.
 */
 
 _stack_start = ORIGIN(STACK) + LENGTH(STACK);
+
+/* The bottom of the stack, for the stack guard. See Runtime::configure_stack_guard(). */
+_stack_end = ORIGIN(STACK);
+
+/* The bounds of the firmware image, for the measurement. See Runtime::measure_firmware(). */
+_firmware_start = ORIGIN(FLASH);
+_firmware_end = ORIGIN(FLASH) + LENGTH(FLASH);
@@ -0,0 +1,39 @@
+#![cfg(debug_assertions)]
+
+use ucsc_ectf_util_no_std::{
+    adc::{AdcAveraging, AdcChannel, AdcReader},
+    Runtime,
+};
+
+pub fn run(rt: &Runtime) {
+    read_range_test();
+    reseed_coexistence_test(rt);
+}
+
+/// Tests that readings are 12-bit at every averaging setting.
+fn read_range_test() {
+    for averaging in [
+        AdcAveraging::None,
+        AdcAveraging::X2,
+        AdcAveraging::X4,
+        AdcAveraging::X8,
+        AdcAveraging::X16,
+        AdcAveraging::X32,
+        AdcAveraging::X64,
+    ] {
+        let mut reader = AdcReader::new(AdcChannel::Ain10, averaging);
+
+        assert!(reader.read().unwrap() <= 0xFFF);
+    }
+}
+
+/// Tests that reseeding the main CSPRNG, which samples ADC0 for entropy, can be interleaved with
+/// readings without either of them failing.
+fn reseed_coexistence_test(rt: &Runtime) {
+    let mut reader = AdcReader::new(AdcChannel::Ain11, AdcAveraging::X64);
+
+    assert!(reader.read().unwrap() <= 0xFFF);
+    rt.reseed_rng().unwrap();
+    assert!(reader.read().unwrap() <= 0xFFF);
+    rt.reseed_rng().unwrap();
+}
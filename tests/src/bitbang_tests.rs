@@ -0,0 +1,181 @@
+#![cfg(debug_assertions)]
+
+use crate::test_utils::PollLimitTimer;
+use core::{
+    cell::{Cell, RefCell},
+    convert::Infallible,
+};
+use embedded_hal::{
+    blocking::delay::DelayUs,
+    digital::v2::{InputPin, OutputPin},
+};
+use ucsc_ectf_util_no_std::{
+    communication::{BitBangUart, CommunicationError, RxChannel, TxChannel},
+    messages::heapless::Vec,
+};
+
+/// The baud rate used by the tests.
+const BAUD: u32 = 9600;
+
+/// The length of a bit at [`BAUD`], rounded to microseconds.
+const BIT_US: u32 = 104;
+
+pub fn run() {
+    waveform_test();
+    round_trip_test();
+    line_error_test();
+    idle_test();
+}
+
+/// A simulated serial line, which records each level driven onto it with the simulated time in
+/// microseconds. Time only passes when a [`LineDelay`] is used or the line is read.
+#[derive(Default)]
+struct Line {
+    now: Cell<u32>,
+    levels: RefCell<Vec<(u32, bool), 128>>,
+}
+
+impl Line {
+    /// Gets whether the line is high at the current time. The line idles high.
+    fn is_high(&self) -> bool {
+        self.levels
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(time, _)| *time <= self.now.get())
+            .map_or(true, |&(_, high)| high)
+    }
+
+    /// Drives the line to the given level from the current time on.
+    fn drive(&self, high: bool) {
+        self.levels
+            .borrow_mut()
+            .push((self.now.get(), high))
+            .unwrap();
+    }
+}
+
+/// An [`OutputPin`] that drives a [`Line`].
+struct LineTx<'a>(&'a Line);
+
+impl OutputPin for LineTx<'_> {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.drive(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.drive(true);
+        Ok(())
+    }
+}
+
+/// An [`InputPin`] that reads a [`Line`]. Each read takes 1 µs, so polling an idle line lets time pass.
+struct LineRx<'a>(&'a Line);
+
+impl InputPin for LineRx<'_> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.0.now.set(self.0.now.get() + 1);
+        Ok(self.0.is_high())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+/// A delay that passes simulated time on a [`Line`].
+struct LineDelay<'a>(&'a Line);
+
+impl DelayUs<u32> for LineDelay<'_> {
+    fn delay_us(&mut self, us: u32) {
+        self.0.now.set(self.0.now.get() + us);
+    }
+}
+
+/// Sends a message over a fresh [`Line`] and returns the line.
+fn send_over_line(msg: &[u8]) -> Line {
+    let line = Line::default();
+    let idle = Line::default();
+    let mut uart = BitBangUart::new(LineTx(&line), LineRx(&idle), LineDelay(&line), BAUD).unwrap();
+    let mut msg_buf = [0; 16];
+    let msg_buf = &mut msg_buf[..msg.len()];
+
+    msg_buf.copy_from_slice(msg);
+    uart.send(msg_buf).unwrap();
+
+    line
+}
+
+/// Tests that each byte is sent as a low start bit, its bits from least significant, and a high stop bit,
+/// each one bit long.
+fn waveform_test() {
+    let line = send_over_line(b"H");
+    let levels = line.levels.borrow();
+
+    // The line is driven high when the UART is created, and then the frame starts with \1.
+    assert_eq!(levels[0], (0, true));
+
+    let expected = [
+        false, true, false, false, false, false, false, false, false, true,
+    ];
+
+    for (i, &high) in expected.iter().enumerate() {
+        assert_eq!(levels[1 + i], (i as u32 * BIT_US, high));
+    }
+
+    // BogoFraming sends "48" between the delimiters, and then the closing \1.
+    assert_eq!(levels.len(), 1 + 4 * 10);
+    assert_eq!(line.now.get(), 4 * 10 * BIT_US);
+}
+
+/// Tests that a message sent over a line is received from it.
+fn round_trip_test() {
+    let line = send_over_line(b"Hello");
+    let idle = Line::default();
+
+    line.now.set(0);
+
+    let mut uart = BitBangUart::new(LineTx(&idle), LineRx(&line), LineDelay(&line), BAUD).unwrap();
+    let mut dest = [0; 16];
+
+    assert_eq!(
+        uart.recv_with_timeout(&mut dest, &mut PollLimitTimer(u32::MAX)),
+        Ok(5)
+    );
+    assert_eq!(&dest[..5], b"Hello");
+}
+
+/// Tests that a line held low is reported as a line error instead of a byte of zeros.
+fn line_error_test() {
+    let line = Line::default();
+    let idle = Line::default();
+
+    line.drive(false);
+
+    let mut uart = BitBangUart::new(LineTx(&idle), LineRx(&line), LineDelay(&line), BAUD).unwrap();
+    let mut dest = [0; 16];
+
+    assert_eq!(
+        uart.recv_with_timeout(&mut dest, &mut PollLimitTimer(u32::MAX)),
+        Err(CommunicationError::LineError)
+    );
+}
+
+/// Tests that receiving on an idle line times out, and that trying to receive doesn't block.
+fn idle_test() {
+    let line = Line::default();
+    let idle = Line::default();
+    let mut uart = BitBangUart::new(LineTx(&idle), LineRx(&line), LineDelay(&line), BAUD).unwrap();
+    let mut dest = [0; 16];
+
+    assert_eq!(
+        uart.recv_with_timeout(&mut dest, &mut PollLimitTimer(100)),
+        Err(CommunicationError::Timeout)
+    );
+    assert_eq!(uart.try_recv(&mut dest), Ok(None));
+}
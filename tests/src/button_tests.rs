@@ -0,0 +1,54 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use tm4c123x_hal::tm4c123x::{Interrupt, GPIO_PORTF, NVIC, TIMER5};
+use ucsc_ectf_util_no_std::Runtime;
+
+/// The mask for the button pins, PF0 and PF4.
+const BUTTON_MASK: u32 = 0b1_0001;
+
+pub fn run(rt: &mut Runtime) {
+    interrupt_config_test();
+    debounce_timer_config_test();
+    idle_debounce_timer_test(rt);
+}
+
+/// Tests that both buttons raise an interrupt on a falling edge.
+fn interrupt_config_test() {
+    // SAFETY: The registers are only read.
+    let regs = unsafe { &*GPIO_PORTF::ptr() };
+
+    assert_eq!(regs.im.read().bits() & BUTTON_MASK, BUTTON_MASK);
+    assert_eq!(regs.is.read().bits() & BUTTON_MASK, 0);
+    assert_eq!(regs.ibe.read().bits() & BUTTON_MASK, 0);
+    assert_eq!(regs.iev.read().bits() & BUTTON_MASK, 0);
+    assert!(NVIC::is_enabled(Interrupt::GPIOF));
+}
+
+/// Tests that the debounce timer is a stopped 32-bit one-shot timer with its timeout interrupt enabled.
+fn debounce_timer_config_test() {
+    // SAFETY: The registers are only read.
+    let regs = unsafe { &*TIMER5::ptr() };
+
+    assert_eq!(regs.cfg.read().bits(), 0);
+    assert_eq!(regs.tamr.read().bits() & 0b11, 0x1);
+    assert_eq!(regs.imr.read().bits() & 1, 1);
+    assert_eq!(regs.ctl.read().bits() & 1, 0);
+    assert!(NVIC::is_enabled(Interrupt::TIMER5A));
+}
+
+/// Tests that the debounce timer running out with no button being debounced leaves the button
+/// interrupts unmasked and doesn't restart the timer or push an event.
+fn idle_debounce_timer_test(rt: &mut Runtime) {
+    NVIC::pend(Interrupt::TIMER5A);
+    cortex_m::asm::dsb();
+    cortex_m::asm::isb();
+
+    // SAFETY: The registers are only read.
+    let (gpio_regs, timer_regs) = unsafe { (&*GPIO_PORTF::ptr(), &*TIMER5::ptr()) };
+
+    assert!(!NVIC::is_pending(Interrupt::TIMER5A));
+    assert_eq!(gpio_regs.im.read().bits() & BUTTON_MASK, BUTTON_MASK);
+    assert_eq!(timer_regs.ctl.read().bits() & 1, 0);
+    assert_eq!(rt.next_event(Duration::ZERO), None);
+}
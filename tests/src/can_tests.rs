@@ -0,0 +1,59 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    can::{CanController, MAX_EXTENDED_CAN_ID, MAX_STANDARD_CAN_ID},
+    communication::CommunicationError,
+    hib::HibController,
+};
+
+pub fn run(can: &mut CanController, hib: &HibController) {
+    can.set_loopback(true);
+
+    loopback_round_trip_test(can, hib);
+    oversized_frame_test(can);
+    invalid_id_test(can);
+    recv_timeout_test(can, hib);
+
+    can.set_loopback(false);
+}
+
+/// Tests that standard and extended frames of every length are received back in loopback mode.
+fn loopback_round_trip_test(can: &mut CanController, hib: &HibController) {
+    const DATA: [u8; 8] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF];
+
+    for id in [0, 0x123, MAX_STANDARD_CAN_ID, 0x800, MAX_EXTENDED_CAN_ID] {
+        for len in 0..=DATA.len() {
+            can.send_frame(id, &DATA[..len]).unwrap();
+
+            let mut timer = hib.create_timer(Duration::from_millis(10));
+            let (recv_id, recv_data) = can.recv_frame(&mut timer).unwrap();
+
+            assert_eq!(recv_id, id);
+            assert_eq!(&recv_data[..], &DATA[..len]);
+        }
+    }
+}
+
+/// Tests that frames with more than 8 bytes of data are rejected.
+fn oversized_frame_test(can: &mut CanController) {
+    assert_eq!(
+        can.send_frame(0x123, &[0; 9]),
+        Err(CommunicationError::SendError)
+    );
+}
+
+/// Tests that identifiers wider than 29 bits are rejected.
+fn invalid_id_test(can: &mut CanController) {
+    assert_eq!(
+        can.send_frame(MAX_EXTENDED_CAN_ID + 1, &[0; 8]),
+        Err(CommunicationError::SendError)
+    );
+}
+
+/// Tests that receiving times out when no frame is sent.
+fn recv_timeout_test(can: &mut CanController, hib: &HibController) {
+    let mut timer = hib.create_timer(Duration::from_millis(10));
+
+    assert_eq!(can.recv_frame(&mut timer), Err(CommunicationError::Timeout));
+}
@@ -0,0 +1,62 @@
+#![cfg(debug_assertions)]
+
+use core::{fmt::Write, time::Duration};
+use cortex_m_semihosting::hio::HostStream;
+use rand_core::RngCore;
+use ucsc_ectf_util_no_std::{crc32, hib::HibController, secondary_rng, timer::Timer};
+
+/// The size of the data used to compare implementations.
+const BENCH_SIZE: usize = 4096;
+
+pub fn run(hib_controller: &HibController, stdout: &mut HostStream) {
+    check_value_test();
+    bench_test(hib_controller, stdout);
+}
+
+/// Computes the CRC-32 a bit at a time, as a reference for [`crc32()`].
+fn crc32_bitwise(data: &[u8]) -> u32 {
+    let mut crc = !0;
+
+    for &byte in data {
+        crc ^= u32::from(byte);
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn check_value_test() {
+    // The standard check value of CRC-32/ISO-HDLC.
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+/// Checks that [`crc32()`] matches the bitwise reference on random data and isn't slower than it.
+fn bench_test(hib_controller: &HibController, stdout: &mut HostStream) {
+    let mut data = [0; BENCH_SIZE];
+    secondary_rng().fill_bytes(&mut data);
+
+    let timer = hib_controller.create_timer(Duration::from_secs(10));
+    let expected = crc32_bitwise(&data);
+    let bitwise_time = timer.elapsed();
+
+    let timer = hib_controller.create_timer(Duration::from_secs(10));
+    let actual = crc32(&data);
+    let table_time = timer.elapsed();
+
+    assert_eq!(actual, expected);
+    assert!(table_time <= bitwise_time);
+
+    writeln!(
+        stdout,
+        "CRC-32 of {BENCH_SIZE} bytes: {table_time:?} with crc32(), {bitwise_time:?} bitwise."
+    )
+    .unwrap();
+}
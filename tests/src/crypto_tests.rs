@@ -0,0 +1,1398 @@
+#![cfg(debug_assertions)]
+
+use crate::test_utils::{LoopbackChannel, NeverTimer};
+use core::{
+    mem::{self, MaybeUninit},
+    ptr,
+};
+use rand_core::CryptoRng;
+use ucsc_ectf_util_no_std::{
+    communication::{
+        self,
+        challenge_response::{issue_challenge, respond, verify_response},
+        lower_layers::{
+            crypto::{
+                decrypt_frame, min_crc_recv_buffer, min_recv_buffer, AeadChannel, Aes128GcmKey,
+                Aes128GcmRxChannel, Aes128GcmTxChannel, Key, KeyedChannel, MacKey,
+                MacOnlyRxChannel, MacOnlyTxChannel, NonceMode, RandomSource, XChaCha20Poly1305,
+                XChacha20Poly1305RxChannel, XChacha20Poly1305TxChannel, AES_128_GCM_METADATA_SIZE,
+                MAC_METADATA_SIZE, MAX_AAD_SIZE, METADATA_SIZE,
+            },
+            fragmentation::{FragmentingTxChannel, ReassemblingRxChannel},
+            framing::{
+                crc::{crc16_ccitt, CrcFramedRxChannel, CRC_SIZE},
+                Frame, FramedTxChannel,
+            },
+        },
+        BoundedChannel, CommunicationError, RxChannel, TxChannel, MAX_VECTORED_PARTS,
+    },
+    constant_time_eq,
+    crypto::{
+        derive_session_keys, hkdf_sha3, verify_ed25519, KdfError, Sha3Hasher, HKDF_MAX_OUTPUT_SIZE,
+    },
+    messages::heapless::{Deque, Vec},
+    timer::Timer,
+};
+
+/// The byte that every byte of the test key is set to.
+const KEY_BYTE: u8 = 0x5A;
+
+/// The length of the test key.
+const KEY_LEN: usize = 32;
+
+/// The size of the buffer used to capture sent messages.
+const CAPTURE_SIZE: usize = 80;
+
+/// The maximum number of frames a [`QueueChannel`] holds.
+const QUEUE_LEN: usize = 8;
+
+/// The size of each fragment sent by the fragmentation tests. This is chosen so that each fragment fits
+/// in [`CAPTURE_SIZE`] bytes once it's encrypted.
+const FRAGMENT_SIZE: usize = CAPTURE_SIZE - METADATA_SIZE;
+
+pub fn run() {
+    rx_key_zeroized_on_drop_test();
+    tx_key_zeroized_on_drop_test();
+    aes_128_gcm_round_trip_test();
+    aes_128_gcm_tamper_test();
+    xchacha20poly1305_round_trip_test();
+    mac_only_test();
+    synthetic_nonce_test();
+    hkdf_sha3_vectors_test();
+    hkdf_sha3_output_too_long_test();
+    derive_session_keys_test();
+    constant_time_eq_test();
+    verify_ed25519_test();
+    challenge_response_test();
+    buffer_round_trip_test();
+    send_vectored_test();
+    fragmentation_round_trip_test();
+    fragmentation_errors_test();
+    decrypt_frame_test();
+    nonce_history_test();
+    bounded_channel_test();
+    sha3_hasher_test();
+    multikey_test();
+    min_recv_buffer_test();
+    replay_protection_test();
+    aead_channel_test();
+}
+
+/// An [`RxChannel`] that never receives anything.
+struct NullRxChannel;
+
+impl RxChannel for NullRxChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        _dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        Err(CommunicationError::Timeout)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        _dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        Err(CommunicationError::Timeout)
+    }
+}
+
+/// A [`FramedTxChannel`] that discards everything sent through it.
+struct NullTxChannel;
+
+impl FramedTxChannel for NullTxChannel {
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        frame().map(|_| ())
+    }
+}
+
+/// A [`FramedTxChannel`] that captures the last frame sent through it.
+struct CaptureTxChannel<'a> {
+    buf: &'a mut [u8; CAPTURE_SIZE],
+    len: &'a mut usize,
+}
+
+impl FramedTxChannel for CaptureTxChannel<'_> {
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        *self.len = 0;
+
+        for component in frame()? {
+            self.buf[*self.len..*self.len + component.len()].copy_from_slice(component);
+            *self.len += component.len();
+        }
+
+        Ok(())
+    }
+}
+
+/// An [`RxChannel`] that receives one stored message.
+struct ReplayRxChannel<'a> {
+    msg: &'a [u8],
+}
+
+impl RxChannel for ReplayRxChannel<'_> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        dest.get_mut(..self.msg.len())
+            .ok_or(CommunicationError::BufferTooSmall {
+                needed: self.msg.len(),
+            })?
+            .copy_from_slice(self.msg);
+
+        Ok(self.msg.len())
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recv_with_data_timeout(dest, timer)
+    }
+}
+
+/// A channel that queues each frame sent through it to be received in order.
+#[derive(Default)]
+struct QueueChannel {
+    frames: Deque<Vec<u8, CAPTURE_SIZE>, QUEUE_LEN>,
+    flushes: u32,
+}
+
+impl FramedTxChannel for &mut QueueChannel {
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        let mut captured = Vec::new();
+
+        for component in frame()? {
+            captured.extend_from_slice(component).unwrap();
+        }
+
+        self.frames.push_back(captured).unwrap();
+
+        Ok(())
+    }
+
+    fn flush_frames(&mut self) -> communication::Result<()> {
+        self.flushes += 1;
+
+        Ok(())
+    }
+}
+
+impl RxChannel for &mut QueueChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        let frame = self.frames.pop_front().ok_or(CommunicationError::Timeout)?;
+
+        dest.get_mut(..frame.len())
+            .ok_or(CommunicationError::BufferTooSmall {
+                needed: frame.len(),
+            })?
+            .copy_from_slice(&frame);
+
+        Ok(frame.len())
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recv_with_data_timeout(dest, timer)
+    }
+}
+
+/// A [`RandomSource`] that always gives zeros.
+struct ZeroRandomSource;
+
+impl RandomSource for ZeroRandomSource {
+    fn fill_rand_slice<T: AsMut<[u8]>>(&mut self, mut slice_ref: T) {
+        slice_ref.as_mut().fill(0);
+    }
+}
+
+// Not secure at all, but secure channels require it. This is only used to test the channels.
+impl CryptoRng for ZeroRandomSource {}
+
+/// A [`RandomSource`] that fills each slice with a counter that increments on each call, so every
+/// call gives different bytes.
+struct CounterRandomSource(u8);
+
+impl RandomSource for CounterRandomSource {
+    fn fill_rand_slice<T: AsMut<[u8]>>(&mut self, mut slice_ref: T) {
+        self.0 = self.0.wrapping_add(1);
+        slice_ref.as_mut().fill(self.0);
+    }
+}
+
+// Not secure at all, but secure channels require it. This is only used to test the channels.
+impl CryptoRng for CounterRandomSource {}
+
+/// Checks whether the memory of a value contains the test key by reading it through a raw pointer.
+fn contains_key<T>(value: &MaybeUninit<T>) -> bool {
+    let ptr = value.as_ptr() as *const u8;
+    let mut run = 0;
+
+    for i in 0..mem::size_of::<T>() {
+        // SAFETY: The memory read is the storage of a MaybeUninit that was zeroed before the value was
+        // written into it and is still in scope. A volatile read is used so that the compiler can't
+        // assume the memory is unchanged after the value is dropped.
+        let byte = unsafe { ptr::read_volatile(ptr.add(i)) };
+
+        run = if byte == KEY_BYTE { run + 1 } else { 0 };
+
+        if run == KEY_LEN {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Tests that the key of an [`XChacha20Poly1305RxChannel`] is wiped when the channel is dropped.
+fn rx_key_zeroized_on_drop_test() {
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut slot = MaybeUninit::<XChacha20Poly1305RxChannel<NullRxChannel>>::zeroed();
+    slot.write(XChacha20Poly1305RxChannel::new(NullRxChannel, &key));
+
+    // Make sure the test can find the key before the channel is dropped.
+    assert!(contains_key(&slot), "Key not found in RX channel.");
+
+    // SAFETY: The slot was initialized above and isn't used again except for raw reads.
+    unsafe { slot.assume_init_drop() };
+
+    assert!(!contains_key(&slot), "Key not wiped on RX channel drop.");
+}
+
+/// Tests that the key of an [`XChacha20Poly1305TxChannel`] is wiped when the channel is dropped.
+fn tx_key_zeroized_on_drop_test() {
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut slot =
+        MaybeUninit::<XChacha20Poly1305TxChannel<NullTxChannel, ZeroRandomSource>>::zeroed();
+    slot.write(XChacha20Poly1305TxChannel::new(
+        NullTxChannel,
+        ZeroRandomSource,
+        &key,
+    ));
+
+    // Make sure the test can find the key before the channel is dropped.
+    assert!(contains_key(&slot), "Key not found in TX channel.");
+
+    // SAFETY: The slot was initialized above and isn't used again except for raw reads.
+    unsafe { slot.assume_init_drop() };
+
+    assert!(!contains_key(&slot), "Key not wiped on TX channel drop.");
+}
+
+/// Sends a message through an [`Aes128GcmTxChannel`], returning the captured frame and its length.
+fn aes_128_gcm_capture(key: &Aes128GcmKey, msg: &[u8]) -> ([u8; CAPTURE_SIZE], usize) {
+    let mut buf = [0; CAPTURE_SIZE];
+    let mut len = 0;
+    let mut plaintext = [0; CAPTURE_SIZE];
+    let plaintext = &mut plaintext[..msg.len()];
+    plaintext.copy_from_slice(msg);
+
+    let mut tx = Aes128GcmTxChannel::new(
+        CaptureTxChannel {
+            buf: &mut buf,
+            len: &mut len,
+        },
+        ZeroRandomSource,
+        key,
+    );
+    tx.send_with_aad(plaintext, b"aad").unwrap();
+    drop(tx);
+
+    (buf, len)
+}
+
+/// Tests that a message sent by an [`Aes128GcmTxChannel`] has the nonce first and can be received by an
+/// [`Aes128GcmRxChannel`].
+fn aes_128_gcm_round_trip_test() {
+    const MSG: &[u8] = b"Hello, world!";
+
+    let key: Aes128GcmKey = [KEY_BYTE; 16].into();
+    let (frame, len) = aes_128_gcm_capture(&key, MSG);
+
+    assert_eq!(len, MSG.len() + AES_128_GCM_METADATA_SIZE);
+
+    // The nonce comes first and has the zeros from the random source, and the first sequence number
+    // is sent in the clear before the ciphertext.
+    assert_eq!(frame[..12], [0; 12]);
+    assert_eq!(frame[12..20], [0; 8]);
+    assert_ne!(&frame[20..20 + MSG.len()], MSG);
+
+    let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    let mut dest = [0; CAPTURE_SIZE];
+    let read = rx
+        .recv_with_timeout_and_aad(&mut dest, b"aad", &mut NeverTimer)
+        .unwrap();
+
+    assert_eq!(&dest[..read], MSG);
+
+    // The associated data must match.
+    let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::RecvError)
+    );
+
+    // Associated data longer than the limit is refused without counting as a failure.
+    let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    assert_eq!(
+        rx.recv_with_timeout_and_aad(&mut dest, &[0; MAX_AAD_SIZE + 1], &mut NeverTimer),
+        Err(CommunicationError::RecvError)
+    );
+    assert_eq!(rx.auth_failures(), 0);
+
+    // There's no mode byte, so every byte of the nonce comes from the random source.
+    let mut frame = [0; CAPTURE_SIZE];
+    let mut len = 0;
+    let mut tx = Aes128GcmTxChannel::new(
+        CaptureTxChannel {
+            buf: &mut frame,
+            len: &mut len,
+        },
+        CounterRandomSource(0),
+        &key,
+    );
+    tx.send(&mut [0x42; 3]).unwrap();
+    assert_eq!(
+        tx.send_with_aad(&mut [0x42; 3], &[0; MAX_AAD_SIZE + 1]),
+        Err(CommunicationError::SendError)
+    );
+    drop(tx);
+
+    // The refused message took no nonce, so the frame is still the first one.
+    assert_eq!(frame[..12], [1; 12]);
+}
+
+/// Tests that a tampered message is rejected by an [`Aes128GcmRxChannel`].
+fn aes_128_gcm_tamper_test() {
+    let key: Aes128GcmKey = [KEY_BYTE; 16].into();
+    let (mut frame, len) = aes_128_gcm_capture(&key, b"Tamper");
+    let mut dest = [0; CAPTURE_SIZE];
+
+    for i in [0, 12, len - 1] {
+        frame[i] ^= 1;
+
+        let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+        assert_eq!(
+            rx.recv_with_timeout_and_aad(&mut dest, b"aad", &mut NeverTimer),
+            Err(CommunicationError::RecvError)
+        );
+
+        frame[i] ^= 1;
+    }
+}
+
+/// Tests that a message sent by a [`MacOnlyTxChannel`] has its payload in the clear and can be received
+/// by a [`MacOnlyRxChannel`] with the same key, but not if it was tampered with, sent with another key,
+/// or replayed.
+fn mac_only_test() {
+    const MSG: &[u8] = b"Car ID query";
+
+    let key: MacKey = [KEY_BYTE; KEY_LEN].into();
+    let mut frames = [[0; CAPTURE_SIZE]; 2];
+    let mut lens = [0; 2];
+
+    {
+        let [first, second] = &mut frames;
+        let [first_len, second_len] = &mut lens;
+        let mut msg = *b"Car ID query";
+
+        let mut tx = MacOnlyTxChannel::new(
+            CaptureTxChannel {
+                buf: first,
+                len: first_len,
+            },
+            &key,
+        );
+        tx.send(&mut msg).unwrap();
+        assert_eq!(msg, MSG);
+
+        *tx.get_mut() = CaptureTxChannel {
+            buf: second,
+            len: second_len,
+        };
+        tx.send(&mut msg).unwrap();
+        assert_eq!(tx.send(&mut []), Err(CommunicationError::SendError));
+    }
+
+    let [first, second] = &frames;
+    let len = lens[0];
+
+    // The payload is sent in the clear, followed by the sequence number.
+    assert_eq!(len, MSG.len() + MAC_METADATA_SIZE);
+    assert_eq!(&first[..MSG.len()], MSG);
+    assert_eq!(first[MSG.len()..MSG.len() + 8], 0u64.to_be_bytes());
+    assert_eq!(second[MSG.len()..MSG.len() + 8], 1u64.to_be_bytes());
+
+    let mut dest = [0; CAPTURE_SIZE];
+    let mut rx = MacOnlyRxChannel::new(ReplayRxChannel { msg: &first[..len] }, &key);
+    let read = rx.recv_with_timeout(&mut dest, &mut NeverTimer).unwrap();
+
+    assert_eq!(&dest[..read], MSG);
+
+    // A replayed message is rejected once replay protection is enabled.
+    rx.set_replay_protection(true);
+    rx.recv_with_timeout(&mut dest, &mut NeverTimer).unwrap();
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::ReplayDetected)
+    );
+    assert_eq!(rx.auth_failures(), 1);
+
+    // Changing the key forgets the last sequence number, but the old key's messages don't authenticate.
+    rx.change_key(&[!KEY_BYTE; KEY_LEN].into());
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::RecvError)
+    );
+    assert_eq!(&dest[..MSG.len()], [0; MSG.len()]);
+
+    // Tampering with the payload, sequence number, or tag is detected.
+    let mut frame = *first;
+
+    for i in [0, MSG.len(), len - 1] {
+        frame[i] ^= 1;
+
+        let mut rx = MacOnlyRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+        assert_eq!(
+            rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+            Err(CommunicationError::RecvError)
+        );
+
+        frame[i] ^= 1;
+    }
+
+    // The buffer needs space for the metadata and at least one byte of payload.
+    let mut rx = MacOnlyRxChannel::new(ReplayRxChannel { msg: &first[..len] }, &key);
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest[..MAC_METADATA_SIZE], &mut NeverTimer),
+        Err(CommunicationError::BufferTooSmall {
+            needed: MAC_METADATA_SIZE + 1
+        })
+    );
+}
+
+/// Tests that a message sent by an [`XChacha20Poly1305TxChannel`] has the nonce after the ciphertext
+/// and can be received by an [`XChacha20Poly1305RxChannel`].
+fn xchacha20poly1305_round_trip_test() {
+    const MSG: &[u8] = b"Hello, world!";
+
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut frame = [0; CAPTURE_SIZE];
+    let mut len = 0;
+    let mut plaintext = *b"Hello, world!";
+
+    let mut tx = XChacha20Poly1305TxChannel::new(
+        CaptureTxChannel {
+            buf: &mut frame,
+            len: &mut len,
+        },
+        ZeroRandomSource,
+        &key,
+    );
+    tx.send(&mut plaintext).unwrap();
+    drop(tx);
+
+    assert_eq!(len, MSG.len() + METADATA_SIZE);
+
+    // The message is encrypted in place and sent after the sequence number, which is in the clear.
+    assert_ne!(&plaintext[..], MSG);
+    assert_eq!(frame[..8], [0; 8]);
+    assert_eq!(frame[8..8 + MSG.len()], plaintext);
+
+    // The nonce comes after the message and its sequence number, and has the zeros from the random
+    // source.
+    assert_eq!(frame[MSG.len() + 8..MSG.len() + 32], [0; 24]);
+
+    let mut rx = XChacha20Poly1305RxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    let mut dest = [0; CAPTURE_SIZE];
+    let read = rx.recv_with_timeout(&mut dest, &mut NeverTimer).unwrap();
+
+    assert_eq!(&dest[..read], MSG);
+}
+
+/// Sends a message with a new [`XChacha20Poly1305TxChannel`] with synthetic nonces, returning the
+/// captured frame and its length.
+fn synthetic_capture(key: &Key, msg: &[u8]) -> ([u8; CAPTURE_SIZE], usize) {
+    let mut frame = [0; CAPTURE_SIZE];
+    let mut len = 0;
+    let mut plaintext = [0; CAPTURE_SIZE];
+    plaintext[..msg.len()].copy_from_slice(msg);
+
+    let mut tx = XChacha20Poly1305TxChannel::with_nonce_mode(
+        CaptureTxChannel {
+            buf: &mut frame,
+            len: &mut len,
+        },
+        ZeroRandomSource,
+        key,
+        NonceMode::Synthetic,
+    );
+    assert_eq!(tx.nonce_mode(), NonceMode::Synthetic);
+    tx.send(&mut plaintext[..msg.len()]).unwrap();
+    drop(tx);
+
+    (frame, len)
+}
+
+/// Tests that synthetic nonces carry their mode byte, are derived from the message instead of the random
+/// source, and are received like random nonces, and that an unknown mode byte is rejected.
+fn synthetic_nonce_test() {
+    const MSG: &[u8] = b"Hello, world!";
+    const NONCE: core::ops::Range<usize> = MSG.len() + 8..MSG.len() + 32;
+
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let (frame, len) = synthetic_capture(&key, MSG);
+
+    assert_eq!(len, MSG.len() + METADATA_SIZE);
+
+    // The mode byte comes first, and the rest is the synthetic IV.
+    let nonce = &frame[NONCE];
+    assert_eq!(nonce[0], NonceMode::Synthetic as u8);
+    assert_ne!(nonce[1..], [0; 23]);
+
+    // The same message gives the same frame even though the sequence number repeated, and a different
+    // message gives a different nonce.
+    assert_eq!(synthetic_capture(&key, MSG), (frame, len));
+    assert_ne!(
+        synthetic_capture(&key, b"Hello, world?").0[NONCE],
+        frame[NONCE]
+    );
+
+    let mut rx = XChacha20Poly1305RxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    let mut dest = [0; CAPTURE_SIZE];
+    let read = rx.recv_with_timeout(&mut dest, &mut NeverTimer).unwrap();
+
+    assert_eq!(&dest[..read], MSG);
+
+    // The random source isn't used, so it isn't checked for repeats.
+    let mut msg = *b"msg";
+    let mut tx = XChacha20Poly1305TxChannel::with_nonce_mode(
+        NullTxChannel,
+        ZeroRandomSource,
+        &key,
+        NonceMode::Synthetic,
+    );
+    tx.send(&mut msg).unwrap();
+    tx.send(&mut msg).unwrap();
+
+    let mut frame = frame;
+    frame[NONCE.start] = 2;
+
+    assert_eq!(
+        decrypt_frame::<XChaCha20Poly1305>(&key, &mut frame[..len], b""),
+        Err(CommunicationError::RecvError)
+    );
+}
+
+/// Decodes a hex string at compile time.
+const fn hex<const N: usize>(s: &str) -> [u8; N] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            _ => c - b'a' + 10,
+        }
+    }
+
+    let s = s.as_bytes();
+    let mut out = [0; N];
+    let mut i = 0;
+
+    while i < N {
+        out[i] = nibble(s[2 * i]) << 4 | nibble(s[2 * i + 1]);
+        i += 1;
+    }
+
+    out
+}
+
+/// Tests [`hkdf_sha3`] against test vectors using the inputs of the test cases in RFC 5869 with
+/// HMAC-SHA3-256, along with a salt longer than the SHA3-256 block size.
+fn hkdf_sha3_vectors_test() {
+    const IKM_1: [u8; 22] = [0x0B; 22];
+    const SALT_1: [u8; 13] = hex("000102030405060708090a0b0c");
+    const INFO_1: [u8; 10] = hex("f0f1f2f3f4f5f6f7f8f9");
+    const OKM_1: [u8; 42] =
+        hex("0c5160501d65021deaf2c14f5abce04c5bd2635abceeba61c2edb6e8ed72674900557728f2c9f2c4c179");
+    const OKM_3: [u8; 42] =
+        hex("bc1342cdd75c05e8b0c3ae609ce4410684d197232875073499b30cdfe2de2853c1c1bed63d725e885e78");
+    const OKM_LONG_SALT: [u8; 32] =
+        hex("96394b9050307d9b341aae3f5141f1cd460f2b39d44219db0e64bb6adc032d11");
+
+    // RFC 5869 test case 1 inputs.
+    let mut okm = [0; 42];
+    hkdf_sha3(&IKM_1, &SALT_1, &INFO_1, &mut okm).unwrap();
+    assert_eq!(okm, OKM_1);
+
+    // RFC 5869 test case 2 inputs, which have lengths that span multiple blocks.
+    let mut ikm = [0; 80];
+    let mut salt = [0; 80];
+    let mut info = [0; 80];
+
+    for i in 0..80 {
+        ikm[i] = i as u8;
+        salt[i] = 0x60 + i as u8;
+        info[i] = 0xB0 + i as u8;
+    }
+
+    let mut okm = [0; 82];
+    hkdf_sha3(&ikm, &salt, &info, &mut okm).unwrap();
+    assert_eq!(
+        okm,
+        hex::<82>(
+            "3dc251e66c75da6560405ec5ac10e17d851eedfbfdc13feafbec16964c25d021bd971465a3e9c615f277\
+             69019e3f0407d84986fb0ba24e729c99834624baa21cb623dc0098f430d52e18bbdf694df4edd8b2"
+        )
+    );
+
+    // RFC 5869 test case 3 inputs, with an empty salt and info.
+    let mut okm = [0; 42];
+    hkdf_sha3(&IKM_1, &[], &[], &mut okm).unwrap();
+    assert_eq!(okm, OKM_3);
+
+    // A salt longer than the block size is hashed before it's used as the HMAC key.
+    let mut salt = [0; 200];
+
+    for (i, byte) in salt.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    let mut okm = [0; 32];
+    hkdf_sha3(b"ikm", &salt, b"info", &mut okm).unwrap();
+    assert_eq!(okm, OKM_LONG_SALT);
+}
+
+/// Tests that [`hkdf_sha3`] rejects outputs longer than 255 hashes.
+fn hkdf_sha3_output_too_long_test() {
+    let mut okm = [0; HKDF_MAX_OUTPUT_SIZE + 1];
+
+    assert_eq!(
+        hkdf_sha3(b"ikm", b"", b"", &mut okm),
+        Err(KdfError::OutputTooLong)
+    );
+    assert!(okm.iter().all(|&b| b == 0));
+
+    assert_eq!(
+        hkdf_sha3(b"ikm", b"", b"", &mut okm[..HKDF_MAX_OUTPUT_SIZE]),
+        Ok(())
+    );
+}
+
+/// Tests that [`derive_session_keys`] gives two different keys that only depend on the secret.
+fn derive_session_keys_test() {
+    let (initiator_key, responder_key) = derive_session_keys(b"shared secret");
+
+    assert_ne!(initiator_key, responder_key);
+    assert_eq!(
+        derive_session_keys(b"shared secret"),
+        (initiator_key, responder_key)
+    );
+    assert_ne!(derive_session_keys(b"other secret").0, initiator_key);
+}
+
+/// Tests that [`constant_time_eq`] gives the same results as `==`, including for mismatches at either
+/// end and for slices of different lengths.
+fn constant_time_eq_test() {
+    let a = [0x11; 16];
+    let mut b = a;
+
+    assert!(constant_time_eq(&a, &b));
+    assert!(constant_time_eq(&[], &[]));
+
+    b[0] ^= 1;
+    assert!(!constant_time_eq(&a, &b));
+
+    b = a;
+    b[15] ^= 0x80;
+    assert!(!constant_time_eq(&a, &b));
+
+    assert!(!constant_time_eq(&a, &a[..15]));
+    assert!(!constant_time_eq(&a[..1], &[]));
+}
+
+/// Tests [`verify_ed25519`] with test vector 2 from RFC 8032, and that it rejects a tampered
+/// signature, a tampered message, and a malformed public key.
+fn verify_ed25519_test() {
+    const PUBLIC_KEY: [u8; 32] =
+        hex("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c");
+    const SIGNATURE: [u8; 64] = hex(concat!(
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+        "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+    ));
+
+    assert!(verify_ed25519(&PUBLIC_KEY, &[0x72], &SIGNATURE));
+    assert!(!verify_ed25519(&PUBLIC_KEY, &[0x73], &SIGNATURE));
+
+    // Tamper with the R half of the signature.
+    let mut tampered_signature = SIGNATURE;
+    tampered_signature[0] ^= 1;
+    assert!(!verify_ed25519(&PUBLIC_KEY, &[0x72], &tampered_signature));
+
+    // Tamper with the S half of the signature.
+    let mut tampered_signature = SIGNATURE;
+    tampered_signature[32] ^= 1;
+    assert!(!verify_ed25519(&PUBLIC_KEY, &[0x72], &tampered_signature));
+
+    // There's no point on the curve with a y-coordinate of 2, so this public key is malformed.
+    let mut malformed_public_key = [0; 32];
+    malformed_public_key[0] = 2;
+    assert!(!verify_ed25519(&malformed_public_key, &[0x72], &SIGNATURE));
+}
+
+/// Tests that challenge responses only verify with the right key and for the challenge they were
+/// computed for.
+fn challenge_response_test() {
+    const KEY: [u8; 32] = [KEY_BYTE; 32];
+
+    let mut random_source = CounterRandomSource(0);
+
+    // A correct response verifies.
+    let challenge = issue_challenge(&mut random_source);
+    let response = respond(challenge.as_bytes(), &KEY);
+    assert!(verify_response(challenge, &KEY, &response));
+
+    // A response computed with the wrong key doesn't verify.
+    let challenge = issue_challenge(&mut random_source);
+    let response = respond(challenge.as_bytes(), &[!KEY_BYTE; 32]);
+    assert!(!verify_response(challenge, &KEY, &response));
+
+    // A response to a stale challenge doesn't verify against a new one.
+    let stale_challenge = issue_challenge(&mut random_source);
+    let stale_response = respond(stale_challenge.as_bytes(), &KEY);
+    let challenge = issue_challenge(&mut random_source);
+    assert_ne!(challenge.as_bytes(), stale_challenge.as_bytes());
+    assert!(!verify_response(challenge, &KEY, &stale_response));
+
+    // A tampered response doesn't verify.
+    let challenge = issue_challenge(&mut random_source);
+    let mut response = respond(challenge.as_bytes(), &KEY);
+    response[31] ^= 1;
+    assert!(!verify_response(challenge, &KEY, &response));
+}
+
+/// Tests that a [`Vec`] only sends the bytes it holds and has its length set to the number of bytes
+/// received, and that its length is cleared when receiving fails.
+fn buffer_round_trip_test() {
+    const MSG: &[u8] = b"Hello, world!";
+
+    let key: Aes128GcmKey = [KEY_BYTE; 16].into();
+    let mut frame = [0; CAPTURE_SIZE];
+    let mut len = 0;
+    let mut src: Vec<u8, CAPTURE_SIZE> = Vec::from_slice(MSG).unwrap();
+
+    let mut tx = Aes128GcmTxChannel::new(
+        CaptureTxChannel {
+            buf: &mut frame,
+            len: &mut len,
+        },
+        ZeroRandomSource,
+        &key,
+    );
+    tx.send_buffer(&mut src).unwrap();
+    drop(tx);
+
+    assert_eq!(len, MSG.len() + AES_128_GCM_METADATA_SIZE);
+
+    let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    let mut dest: Vec<u8, CAPTURE_SIZE> = Vec::new();
+
+    assert_eq!(
+        rx.recv_buffer_with_timeout(&mut dest, &mut NeverTimer),
+        Ok(MSG.len())
+    );
+    assert_eq!(&dest[..], MSG);
+
+    // A tampered message fails to authenticate and leaves the buffer empty.
+    frame[len - 1] ^= 1;
+    let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+
+    assert_eq!(
+        rx.recv_buffer_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::RecvError)
+    );
+    assert!(dest.is_empty());
+}
+
+/// Tests that [`TxChannel::send_vectored`] sends the parts as one message, through a framing layer
+/// channel, which writes the parts into the frame directly, through a crypto layer channel, which
+/// copies them into the buffer it encrypts, so it takes more than [`MAX_VECTORED_PARTS`] parts, and
+/// through the default implementation, and that each refuses parts that don't fit in ``MAX_LEN``.
+fn send_vectored_test() {
+    const HEADER: &[u8] = b"Hello, ";
+    const PAYLOAD: &[u8] = b"world!";
+    const MSG: &[u8] = b"Hello, world!";
+    const PARTS: [&[u8]; MAX_VECTORED_PARTS + 1] = [b"Hel", b"lo, ", b"wo", b"rl", b"d!"];
+
+    let mut frame = [0; CAPTURE_SIZE];
+    let mut len = 0;
+
+    let mut framed = CaptureTxChannel {
+        buf: &mut frame,
+        len: &mut len,
+    };
+    framed
+        .send_vectored::<{ MSG.len() }>(&[HEADER, PAYLOAD])
+        .unwrap();
+    assert_eq!(
+        framed.send_vectored::<CAPTURE_SIZE>(&[HEADER; MAX_VECTORED_PARTS + 1]),
+        Err(CommunicationError::SendError)
+    );
+    assert_eq!(
+        framed.send_vectored::<{ MSG.len() - 1 }>(&[HEADER, PAYLOAD]),
+        Err(CommunicationError::SendError)
+    );
+    drop(framed);
+
+    assert_eq!(&frame[..len], MSG);
+
+    let key: Aes128GcmKey = [KEY_BYTE; 16].into();
+    let mut tx = Aes128GcmTxChannel::new(
+        CaptureTxChannel {
+            buf: &mut frame,
+            len: &mut len,
+        },
+        ZeroRandomSource,
+        &key,
+    );
+    assert_eq!(
+        tx.send_vectored::<{ MSG.len() - 1 }>(&PARTS),
+        Err(CommunicationError::SendError)
+    );
+    tx.send_vectored::<{ MSG.len() }>(&PARTS).unwrap();
+    drop(tx);
+
+    let mut rx = Aes128GcmRxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    let mut dest = [0; CAPTURE_SIZE];
+    let read = rx.recv_with_timeout(&mut dest, &mut NeverTimer).unwrap();
+
+    assert_eq!(&dest[..read], MSG);
+
+    let mut loopback = LoopbackChannel::default();
+
+    assert_eq!(
+        loopback.send_vectored::<{ MSG.len() - 1 }>(&PARTS),
+        Err(CommunicationError::SendError)
+    );
+    loopback.send_vectored::<{ MSG.len() }>(&PARTS).unwrap();
+
+    let read = loopback
+        .recv_with_timeout(&mut dest, &mut NeverTimer)
+        .unwrap();
+
+    assert_eq!(&dest[..read], MSG);
+}
+
+/// Sends a message through a [`FragmentingTxChannel`] above an [`XChacha20Poly1305TxChannel`], queueing
+/// the encrypted fragments in ``queue``.
+fn send_fragmented(queue: &mut QueueChannel, key: &Key, msg: &[u8]) {
+    let mut src = [0; 128];
+    let src = &mut src[..msg.len()];
+    src.copy_from_slice(msg);
+
+    let mut tx = FragmentingTxChannel::<_, FRAGMENT_SIZE>::new(XChacha20Poly1305TxChannel::new(
+        queue,
+        CounterRandomSource(0),
+        key,
+    ));
+    tx.send(src).unwrap();
+
+    // The message isn't changed by the crypto layer encrypting the fragments in place.
+    assert_eq!(src, msg);
+}
+
+/// Receives a message sent with [`send_fragmented`] into ``dest``.
+fn recv_fragmented(
+    queue: &mut QueueChannel,
+    key: &Key,
+    dest: &mut [u8],
+) -> communication::Result<usize> {
+    let mut rx =
+        ReassemblingRxChannel::<_, CAPTURE_SIZE>::new(XChacha20Poly1305RxChannel::new(queue, key));
+
+    rx.recv_with_timeout(dest, &mut NeverTimer)
+}
+
+/// Tests that a message larger than one fragment is split into fragments that each fit in a frame and
+/// is reassembled on the receiving side, and that an empty message is sent as one fragment.
+fn fragmentation_round_trip_test() {
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut queue = QueueChannel::default();
+    let mut msg = [0; 100];
+
+    for (i, byte) in msg.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    // The first fragment carries 24 bytes and every other fragment carries 28 bytes.
+    send_fragmented(&mut queue, &key, &msg);
+    assert_eq!(queue.frames.len(), 4);
+    assert!(queue.frames.iter().all(|f| f.len() <= CAPTURE_SIZE));
+
+    let mut dest = [0; 128];
+    assert_eq!(recv_fragmented(&mut queue, &key, &mut dest), Ok(msg.len()));
+    assert_eq!(dest[..msg.len()], msg);
+    assert!(queue.frames.is_empty());
+
+    send_fragmented(&mut queue, &key, &[]);
+    assert_eq!(queue.frames.len(), 1);
+    assert_eq!(recv_fragmented(&mut queue, &key, &mut dest), Ok(0));
+}
+
+/// Tests that reassembly fails when a fragment is missing, out of order, tampered with, or from another
+/// message, or when the destination buffer is too small for the whole message.
+fn fragmentation_errors_test() {
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut queue = QueueChannel::default();
+    let msg = [0x42; 80];
+    let mut dest = [0; 128];
+
+    send_fragmented(&mut queue, &key, &msg);
+    assert_eq!(
+        recv_fragmented(&mut queue, &key, &mut dest[..msg.len() - 1]),
+        Err(CommunicationError::BufferTooSmall { needed: msg.len() })
+    );
+    queue.frames.clear();
+
+    // A missing fragment.
+    send_fragmented(&mut queue, &key, &msg);
+    let first = queue.frames.pop_front().unwrap();
+    queue.frames.pop_front().unwrap();
+    queue.frames.push_front(first).unwrap();
+    assert_eq!(
+        recv_fragmented(&mut queue, &key, &mut dest),
+        Err(CommunicationError::RecvError)
+    );
+    queue.frames.clear();
+
+    // Fragments out of order.
+    send_fragmented(&mut queue, &key, &msg);
+    let first = queue.frames.pop_front().unwrap();
+    let second = queue.frames.pop_front().unwrap();
+    queue.frames.push_front(first).unwrap();
+    queue.frames.push_back(second).unwrap();
+    assert_eq!(
+        recv_fragmented(&mut queue, &key, &mut dest),
+        Err(CommunicationError::RecvError)
+    );
+    queue.frames.clear();
+
+    // A tampered fragment fails to authenticate.
+    send_fragmented(&mut queue, &key, &msg);
+    queue.frames.iter_mut().nth(1).unwrap()[0] ^= 1;
+    assert_eq!(
+        recv_fragmented(&mut queue, &key, &mut dest),
+        Err(CommunicationError::RecvError)
+    );
+    queue.frames.clear();
+
+    // The first fragment of one message followed by the rest of the next message, whose sequence
+    // numbers line up but whose message ID doesn't.
+    {
+        let mut src = msg;
+        let mut tx = FragmentingTxChannel::<_, FRAGMENT_SIZE>::new(
+            XChacha20Poly1305TxChannel::new(&mut queue, CounterRandomSource(0), &key),
+        );
+        tx.send(&mut src).unwrap();
+        tx.send(&mut src).unwrap();
+    }
+
+    let fragments = queue.frames.len() / 2;
+    let first = queue.frames.pop_front().unwrap();
+    for _ in 0..fragments {
+        queue.frames.pop_front().unwrap();
+    }
+    queue.frames.push_front(first).unwrap();
+    assert_eq!(
+        recv_fragmented(&mut queue, &key, &mut dest),
+        Err(CommunicationError::RecvError)
+    );
+}
+
+/// Tests that [`decrypt_frame`] decrypts a message sent by an [`XChacha20Poly1305TxChannel`] in the same
+/// way as an [`XChacha20Poly1305RxChannel`], and rejects truncated and tampered messages.
+fn decrypt_frame_test() {
+    const MSG: &[u8] = b"Hello, world!";
+
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut frame = [0; CAPTURE_SIZE];
+    let mut len = 0;
+    let mut plaintext = *b"Hello, world!";
+
+    let mut tx = XChacha20Poly1305TxChannel::new(
+        CaptureTxChannel {
+            buf: &mut frame,
+            len: &mut len,
+        },
+        ZeroRandomSource,
+        &key,
+    );
+    tx.send(&mut plaintext).unwrap();
+    drop(tx);
+
+    let mut copy = frame;
+    assert_eq!(
+        decrypt_frame::<XChaCha20Poly1305>(&key, &mut copy[..len], b""),
+        Ok(MSG.len())
+    );
+    assert_eq!(&copy[..MSG.len()], MSG);
+
+    // Messages with no ciphertext or a truncated nonce or tag.
+    for truncated_len in [0, 1, METADATA_SIZE, len - 1] {
+        let mut copy = frame;
+        assert_eq!(
+            decrypt_frame::<XChaCha20Poly1305>(&key, &mut copy[..truncated_len], b""),
+            Err(CommunicationError::RecvError)
+        );
+    }
+
+    let mut copy = frame;
+    copy[0] ^= 1;
+    assert_eq!(
+        decrypt_frame::<XChaCha20Poly1305>(&key, &mut copy[..len], b""),
+        Err(CommunicationError::RecvError)
+    );
+}
+
+/// Tests that an [`XChacha20Poly1305TxChannel`] and an [`Aes128GcmTxChannel`] refuse to send once their
+/// random source repeats the random part of a recent nonce, and that the check can be disabled.
+fn nonce_history_test() {
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut msg = *b"msg";
+
+    let mut tx = XChacha20Poly1305TxChannel::new(NullTxChannel, CounterRandomSource(0), &key);
+
+    for _ in 0..16 {
+        tx.send(&mut msg).unwrap();
+    }
+
+    let mut tx = XChacha20Poly1305TxChannel::new(NullTxChannel, ZeroRandomSource, &key);
+    tx.send(&mut msg).unwrap();
+    assert_eq!(tx.send(&mut msg), Err(CommunicationError::InternalError));
+
+    let mut tx = Aes128GcmTxChannel::new(NullTxChannel, ZeroRandomSource, &[KEY_BYTE; 16].into());
+    tx.send(&mut msg).unwrap();
+    assert_eq!(tx.send(&mut msg), Err(CommunicationError::InternalError));
+
+    let mut tx = XChacha20Poly1305TxChannel::<_, _, 0>::with_nonce_history(
+        NullTxChannel,
+        ZeroRandomSource,
+        &key,
+    );
+    tx.send(&mut msg).unwrap();
+    tx.send(&mut msg).unwrap();
+}
+
+/// Tests that a [`BoundedChannel`] rejects messages longer than its bound when sending and receiving,
+/// and passes shorter messages through unchanged, including when it wraps the crypto layer.
+fn bounded_channel_test() {
+    let mut queue = QueueChannel::default();
+    let mut dest = [0; CAPTURE_SIZE];
+
+    {
+        let mut ch = BoundedChannel::<_, 16>::new(&mut queue);
+
+        assert_eq!(ch.send(&mut [0x11; 16]), Ok(()));
+        assert_eq!(ch.send(&mut [0x22; 17]), Err(CommunicationError::SendError));
+        assert_eq!(
+            ch.send_vectored::<CAPTURE_SIZE>(&[&[0x33; 8], &[0x33; 9]]),
+            Err(CommunicationError::SendError)
+        );
+        assert_eq!(ch.get_mut().frames.len(), 1);
+
+        assert_eq!(ch.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(16));
+        assert_eq!(dest[..16], [0x11; 16]);
+    }
+
+    // A message sent without the bound is too big to receive with it, no matter how big dest is.
+    (&mut queue).send(&mut [0x44; 20]).unwrap();
+
+    {
+        let mut ch = BoundedChannel::<_, 16>::new(&mut queue);
+
+        assert_eq!(
+            ch.recv_with_timeout(&mut dest, &mut NeverTimer),
+            Err(CommunicationError::BufferTooSmall { needed: 20 })
+        );
+    }
+
+    // Around the crypto layer, the plaintext is bounded in both directions, so a message of exactly
+    // the bound can be received into a buffer with room for its metadata.
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut tx = BoundedChannel::<_, 16>::new(XChacha20Poly1305TxChannel::new(
+        &mut queue,
+        CounterRandomSource(0),
+        &key,
+    ));
+
+    assert_eq!(tx.send(&mut [0x55; 16]), Ok(()));
+    assert_eq!(tx.send(&mut [0x66; 17]), Err(CommunicationError::SendError));
+    tx.get_mut().send(&mut [0x77; 17]).unwrap();
+    drop(tx);
+
+    let mut rx = BoundedChannel::<_, 16>::new(XChacha20Poly1305RxChannel::new(&mut queue, &key));
+
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(16));
+    assert_eq!(dest[..16], [0x55; 16]);
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::BufferTooSmall { needed: 17 })
+    );
+    assert!(!dest.contains(&0x77));
+}
+
+/// Tests that a [`Sha3Hasher`] gives the SHA3-256 test vectors, no matter how its input is split.
+fn sha3_hasher_test() {
+    let empty: [u8; 32] = hex("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a");
+    let abc: [u8; 32] = hex("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532");
+    let a3_200: [u8; 32] = hex("79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787");
+
+    assert_eq!(Sha3Hasher::new().finalize(), empty);
+
+    let mut hasher = Sha3Hasher::new();
+    hasher.update(b"abc");
+    assert_eq!(hasher.finalize(), abc);
+
+    let mut hasher = Sha3Hasher::new();
+    hasher.update(b"a");
+    hasher.update(b"");
+    hasher.update(b"bc");
+    assert_eq!(hasher.finalize(), abc);
+
+    // Split the input across the 136-byte block boundary.
+    let input = [0xA3; 200];
+
+    for split in [1, 135, 136, 137, 199] {
+        let mut hasher = Sha3Hasher::new();
+        hasher.update(&input[..split]);
+        hasher.update(&input[split..]);
+        assert_eq!(hasher.finalize(), a3_200);
+    }
+}
+
+/// Tests that a message is received with whichever of several keys it was encrypted with, that the
+/// plaintext survives the keys tried after the match, even if it doesn't look like a message, and that a
+/// message matching none of the keys is rejected.
+fn multikey_test() {
+    const MSG: &[u8] = b"Hello, world!";
+
+    let old_key: Key = [KEY_BYTE; KEY_LEN].into();
+    let new_key: Key = [!KEY_BYTE; KEY_LEN].into();
+    let other_key: Key = [0x11; KEY_LEN].into();
+    let (frame, len) = synthetic_capture(&new_key, MSG);
+
+    for (keys, idx) in [([&old_key, &new_key], 1), ([&new_key, &old_key], 0)] {
+        let mut rx =
+            XChacha20Poly1305RxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &other_key);
+        let mut dest = [0; CAPTURE_SIZE];
+
+        assert_eq!(
+            rx.recv_multikey(&keys, &mut dest, &mut NeverTimer),
+            Ok((MSG.len(), idx))
+        );
+        assert_eq!(&dest[..MSG.len()], MSG);
+        assert_eq!(rx.auth_failures(), 0);
+    }
+
+    // The second of three keys matches a message whose plaintext doesn't start with a valid nonce mode
+    // byte, so the key tried after the match would reject it early if it were checked against the
+    // plaintext instead of the ciphertext.
+    const MODE_MSG: &[u8] = b"\x07 is not a nonce mode";
+
+    let (frame, len) = synthetic_capture(&new_key, MODE_MSG);
+    let mut rx =
+        XChacha20Poly1305RxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &other_key);
+    let mut dest = [0; CAPTURE_SIZE];
+
+    assert_eq!(
+        rx.recv_multikey(
+            &[&old_key, &new_key, &other_key],
+            &mut dest,
+            &mut NeverTimer
+        ),
+        Ok((MODE_MSG.len(), 1))
+    );
+    assert_eq!(&dest[..MODE_MSG.len()], MODE_MSG);
+    assert_eq!(rx.auth_failures(), 0);
+
+    let (frame, len) = synthetic_capture(&new_key, MSG);
+    let mut rx = XChacha20Poly1305RxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &new_key);
+    let mut dest = [0; CAPTURE_SIZE];
+
+    // The key of the channel itself isn't tried.
+    assert_eq!(
+        rx.recv_multikey(&[&old_key, &other_key], &mut dest, &mut NeverTimer),
+        Err(CommunicationError::RecvError)
+    );
+    assert_eq!(
+        rx.recv_multikey(&[], &mut dest, &mut NeverTimer),
+        Err(CommunicationError::RecvError)
+    );
+    assert_eq!(rx.auth_failures(), 2);
+}
+
+/// Tests that a buffer of [`min_recv_buffer()`] bytes receives the longest message and one byte less
+/// doesn't, and that the same holds for [`min_crc_recv_buffer()`] when the message has a CRC.
+fn min_recv_buffer_test() {
+    const MSG: &[u8] = b"Hello, world!";
+    const BUFFER_SIZE: usize = min_recv_buffer(MSG.len());
+
+    // This is the check the function is meant for, so it must work in a const context.
+    const _: () = assert!(BUFFER_SIZE >= min_recv_buffer(MSG.len()));
+
+    assert_eq!(BUFFER_SIZE, MSG.len() + METADATA_SIZE);
+    assert_eq!(min_recv_buffer(0), METADATA_SIZE + 1);
+    assert_eq!(min_recv_buffer(usize::MAX), usize::MAX);
+
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let (frame, len) = synthetic_capture(&key, MSG);
+    let mut rx = XChacha20Poly1305RxChannel::new(ReplayRxChannel { msg: &frame[..len] }, &key);
+    let mut dest = [0; BUFFER_SIZE];
+
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Ok(MSG.len())
+    );
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest[..BUFFER_SIZE - 1], &mut NeverTimer),
+        Err(CommunicationError::BufferTooSmall {
+            needed: BUFFER_SIZE
+        })
+    );
+
+    const CRC_BUFFER_SIZE: usize = min_crc_recv_buffer(MSG.len());
+
+    assert_eq!(CRC_BUFFER_SIZE, BUFFER_SIZE + CRC_SIZE);
+    assert_eq!(min_crc_recv_buffer(usize::MAX), usize::MAX);
+
+    let mut crc_frame = [0; CAPTURE_SIZE];
+    crc_frame[..len].copy_from_slice(&frame[..len]);
+    crc_frame[len..len + CRC_SIZE].copy_from_slice(&crc16_ccitt(&frame[..len]).to_be_bytes());
+
+    let mut rx = XChacha20Poly1305RxChannel::new(
+        CrcFramedRxChannel::new(ReplayRxChannel {
+            msg: &crc_frame[..len + CRC_SIZE],
+        }),
+        &key,
+    );
+    let mut dest = [0; CRC_BUFFER_SIZE];
+
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Ok(MSG.len())
+    );
+    assert_eq!(&dest[..MSG.len()], MSG);
+    assert!(rx
+        .recv_with_timeout(&mut dest[..CRC_BUFFER_SIZE - 1], &mut NeverTimer)
+        .is_err());
+}
+
+/// Tests that a replayed message is rejected once replay protection is enabled, without leaving any of
+/// its plaintext in the buffer, and that a sender that restarts its sequence numbers is rejected until
+/// the receiver is rekeyed.
+fn replay_protection_test() {
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut queue = QueueChannel::default();
+    let mut dest = [0; CAPTURE_SIZE];
+
+    send_from_start(&mut queue, &key, &[b"first", b"second"]);
+    let replayed = queue.frames.front().unwrap().clone();
+
+    // Replay protection is disabled by default, so a replayed message is accepted.
+    queue.frames.push_back(replayed.clone()).unwrap();
+
+    let mut rx = XChacha20Poly1305RxChannel::new(&mut queue, &key);
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(5));
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(6));
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(5));
+    drop(rx);
+
+    // A sender that restarts, such as after a reboot, starts again from sequence number 0.
+    queue.frames.push_back(replayed.clone()).unwrap();
+    queue.frames.push_back(replayed).unwrap();
+    send_from_start(&mut queue, &key, &[b"restarted"]);
+    send_from_start(&mut queue, &key, &[b"restarted"]);
+
+    let mut rx = XChacha20Poly1305RxChannel::new(&mut queue, &key);
+    rx.set_replay_protection(true);
+
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(5));
+    assert_eq!(&dest[..5], b"first");
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::ReplayDetected)
+    );
+    assert_eq!(dest, [0; CAPTURE_SIZE]);
+    assert_eq!(rx.auth_failures(), 1);
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Err(CommunicationError::ReplayDetected)
+    );
+
+    // Rekeying the receiver forgets the last sequence number it accepted.
+    rx.change_key(&key);
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut NeverTimer), Ok(9));
+    assert_eq!(&dest[..9], b"restarted");
+}
+
+/// Sends each of the given messages into the queue through a new [`XChacha20Poly1305TxChannel`], so
+/// the first message has sequence number 0.
+fn send_from_start(queue: &mut QueueChannel, key: &Key, msgs: &[&[u8]]) {
+    let mut tx = XChacha20Poly1305TxChannel::new(queue, CounterRandomSource(0x80), key);
+    let mut buff = [0; CAPTURE_SIZE];
+
+    for msg in msgs {
+        buff[..msg.len()].copy_from_slice(msg);
+        tx.send(&mut buff[..msg.len()]).unwrap();
+    }
+}
+
+/// Tests that an [`AeadChannel`] forwards vectored sends and flushes to its sending half, so more than
+/// [`MAX_VECTORED_PARTS`] parts can be sent and the inner channel is flushed.
+fn aead_channel_test() {
+    const MSG: &[u8] = b"Hello, world!";
+    const PARTS: [&[u8]; MAX_VECTORED_PARTS + 1] = [b"Hel", b"lo, ", b"wo", b"rl", b"d!"];
+
+    let key: Key = [KEY_BYTE; KEY_LEN].into();
+    let mut queue = QueueChannel::default();
+    let mut dest = [0; CAPTURE_SIZE];
+
+    let mut channel = AeadChannel::<XChaCha20Poly1305, _, _, _>::new(
+        ReplayRxChannel { msg: &[] },
+        &mut queue,
+        CounterRandomSource(0),
+        &key,
+        &key,
+    );
+    channel.send_vectored::<{ MSG.len() }>(&PARTS).unwrap();
+    channel.flush().unwrap();
+    drop(channel);
+
+    assert_eq!(queue.flushes, 1);
+
+    let mut rx = XChacha20Poly1305RxChannel::new(&mut queue, &key);
+    assert_eq!(
+        rx.recv_with_timeout(&mut dest, &mut NeverTimer),
+        Ok(MSG.len())
+    );
+    assert_eq!(&dest[..MSG.len()], MSG);
+}
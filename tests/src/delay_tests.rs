@@ -0,0 +1,30 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::Runtime;
+
+pub fn run(rt: &mut Runtime) {
+    delay_ms_test(rt);
+    delay_us_test(rt);
+}
+
+/// Checks that a millisecond delay waits at least as long as requested, measured with the RTC.
+fn delay_ms_test(rt: &mut Runtime) {
+    let start = rt.now();
+    rt.delay_ms(50);
+    let elapsed = rt.now() - start;
+
+    assert!(elapsed >= Duration::from_millis(50));
+    assert!(elapsed < Duration::from_millis(60));
+}
+
+/// Checks that a microsecond delay waits at least as long as requested, allowing for the 1/32768
+/// second resolution of the RTC.
+fn delay_us_test(rt: &mut Runtime) {
+    let start = rt.now();
+    rt.delay_us(500);
+    let elapsed = rt.now() - start;
+
+    assert!(elapsed >= Duration::from_micros(500 - 31));
+    assert!(elapsed < Duration::from_micros(1000));
+}
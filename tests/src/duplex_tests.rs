@@ -0,0 +1,140 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    communication::{self, CommunicationError, DuplexChannel, RxChannel, TxChannel},
+    timer::Timer,
+};
+
+/// The reply sent back by an [`EarlyReplyChannel`].
+const REPLY: &[u8] = b"a reply that's longer than a FIFO";
+
+/// The number of bytes of [`REPLY`] that arrive while the request is still being sent.
+const EARLY_LEN: usize = 16;
+
+pub fn run() {
+    early_reply_test();
+    send_error_test();
+}
+
+/// A [`Timer`] that never expires and counts how many times it has been reset.
+struct ResetCountingTimer {
+    resets: u32,
+}
+
+impl Timer for ResetCountingTimer {
+    fn poll(&mut self) -> bool {
+        false
+    }
+
+    fn reset(&mut self) {
+        self.resets += 1;
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// A channel that buffers the first [`EARLY_LEN`] bytes of [`REPLY`] while a request is being sent,
+/// like a receive FIFO would, and the rest of them once receiving starts.
+struct EarlyReplyChannel {
+    buf: [u8; 64],
+    len: usize,
+    fail_send: bool,
+    recvs: u32,
+}
+
+impl EarlyReplyChannel {
+    fn new(fail_send: bool) -> Self {
+        Self {
+            buf: [0; 64],
+            len: 0,
+            fail_send,
+            recvs: 0,
+        }
+    }
+}
+
+impl RxChannel for EarlyReplyChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recv_with_timeout(dest, timer)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recvs += 1;
+
+        // The rest of the reply arrives.
+        self.buf[self.len..REPLY.len()].copy_from_slice(&REPLY[self.len..]);
+        self.len = REPLY.len();
+
+        let msg = &self.buf[..self.len];
+        let dest = dest
+            .get_mut(..msg.len())
+            .ok_or(CommunicationError::BufferTooSmall { needed: msg.len() })?;
+        dest.copy_from_slice(msg);
+        self.len = 0;
+
+        Ok(msg.len())
+    }
+}
+
+impl TxChannel for EarlyReplyChannel {
+    fn send(&mut self, _src: &mut [u8]) -> communication::Result<()> {
+        if self.fail_send {
+            return Err(CommunicationError::SendError);
+        }
+
+        // The start of the reply arrives before the request has been sent.
+        self.buf[..EARLY_LEN].copy_from_slice(&REPLY[..EARLY_LEN]);
+        self.len = EARLY_LEN;
+
+        Ok(())
+    }
+}
+
+/// Tests that a reply that starts arriving before the request has been sent is received whole, that
+/// the rest of the response buffer is cleared, and that the timer is reset after sending.
+fn early_reply_test() {
+    let mut ch = DuplexChannel::new(EarlyReplyChannel::new(false));
+    let mut timer = ResetCountingTimer { resets: 0 };
+    let mut req = *b"request";
+    let mut resp = [0xFF; 64];
+
+    assert_eq!(
+        ch.request_response(&mut req, &mut resp, &mut timer),
+        Ok(REPLY.len())
+    );
+    assert_eq!(&resp[..REPLY.len()], REPLY);
+    assert!(resp[REPLY.len()..].iter().all(|&b| b == 0));
+    assert_eq!(timer.resets, 1);
+    assert_eq!(ch.get_mut().recvs, 1);
+}
+
+/// Tests that no reply is waited for if the request can't be sent.
+fn send_error_test() {
+    let mut ch = DuplexChannel::new(EarlyReplyChannel::new(true));
+    let mut timer = ResetCountingTimer { resets: 0 };
+    let mut req = *b"request";
+    let mut resp = [0xFF; 64];
+
+    assert_eq!(
+        ch.request_response(&mut req, &mut resp, &mut timer),
+        Err(CommunicationError::SendError)
+    );
+    assert!(resp.iter().all(|&b| b == 0));
+    assert_eq!(timer.resets, 0);
+    assert_eq!(ch.into_inner().recvs, 0);
+}
@@ -1,8 +1,14 @@
 #![cfg(debug_assertions)]
 
 use core::iter;
-use ucsc_ectf_util_no_std::eeprom::{
-    EepromController, EepromReadField, EepromReadOnlyField, EepromReadWriteField, PUBLIC_KEY_SIZE,
+use ucsc_ectf_util_no_std::{
+    eeprom::{
+        AsBytes, EepromController, EepromError, EepromReadField, EepromReadOnlyField,
+        EepromReadWriteField, EepromRegion, EepromRegionTable, FromBytes, WearLeveledCounter,
+        FACTORY_RESET_READ_ONLY_FIELDS, FREE_SPACE_BOUNDS, JOURNAL_BOUNDS, JOURNAL_CAPACITY,
+        PUBLIC_KEY_SIZE,
+    },
+    features::{FeatureSet, FeatureSetError, MAX_FEATURES},
 };
 
 const READ_ONLY_FIELDS: [EepromReadOnlyField; 10] = [
@@ -34,6 +40,30 @@ const READ_WRITE_FIELDS: [EepromReadWriteField; 11] = [
 
 const DEFAULT_EEPROM_DATA: u8 = 0xFF; // All 1s.
 
+/// The number of words in the EEPROM.
+const EEPROM_WORDS: usize = 512;
+
+/// A word offset in the EEPROM that isn't used by any field.
+const UNUSED_WORD_OFFSET: usize = 300;
+
+/// A struct used to test typed EEPROM accesses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+struct TestStruct {
+    flag: u32,
+    key: [u8; 32],
+    counter: u32,
+}
+
+/// The value of the transaction journal's commit marker when it holds a committed transaction.
+const JOURNAL_COMMITTED: u32 = 0x434D_4954;
+
+// SAFETY: TestStruct is repr(C) and its fields are laid out without padding.
+unsafe impl AsBytes for TestStruct {}
+
+// SAFETY: Every field of TestStruct accepts any bit pattern.
+unsafe impl FromBytes for TestStruct {}
+
 pub fn run(eeprom: &mut EepromController) {
     // Erase EEPROM before running tests.
     eeprom.erase_mem();
@@ -42,6 +72,24 @@ pub fn run(eeprom: &mut EepromController) {
     read_default(eeprom);
     basic_write_read_test(eeprom);
     write_read_bleed_test(eeprom);
+    struct_write_read_test(eeprom);
+    struct_bounds_test(eeprom);
+    feature_set_test(eeprom);
+    transaction_commit_test(eeprom);
+    transaction_discard_test(eeprom);
+    transaction_full_test(eeprom);
+    transaction_roll_forward_test(eeprom);
+    transaction_partial_journal_test(eeprom);
+    record_write_read_test(eeprom);
+    record_corruption_test(eeprom);
+    secret_write_read_test(eeprom);
+    secret_erase_test(eeprom);
+    wear_leveled_counter_test(eeprom);
+    wear_leveled_counter_overflow_test(eeprom);
+    region_write_read_test(eeprom);
+    region_bounds_test(eeprom);
+    region_overlap_test();
+    factory_reset_test(eeprom);
 }
 
 /// Tests reads of default EEPROM values (0xFF for all bytes).
@@ -126,3 +174,538 @@ fn write_read_bleed_test(eeprom: &mut EepromController) {
             .all(|&n| n == DEFAULT_EEPROM_DATA));
     }
 }
+
+/// Tests writing and reading typed values.
+fn struct_write_read_test(eeprom: &mut EepromController) {
+    let value = TestStruct {
+        flag: 0x1234_5678,
+        key: [0x5A; 32],
+        counter: 0x0102_0304,
+    };
+
+    eeprom.write_struct(UNUSED_WORD_OFFSET, &value).unwrap();
+    assert_eq!(
+        eeprom
+            .read_struct::<TestStruct>(UNUSED_WORD_OFFSET)
+            .unwrap(),
+        value
+    );
+
+    // Values that aren't a whole number of words must leave the rest of the last word untouched.
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET, &[0xFFu8; 4])
+        .unwrap();
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET, &[0x11u8; 3])
+        .unwrap();
+    assert_eq!(
+        eeprom.read_struct::<[u8; 4]>(UNUSED_WORD_OFFSET).unwrap(),
+        [0x11, 0x11, 0x11, 0xFF]
+    );
+}
+
+/// Tests enabling, disabling, and iterating over features, and storing a feature set to the EEPROM.
+fn feature_set_test(eeprom: &mut EepromController) {
+    let mut features = FeatureSet::new();
+
+    for id in [0, 1, 3, MAX_FEATURES - 1] {
+        features.enable(id).unwrap();
+    }
+    features.disable(1).unwrap();
+
+    assert!(features.is_enabled(0).unwrap());
+    assert!(!features.is_enabled(1).unwrap());
+    assert!(!features.is_enabled(2).unwrap());
+    assert!(features.iter_enabled().eq([0, 3, MAX_FEATURES - 1]));
+
+    // Out of range feature numbers are rejected without wrapping onto another feature.
+    assert_eq!(
+        features.enable(MAX_FEATURES),
+        Err(FeatureSetError::OutOfRange)
+    );
+    assert_eq!(
+        features.disable(MAX_FEATURES),
+        Err(FeatureSetError::OutOfRange)
+    );
+    assert_eq!(
+        features.is_enabled(u32::MAX),
+        Err(FeatureSetError::OutOfRange)
+    );
+    assert!(features.iter_enabled().eq([0, 3, MAX_FEATURES - 1]));
+
+    features
+        .store_to_eeprom(eeprom, UNUSED_WORD_OFFSET)
+        .unwrap();
+    assert_eq!(
+        FeatureSet::load_from_eeprom(eeprom, UNUSED_WORD_OFFSET).unwrap(),
+        features
+    );
+}
+
+/// Tests that typed accesses past the end of the EEPROM are rejected.
+fn struct_bounds_test(eeprom: &mut EepromController) {
+    // The last word is in bounds.
+    eeprom.write_struct(EEPROM_WORDS - 1, &0u32).unwrap();
+    eeprom.read_struct::<u32>(EEPROM_WORDS - 1).unwrap();
+
+    // One byte past the end is out of bounds.
+    assert!(matches!(
+        eeprom.write_struct(EEPROM_WORDS - 1, &0u64),
+        Err(EepromError::BoundsError)
+    ));
+    assert!(matches!(
+        eeprom.read_struct::<u64>(EEPROM_WORDS - 1),
+        Err(EepromError::BoundsError)
+    ));
+    assert!(matches!(
+        eeprom.read_struct::<u8>(EEPROM_WORDS),
+        Err(EepromError::BoundsError)
+    ));
+    assert!(matches!(
+        eeprom.read_struct::<u8>(usize::MAX),
+        Err(EepromError::BoundsError)
+    ));
+}
+
+/// Writes a journal holding a single entry, as if power was lost partway through a commit.
+fn write_journal(eeprom: &mut EepromController, marker: u32, word_offset: usize, word: u32) {
+    let journal_word_offset = JOURNAL_BOUNDS.address / 4;
+    let entry = [(word_offset * 4) as u32, word];
+
+    eeprom
+        .write_struct(journal_word_offset + 2, &entry)
+        .unwrap();
+    eeprom.write_struct(journal_word_offset + 1, &1u32).unwrap();
+    eeprom.write_struct(journal_word_offset, &marker).unwrap();
+}
+
+/// Tests that committed transactions write everything they staged.
+fn transaction_commit_test(eeprom: &mut EepromController) {
+    let car_id = [0x12, 0x34, 0x56, 0x78];
+    let pairing_byte = [0x01, 0x00, 0x00, 0x00];
+    let mut data = [0; 4];
+
+    eeprom.write_struct(UNUSED_WORD_OFFSET, &[0u8; 8]).unwrap();
+
+    let mut transaction = eeprom.begin_transaction();
+    transaction
+        .write_slice(EepromReadWriteField::CarId, &car_id)
+        .unwrap();
+    transaction
+        .write_slice(EepromReadWriteField::PairingByte, &pairing_byte)
+        .unwrap();
+    transaction
+        .write_struct(UNUSED_WORD_OFFSET, &[0xAAu8; 6])
+        .unwrap();
+    // Later writes to the same word replace earlier ones.
+    transaction
+        .write_struct(UNUSED_WORD_OFFSET + 1, &[0xBBu8; 1])
+        .unwrap();
+    transaction.commit().unwrap();
+
+    eeprom
+        .read_slice(EepromReadWriteField::CarId, &mut data)
+        .unwrap();
+    assert_eq!(data, car_id);
+    eeprom
+        .read_slice(EepromReadWriteField::PairingByte, &mut data)
+        .unwrap();
+    assert_eq!(data, pairing_byte);
+    assert_eq!(
+        eeprom.read_struct::<[u8; 8]>(UNUSED_WORD_OFFSET).unwrap(),
+        [0xAA, 0xAA, 0xAA, 0xAA, 0xBB, 0xAA, 0x00, 0x00]
+    );
+}
+
+/// Tests that dropping a transaction without committing it discards its writes.
+fn transaction_discard_test(eeprom: &mut EepromController) {
+    eeprom.write_struct(UNUSED_WORD_OFFSET, &0u32).unwrap();
+
+    let mut transaction = eeprom.begin_transaction();
+    transaction
+        .write_struct(UNUSED_WORD_OFFSET, &0xDEAD_BEEFu32)
+        .unwrap();
+    drop(transaction);
+
+    assert_eq!(eeprom.read_struct::<u32>(UNUSED_WORD_OFFSET).unwrap(), 0);
+}
+
+/// Tests that transactions reject writes they can't stage.
+fn transaction_full_test(eeprom: &mut EepromController) {
+    let mut transaction = eeprom.begin_transaction();
+
+    for i in 0..JOURNAL_CAPACITY {
+        transaction
+            .write_struct(UNUSED_WORD_OFFSET + i, &0u32)
+            .unwrap();
+    }
+
+    assert!(matches!(
+        transaction.write_struct(UNUSED_WORD_OFFSET + JOURNAL_CAPACITY, &0u32),
+        Err(EepromError::TransactionFullError)
+    ));
+    assert!(matches!(
+        transaction.write_struct(JOURNAL_BOUNDS.address / 4, &0u32),
+        Err(EepromError::BoundsError)
+    ));
+}
+
+/// Tests that a committed transaction interrupted by a power loss is rolled forward on boot.
+fn transaction_roll_forward_test(eeprom: &mut EepromController) {
+    eeprom.write_struct(UNUSED_WORD_OFFSET, &0u32).unwrap();
+    write_journal(eeprom, JOURNAL_COMMITTED, UNUSED_WORD_OFFSET, 0x1234_5678);
+
+    eeprom.run_boot_recovery().unwrap();
+
+    assert_eq!(
+        eeprom.read_struct::<u32>(UNUSED_WORD_OFFSET).unwrap(),
+        0x1234_5678
+    );
+    assert_ne!(
+        eeprom
+            .read_struct::<u32>(JOURNAL_BOUNDS.address / 4)
+            .unwrap(),
+        JOURNAL_COMMITTED
+    );
+}
+
+/// Tests that a partial journal left by a power loss before the commit point is discarded on boot.
+fn transaction_partial_journal_test(eeprom: &mut EepromController) {
+    eeprom.write_struct(UNUSED_WORD_OFFSET, &0u32).unwrap();
+    write_journal(eeprom, 0, UNUSED_WORD_OFFSET, 0x1234_5678);
+
+    eeprom.run_boot_recovery().unwrap();
+
+    assert_eq!(eeprom.read_struct::<u32>(UNUSED_WORD_OFFSET).unwrap(), 0);
+}
+
+/// Tests writing and reading CRC-protected records.
+fn record_write_read_test(eeprom: &mut EepromController) {
+    let payload = [0x42; 10];
+    let mut read_payload = [0; 10];
+
+    assert_eq!(EepromController::record_size(payload.len()), 16);
+
+    eeprom.write_record(UNUSED_WORD_OFFSET, &payload).unwrap();
+    eeprom
+        .read_record(UNUSED_WORD_OFFSET, &mut read_payload)
+        .unwrap();
+    assert_eq!(read_payload, payload);
+
+    read_payload.fill(0);
+    assert_eq!(
+        eeprom
+            .read_record_or_default(UNUSED_WORD_OFFSET, &mut read_payload)
+            .map(|payload| &*payload),
+        Some(&payload[..])
+    );
+}
+
+/// Tests that a flipped byte in a stored record is detected.
+fn record_corruption_test(eeprom: &mut EepromController) {
+    let payload = [0x42; 10];
+    let mut read_payload = [0; 10];
+
+    eeprom.write_record(UNUSED_WORD_OFFSET, &payload).unwrap();
+
+    // Flip a bit in the second word of the payload.
+    let word = eeprom.read_struct::<u32>(UNUSED_WORD_OFFSET + 1).unwrap();
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET + 1, &(word ^ 0x100))
+        .unwrap();
+
+    assert!(matches!(
+        eeprom.read_record(UNUSED_WORD_OFFSET, &mut read_payload),
+        Err(EepromError::CrcMismatchError)
+    ));
+    assert!(eeprom
+        .read_record_or_default(UNUSED_WORD_OFFSET, &mut read_payload)
+        .is_none());
+
+    // Erased EEPROM isn't a valid record either.
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET, &[0xFFu8; 16])
+        .unwrap();
+    assert!(eeprom
+        .read_record_or_default(UNUSED_WORD_OFFSET, &mut read_payload)
+        .is_none());
+}
+
+/// Tests writing and reading secrets.
+fn secret_write_read_test(eeprom: &mut EepromController) {
+    let mut secret = [0x5A; 6];
+
+    eeprom
+        .write_secret(UNUSED_WORD_OFFSET, &mut secret)
+        .unwrap();
+
+    // The input buffer is wiped after it's written.
+    assert_eq!(secret, [0; 6]);
+
+    let read_secret = eeprom.read_secret::<32>(UNUSED_WORD_OFFSET, 6).unwrap();
+    assert_eq!(&read_secret[..], &[0x5A; 6]);
+
+    assert!(matches!(
+        eeprom.read_secret::<4>(UNUSED_WORD_OFFSET, 6),
+        Err(EepromError::SizeError)
+    ));
+}
+
+/// Tests that erasing a secret overwrites it and nothing after it.
+fn secret_erase_test(eeprom: &mut EepromController) {
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET, &[0x5Au8; 8])
+        .unwrap();
+    eeprom.erase_secret(UNUSED_WORD_OFFSET, 6).unwrap();
+
+    assert_eq!(
+        eeprom.read_struct::<[u8; 8]>(UNUSED_WORD_OFFSET).unwrap(),
+        [0, 0, 0, 0, 0, 0, 0x5A, 0x5A]
+    );
+}
+
+/// Tests that a wear-leveled counter counts up, rotates through its slots, and keeps its value when
+/// stale slots are left behind.
+fn wear_leveled_counter_test(eeprom: &mut EepromController) {
+    let counter = WearLeveledCounter::<4>::new(UNUSED_WORD_OFFSET);
+
+    counter.clear(eeprom).unwrap();
+    assert_eq!(counter.read(eeprom).unwrap(), 0);
+
+    // Go around the block more than once.
+    for expected in 1..=10 {
+        assert_eq!(counter.increment(eeprom).unwrap(), expected);
+        assert_eq!(counter.read(eeprom).unwrap(), expected);
+    }
+
+    // Each value is in the slot given by its value modulo the number of slots.
+    assert_eq!(
+        eeprom.read_struct::<[u32; 4]>(UNUSED_WORD_OFFSET).unwrap(),
+        [8, 9, 10, 7]
+    );
+
+    // The word after the block isn't touched.
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET + 4, &u32::MAX)
+        .unwrap();
+    counter.increment(eeprom).unwrap();
+    assert_eq!(
+        eeprom.read_struct::<u32>(UNUSED_WORD_OFFSET + 4).unwrap(),
+        u32::MAX
+    );
+
+    // A value in the wrong slot isn't valid.
+    eeprom.write_struct(UNUSED_WORD_OFFSET, &101u32).unwrap();
+    assert_eq!(counter.read(eeprom).unwrap(), 11);
+
+    counter.clear(eeprom).unwrap();
+    assert_eq!(counter.read(eeprom).unwrap(), 0);
+}
+
+/// Tests that a wear-leveled counter refuses to go past the highest value it can store.
+fn wear_leveled_counter_overflow_test(eeprom: &mut EepromController) {
+    let counter = WearLeveledCounter::<2>::new(UNUSED_WORD_OFFSET);
+    let max = u32::MAX - 1;
+
+    counter.clear(eeprom).unwrap();
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET + 1, &(max - 1))
+        .unwrap();
+    assert_eq!(counter.increment(eeprom).unwrap(), max);
+
+    assert!(matches!(
+        counter.increment(eeprom),
+        Err(EepromError::CounterOverflowError)
+    ));
+    assert_eq!(counter.read(eeprom).unwrap(), max);
+}
+
+/// Tests that accesses through a region handle are relative to the start of the region.
+fn region_write_read_test(eeprom: &mut EepromController) {
+    const REGIONS: EepromRegionTable<1> =
+        EepromRegionTable::new([EepromRegion::new(UNUSED_WORD_OFFSET, 4)]);
+    let mut data = [0; 8];
+
+    let mut region = REGIONS.handle(eeprom, 0).unwrap();
+    region.write(2, &[1, 2, 3, 4, 5, 6]).unwrap();
+    region.read(2, &mut data[..6]).unwrap();
+    assert_eq!(data[..6], [1, 2, 3, 4, 5, 6]);
+
+    // The same words are read without going through the region.
+    let raw: [u8; 8] = eeprom.read_struct(UNUSED_WORD_OFFSET + 2).unwrap();
+    REGIONS
+        .handle(eeprom, 0)
+        .unwrap()
+        .read(2, &mut data)
+        .unwrap();
+    assert_eq!(data, raw);
+
+    let mut region = REGIONS.handle(eeprom, 0).unwrap();
+    let value = TestStruct {
+        flag: 1,
+        key: [0; 32],
+        counter: 2,
+    };
+
+    region.write_struct(0, &[7u32, 8u32]).unwrap();
+    assert_eq!(region.read_struct::<[u32; 2]>(0).unwrap(), [7, 8]);
+
+    // The struct is bigger than the region.
+    assert!(matches!(
+        region.write_struct(0, &value),
+        Err(EepromError::BoundsError)
+    ));
+}
+
+/// Tests that accesses past the end of a region are refused without touching the words after it.
+fn region_bounds_test(eeprom: &mut EepromController) {
+    const REGIONS: EepromRegionTable<1> =
+        EepromRegionTable::new([EepromRegion::new(UNUSED_WORD_OFFSET, 2)]);
+    let mut data = [0; 12];
+
+    eeprom
+        .write_struct(UNUSED_WORD_OFFSET + 2, &u32::MAX)
+        .unwrap();
+
+    // There's no second region in the table.
+    assert!(matches!(
+        REGIONS.handle(eeprom, 1),
+        Err(EepromError::BoundsError)
+    ));
+
+    let mut region = REGIONS.handle(eeprom, 0).unwrap();
+
+    assert!(matches!(
+        region.write(1, &[0; 8]),
+        Err(EepromError::BoundsError)
+    ));
+    assert!(matches!(
+        region.write(2, &[0]),
+        Err(EepromError::BoundsError)
+    ));
+    assert!(matches!(
+        region.read(0, &mut data),
+        Err(EepromError::BoundsError)
+    ));
+    assert!(matches!(
+        region.read(usize::MAX, &mut data[..4]),
+        Err(EepromError::BoundsError)
+    ));
+
+    // The last word of the region can still be written.
+    region.write(1, &[0; 4]).unwrap();
+
+    assert_eq!(
+        eeprom.read_struct::<u32>(UNUSED_WORD_OFFSET + 2).unwrap(),
+        u32::MAX
+    );
+}
+
+/// Tests that regions that share words overlap, that regions next to each other don't, and that only
+/// regions in the free space are accepted.
+fn region_overlap_test() {
+    const FREE_SPACE_START_WORD: usize = FREE_SPACE_BOUNDS.address / 4;
+    const FREE_SPACE_END_WORD: usize = (FREE_SPACE_BOUNDS.address + FREE_SPACE_BOUNDS.size) / 4;
+
+    const KEYS: EepromRegion = EepromRegion::new(UNUSED_WORD_OFFSET, 8);
+    const COUNTERS: EepromRegion = EepromRegion::new(UNUSED_WORD_OFFSET + 8, 4);
+    const REGIONS: EepromRegionTable<2> = EepromRegionTable::new([KEYS, COUNTERS]);
+
+    assert_eq!(REGIONS.regions(), &[KEYS, COUNTERS]);
+    assert!(!KEYS.overlaps(&COUNTERS));
+    assert!(KEYS.overlaps(&EepromRegion::new(UNUSED_WORD_OFFSET + 7, 2)));
+    assert!(COUNTERS.overlaps(&EepromRegion::new(0, EEPROM_WORDS)));
+
+    // Empty regions take up no words.
+    assert!(!KEYS.overlaps(&EepromRegion::new(UNUSED_WORD_OFFSET + 4, 0)));
+
+    assert!(KEYS.in_free_space());
+    assert!(EepromRegion::new(
+        FREE_SPACE_START_WORD,
+        FREE_SPACE_END_WORD - FREE_SPACE_START_WORD
+    )
+    .in_free_space());
+
+    // Regions that reach into the fields, the transaction journal, or past the end of the EEPROM.
+    assert!(!EepromRegion::new(0, 1).in_free_space());
+    assert!(!EepromRegion::new(FREE_SPACE_START_WORD - 1, 2).in_free_space());
+    assert!(!EepromRegion::new(FREE_SPACE_END_WORD - 1, 2).in_free_space());
+    assert!(!EepromRegion::new(FREE_SPACE_END_WORD, 1).in_free_space());
+    assert!(!EepromRegion::new(UNUSED_WORD_OFFSET, usize::MAX).in_free_space());
+    assert!(!EepromRegion::new(usize::MAX, 1).in_free_space());
+}
+
+/// Tests that a factory reset wipes the secret and read-write fields, the transaction journal, and the
+/// raw words in the free space, such as a stored feature set and a wear-leveled counter, but leaves the
+/// public fields alone.
+fn factory_reset_test(eeprom: &mut EepromController) {
+    const FREE_SPACE_START_WORD: usize = FREE_SPACE_BOUNDS.address / 4;
+    const FREE_SPACE_END_WORD: usize = (FREE_SPACE_BOUNDS.address + FREE_SPACE_BOUNDS.size) / 4;
+
+    eeprom.erase_mem();
+
+    let mut features = FeatureSet::new();
+    features.enable(2).unwrap();
+    features
+        .store_to_eeprom(eeprom, UNUSED_WORD_OFFSET)
+        .unwrap();
+
+    let counter = WearLeveledCounter::<4>::new(UNUSED_WORD_OFFSET + 2);
+    counter.clear(eeprom).unwrap();
+    for _ in 0..5 {
+        counter.increment(eeprom).unwrap();
+    }
+
+    eeprom
+        .write_slice(EepromReadWriteField::CarId, &[0x5A; 4])
+        .unwrap();
+    write_journal(eeprom, JOURNAL_COMMITTED, UNUSED_WORD_OFFSET, 0x5A5A_5A5A);
+
+    // The first and last words of the free space are wiped too.
+    eeprom
+        .write_struct(FREE_SPACE_START_WORD, &0x5A5A_5A5Au32)
+        .unwrap();
+    eeprom
+        .write_struct(FREE_SPACE_END_WORD - 1, &0x5A5A_5A5Au32)
+        .unwrap();
+
+    eeprom.factory_reset_for_test().unwrap();
+
+    assert_eq!(
+        FeatureSet::load_from_eeprom(eeprom, UNUSED_WORD_OFFSET).unwrap(),
+        FeatureSet::new()
+    );
+    assert_eq!(counter.read(eeprom).unwrap(), 0);
+    assert_eq!(eeprom.read_struct::<u32>(FREE_SPACE_START_WORD).unwrap(), 0);
+    assert_eq!(
+        eeprom.read_struct::<u32>(FREE_SPACE_END_WORD - 1).unwrap(),
+        0
+    );
+
+    // The journal entry was wiped, so nothing is rolled forward.
+    assert_eq!(
+        eeprom
+            .read_struct::<[u32; 4]>(JOURNAL_BOUNDS.address / 4)
+            .unwrap(),
+        [0; 4]
+    );
+
+    let mut data = [0xFF; PUBLIC_KEY_SIZE];
+
+    for field in READ_WRITE_FIELDS {
+        let size = field.get_field_bounds().size;
+        eeprom.read_slice(field, &mut data).unwrap();
+        assert!(data[..size].iter().all(|&n| n == 0));
+    }
+
+    for field in READ_ONLY_FIELDS {
+        let size = field.get_field_bounds().size;
+        let wiped = FACTORY_RESET_READ_ONLY_FIELDS
+            .iter()
+            .any(|&f| f.get_field_bounds().address == field.get_field_bounds().address);
+        let expected = if wiped { 0 } else { DEFAULT_EEPROM_DATA };
+
+        eeprom.read_slice(field, &mut data).unwrap();
+        assert!(data[..size].iter().all(|&n| n == expected));
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    event::{self, RuntimeEvent, EVENT_QUEUE_CAPACITY},
+    Runtime,
+};
+
+pub fn run(rt: &mut Runtime) {
+    empty_test(rt);
+    order_test(rt);
+    coalesce_test(rt);
+    overflow_test(rt);
+}
+
+/// Tests that waiting with no events times out.
+fn empty_test(rt: &mut Runtime) {
+    let start = rt.hib_controller.now();
+    assert_eq!(rt.next_event(Duration::from_millis(50)), None);
+    let elapsed = rt.hib_controller.now() - start;
+
+    assert!(elapsed >= Duration::from_millis(50));
+    assert!(elapsed < Duration::from_millis(100));
+
+    assert_eq!(rt.next_event(Duration::ZERO), None);
+}
+
+/// Tests that events are returned in the order they were pushed, even with a zero timeout.
+fn order_test(rt: &mut Runtime) {
+    assert!(event::push_event(RuntimeEvent::User(1)));
+    assert!(event::push_event(RuntimeEvent::Uart1Rx));
+    assert!(event::push_event(RuntimeEvent::User(2)));
+
+    assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::User(1)));
+    assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::Uart1Rx));
+    assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::User(2)));
+    assert_eq!(rt.next_event(Duration::ZERO), None);
+}
+
+/// Tests that an event equal to a waiting event is merged into it.
+fn coalesce_test(rt: &mut Runtime) {
+    assert!(event::push_event(RuntimeEvent::User(1)));
+    assert!(event::push_event(RuntimeEvent::User(2)));
+    assert!(event::push_event(RuntimeEvent::User(1)));
+
+    assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::User(1)));
+
+    // Once taken, the event can be queued again.
+    assert!(event::push_event(RuntimeEvent::User(1)));
+
+    assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::User(2)));
+    assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::User(1)));
+    assert_eq!(rt.next_event(Duration::ZERO), None);
+}
+
+/// Tests that events pushed to a full queue are dropped and counted, and the waiting events are kept.
+fn overflow_test(rt: &mut Runtime) {
+    let dropped = event::dropped_events();
+
+    for i in 0..EVENT_QUEUE_CAPACITY as u8 {
+        assert!(event::push_event(RuntimeEvent::User(i)));
+    }
+
+    assert!(!event::push_event(RuntimeEvent::Uart1Rx));
+    assert_eq!(event::dropped_events(), dropped + 1);
+
+    // Merging into a waiting event still works with a full queue.
+    assert!(event::push_event(RuntimeEvent::User(0)));
+    assert_eq!(event::dropped_events(), dropped + 1);
+
+    for i in 0..EVENT_QUEUE_CAPACITY as u8 {
+        assert_eq!(rt.next_event(Duration::ZERO), Some(RuntimeEvent::User(i)));
+    }
+
+    assert_eq!(rt.next_event(Duration::ZERO), None);
+}
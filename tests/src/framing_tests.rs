@@ -0,0 +1,1031 @@
+#![cfg(debug_assertions)]
+
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use rand_core::RngCore;
+use ucsc_ectf_util_no_std::{
+    communication::{
+        self,
+        lower_layers::{
+            crypto::XChacha20Poly1305RxChannel,
+            framing::{
+                bogoframing, cobs,
+                crc::{crc16_ccitt, CrcFramedRxChannel, CrcFramedTxChannel, CRC_SIZE},
+                Frame, FramedTxChannel, MAX_OVERSIZED_FRAME_DISCARD,
+            },
+        },
+        CommunicationError, RxChannel, TxChannel,
+    },
+    hib::HibController,
+    messages::heapless::Vec,
+    secondary_rng,
+    timer::{HibTimer, Timer},
+};
+
+/// The byte used to fill the space after the destination buffer.
+const GUARD_BYTE: u8 = 0xA5;
+
+/// The number of guard bytes placed after the destination buffer.
+const GUARD_LEN: usize = 16;
+
+/// The size of the destination buffer handed to the receive functions.
+const DEST_LEN: usize = 32;
+
+/// The largest COBS encoding produced by [`cobs_encode`].
+const COBS_ENCODED_LEN: usize = 300;
+
+/// The time failures are padded to by [`recv_padded_test`].
+const PADDING: Duration = Duration::from_millis(20);
+
+pub fn run(hib_controller: &HibController) {
+    oversized_frame_test(hib_controller);
+    oversized_odd_frame_test(hib_controller);
+    endless_frame_test();
+    lying_inner_channel_test(hib_controller);
+    send_retry_backoff_test(hib_controller);
+    send_retry_errors_test(hib_controller);
+    decode_frame_test();
+    decode_buffered_frame_test();
+    cobs_round_trip_test(hib_controller);
+    cobs_max_run_test(hib_controller);
+    cobs_resync_test(hib_controller);
+    cobs_truncated_frame_test(hib_controller);
+    cobs_endless_frame_test(hib_controller);
+    crc_round_trip_test(hib_controller);
+    crc_corruption_test(hib_controller);
+    recv_until_test(hib_controller);
+    recv_cancellable_test(hib_controller);
+    recv_progress_test(hib_controller);
+    recv_scaled_test(hib_controller);
+    recv_padded_test(hib_controller);
+}
+
+/// A source of bytes to feed to the framing layer. Returns an error once exhausted, which the framing
+/// layer treats the same as an empty UART FIFO.
+struct SliceReader<'a> {
+    data: &'a [u8],
+    idx: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, idx: 0 }
+    }
+
+    fn read(&mut self) -> communication::Result<u8> {
+        let byte = *self
+            .data
+            .get(self.idx)
+            .ok_or(CommunicationError::RecvError)?;
+        self.idx += 1;
+
+        Ok(byte)
+    }
+}
+
+/// An [`RxChannel`] that claims to have received more bytes than fit in the destination buffer.
+struct LyingRxChannel;
+
+impl RxChannel for LyingRxChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        Ok(dest.len() + 1)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        Ok(dest.len() + 1)
+    }
+}
+
+/// An [`RxChannel`] that makes one byte available each time its pacing timer fires, like a peer sending
+/// a message slowly.
+struct TrickleRxChannel<'a> {
+    reader: SliceReader<'a>,
+    pace: HibTimer<'a>,
+}
+
+impl<'a> TrickleRxChannel<'a> {
+    fn read(&mut self) -> communication::Result<u8> {
+        if !self.pace.poll() {
+            return Err(CommunicationError::RecvError);
+        }
+
+        self.pace.reset();
+        self.reader.read()
+    }
+}
+
+impl RxChannel for TrickleRxChannel<'_> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_data_timeout(self, dest, timer, Self::read, 1)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_timeout(self, dest, timer, Self::read, 1)
+    }
+}
+
+/// An [`RxChannel`] that sets a cancel flag once a set number of bytes have been read, like a button
+/// interrupt firing part way through a message.
+struct CancellingRxChannel<'a> {
+    reader: SliceReader<'a>,
+    cancel: &'a AtomicBool,
+    cancel_after: usize,
+}
+
+impl CancellingRxChannel<'_> {
+    fn read(&mut self) -> communication::Result<u8> {
+        if self.reader.idx == self.cancel_after {
+            self.cancel.store(true, Ordering::Release);
+        }
+
+        self.reader.read()
+    }
+}
+
+impl RxChannel for CancellingRxChannel<'_> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_data_timeout(self, dest, timer, Self::read, 1)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        bogoframing::recv_frame_with_timeout(self, dest, timer, Self::read, 1)
+    }
+}
+
+/// A [`TxChannel`] that fails a set number of sends with a set error before succeeding, counting every
+/// send attempted.
+struct FlakyTxChannel {
+    failures: u32,
+    error: CommunicationError,
+    sends: u32,
+}
+
+impl FlakyTxChannel {
+    fn new(failures: u32, error: CommunicationError) -> Self {
+        Self {
+            failures,
+            error,
+            sends: 0,
+        }
+    }
+}
+
+impl TxChannel for FlakyTxChannel {
+    fn send(&mut self, _src: &mut [u8]) -> communication::Result<()> {
+        self.sends += 1;
+
+        if self.sends <= self.failures {
+            return Err(self.error);
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`FramedTxChannel`] that stores the bytes of the last frame sent through it as is, and receives
+/// them back as one message.
+#[derive(Default)]
+struct CaptureChannel {
+    buf: Vec<u8, 64>,
+}
+
+impl FramedTxChannel for CaptureChannel {
+    fn frame<'a, const FRAME_CT: usize>(
+        &mut self,
+        frame: impl FnOnce() -> communication::Result<Frame<'a, FRAME_CT>>,
+    ) -> communication::Result<()> {
+        self.buf.clear();
+
+        for frame_piece in frame()? {
+            self.buf
+                .extend_from_slice(frame_piece)
+                .map_err(|_| CommunicationError::SendError)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl RxChannel for CaptureChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recv_with_timeout(dest, timer)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        let dest = dest
+            .get_mut(..self.buf.len())
+            .ok_or(CommunicationError::BufferTooSmall {
+                needed: self.buf.len(),
+            })?;
+        dest.copy_from_slice(&self.buf);
+
+        Ok(self.buf.len())
+    }
+}
+
+/// Fills `frame` with a BogoFrame containing `len` hex digits and returns the used portion.
+fn build_frame(frame: &mut [u8], len: usize) -> &[u8] {
+    frame[0] = 1;
+    frame[1..len + 1].fill(b'a');
+    frame[len + 1] = 1;
+
+    &frame[..len + 2]
+}
+
+/// Checks that nothing was written past the end of the destination buffer.
+fn assert_guard_intact(buff: &[u8]) {
+    assert!(
+        buff[DEST_LEN..].iter().all(|&n| n == GUARD_BYTE),
+        "Receive wrote past the end of the destination buffer."
+    );
+}
+
+/// Tests that a frame twice the size of the destination buffer is rejected without writing past the end
+/// of the buffer and that the needed buffer length is reported.
+fn oversized_frame_test(hib_controller: &HibController) {
+    let mut frame = [0; DEST_LEN * 4 + 2];
+    let mut reader = SliceReader::new(build_frame(&mut frame, DEST_LEN * 4));
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+    let res = bogoframing::recv_frame_with_timeout(
+        &mut reader,
+        &mut buff[..DEST_LEN],
+        &mut hib_controller.create_timer(Duration::from_millis(10)),
+        |r| r.read(),
+        1,
+    );
+
+    assert_eq!(
+        res,
+        Err(CommunicationError::BufferTooSmall {
+            needed: DEST_LEN * 2
+        })
+    );
+    assert_guard_intact(&buff);
+}
+
+/// Tests that a frame one nibble longer than the destination buffer is rejected without writing past the
+/// end of the buffer.
+fn oversized_odd_frame_test(hib_controller: &HibController) {
+    let mut frame = [0; DEST_LEN * 2 + 3];
+    let mut reader = SliceReader::new(build_frame(&mut frame, DEST_LEN * 2 + 1));
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+    let res = bogoframing::recv_frame_with_data_timeout(
+        &mut reader,
+        &mut buff[..DEST_LEN],
+        &mut hib_controller.create_timer(Duration::from_millis(10)),
+        |r| r.read(),
+        1,
+    );
+
+    assert_eq!(res, Err(CommunicationError::RecvError));
+    assert_guard_intact(&buff);
+}
+
+/// Tests that a frame is read to its end to report its length if it's up to
+/// [`MAX_OVERSIZED_FRAME_DISCARD`] bytes longer than the destination buffer, and is rejected as soon as
+/// it gets any longer than that.
+fn endless_frame_test() {
+    const LIMIT: usize = DEST_LEN + MAX_OVERSIZED_FRAME_DISCARD;
+
+    let mut frame = [0; (LIMIT + 1) * 2 + 2];
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+
+    assert_eq!(
+        bogoframing::decode_frame(build_frame(&mut frame, LIMIT * 2), &mut buff[..DEST_LEN], 1),
+        Err(CommunicationError::BufferTooSmall { needed: LIMIT })
+    );
+
+    // The frame is rejected before its end, so it doesn't need one.
+    let frame = build_frame(&mut frame, (LIMIT + 1) * 2);
+    assert_eq!(
+        bogoframing::decode_frame(&frame[..frame.len() - 1], &mut buff[..DEST_LEN], 1),
+        Err(CommunicationError::RecvError)
+    );
+    assert_guard_intact(&buff);
+}
+
+/// Tests that the crypto layer rejects an inner channel reporting more bytes than fit in the buffer
+/// instead of panicking or reading past the end of it.
+fn lying_inner_channel_test(hib_controller: &HibController) {
+    let mut ch = XChacha20Poly1305RxChannel::new(LyingRxChannel, &Default::default());
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+    let res = ch.recv_with_timeout(
+        &mut buff[..DEST_LEN],
+        &mut hib_controller.create_timer(Duration::from_millis(10)),
+    );
+
+    assert_eq!(res, Err(CommunicationError::RecvError));
+    assert_guard_intact(&buff);
+}
+
+/// Tests that [`TxChannel::send_retry`] retries failed sends after waiting at least half of each
+/// doubling delay, staying idle while it waits, and gives up after the last attempt.
+fn send_retry_backoff_test(hib_controller: &HibController) {
+    const BASE_DELAY: Duration = Duration::from_millis(4);
+
+    let mut rng = secondary_rng();
+    let mut idles = 0;
+    let mut ch = FlakyTxChannel::new(2, CommunicationError::SendError);
+    let mut timer = hib_controller.create_timer(Duration::from_secs(1));
+
+    assert_eq!(
+        ch.send_retry::<16, _>(
+            b"msg",
+            3,
+            BASE_DELAY,
+            &mut timer,
+            || rng.next_u32(),
+            || idles += 1
+        ),
+        Ok(())
+    );
+    assert_eq!(ch.sends, 3);
+    assert!(idles > 0);
+
+    // The waits are at least 2 ms and 4 ms.
+    assert!(timer.elapsed() >= Duration::from_millis(6));
+
+    let mut ch = FlakyTxChannel::new(u32::MAX, CommunicationError::Timeout);
+    let mut timer = hib_controller.create_timer(Duration::from_secs(1));
+
+    assert_eq!(
+        ch.send_retry::<16, _>(b"msg", 3, BASE_DELAY, &mut timer, || rng.next_u32(), || {}),
+        Err(CommunicationError::Timeout)
+    );
+    assert_eq!(ch.sends, 3);
+}
+
+/// Tests that [`TxChannel::send_retry`] doesn't retry errors other than send errors and timeouts,
+/// doesn't try to send messages that can never be sent, and doesn't retry once the wait would pass the
+/// deadline.
+fn send_retry_errors_test(hib_controller: &HibController) {
+    let mut rng = secondary_rng();
+
+    for error in [
+        CommunicationError::InternalError,
+        CommunicationError::SequenceExhausted,
+    ] {
+        let mut ch = FlakyTxChannel::new(1, error);
+        let mut timer = hib_controller.create_timer(Duration::from_secs(1));
+
+        assert_eq!(
+            ch.send_retry::<16, _>(
+                b"msg",
+                3,
+                Duration::from_millis(1),
+                &mut timer,
+                || rng.next_u32(),
+                || {}
+            ),
+            Err(error)
+        );
+        assert_eq!(ch.sends, 1);
+    }
+
+    // Empty messages and messages longer than the limit are refused before the first attempt.
+    for src in [&b""[..], &[0; 17]] {
+        let mut ch = FlakyTxChannel::new(0, CommunicationError::SendError);
+        let mut timer = hib_controller.create_timer(Duration::from_secs(1));
+
+        assert_eq!(
+            ch.send_retry::<16, _>(
+                src,
+                3,
+                Duration::from_millis(1),
+                &mut timer,
+                || rng.next_u32(),
+                || {}
+            ),
+            Err(CommunicationError::SendError)
+        );
+        assert_eq!(ch.sends, 0);
+    }
+
+    let mut ch = FlakyTxChannel::new(1, CommunicationError::SendError);
+    let mut timer = hib_controller.create_timer(Duration::from_millis(5));
+
+    assert_eq!(
+        ch.send_retry::<16, _>(
+            b"msg",
+            3,
+            Duration::from_millis(20),
+            &mut timer,
+            || rng.next_u32(),
+            || {}
+        ),
+        Err(CommunicationError::Timeout)
+    );
+    assert_eq!(ch.sends, 1);
+}
+
+/// Tests that [`bogoframing::decode_frame`] decodes a frame from memory in the same way as receiving it,
+/// and rejects truncated, malformed, and oversized frames without writing past the end of the buffer.
+fn decode_frame_test() {
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+
+    // Garbage and NULLs before the frame and NULLs inside it are ignored, as is anything after it.
+    assert_eq!(
+        bogoframing::decode_frame(
+            b"zz\x01\x01\x0048\x0069\x01\x01aa",
+            &mut buff[..DEST_LEN],
+            1
+        ),
+        Ok(2)
+    );
+    assert_eq!(&buff[..2], b"Hi");
+    assert_guard_intact(&buff);
+
+    // A truncated frame, an odd number of hex digits, a non-hex character, a frame shorter than the
+    // minimum length, and no frame at all.
+    for (input, err) in [
+        (&b"\x014869"[..], CommunicationError::RecvError),
+        (b"\x01486\x01", CommunicationError::RecvError),
+        (b"\x0148g9\x01", CommunicationError::RecvError),
+        (b"\x014869\x01", CommunicationError::RecvError),
+        (b"", CommunicationError::RecvError),
+    ] {
+        assert_eq!(
+            bogoframing::decode_frame(input, &mut buff[..DEST_LEN], 3),
+            Err(err)
+        );
+    }
+
+    let mut frame = [0; DEST_LEN * 4 + 2];
+    assert_eq!(
+        bogoframing::decode_frame(
+            build_frame(&mut frame, DEST_LEN * 4),
+            &mut buff[..DEST_LEN],
+            1
+        ),
+        Err(CommunicationError::BufferTooSmall {
+            needed: DEST_LEN * 2
+        })
+    );
+    assert_guard_intact(&buff);
+}
+
+/// Tests that [`bogoframing::decode_buffered_frame`] waits for the end of a frame, and reports how much
+/// of the input the frame used, whether it was received or rejected.
+fn decode_buffered_frame_test() {
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+    let input = b"zz\x014869\x01\x014869\x01";
+
+    // Nothing is received until the closing delimiter has arrived.
+    for end in 0..8 {
+        assert_eq!(
+            bogoframing::decode_buffered_frame(
+                input[..end].iter().copied(),
+                &mut buff[..DEST_LEN],
+                1
+            ),
+            None
+        );
+    }
+
+    // The next frame is left for the next receive.
+    assert_eq!(
+        bogoframing::decode_buffered_frame(input.iter().copied(), &mut buff[..DEST_LEN], 1),
+        Some((8, Ok(2)))
+    );
+    assert_eq!(&buff[..2], b"Hi");
+
+    // A frame that's rejected as soon as a bad character arrives uses the input up to that character.
+    assert_eq!(
+        bogoframing::decode_buffered_frame(b"\x0148g9".iter().copied(), &mut buff[..DEST_LEN], 1),
+        Some((4, Err(CommunicationError::RecvError)))
+    );
+
+    // A buffer shorter than the minimum length is rejected without using any input.
+    assert_eq!(
+        bogoframing::decode_buffered_frame(input.iter().copied(), &mut buff[..2], 3),
+        Some((0, Err(CommunicationError::BufferTooSmall { needed: 3 })))
+    );
+    assert_guard_intact(&buff);
+}
+
+/// Encodes `msg` as a COBS frame.
+fn cobs_encode(msg: &[u8]) -> Vec<u8, COBS_ENCODED_LEN> {
+    let mut encoded = Vec::new();
+
+    cobs::frame_cobs(
+        &mut encoded,
+        Frame::<1>::new().append(msg).unwrap(),
+        |v: &mut Vec<u8, COBS_ENCODED_LEN>, s| {
+            v.extend_from_slice(s)
+                .map_err(|_| CommunicationError::InternalError)
+        },
+        1,
+    )
+    .unwrap();
+
+    encoded
+}
+
+/// Receives a COBS frame from `input` into `dest`.
+fn cobs_decode(
+    input: &[u8],
+    dest: &mut [u8],
+    hib_controller: &HibController,
+) -> communication::Result<usize> {
+    cobs::recv_frame_with_timeout(
+        &mut SliceReader::new(input),
+        dest,
+        &mut hib_controller.create_timer(Duration::from_millis(10)),
+        |r| r.read(),
+        1,
+    )
+}
+
+/// Tests that a COBS frame is encoded as expected and decodes back to the original message.
+fn cobs_round_trip_test(hib_controller: &HibController) {
+    let mut buff = [0; DEST_LEN];
+
+    assert_eq!(
+        &cobs_encode(b"\x11\x00\x22")[..],
+        b"\x00\x02\x11\x02\x22\x00"
+    );
+
+    for msg in [
+        &b"\x11\x00\x22"[..],
+        b"\x00",
+        b"\x00\x00Hi\x00",
+        b"Hello world",
+    ] {
+        let len = cobs_decode(&cobs_encode(msg), &mut buff, hib_controller).unwrap();
+
+        assert_eq!(&buff[..len], msg);
+    }
+}
+
+/// Tests that a run of 254 non-zero bytes is encoded as one maximum length block, with no zero byte
+/// implied after it, whether or not a zero byte follows the run.
+fn cobs_max_run_test(hib_controller: &HibController) {
+    let mut msg = [0x42; 255];
+    let mut buff = [0; 255];
+
+    // A maximum length block, then an empty block to end the frame.
+    let encoded = cobs_encode(&msg[..254]);
+
+    assert_eq!(encoded.len(), 258);
+    assert_eq!(encoded[1], 0xFF);
+    assert_eq!(&encoded[256..], b"\x01\x00");
+    assert_eq!(cobs_decode(&encoded, &mut buff, hib_controller), Ok(254));
+    assert_eq!(buff[..254], msg[..254]);
+
+    // The zero byte after the run is encoded as its own block.
+    msg[254] = 0;
+
+    let encoded = cobs_encode(&msg);
+
+    assert_eq!(&encoded[256..], b"\x01\x01\x00");
+    assert_eq!(cobs_decode(&encoded, &mut buff, hib_controller), Ok(255));
+    assert_eq!(buff, msg);
+}
+
+/// Tests that everything before the first zero byte is discarded instead of being decoded as a frame.
+fn cobs_resync_test(hib_controller: &HibController) {
+    let mut input = [0; 9];
+    let mut buff = [0; DEST_LEN];
+
+    // The end of a frame that started before the receive, which would decode to 4 bytes on its own.
+    input[..3].copy_from_slice(b"\x05\x41\x42");
+    input[3..].copy_from_slice(&cobs_encode(b"\x11\x00\x22"));
+
+    assert_eq!(cobs_decode(&input, &mut buff, hib_controller), Ok(3));
+    assert_eq!(&buff[..3], b"\x11\x00\x22");
+
+    // Junk with no zero byte after it never decodes to anything.
+    assert_eq!(
+        cobs_decode(b"\x05\x41\x42\x43\x44", &mut buff, hib_controller),
+        Err(CommunicationError::Timeout)
+    );
+}
+
+/// Tests that a frame cut short by the start of the next frame is dropped in favor of the next frame, and
+/// that a frame cut short by the end of the data is never received.
+fn cobs_truncated_frame_test(hib_controller: &HibController) {
+    let mut input = [0; 10];
+    let mut buff = [0; DEST_LEN];
+
+    // A frame whose first block is cut short after 2 of its 4 bytes.
+    input[..4].copy_from_slice(b"\x00\x05\x11\x22");
+    input[4..].copy_from_slice(&cobs_encode(b"\x33\x00\x44"));
+
+    assert_eq!(cobs_decode(&input, &mut buff, hib_controller), Ok(3));
+    assert_eq!(&buff[..3], b"\x33\x00\x44");
+
+    assert_eq!(
+        cobs_decode(&input[..4], &mut buff, hib_controller),
+        Err(CommunicationError::Timeout)
+    );
+}
+
+/// Tests that a COBS frame is read to its end to report its length if it's up to
+/// [`MAX_OVERSIZED_FRAME_DISCARD`] bytes longer than the destination buffer, and is rejected as soon as
+/// it gets any longer than that.
+fn cobs_endless_frame_test(hib_controller: &HibController) {
+    const BLOCK_LEN: usize = 255;
+
+    // Enough maximum length blocks to go past the limit, each decoding to 254 bytes.
+    const BLOCKS: usize = (DEST_LEN + MAX_OVERSIZED_FRAME_DISCARD) / (BLOCK_LEN - 1) + 1;
+
+    let mut input = [0x42; 1 + BLOCKS * BLOCK_LEN + 1];
+    let mut buff = [GUARD_BYTE; DEST_LEN + GUARD_LEN];
+
+    input[0] = 0;
+    for block in input[1..].chunks_mut(BLOCK_LEN).take(BLOCKS) {
+        block[0] = 0xFF;
+    }
+
+    // One block fewer is within the limit.
+    let short_len = 1 + (BLOCKS - 1) * BLOCK_LEN;
+    input[short_len] = 0;
+    assert_eq!(
+        cobs_decode(
+            &input[..short_len + 1],
+            &mut buff[..DEST_LEN],
+            hib_controller
+        ),
+        Err(CommunicationError::BufferTooSmall {
+            needed: (BLOCKS - 1) * (BLOCK_LEN - 1)
+        })
+    );
+    input[short_len] = 0xFF;
+
+    // The frame is rejected before its end, so it doesn't need one.
+    assert_eq!(
+        cobs_decode(
+            &input[..input.len() - 1],
+            &mut buff[..DEST_LEN],
+            hib_controller
+        ),
+        Err(CommunicationError::RecvError)
+    );
+    assert_guard_intact(&buff);
+}
+
+/// Tests that the CRC-16/CCITT matches the standard check value, and that messages sent through a
+/// [`CrcFramedTxChannel`] are received whole by a [`CrcFramedRxChannel`], including frames with more
+/// slices than any fixed limit.
+fn crc_round_trip_test(hib_controller: &HibController) {
+    let mut timer = hib_controller.create_timer(Duration::from_secs(1));
+    let mut dest = [0; DEST_LEN];
+
+    // The check value of CRC-16/CCITT-FALSE.
+    assert_eq!(crc16_ccitt(b"123456789"), 0x29B1);
+
+    let mut tx = CrcFramedTxChannel::new(CaptureChannel::default());
+    let mut msg = *b"123456789";
+    tx.send(&mut msg).unwrap();
+
+    let mut capture = tx.into_inner();
+    assert_eq!(&capture.buf[..], b"123456789\x29\xB1");
+
+    let mut rx = CrcFramedRxChannel::new(capture);
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut timer), Ok(9));
+    assert_eq!(&dest[..9], b"123456789");
+
+    // recv_exact needs room for the CRC at the end of dest.
+    assert_eq!(rx.recv_exact(&mut dest[..9 + CRC_SIZE], &mut timer), Ok(()));
+    assert_eq!(
+        rx.recv_exact(&mut dest[..10 + CRC_SIZE], &mut timer),
+        Err(CommunicationError::RecvError)
+    );
+
+    // A frame with 12 slices.
+    const PARTS: [&[u8]; 12] = [
+        b"a", b"bc", b"", b"def", b"g", b"hi", b"j", b"", b"klm", b"n", b"op", b"q",
+    ];
+
+    capture = rx.into_inner();
+    let mut tx = CrcFramedTxChannel::new(capture);
+    tx.frame::<12>(|| {
+        PARTS
+            .iter()
+            .try_fold(Frame::new(), |frame, &part| frame.append(part))
+    })
+    .unwrap();
+
+    let mut rx = CrcFramedRxChannel::new(tx.into_inner());
+    assert_eq!(rx.recv_with_timeout(&mut dest, &mut timer), Ok(17));
+    assert_eq!(&dest[..17], b"abcdefghijklmnopq");
+
+    // A frame that already has a trailer can't be sent, since the CRC is the trailer.
+    let mut tx = CrcFramedTxChannel::new(rx.into_inner());
+    assert_eq!(
+        tx.frame::<1>(|| Frame::new().append(b"msg")?.with_trailer(b"end")),
+        Err(CommunicationError::InternalError)
+    );
+}
+
+/// Tests that a [`CrcFramedRxChannel`] rejects messages with any single bit flipped, and messages too
+/// short to hold a CRC.
+fn crc_corruption_test(hib_controller: &HibController) {
+    let mut timer = hib_controller.create_timer(Duration::from_secs(1));
+    let mut dest = [0; DEST_LEN];
+
+    let mut tx = CrcFramedTxChannel::new(CaptureChannel::default());
+    let mut msg = *b"corrupt me";
+    tx.send(&mut msg).unwrap();
+    let sent = tx.into_inner().buf;
+
+    for bit in 0..sent.len() * 8 {
+        let mut capture = CaptureChannel { buf: sent.clone() };
+        capture.buf[bit / 8] ^= 1 << (bit % 8);
+
+        let mut rx = CrcFramedRxChannel::new(capture);
+        assert_eq!(
+            rx.recv_with_timeout(&mut dest, &mut timer),
+            Err(CommunicationError::RecvError)
+        );
+    }
+
+    for len in 0..CRC_SIZE {
+        let mut capture = CaptureChannel::default();
+        capture.buf.extend_from_slice(&sent[..len]).unwrap();
+
+        let mut rx = CrcFramedRxChannel::new(capture);
+        assert_eq!(
+            rx.recv_with_timeout(&mut dest, &mut timer),
+            Err(CommunicationError::RecvError)
+        );
+    }
+}
+
+/// Tests that [`RxChannel::recv_until`] applies one deadline across several receives, and doesn't receive
+/// anything once the deadline has elapsed.
+fn recv_until_test(hib_controller: &HibController) {
+    // Each frame is 6 bytes, so each takes about 30 ms to arrive.
+    let mut ch = TrickleRxChannel {
+        reader: SliceReader::new(b"\x014869\x01\x014869\x01"),
+        pace: hib_controller.create_timer(Duration::from_millis(5)),
+    };
+    let mut deadline = hib_controller.create_timer(Duration::from_millis(45));
+    let mut buff = [0; DEST_LEN];
+
+    assert_eq!(ch.recv_until(&mut buff, &mut deadline), Ok(2));
+    assert_eq!(&buff[..2], b"Hi");
+
+    // The second frame would arrive in time for a fresh timeout, but not within the shared deadline.
+    assert_eq!(
+        ch.recv_until(&mut buff, &mut deadline),
+        Err(CommunicationError::Timeout)
+    );
+
+    let read = ch.reader.idx;
+
+    // Nothing is read once the deadline has elapsed.
+    assert_eq!(
+        ch.recv_until(&mut buff, &mut deadline),
+        Err(CommunicationError::Timeout)
+    );
+    assert_eq!(ch.reader.idx, read);
+}
+
+/// Tests that [`RxChannel::recv_cancellable`] stops part way through a message once the cancel flag is
+/// set, doesn't receive anything while the flag is set, and otherwise receives like
+/// [`RxChannel::recv_with_timeout`].
+fn recv_cancellable_test(hib_controller: &HibController) {
+    const FRAME: &[u8] = b"\x014869\x01";
+
+    let cancel = AtomicBool::new(false);
+    let mut buff = [0; DEST_LEN];
+    let mut ch = CancellingRxChannel {
+        reader: SliceReader::new(FRAME),
+        cancel: &cancel,
+        cancel_after: usize::MAX,
+    };
+
+    assert_eq!(
+        ch.recv_cancellable(
+            &mut buff,
+            &mut hib_controller.create_timer(Duration::from_millis(10)),
+            &cancel
+        ),
+        Ok(2)
+    );
+    assert_eq!(&buff[..2], b"Hi");
+
+    // A timeout is still reported as a timeout.
+    assert_eq!(
+        ch.recv_cancellable(
+            &mut buff,
+            &mut hib_controller.create_timer(Duration::from_millis(10)),
+            &cancel
+        ),
+        Err(CommunicationError::Timeout)
+    );
+
+    // The flag is raised after 3 bytes, so the receive stops within a byte read, long before the timeout.
+    let mut ch = CancellingRxChannel {
+        reader: SliceReader::new(FRAME),
+        cancel: &cancel,
+        cancel_after: 3,
+    };
+    let timer = hib_controller.create_timer(Duration::from_secs(10));
+    let mut deadline = hib_controller.create_timer(Duration::from_secs(10));
+
+    assert_eq!(
+        ch.recv_cancellable(&mut buff, &mut deadline, &cancel),
+        Err(CommunicationError::Cancelled)
+    );
+    assert!(ch.reader.idx <= 4);
+    assert!(timer.elapsed() < Duration::from_millis(10));
+
+    // Nothing is read while the flag is still set.
+    let read = ch.reader.idx;
+
+    assert_eq!(
+        ch.recv_cancellable(&mut buff, &mut deadline, &cancel),
+        Err(CommunicationError::Cancelled)
+    );
+    assert_eq!(ch.reader.idx, read);
+}
+
+/// Tests that [`bogoframing::recv_frame_with_progress`] counts every raw byte read, including garbage
+/// before the frame and the bytes of a frame that times out part way through.
+fn recv_progress_test(hib_controller: &HibController) {
+    let mut buff = [0; DEST_LEN];
+
+    // Two bytes of garbage, then a whole frame.
+    let mut reader = SliceReader::new(b"zz\x014869\x01");
+    assert_eq!(
+        bogoframing::recv_frame_with_progress(
+            &mut reader,
+            &mut buff,
+            &mut hib_controller.create_timer(Duration::from_millis(10)),
+            |r| r.read(),
+            1,
+        ),
+        (8, Ok(2))
+    );
+    assert_eq!(&buff[..2], b"Hi");
+
+    // A frame cut off after its opening delimiter and three hex digits.
+    let mut reader = SliceReader::new(b"\x01486");
+    assert_eq!(
+        bogoframing::recv_frame_with_progress(
+            &mut reader,
+            &mut buff,
+            &mut hib_controller.create_timer(Duration::from_millis(10)),
+            |r| r.read(),
+            1,
+        ),
+        (4, Err(CommunicationError::Timeout))
+    );
+}
+
+/// Tests that [`RxChannel::recv_scaled`] gives a long message the time it needs, up to the duration of its
+/// timer, and doesn't wait longer than its base timeout when nothing arrives.
+fn recv_scaled_test(hib_controller: &HibController) {
+    // The frame is 24 bytes, so it takes about 120 ms to arrive.
+    const FRAME: &[u8] = b"\x0148656c6c6f20776f726c64\x01";
+
+    let mut buff = [0; DEST_LEN];
+    let mut ch = TrickleRxChannel {
+        reader: SliceReader::new(FRAME),
+        pace: hib_controller.create_timer(Duration::from_millis(5)),
+    };
+
+    // A fixed timeout that's fine for a short frame is too short for this one.
+    assert_eq!(
+        ch.recv_with_timeout(
+            &mut buff,
+            &mut hib_controller.create_timer(Duration::from_millis(40))
+        ),
+        Err(CommunicationError::Timeout)
+    );
+
+    ch.reader = SliceReader::new(FRAME);
+
+    assert_eq!(
+        ch.recv_scaled(
+            &mut buff,
+            Duration::from_millis(40),
+            Duration::from_millis(10),
+            &mut hib_controller.create_timer(Duration::from_secs(1))
+        ),
+        Ok(11)
+    );
+    assert_eq!(&buff[..11], b"Hello world");
+
+    // The timer caps the deadline, no matter how many bytes have been read.
+    ch.reader = SliceReader::new(FRAME);
+
+    let timer = hib_controller.create_timer(Duration::from_secs(10));
+
+    assert_eq!(
+        ch.recv_scaled(
+            &mut buff,
+            Duration::from_millis(40),
+            Duration::from_millis(10),
+            &mut hib_controller.create_timer(Duration::from_millis(60))
+        ),
+        Err(CommunicationError::Timeout)
+    );
+    assert!(timer.elapsed() < Duration::from_millis(100));
+
+    // With nothing to read, the receive gives up after the base timeout.
+    ch.reader = SliceReader::new(&[]);
+
+    let timer = hib_controller.create_timer(Duration::from_secs(10));
+
+    assert_eq!(
+        ch.recv_scaled(
+            &mut buff,
+            Duration::from_millis(20),
+            Duration::from_millis(10),
+            &mut hib_controller.create_timer(Duration::from_secs(1))
+        ),
+        Err(CommunicationError::Timeout)
+    );
+    assert!(timer.elapsed() < Duration::from_millis(50));
+}
+
+/// Tests that a padded AEAD receive takes the padding time to fail, whether the message is rejected for
+/// its length or for failing to authenticate.
+fn recv_padded_test(hib_controller: &HibController) {
+    let mut padding = hib_controller.create_timer(PADDING);
+    let mut buff = [0; DEST_LEN * 2];
+
+    // The inner channel's length is rejected as soon as it returns.
+    assert_padded(
+        &mut XChacha20Poly1305RxChannel::new(LyingRxChannel, &Default::default()),
+        &mut buff,
+        &mut padding,
+        hib_controller,
+    );
+
+    // The message is 64 bytes of 0xAA, except for a valid nonce mode byte at the start of the nonce,
+    // which comes after the 24 bytes of ciphertext, so it's decrypted and fails to authenticate.
+    let mut frame = [0; DEST_LEN * 4 + 2];
+    build_frame(&mut frame, DEST_LEN * 4);
+    frame[49..51].copy_from_slice(b"00");
+
+    let mut ch = XChacha20Poly1305RxChannel::new(
+        TrickleRxChannel {
+            reader: SliceReader::new(&frame),
+            pace: hib_controller.create_timer(Duration::ZERO),
+        },
+        &Default::default(),
+    );
+
+    assert_padded(&mut ch, &mut buff, &mut padding, hib_controller);
+    assert_eq!(ch.auth_failures(), 1);
+}
+
+/// Checks that a padded receive on the channel fails with a [`CommunicationError::RecvError`] after
+/// [`PADDING`].
+fn assert_padded<T: RxChannel>(
+    ch: &mut XChacha20Poly1305RxChannel<T>,
+    buff: &mut [u8],
+    padding: &mut HibTimer,
+    hib_controller: &HibController,
+) {
+    let start = hib_controller.now();
+    let res = ch.recv_with_timeout_padded(
+        buff,
+        b"",
+        &mut hib_controller.create_timer(Duration::from_millis(100)),
+        padding,
+    );
+    let elapsed = hib_controller.now() - start;
+
+    assert_eq!(res, Err(CommunicationError::RecvError));
+    assert!(elapsed >= PADDING);
+    assert!(elapsed < PADDING + Duration::from_millis(5));
+}
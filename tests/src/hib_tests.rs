@@ -0,0 +1,64 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    hib::{HibDataError, HIB_NV_WORDS},
+    timer::Timer,
+    Runtime,
+};
+
+pub fn run(rt: &Runtime) {
+    now_monotonic_test(rt);
+    set_rtc_test(rt);
+    data_test(rt);
+}
+
+/// Checks that the RTC time moves forward as time passes.
+fn now_monotonic_test(rt: &Runtime) {
+    let start = rt.now();
+    let mut timer = rt.hib_controller.create_timer(Duration::from_millis(50));
+
+    while !timer.poll() {
+        assert!(rt.now() >= start);
+    }
+
+    assert!(rt.now() >= start + Duration::from_millis(50));
+}
+
+/// Checks that setting the RTC loads whole seconds and restarts the subsecond count.
+fn set_rtc_test(rt: &Runtime) {
+    rt.set_rtc(Duration::from_millis(1_000_500));
+
+    let now = rt.now();
+    assert!(now >= Duration::from_secs(1000));
+    assert!(now < Duration::from_millis(1_000_500));
+
+    let mut timer = rt.hib_controller.create_timer(Duration::from_millis(100));
+    while !timer.poll() {}
+
+    assert!(rt.now() >= Duration::from_millis(1_000_100));
+}
+
+/// Checks that words stored in the battery-backed data registers are loaded back, and that indices
+/// past the end are rejected.
+fn data_test(rt: &Runtime) {
+    rt.hib_controller.clear_data().unwrap();
+
+    for index in 0..HIB_NV_WORDS {
+        assert_eq!(rt.hib_load(index), Ok(0));
+    }
+
+    for index in 0..HIB_NV_WORDS {
+        rt.hib_store(index, 0xA5A5_0000 | index as u32).unwrap();
+    }
+
+    for index in 0..HIB_NV_WORDS {
+        assert_eq!(rt.hib_load(index), Ok(0xA5A5_0000 | index as u32));
+    }
+
+    assert_eq!(rt.hib_store(HIB_NV_WORDS, 0), Err(HibDataError::OutOfRange));
+    assert_eq!(rt.hib_load(HIB_NV_WORDS), Err(HibDataError::OutOfRange));
+
+    rt.hib_controller.clear_data().unwrap();
+    assert_eq!(rt.hib_load(0), Ok(0));
+}
@@ -0,0 +1,93 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use tm4c123x_hal::tm4c123x::GPIO_PORTF;
+use ucsc_ectf_util_no_std::{
+    led::{BlinkPattern, LedState},
+    Runtime,
+};
+
+/// The mask for the LED pins, PF1 to PF3.
+const LED_MASK: u32 = 0b1110;
+
+/// The mask for the button pins, PF0 and PF4.
+const BUTTON_MASK: u32 = 0b1_0001;
+
+pub fn run(rt: &mut Runtime) {
+    state_test(rt);
+    blink_test(rt);
+    button_sharing_test(rt);
+}
+
+/// Reads which LED pins are driven high.
+fn led_pins() -> u32 {
+    // SAFETY: The data register is only read.
+    unsafe { &*GPIO_PORTF::ptr() }.data.read().bits() & LED_MASK
+}
+
+/// Tests that each state drives its colors and that the state is kept.
+fn state_test(rt: &mut Runtime) {
+    assert_eq!(rt.status_led.state(), LedState::Off);
+    assert_eq!(led_pins(), 0);
+
+    for (state, pins) in [
+        (LedState::Idle, 0b0100),
+        (LedState::Pairing, 0b1010),
+        (LedState::Unlocked, 0b1000),
+        (LedState::Error, 0b0010),
+        (LedState::Off, 0),
+    ] {
+        rt.status_led.set_state(state);
+        assert_eq!(rt.status_led.state(), state);
+        assert_eq!(led_pins(), pins);
+    }
+}
+
+/// Tests that blinking takes as long as the pattern and then shows the previous state again.
+fn blink_test(rt: &mut Runtime) {
+    let pattern = BlinkPattern {
+        state: LedState::Error,
+        on_time: Duration::from_millis(50),
+        off_time: Duration::from_millis(50),
+        count: 3,
+    };
+
+    rt.status_led.set_state(LedState::Unlocked);
+
+    let start = rt.hib_controller.now();
+    rt.status_led.blink(pattern, &rt.hib_controller);
+    let elapsed = rt.hib_controller.now() - start;
+
+    assert!(elapsed >= Duration::from_millis(300));
+    assert!(elapsed < Duration::from_millis(400));
+    assert_eq!(rt.status_led.state(), LedState::Unlocked);
+    assert_eq!(led_pins(), 0b1000);
+
+    // A pattern with no blinks does nothing.
+    rt.status_led.blink(
+        BlinkPattern {
+            count: 0,
+            ..pattern
+        },
+        &rt.hib_controller,
+    );
+    assert_eq!(rt.status_led.state(), LedState::Unlocked);
+
+    rt.status_led.set_state(LedState::Off);
+}
+
+/// Tests that driving the LED leaves the button pins configured as pull-up inputs.
+fn button_sharing_test(rt: &mut Runtime) {
+    // SAFETY: The registers are only read.
+    let regs = unsafe { &*GPIO_PORTF::ptr() };
+
+    rt.status_led.set_state(LedState::Pairing);
+
+    assert_eq!(regs.dir.read().bits() & BUTTON_MASK, 0);
+    assert_eq!(regs.pur.read().bits() & BUTTON_MASK, BUTTON_MASK);
+    assert_eq!(regs.den.read().bits() & BUTTON_MASK, BUTTON_MASK);
+    assert_eq!(regs.dir.read().bits() & LED_MASK, LED_MASK);
+    assert!(!rt.sw2_button.is_pressed());
+
+    rt.status_led.set_state(LedState::Off);
+}
@@ -0,0 +1,70 @@
+#![cfg(debug_assertions)]
+
+use ucsc_ectf_util_no_std::{
+    debug, debug_hex, info,
+    log::{self, Level, MAX_LEVEL},
+    messages::heapless::String,
+    warn,
+};
+
+pub fn run() {
+    level_test();
+    macro_test();
+    hexdump_test();
+}
+
+fn level_test() {
+    if cfg!(feature = "log-off") {
+        // The log-off feature skips every level.
+        assert_eq!(MAX_LEVEL, Level::Off);
+        assert!(!log::enabled(Level::Debug));
+        assert!(!log::enabled(Level::Info));
+        assert!(!log::enabled(Level::Warn));
+    } else {
+        // Debug builds write every level otherwise.
+        assert_eq!(MAX_LEVEL, Level::Debug);
+        assert!(log::enabled(Level::Debug));
+        assert!(log::enabled(Level::Info));
+        assert!(log::enabled(Level::Warn));
+    }
+
+    assert!(!log::enabled(Level::Off));
+}
+
+fn macro_test() {
+    let mut evaluated = 0;
+
+    debug!("Verify this debug message: {}", {
+        evaluated += 1;
+        evaluated
+    });
+    info!("Verify this info message: {:02X?}", [0xAB, 0xCD]);
+    warn!("Verify this warning message.");
+    debug_hex!(b"Hex dump\x00\x01");
+    debug_hex!([0xAB, 0xCD], "Verify this hex dump of {} bytes:", 2);
+
+    // Arguments of messages that are written are evaluated exactly once, and those of messages that
+    // are skipped aren't evaluated at all.
+    assert_eq!(evaluated, if cfg!(feature = "log-off") { 0 } else { 1 });
+}
+
+fn hexdump_test() {
+    let mut out = String::<256>::new();
+    log::hexdump(b"Hello, hexdump!\x00\x01\xff", &mut out).unwrap();
+
+    // A short last line is padded so the ASCII column lines up.
+    assert_eq!(
+        out,
+        "00000000  48 65 6c 6c 6f 2c 20 68  65 78 64 75 6d 70 21 00  |Hello, hexdump!.|\n\
+         00000010  01 ff                                             |..|\n"
+    );
+
+    // Nothing is written for no bytes.
+    out.clear();
+    log::hexdump(&[], &mut out).unwrap();
+    assert!(out.is_empty());
+
+    // Running out of space is an error.
+    let mut small = String::<16>::new();
+    assert!(log::hexdump(&[0; 4], &mut small).is_err());
+}
@@ -8,21 +8,52 @@ extern crate panic_semihosting;
 #[cfg(not(debug_assertions))]
 extern crate tm4c123x_hal;
 
+mod adc_tests;
+mod bitbang_tests;
+mod button_tests;
+mod can_tests;
+mod crc_tests;
+mod crypto_tests;
+mod delay_tests;
+mod duplex_tests;
 mod eeprom_tests;
+mod event_tests;
+mod framing_tests;
+mod hib_tests;
+mod led_tests;
+mod log_tests;
+mod measurement_tests;
+mod messages_tests;
 mod random_tests;
+mod rate_limit_tests;
+mod rs485_tests;
 mod rt_comm_tests;
+mod runtime_tests;
+mod select_tests;
+mod test_utils;
 mod timer_tests;
+mod watchdog_tests;
+mod writer_tests;
 
 use core::fmt::Write;
 use cortex_m_rt::entry;
 use cortex_m_semihosting::hio;
 
+#[cfg(debug_assertions)]
+use core::ptr;
+
 #[cfg(debug_assertions)]
 use tm4c123x_hal::{CorePeripherals, Peripherals};
 
 #[cfg(debug_assertions)]
 use ucsc_ectf_util_no_std::{Runtime, RuntimePeripherals};
 
+#[cfg(debug_assertions)]
+extern "C" {
+    /// The bottom of the stack, defined in memory.x.
+    static _stack_end: u32;
+}
+
 #[cfg(debug_assertions)]
 #[entry]
 fn main() -> ! {
@@ -39,18 +70,57 @@ fn main() -> ! {
             &mut rt_peripherals,
             &Default::default(),
             &Default::default(),
-        );
+        )
+        .unwrap();
+
+        // Fault on a stack overflow instead of running off the bottom of the stack.
+        // SAFETY: _stack_end is defined in memory.x, and only its address is taken.
+        rt.configure_stack_guard(unsafe { ptr::addr_of!(_stack_end) });
 
         // Insert tests relying on runtime below. Use asserts to panic if tests fail.
+        adc_tests::run(&rt);
+        button_tests::run(&mut rt);
+        can_tests::run(&mut rt.can0_controller, &rt.hib_controller);
+        crc_tests::run(&rt.hib_controller, &mut stdout);
+        delay_tests::run(&mut rt);
         eeprom_tests::run(&mut rt.eeprom_controller);
+        event_tests::run(&mut rt);
+        framing_tests::run(&rt.hib_controller);
+        rate_limit_tests::run(&mut rt.eeprom_controller, &rt.hib_controller);
         random_tests::run(&mut rt, &mut stdout);
-        rt_comm_tests::run(&mut rt.uart0_controller, &mut rt.uart1_controller);
+        rt_comm_tests::run(
+            &mut rt.uart0_controller,
+            &mut rt.uart1_controller,
+            &rt.hib_controller,
+        );
+        hib_tests::run(&rt);
+        measurement_tests::run(&rt);
+        led_tests::run(&mut rt);
+        watchdog_tests::run(&mut rt);
     }
 
     // Insert non-runtime tests below. Use asserts to panic if tests fail.
 
+    bitbang_tests::run();
+
+    crypto_tests::run();
+
+    duplex_tests::run();
+
+    log_tests::run();
+
+    messages_tests::run();
+
+    rs485_tests::run();
+
+    runtime_tests::run(&mut rt_peripherals);
+
+    select_tests::run();
+
     timer_tests::run(&rt_peripherals.hib, &mut rt_peripherals.delay);
 
+    writer_tests::run();
+
     // Insert non-runtime tests above. Use asserts to panic if tests fail.
 
     writeln!(stdout, "Tests passed!").unwrap();
@@ -0,0 +1,86 @@
+#![cfg(debug_assertions)]
+
+use crate::test_utils::{LoopbackChannel, NeverTimer};
+use core::ptr;
+use ucsc_ectf_util_no_std::{
+    communication::RxChannel,
+    measurement::{self, MEASUREMENT_SIZE},
+    Runtime,
+};
+
+extern "C" {
+    /// The start of the firmware image, defined in memory.x.
+    static _firmware_start: u8;
+
+    /// The end of the firmware image, defined in memory.x.
+    static _firmware_end: u8;
+}
+
+/// The SHA3-256 hash of "abc".
+const ABC_SHA3_256: [u8; 32] = [
+    0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3, 0x90, 0xbd,
+    0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45, 0x11, 0x43, 0x15, 0x32,
+];
+
+/// The SHA3-256 hash of no data.
+const EMPTY_SHA3_256: [u8; 32] = [
+    0xa7, 0xff, 0xc6, 0xf8, 0xbf, 0x1e, 0xd7, 0x66, 0x51, 0xc1, 0x47, 0x56, 0xa0, 0x61, 0xd6, 0x62,
+    0xf5, 0x80, 0xff, 0x4d, 0xe4, 0x3b, 0x49, 0xfa, 0x82, 0xd8, 0x0a, 0x4b, 0x80, 0xf8, 0x43, 0x4a,
+];
+
+/// Flash to measure in place of the firmware image, so the measurement is known.
+static ABC: [u8; 3] = *b"abc";
+
+pub fn run(rt: &Runtime) {
+    known_measurement_test(rt);
+    firmware_measurement_test(rt);
+    report_test(rt);
+}
+
+/// Measures the firmware image between the symbols in memory.x.
+fn measure_image(rt: &Runtime) -> [u8; MEASUREMENT_SIZE] {
+    // SAFETY: The symbols are defined in memory.x as the bounds of the flash region, and only their
+    // addresses are taken.
+    unsafe { rt.measure_firmware(ptr::addr_of!(_firmware_start), ptr::addr_of!(_firmware_end)) }
+}
+
+/// Tests that measuring a known region of flash gives its SHA3-256 hash, including an empty region.
+fn known_measurement_test(rt: &Runtime) {
+    let range = ABC.as_ptr_range();
+
+    // SAFETY: ABC is a static, so the range is readable and never written to.
+    let measurement = unsafe { rt.measure_firmware(range.start, range.end) };
+
+    assert_eq!(measurement, ABC_SHA3_256);
+
+    // SAFETY: An empty range reads nothing.
+    let empty = unsafe { rt.measure_firmware(range.start, range.start) };
+
+    assert_eq!(empty, EMPTY_SHA3_256);
+}
+
+/// Tests that the firmware image between the symbols in memory.x covers flash and measures the same
+/// every time.
+fn firmware_measurement_test(rt: &Runtime) {
+    // SAFETY: Only the addresses of the symbols are taken.
+    let (start, end) = unsafe { (ptr::addr_of!(_firmware_start), ptr::addr_of!(_firmware_end)) };
+
+    assert!(start < end);
+    assert!(start <= ABC.as_ptr() && ABC.as_ptr_range().end <= end);
+    assert_eq!(measure_image(rt), measure_image(rt));
+}
+
+/// Tests that a reported measurement is sent as one message of its bytes.
+fn report_test(rt: &Runtime) {
+    let mut channel = LoopbackChannel::default();
+    let measurement = measure_image(rt);
+
+    measurement::report_measurement(&measurement, &mut channel).unwrap();
+
+    let mut received = [0; 64];
+    let len = channel
+        .recv_with_timeout(&mut received, &mut NeverTimer)
+        .unwrap();
+
+    assert_eq!(&received[..len], &measurement);
+}
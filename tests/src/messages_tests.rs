@@ -0,0 +1,83 @@
+#![cfg(debug_assertions)]
+
+use crate::test_utils::{LoopbackChannel, NeverTimer};
+use ucsc_ectf_util_no_std::{
+    communication::CommunicationError,
+    messages::{self, PairingRequest, Uart1Message, UnlockChallenge, UnlockRequest},
+};
+
+pub fn run() {
+    encode_decode_test();
+    unknown_type_test();
+    encode_too_small_test();
+    send_recv_message_test();
+}
+
+/// Tests that a message decodes to the same message it was encoded from, and that its first byte is
+/// its message type.
+fn encode_decode_test() {
+    let msg = Uart1Message::UnlockChallenge(UnlockChallenge {
+        car_id: 0x1234,
+        challenge: [0xAB; 16],
+    });
+    let mut buf = [0; 64];
+    let len = msg.encode(&mut buf).unwrap();
+
+    // UnlockChallenge is the second variant.
+    assert_eq!(buf[0], 1);
+
+    let Ok(Uart1Message::UnlockChallenge(decoded)) = Uart1Message::decode(&buf[..len]) else {
+        panic!("Decoded the wrong message");
+    };
+
+    assert_eq!(decoded.car_id, 0x1234);
+    assert_eq!(decoded.challenge, [0xAB; 16]);
+}
+
+/// Tests that a message with an unknown type or no data fails to decode.
+fn unknown_type_test() {
+    assert!(matches!(
+        Uart1Message::decode(&[0x7F]),
+        Err(CommunicationError::RecvError)
+    ));
+    assert!(matches!(
+        Uart1Message::decode(&[]),
+        Err(CommunicationError::RecvError)
+    ));
+
+    // A known type that's cut short also fails to decode.
+    assert!(matches!(
+        Uart1Message::decode(&[5, 0xAB]),
+        Err(CommunicationError::RecvError)
+    ));
+}
+
+/// Tests that encoding a message into a buffer that's too small fails.
+fn encode_too_small_test() {
+    let msg = Uart1Message::PairingRequest(PairingRequest([0; 16]));
+    let mut buf = [0; 8];
+
+    assert_eq!(msg.encode(&mut buf), Err(CommunicationError::SendError));
+}
+
+/// Tests that a message sent with [`messages::send_message`] is received with
+/// [`messages::recv_message`].
+fn send_recv_message_test() {
+    let mut channel = LoopbackChannel::default();
+    let mut buf = [0; 64];
+
+    messages::send_message(
+        &mut channel,
+        &Uart1Message::UnlockRequest(UnlockRequest(42)),
+        &mut buf,
+    )
+    .unwrap();
+
+    let mut buf = [0; 64];
+    let msg = messages::recv_message::<Uart1Message, _>(&mut channel, &mut buf, &mut NeverTimer);
+
+    assert!(matches!(
+        msg,
+        Ok(Uart1Message::UnlockRequest(UnlockRequest(42)))
+    ));
+}
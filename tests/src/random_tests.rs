@@ -2,10 +2,106 @@
 
 use core::fmt::Write;
 use cortex_m_semihosting::hio::HostStream;
-use ucsc_ectf_util_no_std::Runtime;
+use rand_core::RngCore;
+use ucsc_ectf_util_no_std::{
+    bytes_since_reseed, communication::lower_layers::crypto::RandomSource, entropy_report,
+    health_test_bits, health_test_readings, last_entropy_health, main_rng, random_key,
+    secondary_rng, seed_main_for_test, EntropyHealth, EntropySourceKind, Runtime,
+};
 
 pub fn run(rt: &mut Runtime, stdout: &mut HostStream) {
+    entropy_health_test();
+    health_failure_test();
+    entropy_report_test(stdout);
     basic_slice_test(rt, stdout);
+    reseed_test(rt);
+    auto_reseed_test(rt);
+    reseed_secondary_test(rt);
+    rand_array_test(rt);
+    gen_range_bounds_test(rt);
+    gen_range_residue_test(rt);
+    rng_handle_test();
+    seed_main_for_test_test(rt);
+}
+
+fn entropy_health_test() {
+    // The runtime panics on initialization if the health tests fail, so they must have passed.
+    assert_eq!(last_entropy_health(), EntropyHealth::Healthy);
+}
+
+fn health_failure_test() {
+    use EntropySourceKind::{Adc, ClockDrift, TempSensor};
+
+    // 50 bytes, like the ADC samples. Alternating bits are healthy.
+    let mut samples = [0x55; 50];
+    assert_eq!(health_test_bits(&samples, Adc), EntropyHealth::Healthy);
+
+    // 40 identical bits in a row pass the repetition count test, but 41 don't.
+    samples[..5].fill(0);
+    samples[5] = 0x01;
+    assert_eq!(health_test_bits(&samples, Adc), EntropyHealth::Healthy);
+
+    samples[5] = 0x02;
+    assert_eq!(
+        health_test_bits(&samples, ClockDrift),
+        EntropyHealth::RepetitionCountFailure(ClockDrift)
+    );
+
+    // A stuck source fails the repetition count test.
+    assert_eq!(
+        health_test_bits(&[0; 50], Adc),
+        EntropyHealth::RepetitionCountFailure(Adc)
+    );
+
+    // 23 ones for every zero never repeats 41 times in a row, but is far too biased for the adaptive
+    // proportion test.
+    let mut samples = [0xFF; 48];
+    for byte in samples.iter_mut().skip(2).step_by(3) {
+        *byte = 0xFE;
+    }
+
+    assert_eq!(
+        health_test_bits(&samples, Adc),
+        EntropyHealth::AdaptiveProportionFailure(Adc)
+    );
+
+    // A partial window at the end isn't tested.
+    assert_eq!(
+        health_test_bits(&samples[..15], Adc),
+        EntropyHealth::Healthy
+    );
+
+    // Readings only fail once the same one repeats 21 times in a row.
+    let mut readings = [0u16; 64];
+    for (i, reading) in readings.iter_mut().enumerate() {
+        *reading = i as u16;
+    }
+
+    readings[10..30].fill(7);
+    assert_eq!(
+        health_test_readings(&readings, TempSensor),
+        EntropyHealth::Healthy
+    );
+
+    readings[30] = 7;
+    assert_eq!(
+        health_test_readings(&readings, TempSensor),
+        EntropyHealth::RepetitionCountFailure(TempSensor)
+    );
+}
+
+fn entropy_report_test(stdout: &mut HostStream) {
+    let report = entropy_report();
+
+    writeln!(stdout, "Entropy report: {report:?}").unwrap();
+
+    assert!(report.every_source_contributed());
+    assert!(report.adc.bits > 0 && report.adc.ones <= report.adc.bits);
+    assert!(report.temp_sensor.readings > 0);
+    // The UART1 RX line noise is skipped if another board holds the line, so it may be empty.
+    assert!(report.uart_noise.ones <= report.uart_noise.bits);
+    assert!(report.uart_noise.bits > 0 || report.uart_noise.estimated_entropy == 0);
+    assert!(report.total_estimated_entropy() >= report.adc.estimated_entropy);
 }
 
 fn basic_slice_test(rt: &mut Runtime, stdout: &mut HostStream) {
@@ -16,3 +112,185 @@ fn basic_slice_test(rt: &mut Runtime, stdout: &mut HostStream) {
 
     writeln!(stdout, "Verify the randomness of this slice: {:02X?}", data).unwrap();
 }
+
+fn reseed_test(rt: &mut Runtime) {
+    let mut before = [0; 16];
+    let mut after = [0; 16];
+    let report = entropy_report();
+
+    rt.fill_rand_slice(&mut before);
+    assert!(bytes_since_reseed() >= before.len());
+
+    rt.reseed_rng().unwrap();
+    assert_eq!(bytes_since_reseed(), 0);
+
+    rt.fill_rand_slice(&mut after);
+    assert_eq!(bytes_since_reseed(), after.len());
+
+    // A reseed doesn't change the report.
+    assert_eq!(entropy_report(), report);
+
+    assert!(!after.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+    assert_ne!(before, after);
+    assert_eq!(last_entropy_health(), EntropyHealth::Healthy);
+}
+
+fn auto_reseed_test(rt: &mut Runtime) {
+    let mut data = [0; 16];
+
+    // Below the threshold, the count keeps growing.
+    rt.set_rng_reseed_threshold(data.len() * 2);
+    rt.fill_rand_slice(&mut data);
+    assert_eq!(bytes_since_reseed(), data.len());
+
+    // Crossing the threshold reseeds, which resets the count, then restore the default.
+    rt.fill_rand_slice(&mut data);
+    assert_eq!(last_entropy_health(), EntropyHealth::Healthy);
+    assert_eq!(bytes_since_reseed(), 0);
+
+    rt.set_rng_reseed_threshold(1024 * 1024);
+
+    assert!(!data.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+}
+
+fn reseed_secondary_test(rt: &mut Runtime) {
+    let mut secondary = secondary_rng();
+    let mut before = [0; 12];
+    let mut after = [0; 12];
+
+    secondary.fill_bytes(&mut before);
+    rt.reseed_secondary_rng();
+    secondary.fill_bytes(&mut after);
+
+    assert!(!after.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+    assert_ne!(before, after);
+}
+
+fn rand_array_test(rt: &mut Runtime) {
+    let a: [u8; 16] = rt.fill_rand_array();
+    let b: [u8; 16] = rt.fill_rand_array();
+
+    assert!(!a.iter().all(|&n| n == 0)); // Check that the array is not all zeros.
+    assert_ne!(a, b);
+
+    // Arrays large enough to be a key can't come from the secondary CSPRNG.
+    let a: [u8; 8] = rt.fill_rand_array_secondary();
+    let b: [u8; 8] = rt.fill_rand_array_secondary();
+
+    assert!(!a.iter().all(|&n| n == 0)); // Check that the array is not all zeros.
+    assert_ne!(a, b);
+
+    let key = rt.random_key();
+
+    assert_eq!(key.len(), 32);
+    assert!(!key.iter().all(|&n| n == 0)); // Check that the key is not all zeros.
+    assert_ne!(key, rt.random_key());
+    assert_ne!(key, random_key(&mut main_rng()));
+
+    // Arrays of any size can be generated, including empty ones.
+    assert_eq!(rt.fill_rand_array::<0>(), []);
+    assert!(!rt.fill_rand_array::<64>().iter().all(|&n| n == 0));
+}
+
+fn gen_range_bounds_test(rt: &mut Runtime) {
+    for _ in 0..256 {
+        let n = rt.gen_range_u32(10, 13);
+        assert!((10..13).contains(&n));
+
+        let n = rt.gen_range_u32_secondary(10, 13);
+        assert!((10..13).contains(&n));
+    }
+
+    // Ranges of a single value and ranges close to u32::MAX must work too.
+    assert_eq!(rt.gen_range_u32(7, 8), 7);
+    assert!(rt.gen_range_u32(1, u32::MAX) >= 1);
+}
+
+/// Checks that every residue class appears for small ranges, which modulo bias or an off-by-one in
+/// the rejection zone would skew or drop.
+fn gen_range_residue_test(rt: &mut Runtime) {
+    const SAMPLES_PER_VALUE: usize = 64;
+
+    for range in 2..=7 {
+        let mut counts = [0usize; 7];
+
+        for _ in 0..range as usize * SAMPLES_PER_VALUE {
+            counts[rt.gen_range_u32(0, range) as usize] += 1;
+        }
+
+        for &count in &counts[..range as usize] {
+            assert!(count > 0, "Residue class missing for range {range}.");
+            // Each value is expected SAMPLES_PER_VALUE times. Allow a generous margin.
+            assert!(
+                count < SAMPLES_PER_VALUE * 3,
+                "Biased output for range {range}."
+            );
+        }
+
+        let mut counts = [0usize; 7];
+
+        for _ in 0..range as usize * SAMPLES_PER_VALUE {
+            counts[rt.gen_range_u32_secondary(0, range) as usize] += 1;
+        }
+
+        assert!(counts[..range as usize].iter().all(|&count| count > 0));
+    }
+}
+
+fn rng_handle_test() {
+    let mut main = main_rng();
+    let mut data = [0; 16];
+    main.fill_bytes(&mut data);
+
+    assert!(!data.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+    assert_ne!(main.next_u64(), main.next_u64());
+
+    // The main CSPRNG can generate the nonces of secure channels.
+    let mut nonce = [0; 24];
+    main.fill_rand_slice(&mut nonce);
+
+    assert!(!nonce.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+
+    let mut secondary = secondary_rng();
+    let mut data = [0; 12];
+    secondary.fill_bytes(&mut data);
+
+    assert!(!data.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+    assert_ne!(secondary.next_u32(), secondary.next_u32());
+
+    // Slices the size of a key can't be filled from the secondary CSPRNG, but larger ones can.
+    let mut key = [0; 16];
+    assert!(secondary.try_fill_bytes(&mut key).is_err());
+    assert_eq!(key, [0; 16]);
+
+    let mut key = [0; 32];
+    assert!(secondary.try_fill_bytes(&mut key).is_err());
+    assert_eq!(key, [0; 32]);
+
+    let mut bulk = [0; 64];
+    assert!(secondary.try_fill_bytes(&mut bulk).is_ok());
+    assert!(!bulk.iter().all(|&n| n == 0)); // Check that the slice is not all zeros.
+}
+
+fn seed_main_for_test_test(rt: &mut Runtime) {
+    let mut first = [0; 32];
+    let mut second = [0; 32];
+
+    seed_main_for_test([7; 32]);
+    rt.fill_rand_slice(&mut first);
+    seed_main_for_test([7; 32]);
+    rt.fill_rand_slice(&mut second);
+
+    assert_eq!(first, second);
+
+    seed_main_for_test([8; 32]);
+    rt.fill_rand_slice(&mut second);
+
+    assert_ne!(first, second);
+
+    // Go back to an unpredictable state for the rest of the tests.
+    rt.reseed_rng().unwrap();
+    rt.fill_rand_slice(&mut second);
+
+    assert_ne!(first, second);
+}
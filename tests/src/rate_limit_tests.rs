@@ -0,0 +1,103 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    eeprom::{EepromController, WearLeveledCounter},
+    hib::HibController,
+    rate_limit::{LockedOut, RateLimiter, RateLimiterConfig},
+    timer::Timer,
+};
+
+/// A word offset in the EEPROM that isn't used by any field.
+const UNUSED_WORD_OFFSET: usize = 300;
+
+/// The number of words in the failure counter.
+const COUNTER_WORDS: usize = 4;
+
+/// A configuration with short delays, so the tests don't take long.
+const CONFIG: RateLimiterConfig = RateLimiterConfig {
+    base_delay: Duration::from_millis(50),
+    max_delay: Duration::from_millis(150),
+    lockout_threshold: 5,
+};
+
+pub fn run(eeprom: &mut EepromController, hib: &HibController) {
+    WearLeveledCounter::<COUNTER_WORDS>::new(UNUSED_WORD_OFFSET)
+        .clear(eeprom)
+        .unwrap();
+
+    backoff_test(eeprom, hib);
+    persistence_test(eeprom, hib);
+    lockout_test(eeprom, hib);
+}
+
+/// Waits until the rate limiter allows an attempt, returning how long that took.
+fn wait_until_allowed(limiter: &RateLimiter<COUNTER_WORDS>, hib: &HibController) -> Duration {
+    let start = hib.now();
+
+    while let Err(LockedOut::Delayed(_)) = limiter.check() {}
+
+    hib.now() - start
+}
+
+/// Tests that each failure doubles the delay up to the maximum, and that a success clears it.
+fn backoff_test(eeprom: &mut EepromController, hib: &HibController) {
+    let mut limiter =
+        RateLimiter::<COUNTER_WORDS>::load(eeprom, hib, UNUSED_WORD_OFFSET, CONFIG).unwrap();
+
+    assert_eq!(limiter.failures(), 0);
+    assert_eq!(limiter.check(), Ok(()));
+
+    for expected in [50, 100, 150] {
+        limiter.record_failure(eeprom).unwrap();
+        assert!(matches!(limiter.check(), Err(LockedOut::Delayed(_))));
+
+        let waited = wait_until_allowed(&limiter, hib);
+        assert!(waited >= Duration::from_millis(expected - 5));
+        assert!(waited <= Duration::from_millis(expected + 20));
+    }
+
+    limiter.record_success(eeprom).unwrap();
+    assert_eq!(limiter.failures(), 0);
+    assert_eq!(limiter.check(), Ok(()));
+}
+
+/// Tests that failures and their delay survive reloading the rate limiter, like after a power cycle.
+fn persistence_test(eeprom: &mut EepromController, hib: &HibController) {
+    let mut limiter =
+        RateLimiter::<COUNTER_WORDS>::load(eeprom, hib, UNUSED_WORD_OFFSET, CONFIG).unwrap();
+
+    limiter.record_failure(eeprom).unwrap();
+    limiter.record_failure(eeprom).unwrap();
+    wait_until_allowed(&limiter, hib);
+
+    // The delay starts over when the rate limiter is loaded again.
+    let limiter =
+        RateLimiter::<COUNTER_WORDS>::load(eeprom, hib, UNUSED_WORD_OFFSET, CONFIG).unwrap();
+    assert_eq!(limiter.failures(), 2);
+    assert!(matches!(limiter.check(), Err(LockedOut::Delayed(_))));
+    assert!(wait_until_allowed(&limiter, hib) >= Duration::from_millis(95));
+}
+
+/// Tests that attempts are refused for good after the lockout threshold, even after reloading.
+fn lockout_test(eeprom: &mut EepromController, hib: &HibController) {
+    let mut limiter =
+        RateLimiter::<COUNTER_WORDS>::load(eeprom, hib, UNUSED_WORD_OFFSET, CONFIG).unwrap();
+
+    while limiter.failures() < CONFIG.lockout_threshold {
+        limiter.record_failure(eeprom).unwrap();
+    }
+
+    // The lockout doesn't expire.
+    let mut timer = hib.create_timer(CONFIG.max_delay * 2);
+    while !timer.poll() {}
+    assert_eq!(limiter.check(), Err(LockedOut::Permanently));
+
+    let limiter =
+        RateLimiter::<COUNTER_WORDS>::load(eeprom, hib, UNUSED_WORD_OFFSET, CONFIG).unwrap();
+    assert_eq!(limiter.check(), Err(LockedOut::Permanently));
+
+    WearLeveledCounter::<COUNTER_WORDS>::new(UNUSED_WORD_OFFSET)
+        .clear(eeprom)
+        .unwrap();
+}
@@ -0,0 +1,116 @@
+#![cfg(debug_assertions)]
+
+use core::{cell::Cell, convert::Infallible};
+use embedded_hal::digital::v2::OutputPin;
+use ucsc_ectf_util_no_std::{
+    communication::{self, CommunicationError, Rs485Channel, RxChannel, TxChannel},
+    timer::Timer,
+};
+
+pub fn run() {
+    driver_enable_test();
+    driver_enable_on_error_test();
+}
+
+/// An [`OutputPin`] that stores its state in a shared cell.
+struct MockPin<'a> {
+    high: &'a Cell<bool>,
+}
+
+impl OutputPin for MockPin<'_> {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.high.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.high.set(true);
+        Ok(())
+    }
+}
+
+/// A channel that records whether the driver enable pin was high while sending and flushing.
+struct MockChannel<'a> {
+    de_high: &'a Cell<bool>,
+    high_on_send: bool,
+    high_on_flush: bool,
+    fail_send: bool,
+}
+
+impl RxChannel for MockChannel<'_> {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        _dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        Err(CommunicationError::Timeout)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        _dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        Err(CommunicationError::Timeout)
+    }
+}
+
+impl TxChannel for MockChannel<'_> {
+    fn send(&mut self, _src: &mut [u8]) -> communication::Result<()> {
+        self.high_on_send = self.de_high.get();
+
+        if self.fail_send {
+            return Err(CommunicationError::SendError);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.high_on_flush = self.de_high.get();
+        Ok(())
+    }
+}
+
+/// Tests that the driver enable pin is high while sending and while waiting for the transmission to
+/// finish, and low otherwise.
+fn driver_enable_test() {
+    let de_high = Cell::new(true);
+    let mock_channel = MockChannel {
+        de_high: &de_high,
+        high_on_send: false,
+        high_on_flush: false,
+        fail_send: false,
+    };
+    let mut channel = Rs485Channel::new(mock_channel, MockPin { high: &de_high }).unwrap();
+
+    // The transceiver starts out listening.
+    assert!(!de_high.get());
+
+    channel.send(&mut [0; 4]).unwrap();
+
+    assert!(!de_high.get());
+    assert!(channel.get_mut().high_on_send);
+    assert!(channel.get_mut().high_on_flush);
+}
+
+/// Tests that the driver enable pin is driven low again when sending fails.
+fn driver_enable_on_error_test() {
+    let de_high = Cell::new(false);
+    let mock_channel = MockChannel {
+        de_high: &de_high,
+        high_on_send: false,
+        high_on_flush: false,
+        fail_send: true,
+    };
+    let mut channel = Rs485Channel::new(mock_channel, MockPin { high: &de_high }).unwrap();
+
+    assert_eq!(
+        channel.send(&mut [0; 4]),
+        Err(CommunicationError::SendError)
+    );
+    assert!(!de_high.get());
+    assert!(channel.get_mut().high_on_send);
+}
@@ -1,15 +1,50 @@
 #![cfg(debug_assertions)]
 
+use crate::test_utils::PollLimitTimer;
+use core::time::Duration;
+use embedded_hal::serial::{Read, Write};
+use tm4c123x_hal::tm4c123x::{UART0, UART1};
 use ucsc_ectf_util_no_std::{
-    communication::{self, TxChannel},
+    communication::{self, ChannelStats, CommunicationError, LineErrorStats, RxChannel, TxChannel},
+    hib::HibController,
+    timer::Timer,
     Uart0RxPin, Uart0TxPin, Uart1RxPin, Uart1TxPin,
 };
 
 type Uart0Controller<'a> = communication::Uart0Controller<'a, Uart0TxPin, Uart0RxPin>;
 type Uart1Controller<'a> = communication::Uart1Controller<'a, Uart1TxPin, Uart1RxPin>;
 
-pub fn run(uart0: &mut Uart0Controller, _uart1: &mut Uart1Controller) {
+pub fn run(
+    uart0: &mut Uart0Controller,
+    uart1: &mut Uart1Controller,
+    hib_controller: &HibController,
+) {
     basic_uart0_send_test(uart0);
+    uart0_flush_test(uart0);
+    uart0_interframe_gap_test(uart0, hib_controller);
+    uart0_raw_loopback_test(uart0);
+    keyless_aad_test(uart0, uart1);
+    uart1_stats_test(uart1);
+    uart1_line_error_test(uart1);
+}
+
+/// A [`Timer`] that has always expired.
+struct ExpiredTimer;
+
+impl Timer for ExpiredTimer {
+    fn poll(&mut self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {}
+
+    fn duration(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
 }
 
 /// This test requires manual intervention. Verify that the "Basic test!!!" was truly sent over UART 0.
@@ -20,3 +55,167 @@ fn basic_uart0_send_test(uart0: &mut Uart0Controller) {
     basic_test.copy_from_slice(b"Basic test!!!");
     uart0.send(&mut basic_test).expect("Failed to send");
 }
+
+/// Tests that flushing after a send waits until UART0 has finished transmitting.
+fn uart0_flush_test(uart0: &mut Uart0Controller) {
+    let mut flush_test = [0; 13];
+    flush_test.copy_from_slice(b"Flush test!!!");
+    uart0.send(&mut flush_test).expect("Failed to send");
+    uart0.flush().expect("Failed to flush");
+
+    // SAFETY: The flag register is only read.
+    let fr = unsafe { &(*UART0::ptr()).fr }.read();
+    assert!(fr.txfe().bit_is_set());
+    assert!(fr.busy().bit_is_clear());
+}
+
+/// Tests that a frame sent right after another with a minimum interframe gap set waits for the gap,
+/// timed with the RTC, and that the gap can be turned off again.
+fn uart0_interframe_gap_test(uart0: &mut Uart0Controller, hib_controller: &HibController) {
+    assert_eq!(uart0.min_interframe_gap(), Duration::ZERO);
+
+    uart0.set_min_interframe_gap(Duration::from_millis(20));
+    assert_eq!(uart0.min_interframe_gap(), Duration::from_millis(20));
+
+    let mut gap_test = [0; 11];
+    gap_test.copy_from_slice(b"Gap test!!!");
+    uart0.send(&mut gap_test).expect("Failed to send");
+
+    // The gap is counted from when the first send finished.
+    let timer = hib_controller.create_timer(Duration::from_secs(1));
+    gap_test.copy_from_slice(b"Gap test!!!");
+    uart0.send(&mut gap_test).expect("Failed to send");
+    assert!(timer.elapsed() >= Duration::from_millis(20));
+
+    uart0.set_min_interframe_gap(Duration::ZERO);
+    assert_eq!(uart0.min_interframe_gap(), Duration::ZERO);
+}
+
+/// Tests that the UART1 statistics count sent frames and timeouts, and can be reset. Nothing may be
+/// sending to UART1 during this test.
+fn uart1_stats_test(uart1: &mut Uart1Controller) {
+    uart1.reset_stats();
+    assert_eq!(*uart1.stats(), ChannelStats::default());
+
+    let mut msg = [0; 11];
+    msg.copy_from_slice(b"Stats test!");
+    uart1.send(&mut msg).expect("Failed to send");
+
+    // No frame is buffered, so this doesn't count as a receive operation.
+    let mut dest = [0; 64];
+    assert_eq!(uart1.try_recv(&mut dest), Ok(None));
+
+    assert_eq!(
+        uart1.recv_with_timeout(&mut dest, &mut ExpiredTimer),
+        Err(CommunicationError::Timeout)
+    );
+
+    assert_eq!(
+        *uart1.stats(),
+        ChannelStats {
+            frames_sent: 1,
+            timeouts: 1,
+            ..Default::default()
+        }
+    );
+
+    uart1.reset_stats();
+    assert_eq!(*uart1.stats(), ChannelStats::default());
+}
+
+/// Tests that a break received by UART1 fails the receive with a line error instead of being decoded,
+/// and is counted in the statistics. UART1 sends the break to itself in loopback mode, so nothing may be
+/// sending to UART1 during this test.
+fn uart1_line_error_test(uart1: &mut Uart1Controller) {
+    // SAFETY: Only the loopback and break bits are changed, and both are restored before returning.
+    let regs = unsafe { &*UART1::ptr() };
+
+    uart1.reset_stats();
+
+    // The UART must be disabled while its loopback bit is changed.
+    regs.ctl.modify(|_, w| w.uarten().clear_bit());
+    regs.ctl.modify(|_, w| w.lbe().set_bit());
+    regs.ctl.modify(|_, w| w.uarten().set_bit());
+
+    // Hold the line low for much longer than a whole byte.
+    regs.lcrh.modify(|_, w| w.brk().set_bit());
+    cortex_m::asm::delay(100_000);
+    regs.lcrh.modify(|_, w| w.brk().clear_bit());
+
+    let mut dest = [0; 64];
+    let res = uart1.recv_with_timeout(&mut dest, &mut PollLimitTimer(100_000));
+
+    regs.ctl.modify(|_, w| w.uarten().clear_bit());
+    regs.ctl.modify(|_, w| w.lbe().clear_bit());
+    regs.ctl.modify(|_, w| w.uarten().set_bit());
+
+    assert_eq!(res, Err(CommunicationError::LineError));
+
+    let stats = uart1.stats();
+    assert!(stats.line_errors.breaks >= 1);
+    assert_eq!(stats.line_errors.parity, 0);
+    assert_eq!(stats.malformed, 0);
+
+    uart1.reset_stats();
+    assert_eq!(uart1.stats().line_errors, LineErrorStats::default());
+}
+
+/// Tests that bytes written through the raw view of UART0 are read back unframed through it in loopback
+/// mode, and that reading with nothing received doesn't block.
+fn uart0_raw_loopback_test(uart0: &mut Uart0Controller) {
+    // SAFETY: Only the loopback bit is changed, and it's restored before returning.
+    let regs = unsafe { &*UART0::ptr() };
+    let mut raw = uart0.raw();
+
+    // Drain anything received before the test.
+    while raw.read().is_ok() {}
+
+    assert_eq!(raw.read(), Err(nb::Error::WouldBlock));
+
+    // The UART must be disabled while its loopback bit is changed.
+    regs.ctl.modify(|_, w| w.uarten().clear_bit());
+    regs.ctl.modify(|_, w| w.lbe().set_bit());
+    regs.ctl.modify(|_, w| w.uarten().set_bit());
+
+    for &byte in b"AT\r\n" {
+        nb::block!(raw.write(byte)).unwrap();
+    }
+
+    nb::block!(raw.flush()).unwrap();
+
+    let mut received = [0; 4];
+
+    for byte in &mut received {
+        *byte = nb::block!(raw.read()).unwrap();
+    }
+
+    regs.ctl.modify(|_, w| w.uarten().clear_bit());
+    regs.ctl.modify(|_, w| w.lbe().clear_bit());
+    regs.ctl.modify(|_, w| w.uarten().set_bit());
+
+    assert_eq!(&received, b"AT\r\n");
+}
+
+/// Tests that the keyless UART0 controller refuses to bind associated data to messages, without counting
+/// anything in its statistics, while the keyed UART1 controller has keys for both directions.
+fn keyless_aad_test(uart0: &mut Uart0Controller, uart1: &mut Uart1Controller) {
+    assert_eq!(uart0.is_keyed(), (false, false));
+    assert_eq!(uart1.is_keyed(), (true, true));
+
+    let stats = *uart0.stats();
+    let mut buf = [0; 64];
+
+    assert_eq!(
+        uart0.send_with_aad(&mut buf[..16], b"aad"),
+        Err(CommunicationError::KeyNotSet)
+    );
+    assert_eq!(
+        uart0.recv_with_timeout_and_aad(&mut buf, b"aad", &mut ExpiredTimer),
+        Err(CommunicationError::KeyNotSet)
+    );
+    assert_eq!(
+        uart0.recv_with_data_timeout_and_aad(&mut buf, b"aad", &mut ExpiredTimer),
+        Err(CommunicationError::KeyNotSet)
+    );
+    assert_eq!(*uart0.stats(), stats);
+}
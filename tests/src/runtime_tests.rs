@@ -0,0 +1,156 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use cortex_m::peripheral::SCB;
+use tm4c123x_hal::{
+    sysctl::{Clocks, PllOutputFrequency},
+    tm4c123x::{Interrupt, NVIC, UART1},
+};
+use ucsc_ectf_util_no_std::{
+    communication::RxFifoTrigger, Runtime, RuntimeConfig, RuntimePeripherals,
+};
+
+pub fn run(rt_peripherals: &mut RuntimePeripherals) {
+    default_config_test(&rt_peripherals.clocks);
+    baud_achievable_test(&rt_peripherals.clocks);
+    can_bitrate_achievable_test(&rt_peripherals.clocks);
+    release_test(rt_peripherals);
+    run_until_test(rt_peripherals);
+    low_power_wake_test(rt_peripherals);
+}
+
+/// Tests that the default configuration keeps the system clock at 80 MHz, the UARTs at 115200 baud,
+/// the UART1 receive FIFO trigger at the hardware default, and CAN0 at 500 kbit/s, and leaves I2C
+/// unconfigured.
+fn default_config_test(clocks: &Clocks) {
+    let config = RuntimeConfig::default();
+
+    assert!(matches!(config.sysclk, PllOutputFrequency::_80_00mhz));
+
+    assert_eq!(config.uart0_baud, 115200);
+    assert_eq!(config.uart1_baud, 115200);
+    assert!(RuntimeConfig::is_baud_achievable(config.uart0_baud, clocks));
+    assert!(RuntimeConfig::is_baud_achievable(config.uart1_baud, clocks));
+
+    // The peripherals were initialized with the default configuration, so the RX interrupt FIFO level
+    // select field should still be 1/2.
+    // SAFETY: The register is only read.
+    let ifls = unsafe { &*UART1::ptr() }.ifls.read().bits();
+
+    assert_eq!(config.uart1_rx_fifo_trigger, RxFifoTrigger::Half);
+    assert_eq!((ifls >> 3) & 0b111, 0x2);
+
+    assert_eq!(config.i2c, None);
+
+    assert_eq!(config.can0_bitrate, 500_000);
+    assert!(RuntimeConfig::is_can_bitrate_achievable(
+        config.can0_bitrate,
+        clocks
+    ));
+}
+
+/// Tests which baud rates are achievable at the 80 MHz system clock.
+fn baud_achievable_test(clocks: &Clocks) {
+    assert_eq!(clocks.sysclk.0, 80_000_000);
+
+    for baud in [9600, 57600, 921_600, 5_000_000] {
+        assert!(RuntimeConfig::is_baud_achievable(baud, clocks));
+    }
+
+    // Too slow for the 16-bit integer divisor, too fast for a divisor of at least 1, and zero.
+    for baud in [50, 6_000_000, 0] {
+        assert!(!RuntimeConfig::is_baud_achievable(baud, clocks));
+    }
+}
+
+/// Tests which CAN bitrates are achievable at the 80 MHz system clock.
+fn can_bitrate_achievable_test(clocks: &Clocks) {
+    for bitrate in [125_000, 250_000, 500_000, 1_000_000] {
+        assert!(RuntimeConfig::is_can_bitrate_achievable(bitrate, clocks));
+    }
+
+    // Too slow for the prescaler, too fast for 8 time quanta, not a divisor of the system clock, and
+    // zero.
+    for bitrate in [1000, 20_000_000, 33_333, 0] {
+        assert!(!RuntimeConfig::is_can_bitrate_achievable(bitrate, clocks));
+    }
+}
+
+/// Tests that releasing a runtime stops what it set up running, and that a new runtime can be created
+/// from the same peripherals afterwards.
+fn release_test(rt_peripherals: &mut RuntimePeripherals) {
+    let mut rt = Runtime::new(rt_peripherals, &Default::default(), &Default::default()).unwrap();
+
+    rt.uart1_controller.enable_rx_interrupt();
+    rt.enable_watchdog(Duration::from_secs(10));
+
+    assert!(NVIC::is_enabled(Interrupt::UART1));
+
+    rt.release();
+
+    assert!(!NVIC::is_enabled(Interrupt::UART1));
+
+    let rt = Runtime::new(rt_peripherals, &Default::default(), &Default::default()).unwrap();
+
+    assert!(!rt.watchdog_controller.is_enabled());
+    assert!(NVIC::is_enabled(Interrupt::UART1));
+
+    rt.release();
+}
+
+/// Tests that running until a condition runs the callback until it returns true, then returns.
+fn run_until_test(rt_peripherals: &mut RuntimePeripherals) {
+    let mut rt = Runtime::new(rt_peripherals, &Default::default(), &Default::default()).unwrap();
+    let mut iterations = 0;
+
+    rt.run_until(|_| {
+        iterations += 1;
+        iterations == 5
+    });
+
+    assert_eq!(iterations, 5);
+
+    // The callback is run at least once, even if it's done right away.
+    iterations = 0;
+
+    rt.run_until(|_| {
+        iterations += 1;
+        true
+    });
+
+    assert_eq!(iterations, 1);
+
+    rt.release();
+}
+
+/// Tests that UART1 can only wake the core from the low-power loop once its receive interrupt is
+/// enabled: any pending interrupt sets the event register, and the UART1 interrupt is unmasked in the
+/// NVIC, but the UART1 receive interrupts themselves are masked until then.
+fn low_power_wake_test(rt_peripherals: &mut RuntimePeripherals) {
+    let uart1_rx_interrupts_masked = || {
+        // SAFETY: The register is only read.
+        let im = unsafe { &*UART1::ptr() }.im.read();
+
+        !im.rxim().bit_is_set() && !im.rtim().bit_is_set()
+    };
+    let sevonpend = || {
+        // SAFETY: The register is only read.
+        let scr = unsafe { (*SCB::PTR).scr.read() };
+
+        (scr & (1 << 4)) != 0
+    };
+
+    let mut rt = Runtime::new(rt_peripherals, &Default::default(), &Default::default()).unwrap();
+
+    assert!(sevonpend());
+    assert!(NVIC::is_enabled(Interrupt::UART1));
+    assert!(uart1_rx_interrupts_masked());
+
+    rt.uart1_controller.enable_rx_interrupt();
+    assert!(!uart1_rx_interrupts_masked());
+
+    rt.uart1_controller.disable_rx_interrupt();
+    assert!(uart1_rx_interrupts_masked());
+
+    rt.release();
+}
@@ -0,0 +1,183 @@
+#![cfg(debug_assertions)]
+
+use crate::test_utils::{NeverTimer, PollLimitTimer};
+use core::{cell::Cell, convert::Infallible};
+use embedded_hal::digital::v2::InputPin;
+use ucsc_ectf_util_no_std::{
+    button::Button,
+    communication::{self, select, CommunicationError, Pollable, RecvSource, RxChannel, Selected},
+    timer::Timer,
+};
+
+pub fn run() {
+    timeout_test();
+    order_test();
+    recv_source_test();
+    button_test();
+}
+
+/// A source that becomes ready after it has been polled a given number of times.
+struct CountdownSource(u32);
+
+impl Pollable for CountdownSource {
+    fn poll_ready(&mut self) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 == 0
+    }
+}
+
+/// A channel that has nothing pending until it has been polled a given number of times, and then
+/// receives a fixed message.
+struct DelayedChannel {
+    polls_left: u32,
+    msg: &'static [u8],
+}
+
+impl RxChannel for DelayedChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        let dest = dest
+            .get_mut(..self.msg.len())
+            .ok_or(CommunicationError::BufferTooSmall {
+                needed: self.msg.len(),
+            })?;
+        dest.copy_from_slice(self.msg);
+
+        Ok(self.msg.len())
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recv_with_data_timeout(dest, timer)
+    }
+
+    fn try_recv(&mut self, dest: &mut [u8]) -> communication::Result<Option<usize>> {
+        self.polls_left = self.polls_left.saturating_sub(1);
+
+        if self.polls_left > 0 {
+            return Ok(None);
+        }
+
+        self.recv_with_data_timeout(dest, &mut NeverTimer).map(Some)
+    }
+}
+
+/// An [`InputPin`] that reads the state of a shared cell.
+struct MockPin<'a> {
+    low: &'a Cell<bool>,
+}
+
+impl InputPin for MockPin<'_> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(!self.low.get())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.low.get())
+    }
+}
+
+/// Tests that select times out if no source becomes ready, and with no sources at all.
+fn timeout_test() {
+    let mut never = CountdownSource(u32::MAX);
+
+    assert_eq!(
+        select(&mut [&mut never], &mut PollLimitTimer(10)),
+        Selected::Timeout
+    );
+    assert_eq!(select(&mut [], &mut PollLimitTimer(10)), Selected::Timeout);
+}
+
+/// Tests that select returns the source that becomes ready first, and the earlier source if several
+/// are ready on the same pass.
+fn order_test() {
+    let mut slow = CountdownSource(5);
+    let mut fast = CountdownSource(2);
+
+    assert_eq!(
+        select(&mut [&mut slow, &mut fast], &mut PollLimitTimer(100)),
+        Selected::Source(1)
+    );
+
+    let mut first = CountdownSource(3);
+    let mut second = CountdownSource(3);
+
+    assert_eq!(
+        select(&mut [&mut first, &mut second], &mut PollLimitTimer(100)),
+        Selected::Source(0)
+    );
+
+    // A ready source wins even if the timer has already expired.
+    let mut ready = CountdownSource(1);
+
+    assert_eq!(
+        select(&mut [&mut ready], &mut PollLimitTimer(1)),
+        Selected::Source(0)
+    );
+}
+
+/// Tests that a [`RecvSource`] is ready once its channel has received a message, and keeps the result
+/// until it's taken.
+fn recv_source_test() {
+    let mut channel = DelayedChannel {
+        polls_left: 3,
+        msg: b"select",
+    };
+    let mut dest = [0; 16];
+    let mut source = RecvSource::new(&mut channel, &mut dest);
+    let mut never = CountdownSource(u32::MAX);
+
+    assert_eq!(
+        select(&mut [&mut never, &mut source], &mut PollLimitTimer(100)),
+        Selected::Source(1)
+    );
+    assert_eq!(source.result(), Some(Ok(6)));
+    assert!(source.poll_ready());
+    assert_eq!(source.take_result(), Some(Ok(6)));
+    assert_eq!(source.result(), None);
+    assert_eq!(&dest[..6], b"select");
+
+    // A receive that fails is also reported.
+    let mut channel = DelayedChannel {
+        polls_left: 1,
+        msg: b"too long for the buffer",
+    };
+    let mut dest = [0; 4];
+    let mut source = RecvSource::new(&mut channel, &mut dest);
+
+    assert_eq!(
+        select(&mut [&mut source], &mut PollLimitTimer(100)),
+        Selected::Source(0)
+    );
+    assert_eq!(
+        source.result(),
+        Some(Err(CommunicationError::BufferTooSmall { needed: 23 }))
+    );
+}
+
+/// Tests that a [`Button`] is ready while it's pressed.
+fn button_test() {
+    let low = Cell::new(false);
+    let mut pin = MockPin { low: &low };
+    let mut button = Button::new(&mut pin);
+
+    assert_eq!(
+        select(&mut [&mut button], &mut PollLimitTimer(10)),
+        Selected::Timeout
+    );
+
+    low.set(true);
+
+    assert_eq!(
+        select(&mut [&mut button], &mut PollLimitTimer(10)),
+        Selected::Source(0)
+    );
+}
@@ -0,0 +1,100 @@
+//! Timers and channels shared by the tests that don't need real hardware behind them.
+
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    communication::{self, CommunicationError, RxChannel, TxChannel},
+    timer::Timer,
+};
+
+/// A [`Timer`] that never expires.
+pub struct NeverTimer;
+
+impl Timer for NeverTimer {
+    fn poll(&mut self) -> bool {
+        false
+    }
+
+    fn reset(&mut self) {}
+
+    fn duration(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// A [`Timer`] that expires after it has been polled a given number of times.
+pub struct PollLimitTimer(pub u32);
+
+impl Timer for PollLimitTimer {
+    fn poll(&mut self) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 == 0
+    }
+
+    fn reset(&mut self) {}
+
+    fn duration(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// A channel that receives the last message sent through it.
+pub struct LoopbackChannel {
+    buf: [u8; 64],
+    len: usize,
+}
+
+impl Default for LoopbackChannel {
+    /// Creates a channel with no message in it.
+    fn default() -> Self {
+        Self {
+            buf: [0; 64],
+            len: 0,
+        }
+    }
+}
+
+impl RxChannel for LoopbackChannel {
+    fn recv_with_data_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        timer: &mut T,
+    ) -> communication::Result<usize> {
+        self.recv_with_timeout(dest, timer)
+    }
+
+    fn recv_with_timeout<T: Timer>(
+        &mut self,
+        dest: &mut [u8],
+        _timer: &mut T,
+    ) -> communication::Result<usize> {
+        let msg = &self.buf[..self.len];
+        let dest = dest
+            .get_mut(..msg.len())
+            .ok_or(CommunicationError::BufferTooSmall { needed: msg.len() })?;
+        dest.copy_from_slice(msg);
+
+        Ok(msg.len())
+    }
+}
+
+impl TxChannel for LoopbackChannel {
+    fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
+        self.buf
+            .get_mut(..src.len())
+            .ok_or(CommunicationError::SendError)?
+            .copy_from_slice(src);
+        self.len = src.len();
+
+        Ok(())
+    }
+}
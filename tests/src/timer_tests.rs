@@ -4,7 +4,7 @@ use core::time::Duration;
 use cortex_m::prelude::_embedded_hal_blocking_delay_DelayMs;
 use tm4c123x_hal::delay::Delay;
 use ucsc_ectf_util_no_std::{
-    timer::{HibTimer, Timer},
+    timer::{spin_until, HibTimer, PeriodicTimer, Timer},
     Arc, HibPool,
 };
 
@@ -14,6 +14,12 @@ pub fn run(hib: &Arc<HibPool>, delay: &mut Delay) {
     too_slow_ms_repeated_test(hib, delay);
     too_fast_ms_repeated_test(hib, delay);
     immediate_return(hib, delay);
+    elapsed_remaining_test(hib, delay);
+    concurrent_timers_test(hib, delay);
+    periodic_timer_test(hib, delay);
+    periodic_timer_skip_test(hib, delay);
+    periodic_timer_drift_test(hib, delay);
+    spin_until_test(hib, delay);
 }
 
 /// Tests if the timer is done 1 ms after the duration of the timer.
@@ -62,3 +68,123 @@ fn immediate_return(hib: &Arc<HibPool>, _delay: &mut Delay) {
 
     assert!(new_timer.poll())
 }
+
+/// Checks that the elapsed and remaining time of a timer track the time passed and saturate once
+/// time is up.
+fn elapsed_remaining_test(hib: &Arc<HibPool>, delay: &mut Delay) {
+    let duration = Duration::from_millis(500);
+    let new_timer = HibTimer::new(hib, duration);
+
+    delay.delay_ms(200u32);
+
+    let elapsed = new_timer.elapsed();
+    let remaining = new_timer.remaining();
+    assert!(elapsed >= Duration::from_millis(199) && elapsed <= Duration::from_millis(210));
+    assert!(remaining >= Duration::from_millis(290) && remaining <= Duration::from_millis(301));
+
+    delay.delay_ms(400u32);
+
+    assert_eq!(new_timer.elapsed(), duration);
+    assert_eq!(new_timer.remaining(), Duration::ZERO);
+}
+
+/// Checks that timers running at the same time don't interfere with each other.
+fn concurrent_timers_test(hib: &Arc<HibPool>, delay: &mut Delay) {
+    let long_timer = HibTimer::new(hib, Duration::from_millis(400));
+
+    delay.delay_ms(100u32);
+
+    let mut short_timer = HibTimer::new(hib, Duration::from_millis(100));
+
+    delay.delay_ms(101u32);
+
+    assert!(short_timer.has_elapsed());
+    assert!(!long_timer.has_elapsed());
+
+    // Resetting one timer doesn't affect the other.
+    short_timer.reset();
+
+    delay.delay_ms(50u32);
+
+    assert!(!short_timer.has_elapsed());
+    assert!(!long_timer.has_elapsed());
+
+    delay.delay_ms(100u32);
+
+    assert!(short_timer.has_elapsed());
+    assert!(!long_timer.has_elapsed());
+
+    delay.delay_ms(60u32);
+
+    assert!(long_timer.has_elapsed());
+}
+
+/// Checks that a periodic timer fires once per period.
+fn periodic_timer_test(hib: &Arc<HibPool>, delay: &mut Delay) {
+    let mut periodic_timer = PeriodicTimer::new(hib, Duration::from_millis(100));
+
+    for _ in 0..5 {
+        delay.delay_ms(50u32);
+        assert!(!periodic_timer.poll());
+
+        delay.delay_ms(51u32);
+        assert!(periodic_timer.poll());
+        assert!(!periodic_timer.poll());
+    }
+}
+
+/// Checks that a periodic timer polled late skips missed periods without drifting.
+fn periodic_timer_skip_test(hib: &Arc<HibPool>, delay: &mut Delay) {
+    let mut periodic_timer = PeriodicTimer::new(hib, Duration::from_millis(100));
+
+    // Miss two whole periods. The timer only fires once for them.
+    delay.delay_ms(350u32);
+    assert!(periodic_timer.poll());
+    assert!(!periodic_timer.poll());
+
+    // The next deadline is still on the original schedule at 400 ms, not 100 ms after the late poll.
+    delay.delay_ms(40u32);
+    assert!(!periodic_timer.poll());
+
+    delay.delay_ms(15u32);
+    assert!(periodic_timer.poll());
+}
+
+/// Checks that a periodic timer with a period that isn't a whole number of RTC ticks doesn't drift. A
+/// 1 ms period is 32.768 ticks, so rounding it to 32 ticks would fire about 511 times in 500 ms.
+fn periodic_timer_drift_test(hib: &Arc<HibPool>, _delay: &mut Delay) {
+    let mut periodic_timer = PeriodicTimer::new(hib, Duration::from_millis(1));
+    let mut timer = HibTimer::new(hib, Duration::from_millis(500));
+    let mut fires = 0;
+
+    while !timer.poll() {
+        if periodic_timer.poll() {
+            fires += 1;
+        }
+    }
+
+    assert!((499..=500).contains(&fires));
+}
+
+/// Tests that spinning stops as soon as the predicate is true, or once the timer is up if it never is,
+/// and that the predicate is checked even if the timer is already up.
+fn spin_until_test(hib: &Arc<HibPool>, _delay: &mut Delay) {
+    let mut checks = 0;
+    let mut timer = HibTimer::new(hib, Duration::from_millis(100));
+
+    assert!(spin_until(&mut timer, || {
+        checks += 1;
+        checks == 5
+    }));
+    assert_eq!(checks, 5);
+    assert!(!timer.poll());
+
+    let mut timer = HibTimer::new(hib, Duration::from_millis(20));
+
+    assert!(!spin_until(&mut timer, || false));
+    assert!(timer.poll());
+
+    let mut timer = HibTimer::new(hib, Duration::ZERO);
+
+    assert!(spin_until(&mut timer, || true));
+}
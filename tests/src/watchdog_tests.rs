@@ -0,0 +1,110 @@
+#![cfg(debug_assertions)]
+
+use core::time::Duration;
+use ucsc_ectf_util_no_std::{
+    communication::{self, CommunicationError, TxChannel},
+    timer::Timer,
+    Runtime,
+};
+
+pub fn run(rt: &mut Runtime) {
+    fed_watchdog_test(rt);
+    disabled_watchdog_test(rt);
+    spin_until_test(rt);
+    send_retry_test(rt);
+}
+
+/// A [`TxChannel`] whose first send fails.
+struct FailOnceTxChannel {
+    sends: u32,
+}
+
+impl TxChannel for FailOnceTxChannel {
+    fn send(&mut self, _src: &mut [u8]) -> communication::Result<()> {
+        self.sends += 1;
+
+        if self.sends == 1 {
+            return Err(CommunicationError::SendError);
+        }
+
+        Ok(())
+    }
+}
+
+/// Waits for a duration while optionally feeding the watchdog.
+fn wait(rt: &Runtime, duration: Duration, feed: bool) {
+    let mut timer = rt.hib_controller.create_timer(duration);
+
+    while !timer.poll() {
+        if feed {
+            rt.feed_watchdog();
+        }
+    }
+}
+
+/// Checks that a fed watchdog doesn't reset the chip for longer than its timeout.
+fn fed_watchdog_test(rt: &mut Runtime) {
+    rt.enable_watchdog(Duration::from_millis(200));
+    assert!(rt.watchdog_controller.is_enabled());
+
+    wait(rt, Duration::from_millis(600), true);
+
+    rt.disable_watchdog();
+}
+
+/// Checks that a disabled watchdog doesn't reset the chip when it isn't fed.
+fn disabled_watchdog_test(rt: &mut Runtime) {
+    rt.enable_watchdog(Duration::from_millis(200));
+    rt.disable_watchdog();
+    assert!(!rt.watchdog_controller.is_enabled());
+
+    wait(rt, Duration::from_millis(600), false);
+}
+
+/// Checks that spinning feeds the watchdog for longer than its timeout, and reports whether the
+/// predicate became true.
+fn spin_until_test(rt: &mut Runtime) {
+    rt.enable_watchdog(Duration::from_millis(200));
+
+    let start = rt.hib_controller.now();
+
+    assert!(!rt.spin_until(Duration::from_millis(600), || false));
+    assert!(rt.hib_controller.now() - start >= Duration::from_millis(590));
+
+    let mut checks = 0;
+
+    assert!(rt.spin_until(Duration::from_millis(600), || {
+        checks += 1;
+        checks == 3
+    }));
+    assert_eq!(checks, 3);
+
+    rt.disable_watchdog();
+}
+
+/// Checks that the backoff of [`communication::send_retry()`] feeds the watchdog while it waits for
+/// longer than the watchdog timeout.
+fn send_retry_test(rt: &mut Runtime) {
+    rt.enable_watchdog(Duration::from_millis(100));
+
+    let mut ch = FailOnceTxChannel { sends: 0 };
+    let mut timer = rt.hib_controller.create_timer(Duration::from_secs(2));
+
+    assert_eq!(
+        communication::send_retry::<16, _, _>(
+            &mut ch,
+            b"msg",
+            2,
+            Duration::from_millis(400),
+            &mut timer,
+            &rt.watchdog_controller,
+        ),
+        Ok(())
+    );
+    assert_eq!(ch.sends, 2);
+
+    // The wait is at least half of the base delay.
+    assert!(timer.elapsed() >= Duration::from_millis(200));
+
+    rt.disable_watchdog();
+}
@@ -0,0 +1,135 @@
+#![cfg(debug_assertions)]
+
+use core::fmt::Write;
+use ucsc_ectf_util_no_std::communication::{self, ChannelWriter, CommunicationError, TxChannel};
+
+/// The number of bytes a [`RecordingTxChannel`] can record.
+const RECORD_LEN: usize = 64;
+
+/// The byte a [`RecordingTxChannel`] records after each message.
+const SEPARATOR: u8 = b'|';
+
+pub fn run() {
+    line_test();
+    buffer_full_test();
+    flush_test();
+    drop_test();
+    error_test();
+}
+
+/// A [`TxChannel`] that records each message sent followed by a [`SEPARATOR`], optionally failing every
+/// send with an error.
+struct RecordingTxChannel {
+    record: [u8; RECORD_LEN],
+    len: usize,
+    flushes: u32,
+    error: Option<CommunicationError>,
+}
+
+impl RecordingTxChannel {
+    fn new(error: Option<CommunicationError>) -> Self {
+        Self {
+            record: [0; RECORD_LEN],
+            len: 0,
+            flushes: 0,
+            error,
+        }
+    }
+
+    /// Gets the messages sent so far.
+    fn sent(&self) -> &[u8] {
+        &self.record[..self.len]
+    }
+}
+
+impl TxChannel for RecordingTxChannel {
+    fn send(&mut self, src: &mut [u8]) -> communication::Result<()> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        self.record[self.len..self.len + src.len()].copy_from_slice(src);
+        self.record[self.len + src.len()] = SEPARATOR;
+        self.len += src.len() + 1;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> communication::Result<()> {
+        self.flushes += 1;
+
+        Ok(())
+    }
+}
+
+/// Tests that each line is sent as one message once its newline is written.
+fn line_test() {
+    let mut channel = RecordingTxChannel::new(None);
+    let mut writer = ChannelWriter::<_, 16>::new(&mut channel);
+
+    write!(writer, "id={}", 42).unwrap();
+    assert!(writer.get_mut().sent().is_empty());
+
+    writeln!(writer, ", ok").unwrap();
+    write!(writer, "a\nb\n").unwrap();
+    drop(writer);
+
+    assert_eq!(channel.sent(), b"id=42, ok\n|a\n|b\n|");
+}
+
+/// Tests that a line longer than the buffer is split into messages the size of the buffer.
+fn buffer_full_test() {
+    let mut channel = RecordingTxChannel::new(None);
+    let mut writer = ChannelWriter::<_, 4>::new(&mut channel);
+
+    writeln!(writer, "0123456789").unwrap();
+    drop(writer);
+
+    assert_eq!(channel.sent(), b"0123|4567|89\n|");
+}
+
+/// Tests that flushing sends a partial line and flushes the wrapped channel, and that flushing with
+/// nothing buffered sends nothing.
+fn flush_test() {
+    let mut channel = RecordingTxChannel::new(None);
+    let mut writer = ChannelWriter::<_, 16>::new(&mut channel);
+
+    write!(writer, "partial").unwrap();
+    writer.flush().unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    assert_eq!(channel.sent(), b"partial|");
+    assert_eq!(channel.flushes, 2);
+}
+
+/// Tests that dropping the writer sends a partial line.
+fn drop_test() {
+    let mut channel = RecordingTxChannel::new(None);
+
+    write!(ChannelWriter::<_, 16>::new(&mut channel), "tail").unwrap();
+
+    assert_eq!(channel.sent(), b"tail|");
+}
+
+/// Tests that a failed send is reported as a formatting error and its error can be taken once.
+fn error_test() {
+    let mut channel = RecordingTxChannel::new(Some(CommunicationError::SendError));
+    let mut writer = ChannelWriter::<_, 16>::new(&mut channel);
+
+    assert_eq!(writer.take_error(), None);
+
+    // Nothing is sent until the newline, so the error isn't seen yet.
+    write!(writer, "no newline").unwrap();
+
+    assert!(writeln!(writer).is_err());
+    assert_eq!(writer.take_error(), Some(CommunicationError::SendError));
+    assert_eq!(writer.take_error(), None);
+
+    // The failed message was discarded, so there's nothing left to send.
+    writer.get_mut().error = None;
+    writer.flush().unwrap();
+    drop(writer);
+
+    assert!(channel.sent().is_empty());
+}